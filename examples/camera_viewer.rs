@@ -0,0 +1,14 @@
+use egami::providers::CameraProvider;
+use egami::viewer::Viewer;
+
+// shows /dev/video0 or the device given, f1 shows the frame rate
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+
+    let path = std::env::args().nth(1).unwrap_or_else(|| String::from("/dev/video0"));
+    let camera = CameraProvider::open(&path, (1280, 720))?;
+    let size = camera.size();
+
+    Viewer::new(path, camera).with_size(size).with_hud(true).run()?;
+    Ok(())
+}
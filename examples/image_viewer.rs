@@ -1,182 +1,90 @@
-use std::sync::Arc;
-
-use winit::{
-    application::ApplicationHandler, dpi::PhysicalSize, error::EventLoopError, event::*, event_loop::{ControlFlow, EventLoop}, keyboard::{KeyCode, PhysicalKey}, window::Window
-};
-
-use egami::types::{FrameRenderContext, HasData, HasPosition, HasSize, Pair};
-use egami::render::{self, WgpuFrameRenderContext, WgpuFrameRenderContextInit};
-
-#[derive(Default)]
-struct App {
-    window: Option<Arc<Window>>,
-    render_context: Option<render::WgpuFrameRenderContext>,
-    frame_provider: Option<WgpuImageProvider>,
-}
-
-impl App {
-    fn clear(&mut self) {
-        self.window = None;
-        self.render_context = None;
-        self.frame_provider = None;
-    }
-
-    fn run() -> Result<(), EventLoopError> {
-        env_logger::init();
-
-        let event_loop = EventLoop::new()?;
-        event_loop.set_control_flow(ControlFlow::Poll);
-
-        let mut app = Self::default();
-        event_loop.run_app(&mut app)
-    }
-
-    fn resize(&mut self, size: Pair<u32>) -> Result<(), bool> {
-        match self.render_context.as_mut() {
-            Some(context) => {
-                context.configure(size);
-                self.render()
-            },
-            None => Ok(()),
-        }
+use egami::diagnostics;
+use egami::providers::{AnimatedImageProvider, DirectoryProvider, FileImageProvider, SortOrder};
+use egami::recording::InteractionPlayer;
+use egami::types::{FrameProvider, HasData, HasFormat, HasOrientation, HasPosition, HasSize};
+use egami::viewer::{KeyResponse, Viewer, WindowSize};
+use winit::keyboard::KeyCode;
+
+fn view<Provider, Frame>(title: String, frame_provider: Provider) -> Result<(), winit::error::EventLoopError>
+where
+    Provider: FrameProvider<Frame = Frame>,
+    Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData,
+{
+    let mut viewer = Viewer::new(title, frame_provider)
+        .with_window_size(WindowSize::Native)
+        .with_strict_validation(std::env::var_os("EGAMI_STRICT").is_some())
+        .with_annotation_handler(|event| log::info!("annotation: {event:?}"));
+
+    // e.g. EGAMI_RECORD=input.txt to attach to a bug report, EGAMI_REPLAY=input.txt to
+    // play it back
+    if let Some(path) = std::env::var_os("EGAMI_RECORD") {
+        viewer = viewer.with_recording(path);
     }
-
-    fn render(&mut self) -> Result<(), bool> {
-        match self.render_context.as_mut() {
-            Some(context) => {
-                match context.draw_frame(self.frame_provider.as_ref().unwrap()) {
-                    Ok(_) => {
-                        self.window.as_ref().unwrap().request_redraw();
-                        Ok(())
-                    },
-                    // Err(wgpu::SurfaceError::Lost) => renderer.resize(renderer.size),
-                    Err(wgpu::SurfaceError::OutOfMemory) => Err(true),
-                    Err(_) => Err(false),
-                }
-                
-            },
-            None => Ok(()),
-        }
+    if let Some(path) = std::env::var_os("EGAMI_REPLAY") {
+        viewer = viewer.with_replay(InteractionPlayer::load(path).unwrap());
     }
 
-    fn has_window(&self, window_id: winit::window::WindowId) -> bool {
-        match &self.window {
-            Some(window) => window.id() == window_id,
-            None => false,
-        }
-    }
+    viewer.run()
 }
 
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let attributes = Window::default_attributes()
-            .with_title("xixi")
-            .with_inner_size(PhysicalSize::new(2400, 960));
-
-        let window = Arc::new(event_loop.create_window(attributes).unwrap());
-        window.request_redraw();
-
-        let window_size = window.inner_size();
-        self.window = Some(Arc::clone(&window));
-        self.frame_provider = Some(WgpuImageProvider::new());
-        self.render_context = Some(WgpuFrameRenderContext::init(WgpuFrameRenderContextInit {
-            clear_color: None,
-            surface_handle: window.into(),
-            surface_size: (window_size.width, window_size.height),
-        }));
-    }
-
-    fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
-        self.clear();
-    }
-
-    fn suspended(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
-        self.clear();
-    }
-
-    fn window_event(
-        &mut self,
-        event_loop: &winit::event_loop::ActiveEventLoop,
-        window_id: winit::window::WindowId,
-        event: WindowEvent,
-    ) {
-        if self.has_window(window_id) {
-            match event {
-                WindowEvent::CloseRequested | WindowEvent::KeyboardInput {
-                    event: KeyEvent {
-                        state: ElementState::Pressed,
-                        physical_key: PhysicalKey::Code(KeyCode::Escape),
-                        ..
-                    },
-                    ..
-                } => event_loop.exit(),
-                WindowEvent::Resized(new_size) => match self.resize((new_size.width, new_size.height)) {
-                    Err(true) => event_loop.exit(),
-                    _ => {},
-                },
-                WindowEvent::RedrawRequested => match self.render() {
-                    Err(true) => event_loop.exit(),
-                    _ => {},
-                },
-                _ => {},
-            }
-        }
-    }
+fn animation(path: &str) -> Option<AnimatedImageProvider> {
+    let bytes = std::fs::read(path).ok()?;
+    AnimatedImageProvider::from_bytes(&bytes).ok().filter(|animation| animation.frame_count() > 1)
 }
 
-struct WgpuImageProvider {
-    size: Pair<u32>,
-    image_buffer: Vec<u8>,
-}
-
-struct WgpuImageFrame {
-    size: Pair<u32>,
-    buffer: Vec<u8>,
-}
-
-impl HasPosition<u32> for WgpuImageFrame {
-    fn position(&self) -> Pair<u32> {
-        (0, 0)
-    }
-}
-
-impl HasSize<u32> for WgpuImageFrame {
-    fn size(&self) -> Pair<u32> {
-        self.size
-    }
-}
-
-impl HasData for WgpuImageFrame {
-    fn data(&self) -> &[u8] {
-        &self.buffer
-    }
-}
-
-impl WgpuImageProvider {
-    fn new() -> Self {
-        let bytes = include_bytes!("xixi.png");
-        let image = image::load_from_memory(bytes).unwrap();
-
-        let width = image.width();
-        let height = image.height();
-        let buffer = image.into_rgba8();
-        let rgba8 = buffer.into_vec();
-
-        Self {
-            image_buffer: rgba8,
-            size: (width, height),
-        }
+fn main() -> Result<(), winit::error::EventLoopError> {
+    let logger = env_logger::Builder::from_default_env().build();
+    let max_level = logger.filter();
+    diagnostics::DiagnosticLogger::new(logger).init(max_level).unwrap();
+
+    // opt-in, e.g. EGAMI_CRASH_DIR=./crash-reports
+    if let Some(directory) = std::env::var_os("EGAMI_CRASH_DIR") {
+        diagnostics::install(diagnostics::CrashReportConfig {
+            directory: directory.into(),
+            ..Default::default()
+        });
     }
-}
-
-impl<'iter> Iterator for &'iter WgpuImageProvider {
-    type Item = WgpuImageFrame;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        Some(WgpuImageFrame { size: self.size, buffer: self.image_buffer.clone() })
+    // an optional path to any supported image, gif and apng animations play at their own frame delays,
+    // a directory is stepped through with the arrow keys
+    match std::env::args().nth(1) {
+        Some(path) if std::path::Path::new(&path).is_dir() => {
+            let frame_provider = DirectoryProvider::new(&path, SortOrder::Name).unwrap();
+            diagnostics::set_source_description(format!("{path} ({} images)", frame_provider.len()));
+
+            Viewer::new(path, frame_provider)
+                .with_window_size(WindowSize::Native)
+                .with_auto_resize(true)
+                .with_aspect_lock(true)
+                .with_key_handler(|directory, key| {
+                    let moved = match key {
+                        KeyCode::ArrowRight => directory.advance(),
+                        KeyCode::ArrowLeft => directory.back(),
+                        _ => return None,
+                    };
+
+                    let path = directory.current_path().filter(|_| moved)?;
+                    let title = format!("{} [{}/{}]", path.display(), directory.index() + 1, directory.len());
+                    Some(KeyResponse { title: Some(title), ..Default::default() })
+                })
+                .run()
+        },
+        // animations are told apart by their contents, a single frame gif or png is a still
+        Some(path) => match animation(&path) {
+            Some(frame_provider) => {
+                diagnostics::set_source_description(format!("{path} ({} frames)", frame_provider.frame_count()));
+                view(path, frame_provider)
+            },
+            None => {
+                let frame_provider = FileImageProvider::open(&path).unwrap();
+                diagnostics::set_source_description(format!("{path} ({:?})", frame_provider.format()));
+                view(path, frame_provider)
+            },
+        },
+        None => {
+            let frame_provider = AnimatedImageProvider::from_bytes(include_bytes!("xixi.png")).unwrap();
+            diagnostics::set_source_description("embedded xixi.png");
+            view(String::from("xixi"), frame_provider)
+        },
     }
 }
-
-fn main() -> Result<(), winit::error::EventLoopError> {
-    App::run()
-}
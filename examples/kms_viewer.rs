@@ -0,0 +1,24 @@
+use std::time::{Duration, Instant};
+
+use egami::kms::{KmsFrameRenderContext, KmsFrameRenderContextInit};
+use egami::providers::ImageProvider;
+use egami::types::{FrameRenderContext, HasSize};
+
+// run from a text console, usually needs root or membership in the video group
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+
+    let mut frame_provider = ImageProvider::from_bytes(include_bytes!("xixi.png"))?;
+    let mut render_context = KmsFrameRenderContext::open(KmsFrameRenderContextInit::default())?;
+    let size = render_context.size();
+    render_context.configure(size);
+
+    let started = Instant::now();
+
+    while started.elapsed() < Duration::from_secs(10) {
+        render_context.draw_frame(&mut frame_provider)?;
+        std::thread::sleep(Duration::from_millis(16));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::rc::Rc;
+
+use egami::providers::FileImageProvider;
+use egami::shading::ShadingMode;
+use egami::snapshot::{SnapshotMode, SnapshotSuite};
+use egami::types::FrameRenderContext;
+
+// checks the views below against the png files in `examples/snapshots`, or rewrites them
+// with `--update` (or EGAMI_UPDATE_SNAPSHOTS=1), printing which ones changed either way
+fn main() {
+    env_logger::init();
+
+    let update = std::env::args().skip(1).any(|arg| arg == "--update");
+    let mode = if update { SnapshotMode::Update } else { SnapshotMode::from_env() };
+
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let image = Rc::new(FileImageProvider::open(manifest_dir.join("examples/xixi.png")).unwrap());
+
+    let suite = SnapshotSuite::new(manifest_dir.join("examples/snapshots"))
+        .case("fit", {
+            let image = image.clone();
+            move |context| context.draw_frame(&*image)
+        })
+        .case("zoom_2x", {
+            let image = image.clone();
+            move |context| {
+                context.set_zoom(2.0);
+                context.draw_frame(&*image)
+            }
+        })
+        .case("checkerboard", {
+            let image = image.clone();
+            move |context| {
+                context.set_checkerboard(true);
+                context.set_zoom(0.5);
+                context.draw_frame(&*image)
+            }
+        })
+        .case("normal_map", {
+            let image = image.clone();
+            move |context| {
+                context.set_shading_mode(ShadingMode::NormalMap { light: [0.5, 0.5, 1.0] });
+                context.draw_frame(&*image)
+            }
+        })
+        .case("hud", move |context| {
+            context.set_hud(Some("xixi.png 100%"));
+            context.draw_frame(&*image)
+        });
+
+    let report = suite.run_as(mode).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(2);
+    });
+
+    println!("{report}");
+    if !report.passed() {
+        eprintln!("run with --update to accept the changes");
+        std::process::exit(1);
+    }
+}
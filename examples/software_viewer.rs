@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use egami::providers::FileImageProvider;
+use egami::software::{SoftbufferFrameRenderContext, SoftbufferFrameRenderContextInit};
+use egami::types::FrameRenderContext;
+use winit::application::ApplicationHandler;
+use winit::event::{ElementState, KeyEvent, MouseScrollDelta, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{Window, WindowId};
+
+// shows an image without a gpu, scroll to zoom, escape to quit
+struct App {
+    image: FileImageProvider,
+    window: Option<Arc<Window>>,
+    context: Option<SoftbufferFrameRenderContext>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window = Arc::new(event_loop.create_window(Window::default_attributes().with_title("software viewer")).unwrap());
+        let size = window.inner_size();
+
+        let init = SoftbufferFrameRenderContextInit {
+            window: window.clone(),
+            surface_size: (size.width, size.height),
+            clear_color: Some([24, 24, 24]),
+        };
+
+        self.context = Some(SoftbufferFrameRenderContext::init(init).unwrap());
+        self.window = Some(window);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let (Some(window), Some(context)) = (&self.window, &mut self.context) else {
+            return;
+        };
+
+        match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(KeyCode::Escape), state: ElementState::Pressed, .. }, .. } => event_loop.exit(),
+            WindowEvent::Resized(size) => {
+                context.configure((size.width, size.height));
+                window.request_redraw();
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                };
+                context.set_zoom(context.zoom() * 1.1f32.powf(lines));
+                window.request_redraw();
+            },
+            WindowEvent::RedrawRequested => {
+                if let Err(error) = context.draw_frame(&self.image) {
+                    eprintln!("{error}");
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn main() -> Result<(), winit::error::EventLoopError> {
+    env_logger::init();
+
+    let path = std::env::args().nth(1).unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/examples/xixi.png").to_string());
+    let image = FileImageProvider::open(path).unwrap();
+
+    EventLoop::new()?.run_app(&mut App { image, window: None, context: None })
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+use egami::providers::SpriteSheet;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|json: &str| {
+    let _ = SpriteSheet::from_texture_packer(json);
+});
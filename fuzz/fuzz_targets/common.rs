@@ -0,0 +1,17 @@
+use egami::providers::DecodeLimits;
+
+// small enough that an input claiming a huge image is rejected instead of running the
+// fuzzer out of memory, and without a timeout so decoding stays on the fuzzer's thread and
+// panics surface as crashes
+pub fn limits() -> DecodeLimits {
+    DecodeLimits {
+        max_input_bytes: 1024 * 1024,
+        max_size: (4096, 4096),
+        max_alloc: 64 * 1024 * 1024,
+        max_pixels: 4 * 1024 * 1024,
+        max_dimension: 4096,
+        timeout: None,
+        salvage: true,
+        cancel: None,
+    }
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+mod common;
+
+use egami::providers::{decode, read_metadata, sniff};
+use libfuzzer_sys::fuzz_target;
+
+// every format behind the shared entry points, including the crate's own qoi, pfm, ani and
+// exif readers
+fuzz_target!(|bytes: &[u8]| {
+    let limits = common::limits();
+
+    let _ = sniff(bytes);
+    let _ = read_metadata(bytes.to_vec(), &limits);
+    let _ = decode::decode(bytes.to_vec(), &limits);
+    let _ = decode::decode_animation(bytes.to_vec(), &limits);
+});
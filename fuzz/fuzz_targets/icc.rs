@@ -0,0 +1,8 @@
+#![no_main]
+
+use egami::color::IccProfile;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = IccProfile::parse(bytes);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+mod common;
+
+use egami::providers::IconProvider;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = IconProvider::from_bytes_with_limits(bytes, &common::limits());
+});
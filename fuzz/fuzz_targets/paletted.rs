@@ -0,0 +1,10 @@
+#![no_main]
+
+mod common;
+
+use egami::providers::PalettedImageProvider;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = PalettedImageProvider::from_bytes_with_limits(bytes, &common::limits());
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use std::time::Duration;
+
+use egami::subtitles::Subtitles;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|srt: &str| {
+    if let Ok(subtitles) = Subtitles::from_srt(srt) {
+        for cue in subtitles.cues() {
+            let _ = subtitles.text_at(cue.start);
+            let _ = subtitles.text_at(cue.end.saturating_add(Duration::from_millis(1)));
+        }
+    }
+});
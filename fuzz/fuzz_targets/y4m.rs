@@ -0,0 +1,27 @@
+#![no_main]
+
+mod common;
+
+use std::io::Cursor;
+
+use egami::providers::{StreamingFrameProvider, Y4mProvider};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let Ok(mut provider) = Y4mProvider::with_limits(Cursor::new(bytes), &common::limits()) else {
+        return;
+    };
+
+    // a few frames, then the same again after a rewind
+    for _ in 0..2 {
+        for _ in 0..8 {
+            if !matches!(provider.next_frame(), Ok(Some(_))) {
+                break;
+            }
+        }
+
+        if provider.rewind().is_err() {
+            break;
+        }
+    }
+});
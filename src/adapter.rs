@@ -0,0 +1,66 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum AdapterSelection {
+    #[default]
+    Default,
+    HighPerformance,
+    LowPower,
+    // case-insensitive substring of the adapter name
+    Name(String),
+    // position in `available_adapters()`
+    Index(usize),
+}
+
+#[derive(Debug)]
+pub enum AdapterError {
+    NotFound(AdapterSelection),
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for AdapterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdapterError::NotFound(selection) => write!(f, "no compatible adapter matches {selection:?}"),
+            AdapterError::RequestDevice(error) => write!(f, "failed to request device: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AdapterError {}
+
+pub(crate) fn compatible_adapters(instance: &wgpu::Instance, compatible_surface: Option<&wgpu::Surface>) -> Vec<wgpu::Adapter> {
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .into_iter()
+        .filter(|adapter| compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface)))
+        .collect()
+}
+
+pub(crate) async fn select_adapter(
+    instance: &wgpu::Instance,
+    compatible_surface: Option<&wgpu::Surface<'_>>,
+    selection: &AdapterSelection,
+) -> Option<wgpu::Adapter> {
+    let power_preference = match selection {
+        AdapterSelection::Default => wgpu::PowerPreference::default(),
+        AdapterSelection::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        AdapterSelection::LowPower => wgpu::PowerPreference::LowPower,
+        AdapterSelection::Name(name) => {
+            let name = name.to_lowercase();
+
+            return compatible_adapters(instance, compatible_surface)
+                .into_iter()
+                .find(|adapter| adapter.get_info().name.to_lowercase().contains(&name));
+        },
+        AdapterSelection::Index(index) => {
+            return compatible_adapters(instance, compatible_surface).into_iter().nth(*index);
+        },
+    };
+
+    instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
+        force_fallback_adapter: false,
+        compatible_surface,
+        power_preference,
+    }).await
+}
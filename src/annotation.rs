@@ -0,0 +1,334 @@
+use crate::types::Pair;
+
+// an axis aligned box in frame pixels, `min` is the top left corner
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Pair<f32>,
+    pub max: Pair<f32>,
+}
+
+impl BoundingBox {
+    // any two opposite corners
+    pub fn from_corners(a: Pair<f32>, b: Pair<f32>) -> Self {
+        Self {
+            min: (a.0.min(b.0), a.1.min(b.1)),
+            max: (a.0.max(b.0), a.1.max(b.1)),
+        }
+    }
+
+    pub fn size(&self) -> Pair<f32> {
+        (self.max.0 - self.min.0, self.max.1 - self.min.1)
+    }
+
+    pub fn contains(&self, point: Pair<f32>) -> bool {
+        (self.min.0..=self.max.0).contains(&point.0) && (self.min.1..=self.max.1).contains(&point.1)
+    }
+
+    pub fn translated(&self, delta: Pair<f32>) -> Self {
+        Self {
+            min: (self.min.0 + delta.0, self.min.1 + delta.1),
+            max: (self.max.0 + delta.0, self.max.1 + delta.1),
+        }
+    }
+
+    // shifted back inside `0..size` without changing its size where it fits
+    fn kept_inside(&self, size: Pair<f32>) -> Self {
+        let shift = |min: f32, max: f32, len: f32| match (min < 0.0, max > len) {
+            (true, _) => -min,
+            (false, true) => (len - max).max(-min),
+            (false, false) => 0.0,
+        };
+
+        let moved = self.translated((shift(self.min.0, self.max.0, size.0), shift(self.min.1, self.max.1, size.1)));
+        moved.clamped(size)
+    }
+
+    fn clamped(&self, size: Pair<f32>) -> Self {
+        let clamp = |point: Pair<f32>| (point.0.clamp(0.0, size.0), point.1.clamp(0.0, size.1));
+        Self { min: clamp(self.min), max: clamp(self.max) }
+    }
+}
+
+// the grips drawn around the selected box
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+}
+
+impl Handle {
+    pub const ALL: [Handle; 8] = [
+        Handle::TopLeft,
+        Handle::Top,
+        Handle::TopRight,
+        Handle::Right,
+        Handle::BottomRight,
+        Handle::Bottom,
+        Handle::BottomLeft,
+        Handle::Left,
+    ];
+
+    pub fn position(self, rect: &BoundingBox) -> Pair<f32> {
+        let center = ((rect.min.0 + rect.max.0) / 2.0, (rect.min.1 + rect.max.1) / 2.0);
+
+        match self {
+            Handle::TopLeft => rect.min,
+            Handle::Top => (center.0, rect.min.1),
+            Handle::TopRight => (rect.max.0, rect.min.1),
+            Handle::Right => (rect.max.0, center.1),
+            Handle::BottomRight => rect.max,
+            Handle::Bottom => (center.0, rect.max.1),
+            Handle::BottomLeft => (rect.min.0, rect.max.1),
+            Handle::Left => (rect.min.0, center.1),
+        }
+    }
+
+    // `rect` with the edges under this handle moved to `point`, dragging past the opposite
+    // edge flips the box
+    fn drag(self, rect: &BoundingBox, point: Pair<f32>) -> BoundingBox {
+        let (mut min, mut max) = (rect.min, rect.max);
+
+        match self {
+            Handle::TopLeft | Handle::Left | Handle::BottomLeft => min.0 = point.0,
+            Handle::TopRight | Handle::Right | Handle::BottomRight => max.0 = point.0,
+            Handle::Top | Handle::Bottom => {},
+        }
+
+        match self {
+            Handle::TopLeft | Handle::Top | Handle::TopRight => min.1 = point.1,
+            Handle::BottomLeft | Handle::Bottom | Handle::BottomRight => max.1 = point.1,
+            Handle::Left | Handle::Right => {},
+        }
+
+        BoundingBox::from_corners(min, max)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AnnotationId(pub u64);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub id: AnnotationId,
+    pub rect: BoundingBox,
+    // linear rgba of the outline
+    pub color: [f32; 4],
+}
+
+// finished edits, all rectangles in frame pixels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AnnotationEvent {
+    Created { id: AnnotationId, rect: BoundingBox },
+    Moved { id: AnnotationId, from: BoundingBox, to: BoundingBox },
+    Resized { id: AnnotationId, from: BoundingBox, to: BoundingBox },
+    Deleted { id: AnnotationId, rect: BoundingBox },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Drag {
+    Create { id: AnnotationId, start: Pair<f32> },
+    Move { id: AnnotationId, start: Pair<f32>, original: BoundingBox },
+    Resize { id: AnnotationId, handle: Handle, original: BoundingBox },
+}
+
+// boxes drawn smaller than this on either side are dropped instead of created
+const MIN_SIZE: f32 = 1.0;
+
+// pointer driven editing of a set of boxes, all positions are in frame pixels
+// pressing on a handle of the selected box resizes it, inside a box selects and moves it
+// and anywhere else draws a new one, the boxes reflect a drag while it happens and the
+// event is returned once the pointer is released
+#[derive(Clone, Debug, Default)]
+pub struct BoxEditor {
+    annotations: Vec<Annotation>,
+    selected: Option<AnnotationId>,
+    drag: Option<Drag>,
+    next_id: u64,
+    bounds: Option<Pair<f32>>,
+    color: Option<[f32; 4]>,
+}
+
+impl BoxEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // existing boxes, e.g. labels loaded from disk
+    pub fn with_annotations(mut self, annotations: Vec<Annotation>) -> Self {
+        self.next_id = annotations.iter().map(|annotation| annotation.id.0 + 1).max().unwrap_or(0);
+        self.annotations = annotations;
+        self
+    }
+
+    // outline of new boxes, yellow unless set
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    pub fn selected(&self) -> Option<AnnotationId> {
+        self.selected
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    // keeps boxes inside a frame of this size, usually the size of the shown frame
+    pub fn set_bounds(&mut self, size: Option<Pair<u32>>) {
+        self.bounds = size.map(|size| (size.0 as f32, size.1 as f32));
+    }
+
+    pub fn select(&mut self, id: Option<AnnotationId>) {
+        self.selected = id.filter(|id| self.get(*id).is_some());
+    }
+
+    fn get(&self, id: AnnotationId) -> Option<&Annotation> {
+        self.annotations.iter().find(|annotation| annotation.id == id)
+    }
+
+    fn get_mut(&mut self, id: AnnotationId) -> Option<&mut Annotation> {
+        self.annotations.iter_mut().find(|annotation| annotation.id == id)
+    }
+
+    fn clamp_point(&self, point: Pair<f32>) -> Pair<f32> {
+        match self.bounds {
+            Some(size) => (point.0.clamp(0.0, size.0), point.1.clamp(0.0, size.1)),
+            None => point,
+        }
+    }
+
+    // `tolerance` is how far from a handle a press still grabs it, in frame pixels
+    pub fn pointer_down(&mut self, point: Pair<f32>, tolerance: f32) {
+        let point = self.clamp_point(point);
+
+        let handle = self.selected.and_then(|id| self.get(id)).and_then(|annotation| {
+            Handle::ALL
+                .into_iter()
+                .find(|handle| {
+                    let position = handle.position(&annotation.rect);
+                    (position.0 - point.0).abs() <= tolerance && (position.1 - point.1).abs() <= tolerance
+                })
+                .map(|handle| Drag::Resize { id: annotation.id, handle, original: annotation.rect })
+        });
+
+        // the last box is drawn on top so it is the one picked
+        let inside = || {
+            self.annotations
+                .iter()
+                .rev()
+                .find(|annotation| annotation.rect.contains(point))
+                .map(|annotation| Drag::Move { id: annotation.id, start: point, original: annotation.rect })
+        };
+
+        self.drag = Some(match handle.or_else(inside) {
+            Some(drag) => drag,
+            None => {
+                let id = AnnotationId(self.next_id);
+                self.next_id += 1;
+
+                self.annotations.push(Annotation {
+                    id,
+                    rect: BoundingBox::from_corners(point, point),
+                    color: self.color.unwrap_or([1.0, 0.85, 0.0, 1.0]),
+                });
+
+                Drag::Create { id, start: point }
+            },
+        });
+
+        self.selected = match self.drag {
+            Some(Drag::Create { id, .. } | Drag::Move { id, .. } | Drag::Resize { id, .. }) => Some(id),
+            None => None,
+        };
+    }
+
+    pub fn pointer_moved(&mut self, point: Pair<f32>) {
+        let point = self.clamp_point(point);
+        let bounds = self.bounds;
+
+        let (id, rect) = match self.drag {
+            Some(Drag::Create { id, start }) => (id, BoundingBox::from_corners(start, point)),
+            Some(Drag::Move { id, start, original }) => {
+                let moved = original.translated((point.0 - start.0, point.1 - start.1));
+                (id, bounds.map_or(moved, |size| moved.kept_inside(size)))
+            },
+            Some(Drag::Resize { id, handle, original }) => (id, handle.drag(&original, point)),
+            None => return,
+        };
+
+        if let Some(annotation) = self.get_mut(id) {
+            annotation.rect = rect;
+        }
+    }
+
+    pub fn pointer_up(&mut self, point: Pair<f32>) -> Option<AnnotationEvent> {
+        self.pointer_moved(point);
+
+        let event = match self.drag.take()? {
+            Drag::Create { id, .. } => {
+                let rect = self.get(id)?.rect;
+                let size = rect.size();
+
+                if size.0 < MIN_SIZE || size.1 < MIN_SIZE {
+                    self.annotations.retain(|annotation| annotation.id != id);
+                    self.selected = None;
+                    return None;
+                }
+
+                AnnotationEvent::Created { id, rect }
+            },
+            Drag::Move { id, original, .. } => {
+                let rect = self.get(id)?.rect;
+                match rect == original {
+                    true => return None,
+                    false => AnnotationEvent::Moved { id, from: original, to: rect },
+                }
+            },
+            Drag::Resize { id, original, .. } => {
+                let rect = self.get(id)?.rect;
+                match rect == original {
+                    true => return None,
+                    false => AnnotationEvent::Resized { id, from: original, to: rect },
+                }
+            },
+        };
+
+        Some(event)
+    }
+
+    // puts a box being dragged back where it was
+    pub fn cancel_drag(&mut self) {
+        match self.drag.take() {
+            Some(Drag::Create { id, .. }) => {
+                self.annotations.retain(|annotation| annotation.id != id);
+                self.selected = None;
+            },
+            Some(Drag::Move { id, original, .. } | Drag::Resize { id, original, .. }) => {
+                if let Some(annotation) = self.get_mut(id) {
+                    annotation.rect = original;
+                }
+            },
+            None => {},
+        }
+    }
+
+    pub fn delete_selected(&mut self) -> Option<AnnotationEvent> {
+        let id = self.selected.take()?;
+        self.cancel_drag();
+
+        let index = self.annotations.iter().position(|annotation| annotation.id == id)?;
+        let rect = self.annotations.remove(index).rect;
+        Some(AnnotationEvent::Deleted { id, rect })
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::adapter::AdapterSelection;
+use crate::error::RenderInitError;
+use crate::feedback::FeedbackHandle;
+use crate::gpu::GpuContext;
+use crate::render::{AlphaBehavior, AutoFilterThresholds, FilterPreset, HdrMode, PendingRenderContext, PresentPreference, UploadPath, WgpuFrameRenderContext, WgpuFrameRenderContextInit};
+use crate::types::{FrameRenderContext, Pair};
+
+#[derive(Debug)]
+pub enum BuildError {
+    EmptySize(Pair<u32>),
+    Init(RenderInitError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::EmptySize((width, height)) => write!(f, "surface size {width}x{height} must be non-zero"),
+            BuildError::Init(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<RenderInitError> for BuildError {
+    fn from(error: RenderInitError) -> Self {
+        BuildError::Init(error)
+    }
+}
+
+pub struct RenderContextBuilder {
+    init: WgpuFrameRenderContextInit,
+}
+
+impl RenderContextBuilder {
+    // the size defaults to 1x1 and should be set to the window's inner size
+    pub fn new<Surface>(surface: Surface) -> Self
+    where
+        Surface: Into<wgpu::SurfaceTarget<'static>>
+    {
+        Self {
+            init: WgpuFrameRenderContextInit {
+                surface_size: (1, 1),
+                clear_color: None,
+                surface_handle: surface.into(),
+                present_mode: PresentPreference::Vsync,
+                wide_gamut: false,
+                hdr: HdrMode::Off,
+                alpha: AlphaBehavior::default(),
+                upload_path: UploadPath::Queue,
+                filter_preset: FilterPreset::Auto,
+                auto_filter: AutoFilterThresholds::default(),
+                adapter: AdapterSelection::Default,
+                strict_validation: false,
+                label_prefix: None,
+                feedback: FeedbackHandle::default(),
+                gpu: None,
+            },
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.init.surface_size = (width, height);
+        self
+    }
+
+    pub fn clear_color(mut self, clear_color: wgpu::Color) -> Self {
+        self.init.clear_color = Some(clear_color);
+        self
+    }
+
+    // vsync unless set, unsupported modes fall back to the closest supported one
+    pub fn present_mode(mut self, present_mode: PresentPreference) -> Self {
+        self.init.present_mode = present_mode;
+        self
+    }
+
+    // prefers a float surface that can show colors outside srgb, e.g. display p3 photos
+    pub fn wide_gamut(mut self, wide_gamut: bool) -> Self {
+        self.init.wide_gamut = wide_gamut;
+        self
+    }
+
+    // see `HdrMode`, off unless set
+    pub fn hdr(mut self, hdr: HdrMode) -> Self {
+        self.init.hdr = hdr;
+        self
+    }
+
+    // see `AlphaBehavior`, straight unless set
+    pub fn alpha_behavior(mut self, alpha: AlphaBehavior) -> Self {
+        self.init.alpha = alpha;
+        self
+    }
+
+    // see `FilterPreset`, auto unless set
+    pub fn filter_preset(mut self, filter_preset: FilterPreset) -> Self {
+        self.init.filter_preset = filter_preset;
+        self
+    }
+
+    // where `FilterPreset::Auto` switches, pixelated from 100% unless set
+    pub fn auto_filter(mut self, thresholds: AutoFilterThresholds) -> Self {
+        self.init.auto_filter = thresholds;
+        self
+    }
+
+    // see `UploadPath`, the queue unless set
+    pub fn upload_path(mut self, upload_path: UploadPath) -> Self {
+        self.init.upload_path = upload_path;
+        self
+    }
+
+    pub fn adapter(mut self, adapter: AdapterSelection) -> Self {
+        self.init.adapter = adapter;
+        self
+    }
+
+    pub fn strict_validation(mut self, strict_validation: bool) -> Self {
+        self.init.strict_validation = strict_validation;
+        self
+    }
+
+    // see `WgpuFrameRenderContext::set_label_prefix`
+    pub fn label_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.init.label_prefix = Some(prefix.into());
+        self
+    }
+
+    // draws with an existing device instead of requesting one, `adapter` and
+    // `strict_validation` don't apply then
+    pub fn gpu(mut self, gpu: GpuContext) -> Self {
+        self.init.gpu = Some(gpu);
+        self
+    }
+
+    // share a handle the frame provider already holds
+    pub fn feedback(mut self, feedback: FeedbackHandle) -> Self {
+        self.init.feedback = feedback;
+        self
+    }
+
+    pub fn into_init(self) -> Result<WgpuFrameRenderContextInit, BuildError> {
+        match self.init.surface_size {
+            (0, _) | (_, 0) => Err(BuildError::EmptySize(self.init.surface_size)),
+            _ => Ok(self.init),
+        }
+    }
+
+    pub fn build(self) -> Result<WgpuFrameRenderContext, BuildError> {
+        Ok(WgpuFrameRenderContext::init(self.into_init()?)?)
+    }
+
+    // requests the adapter and device on a worker thread, see `WgpuFrameRenderContext::spawn_init`
+    pub fn spawn(self) -> Result<PendingRenderContext, BuildError> {
+        Ok(WgpuFrameRenderContext::spawn_init(self.into_init()?))
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+
+// rgb matrix/trc icc profiles (display p3, adobe rgb, prophoto and most camera and monitor
+// profiles), enough to bring tagged images into srgb, lut based profiles are rejected
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    // columns are the xyz (d50) of the red, green and blue primaries
+    pub primaries: [[f32; 3]; 3],
+    pub curves: [ToneCurve; 3],
+}
+
+// decodes a stored channel value in 0..1 to linear light
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneCurve {
+    // evenly spaced samples, linearly interpolated
+    Table(Vec<f32>),
+    // `(a * x + b)^g + e` from `d` up, `c * x + f` below, covers every icc parametric type
+    Parametric { g: f32, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccError {
+    pub reason: &'static str,
+}
+
+impl fmt::Display for IccError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported icc profile: {}", self.reason)
+    }
+}
+
+impl std::error::Error for IccError {}
+
+fn error(reason: &'static str) -> IccError {
+    IccError { reason }
+}
+
+impl ToneCurve {
+    pub fn gamma(gamma: f32) -> Self {
+        ToneCurve::Parametric { g: gamma, a: 1.0, b: 0.0, c: 0.0, d: 0.0, e: 0.0, f: 0.0 }
+    }
+
+    pub fn srgb() -> Self {
+        ToneCurve::Parametric { g: 2.4, a: 1.0 / 1.055, b: 0.055 / 1.055, c: 1.0 / 12.92, d: 0.04045, e: 0.0, f: 0.0 }
+    }
+
+    pub fn eval(&self, x: f32) -> f32 {
+        match self {
+            ToneCurve::Table(samples) if samples.len() < 2 => samples.first().copied().unwrap_or(x),
+            ToneCurve::Table(samples) => {
+                let position = x.clamp(0.0, 1.0) * (samples.len() - 1) as f32;
+                let index = (position as usize).min(samples.len() - 2);
+                let t = position - index as f32;
+                samples[index] * (1.0 - t) + samples[index + 1] * t
+            },
+            ToneCurve::Parametric { g, a, b, c, d, e, f } => match x >= *d {
+                true => (a * x + b).max(0.0).powf(*g) + e,
+                false => c * x + f,
+            },
+        }
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, IccError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+        .ok_or(error("truncated"))
+}
+
+fn s15_fixed16(bytes: &[u8], offset: usize) -> Result<f32, IccError> {
+    Ok(u32_at(bytes, offset)? as i32 as f32 / 65536.0)
+}
+
+impl IccProfile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, IccError> {
+        if bytes.get(36..40) != Some(b"acsp") {
+            return Err(error("not an icc profile"));
+        }
+
+        if bytes.get(16..20) != Some(b"RGB ") {
+            return Err(error("not an rgb profile"));
+        }
+
+        if bytes.get(20..24) != Some(b"XYZ ") {
+            return Err(error("lab connection space"));
+        }
+
+        let tag_count = u32_at(bytes, 128)? as usize;
+        let tag = |signature: &[u8; 4]| -> Result<&[u8], IccError> {
+            (0..tag_count.min(1024))
+                .map(|index| 132 + 12 * index)
+                .find(|entry| bytes.get(*entry..entry + 4) == Some(signature.as_slice()))
+                .ok_or(error("no matrix and tone curves"))
+                .and_then(|entry| {
+                    let (offset, len) = (u32_at(bytes, entry + 4)? as usize, u32_at(bytes, entry + 8)? as usize);
+                    bytes.get(offset..offset.saturating_add(len)).ok_or(error("tag outside the profile"))
+                })
+        };
+
+        let xyz = |signature: &[u8; 4]| -> Result<[f32; 3], IccError> {
+            let data = tag(signature)?;
+            if data.get(..4) != Some(b"XYZ ") {
+                return Err(error("primary is not an xyz value"));
+            }
+            Ok([s15_fixed16(data, 8)?, s15_fixed16(data, 12)?, s15_fixed16(data, 16)?])
+        };
+
+        let (red, green, blue) = (xyz(b"rXYZ")?, xyz(b"gXYZ")?, xyz(b"bXYZ")?);
+
+        Ok(Self {
+            primaries: [
+                [red[0], green[0], blue[0]],
+                [red[1], green[1], blue[1]],
+                [red[2], green[2], blue[2]],
+            ],
+            curves: [parse_curve(tag(b"rTRC")?)?, parse_curve(tag(b"gTRC")?)?, parse_curve(tag(b"bTRC")?)?],
+        })
+    }
+
+    pub fn srgb() -> Self {
+        Self { primaries: SRGB_TO_XYZ_D50, curves: [ToneCurve::srgb(), ToneCurve::srgb(), ToneCurve::srgb()] }
+    }
+}
+
+fn parse_curve(data: &[u8]) -> Result<ToneCurve, IccError> {
+    match data.get(..4) {
+        Some(b"curv") => {
+            let count = u32_at(data, 8)? as usize;
+            match count {
+                0 => Ok(ToneCurve::gamma(1.0)),
+                1 => {
+                    let gamma = data.get(12..14).ok_or(error("truncated"))?;
+                    Ok(ToneCurve::gamma(u16::from_be_bytes([gamma[0], gamma[1]]) as f32 / 256.0))
+                },
+                _ => {
+                    let samples = data.get(12..count.saturating_mul(2).saturating_add(12)).ok_or(error("truncated"))?;
+                    Ok(ToneCurve::Table(samples.chunks_exact(2).map(|sample| u16::from_be_bytes([sample[0], sample[1]]) as f32 / 65535.0).collect()))
+                },
+            }
+        },
+        Some(b"para") => {
+            let function = data.get(8..10).map(|kind| u16::from_be_bytes([kind[0], kind[1]])).ok_or(error("truncated"))?;
+            let count = match function {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(error("unknown parametric curve")),
+            };
+
+            let mut params = [0.0; 7];
+            for (index, param) in params.iter_mut().take(count).enumerate() {
+                *param = s15_fixed16(data, 12 + 4 * index)?;
+            }
+
+            let [g, a, b, c, d, e, f] = params;
+            Ok(match function {
+                0 => ToneCurve::gamma(g),
+                1 => ToneCurve::Parametric { g, a, b, c: 0.0, d: -b / a, e: 0.0, f: 0.0 },
+                2 => ToneCurve::Parametric { g, a, b, c: 0.0, d: -b / a, e: c, f: c },
+                3 => ToneCurve::Parametric { g, a, b, c, d, e: 0.0, f: 0.0 },
+                _ => ToneCurve::Parametric { g, a, b, c, d, e, f },
+            })
+        },
+        _ => Err(error("unknown tone curve type")),
+    }
+}
+
+// the srgb primaries adapted to d50 with bradford, as in the icc's own srgb profile
+const SRGB_TO_XYZ_D50: [[f32; 3]; 3] = [
+    [0.436_074_7, 0.385_064_9, 0.143_080_4],
+    [0.222_504_5, 0.716_878_6, 0.060_616_9],
+    [0.013_932_2, 0.097_104_5, 0.714_173_3],
+];
+
+const XYZ_D50_TO_SRGB: [[f32; 3]; 3] = [
+    [3.133_856, -1.616_867, -0.490_614_6],
+    [-0.978_768_4, 1.916_141_5, 0.033_454],
+    [0.071_945_3, -0.228_991_4, 1.405_242_7],
+];
+
+fn multiply(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut product = [[0.0; 3]; 3];
+    for (row, product_row) in product.iter_mut().enumerate() {
+        for (column, value) in product_row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[row][k] * b[k][column]).sum();
+        }
+    }
+    product
+}
+
+// tables for the tone curves keep large images from paying a powf per sample
+const CURVE_SAMPLES: usize = 4096;
+
+// stored values under a profile to linear light with srgb primaries, colors outside the
+// srgb gamut come out below 0 or above 1 (extended linear srgb, like scrgb surfaces take)
+#[derive(Debug, Clone)]
+pub struct ColorTransform {
+    matrix: [[f32; 3]; 3],
+    curves: [Vec<f32>; 3],
+}
+
+impl ColorTransform {
+    pub fn to_linear_srgb(profile: &IccProfile) -> Self {
+        let table = |curve: &ToneCurve| (0..CURVE_SAMPLES).map(|index| curve.eval(index as f32 / (CURVE_SAMPLES - 1) as f32)).collect();
+
+        Self {
+            matrix: multiply(&XYZ_D50_TO_SRGB, &profile.primaries),
+            curves: [table(&profile.curves[0]), table(&profile.curves[1]), table(&profile.curves[2])],
+        }
+    }
+
+    // close enough to srgb that converting wouldn't change an 8 bit value
+    pub fn is_srgb(&self) -> bool {
+        let srgb = ToneCurve::srgb();
+        let identity = self.matrix.iter().enumerate().all(|(row, values)| {
+            values.iter().enumerate().all(|(column, value)| (value - (row == column) as u8 as f32).abs() < 0.01)
+        });
+
+        identity && self.curves.iter().all(|curve| {
+            (0..=16).all(|step| {
+                let x = step as f32 / 16.0;
+                (curve[(x * (CURVE_SAMPLES - 1) as f32) as usize] - srgb.eval(x)).abs() < 0.5 / 255.0
+            })
+        })
+    }
+
+    pub fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let lookup = |curve: &Vec<f32>, x: f32| {
+            let position = x.clamp(0.0, 1.0) * (CURVE_SAMPLES - 1) as f32;
+            let index = (position as usize).min(CURVE_SAMPLES - 2);
+            let t = position - index as f32;
+            curve[index] * (1.0 - t) + curve[index + 1] * t
+        };
+
+        let linear = [lookup(&self.curves[0], rgb[0]), lookup(&self.curves[1], rgb[1]), lookup(&self.curves[2], rgb[2])];
+        self.matrix.map(|row| row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(value: f32) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+
+    fn xyz(value: [f32; 3]) -> Vec<u8> {
+        [&b"XYZ \0\0\0\0"[..], &fixed(value[0]), &fixed(value[1]), &fixed(value[2])].concat()
+    }
+
+    fn para_srgb() -> Vec<u8> {
+        let params = [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045].map(fixed).concat();
+        [&b"para\0\0\0\0\0\x03\0\0"[..], &params].concat()
+    }
+
+    // an rgb profile with the given tags after a zeroed header
+    fn profile(tags: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut bytes = vec![0; 128];
+        bytes[16..20].copy_from_slice(b"RGB ");
+        bytes[20..24].copy_from_slice(b"XYZ ");
+        bytes[36..40].copy_from_slice(b"acsp");
+        bytes.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+
+        let mut offset = bytes.len() + 12 * tags.len();
+        for (signature, data) in tags {
+            bytes.extend_from_slice(*signature);
+            bytes.extend_from_slice(&(offset as u32).to_be_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            offset += data.len();
+        }
+
+        tags.iter().for_each(|(_, data)| bytes.extend_from_slice(data));
+        bytes
+    }
+
+    fn tags(curve: Vec<u8>) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+        let column = |index: usize| SRGB_TO_XYZ_D50.map(|row| row[index]);
+        vec![
+            (b"rXYZ", xyz(column(0))),
+            (b"gXYZ", xyz(column(1))),
+            (b"bXYZ", xyz(column(2))),
+            (b"rTRC", curve.clone()),
+            (b"gTRC", curve.clone()),
+            (b"bTRC", curve),
+        ]
+    }
+
+    #[test]
+    fn recognizes_srgb() {
+        let profile = IccProfile::parse(&profile(&tags(para_srgb()))).unwrap();
+        assert!(ColorTransform::to_linear_srgb(&profile).is_srgb());
+        assert!(ColorTransform::to_linear_srgb(&IccProfile::srgb()).is_srgb());
+    }
+
+    #[test]
+    fn reads_gamma_and_table_curves() {
+        let gamma = IccProfile::parse(&profile(&tags(b"curv\0\0\0\0\0\0\0\x01\x02\x33".to_vec()))).unwrap();
+        assert!((gamma.curves[0].eval(0.5) - 0.5f32.powf(2.2)).abs() < 1e-3);
+        assert!(!ColorTransform::to_linear_srgb(&gamma).is_srgb());
+
+        let table = IccProfile::parse(&profile(&tags(b"curv\0\0\0\0\0\0\0\x03\0\0\x40\0\xff\xff".to_vec()))).unwrap();
+        assert!((table.curves[1].eval(0.25) - 0.125).abs() < 1e-3);
+        assert_eq!(table.curves[1].eval(2.0), 1.0);
+
+        let transform = ColorTransform::to_linear_srgb(&table);
+        let white = transform.apply([1.0; 3]);
+        assert!(white.iter().all(|value| (value - 1.0).abs() < 0.01));
+    }
+
+    #[test]
+    fn rejects_unsupported_profiles() {
+        let reason = |bytes: &[u8]| IccProfile::parse(bytes).unwrap_err().reason;
+        let valid = profile(&tags(para_srgb()));
+
+        assert_eq!(reason(&valid[..32]), "not an icc profile");
+        assert_eq!(reason(&valid[..64]), "truncated");
+
+        let mut lab = valid.clone();
+        lab[20..24].copy_from_slice(b"Lab ");
+        assert_eq!(reason(&lab), "lab connection space");
+
+        let mut cmyk = valid.clone();
+        cmyk[16..20].copy_from_slice(b"CMYK");
+        assert_eq!(reason(&cmyk), "not an rgb profile");
+
+        assert_eq!(reason(&profile(&tags(para_srgb())[..5])), "no matrix and tone curves");
+        assert_eq!(reason(&valid[..valid.len() - 1]), "tag outside the profile");
+        assert_eq!(reason(&profile(&tags(b"para\0\0\0\0\0\x09\0\0".to_vec()))), "unknown parametric curve");
+        assert_eq!(reason(&profile(&tags(b"curv\0\0\0\0\0\0\0\x09".to_vec()))), "truncated");
+    }
+}
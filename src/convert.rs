@@ -0,0 +1,272 @@
+// pixel layout helpers shared by providers, all functions panic when the slice lengths
+// don't describe whole pixels or rows, like `copy_from_slice` does
+
+// bgra <-> rgba, the same swap works both ways
+pub fn swap_red_blue(pixels: &mut [u8]) {
+    assert!(pixels.len().is_multiple_of(4), "pixel data is not made of 4 byte pixels");
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("ssse3") {
+        // SAFETY: ssse3 support was just checked
+        unsafe { swap_red_blue_ssse3(pixels) };
+        return;
+    }
+
+    swap_red_blue_scalar(pixels);
+}
+
+fn swap_red_blue_scalar(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+        let swapped = (value & 0xff00ff00) | ((value >> 16) & 0xff) | ((value & 0xff) << 16);
+        pixel.copy_from_slice(&swapped.to_le_bytes());
+    }
+}
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[target_feature(enable = "ssse3")]
+unsafe fn swap_red_blue_ssse3(pixels: &mut [u8]) {
+    use core::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_setr_epi8, _mm_shuffle_epi8, _mm_storeu_si128};
+
+    let mask = _mm_setr_epi8(2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15);
+    let mut chunks = pixels.chunks_exact_mut(16);
+
+    for chunk in &mut chunks {
+        let value = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        _mm_storeu_si128(chunk.as_mut_ptr() as *mut __m128i, _mm_shuffle_epi8(value, mask));
+    }
+
+    swap_red_blue_scalar(chunks.into_remainder());
+}
+
+pub fn rgb_to_rgba(source: &[u8], target: &mut [u8], alpha: u8) {
+    assert_eq!(source.len() / 3 * 4, target.len(), "target must hold one rgba pixel per rgb pixel");
+
+    for (rgb, rgba) in source.chunks_exact(3).zip(target.chunks_exact_mut(4)) {
+        rgba.copy_from_slice(&[rgb[0], rgb[1], rgb[2], alpha]);
+    }
+}
+
+pub fn bgr_to_rgba(source: &[u8], target: &mut [u8], alpha: u8) {
+    assert_eq!(source.len() / 3 * 4, target.len(), "target must hold one rgba pixel per bgr pixel");
+
+    for (bgr, rgba) in source.chunks_exact(3).zip(target.chunks_exact_mut(4)) {
+        rgba.copy_from_slice(&[bgr[2], bgr[1], bgr[0], alpha]);
+    }
+}
+
+pub fn gray_to_rgba(source: &[u8], target: &mut [u8], alpha: u8) {
+    assert_eq!(source.len() * 4, target.len(), "target must hold one rgba pixel per gray pixel");
+
+    for (gray, rgba) in source.iter().zip(target.chunks_exact_mut(4)) {
+        rgba.copy_from_slice(&[*gray, *gray, *gray, alpha]);
+    }
+}
+
+// turns bottom-up images (bmp, gl readbacks) into top-down ones and back
+pub fn flip_rows(pixels: &mut [u8], row_len: usize) {
+    assert!(row_len > 0 && pixels.len().is_multiple_of(row_len), "pixel data is not made of whole rows");
+
+    let rows = pixels.len() / row_len;
+    let (top, bottom) = pixels.split_at_mut(rows / 2 * row_len);
+    let bottom_start = bottom.len() - top.len();
+
+    for (upper, lower) in top.chunks_exact_mut(row_len).zip(bottom[bottom_start..].chunks_exact_mut(row_len).rev()) {
+        upper.swap_with_slice(lower);
+    }
+}
+
+// drops row padding, `stride` is the distance between row starts in `source`
+pub fn remove_stride(source: &[u8], row_len: usize, stride: usize, target: &mut [u8]) {
+    assert!(stride >= row_len, "stride is shorter than a row");
+    assert!(row_len > 0 && target.len().is_multiple_of(row_len), "target is not made of whole rows");
+
+    let rows = target.len() / row_len;
+    assert!(rows == 0 || source.len() >= (rows - 1) * stride + row_len, "source is too short for {rows} rows");
+
+    if stride == row_len {
+        target.copy_from_slice(&source[..target.len()]);
+        return;
+    }
+
+    for (row, target_row) in target.chunks_exact_mut(row_len).enumerate() {
+        let start = row * stride;
+        target_row.copy_from_slice(&source[start..start + row_len]);
+    }
+}
+
+// ieee 754 binary16 bits for rgba16float textures, rounded to nearest even, values out of
+// range saturate to infinity
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let half_exponent = exponent - 127 + 15;
+
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    if half_exponent <= 0 {
+        // subnormal, or zero when even that is too small
+        if half_exponent < -10 {
+            return sign;
+        }
+
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let half_mantissa = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round_up = remainder > halfway || (remainder == halfway && half_mantissa & 1 == 1);
+
+        return sign | (half_mantissa + round_up as u32) as u16;
+    }
+
+    // a carry out of the mantissa correctly bumps the exponent
+    let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    let round_up = remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1);
+
+    sign | (half + round_up as u32) as u16
+}
+
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        // subnormal, the leading one becomes the implicit bit of a normal f32
+        (0, mantissa) => {
+            let shift = mantissa.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | (((mantissa << shift) & 0x3ff) << 13)
+        },
+        (0x1f, mantissa) => sign | 0x7f80_0000 | (mantissa << 13),
+        (exponent, mantissa) => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+// bt.601 studio range, what video decoders hand out unless the stream says otherwise
+pub fn yuv_to_rgba(y: u8, u: u8, v: u8) -> [u8; 4] {
+    let c = 298 * (y as i32 - 16);
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+    let clamp = |value: i32| ((value + 128) >> 8).clamp(0, 255) as u8;
+
+    [clamp(c + 409 * e), clamp(c - 100 * d - 208 * e), clamp(c + 516 * d), u8::MAX]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_past_the_simd_chunks() {
+        // 16 byte chunks and a remainder of three pixels
+        let mut pixels: Vec<u8> = (0..28).collect();
+        let mut expected = pixels.clone();
+        swap_red_blue_scalar(&mut expected);
+        swap_red_blue(&mut pixels);
+
+        assert_eq!(pixels, expected);
+        assert_eq!(pixels[24..], [26, 25, 24, 27]);
+    }
+
+    #[test]
+    #[should_panic(expected = "4 byte pixels")]
+    fn rejects_partial_pixels() {
+        swap_red_blue(&mut [0; 6]);
+    }
+
+    #[test]
+    fn expands_to_rgba() {
+        let mut target = [0; 8];
+
+        rgb_to_rgba(&[1, 2, 3, 4, 5, 6], &mut target, 9);
+        assert_eq!(target, [1, 2, 3, 9, 4, 5, 6, 9]);
+
+        bgr_to_rgba(&[1, 2, 3, 4, 5, 6], &mut target, 9);
+        assert_eq!(target, [3, 2, 1, 9, 6, 5, 4, 9]);
+
+        gray_to_rgba(&[7, 8], &mut target, 255);
+        assert_eq!(target, [7, 7, 7, 255, 8, 8, 8, 255]);
+    }
+
+    #[test]
+    fn flips_and_unpads_rows() {
+        let mut pixels = [1, 1, 2, 2, 3, 3];
+        flip_rows(&mut pixels, 2);
+        assert_eq!(pixels, [3, 3, 2, 2, 1, 1]);
+
+        let mut even = [1, 2, 3, 4];
+        flip_rows(&mut even, 2);
+        assert_eq!(even, [3, 4, 1, 2]);
+
+        // the last row needs no padding after it
+        let mut target = [0; 6];
+        remove_stride(&[1, 2, 3, 0, 4, 5, 6], 3, 4, &mut target);
+        assert_eq!(target, [1, 2, 3, 4, 5, 6]);
+
+        remove_stride(&[6, 5, 4, 3, 2, 1, 0], 3, 3, &mut target);
+        assert_eq!(target, [6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "too short")]
+    fn rejects_short_strided_sources() {
+        remove_stride(&[0; 6], 3, 4, &mut [0; 6]);
+    }
+
+    #[test]
+    fn round_trips_every_half() {
+        for half in 0..=u16::MAX {
+            let value = f16_to_f32(half);
+
+            match value.is_nan() {
+                true => assert!(f16_to_f32(f32_to_f16(value)).is_nan()),
+                false => assert_eq!(f32_to_f16(value), half, "{half:#06x} is {value}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rounds_to_nearest_even() {
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(65519.0), 0x7bff);
+        assert_eq!(f32_to_f16(65520.0), 0x7c00);
+        assert_eq!(f32_to_f16(1e10), 0x7c00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xfc00);
+
+        // halfway between 1.0 and the next half goes to the even 1.0, past it rounds up
+        assert_eq!(f32_to_f16(1.0 + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+
+        // subnormals, down to half of the smallest one which ties to zero
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(2f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_f16(1.5 * 2f32.powi(-25)), 0x0001);
+        assert_eq!(f32_to_f16(-2f32.powi(-30)), 0x8000);
+        // the largest subnormal rounding up into the normals
+        assert_eq!(f32_to_f16(2f32.powi(-14) - 2f32.powi(-26)), 0x0400);
+    }
+
+    #[test]
+    fn converts_studio_range_yuv() {
+        assert_eq!(yuv_to_rgba(16, 128, 128), [0, 0, 0, 255]);
+        assert_eq!(yuv_to_rgba(235, 128, 128), [255, 255, 255, 255]);
+        assert_eq!(yuv_to_rgba(0, 0, 0), [0, 135, 0, 255]);
+        assert_eq!(yuv_to_rgba(255, 255, 255), [255, 125, 255, 255]);
+    }
+}
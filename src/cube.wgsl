@@ -0,0 +1,80 @@
+// shared by shader.wgsl and cubemap.wgsl, which are compiled with this file prepended
+
+// where each face sits in a cube image, `cells[face]` holds its column, row and whether
+// it is stored upside down
+struct CubeCells {
+    grid : vec2<u32>,
+    cells : array<vec4<u32>, 6>,
+}
+
+struct FaceCoords {
+    face : u32,
+    uv : vec2<f32>,
+}
+
+// the face a direction points at and where on it, in the +x, -x, +y, -y, +z, -z order
+fn cube_face(dir : vec3<f32>) -> FaceCoords {
+    let a = abs(dir);
+
+    if a.x >= a.y && a.x >= a.z {
+        if dir.x > 0.0 {
+            return FaceCoords(0u, vec2<f32>(-dir.z, -dir.y) / a.x * 0.5 + 0.5);
+        }
+        return FaceCoords(1u, vec2<f32>(dir.z, -dir.y) / a.x * 0.5 + 0.5);
+    }
+
+    if a.y >= a.z {
+        if dir.y > 0.0 {
+            return FaceCoords(2u, vec2<f32>(dir.x, dir.z) / a.y * 0.5 + 0.5);
+        }
+        return FaceCoords(3u, vec2<f32>(dir.x, -dir.z) / a.y * 0.5 + 0.5);
+    }
+
+    if dir.z > 0.0 {
+        return FaceCoords(4u, vec2<f32>(dir.x, -dir.y) / a.z * 0.5 + 0.5);
+    }
+    return FaceCoords(5u, vec2<f32>(-dir.x, -dir.y) / a.z * 0.5 + 0.5);
+}
+
+// texture coordinates of a direction in a cube image of `size` pixels, kept half a pixel
+// inside the face so filtering doesn't pick up its neighbours
+fn cube_uv(cube_cells : CubeCells, dir : vec3<f32>, size : vec2<u32>) -> vec2<f32> {
+    let coords = cube_face(dir);
+    // a copy, arrays passed by value can only be indexed by constants
+    var cells = cube_cells.cells;
+    let cell = cells[coords.face];
+    let face_size = vec2<f32>(size / cube_cells.grid);
+
+    var uv = clamp(coords.uv, 0.5 / face_size, 1.0 - 0.5 / face_size);
+    if cell.z != 0u {
+        uv = 1.0 - uv;
+    }
+
+    return (vec2<f32>(cell.xy) + uv) / vec2<f32>(cube_cells.grid);
+}
+
+// the direction through a point of an equirectangular panorama, its center looks at +z
+fn equirect_dir(uv : vec2<f32>) -> vec3<f32> {
+    let longitude = (uv.x - 0.5) * 6.2831853;
+    let latitude = (0.5 - uv.y) * 3.1415927;
+    return vec3<f32>(cos(latitude) * sin(longitude), sin(latitude), cos(latitude) * cos(longitude));
+}
+
+fn equirect_uv(dir : vec3<f32>) -> vec2<f32> {
+    let unit = normalize(dir);
+    return vec2<f32>(atan2(unit.x, unit.z) / 6.2831853 + 0.5, 0.5 - asin(clamp(unit.y, -1.0, 1.0)) / 3.1415927);
+}
+
+// inverse of `cube_face`
+fn face_dir(face : u32, uv : vec2<f32>) -> vec3<f32> {
+    let s = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -s.y, -s.x); }
+        case 1u: { return vec3<f32>(-1.0, -s.y, s.x); }
+        case 2u: { return vec3<f32>(s.x, 1.0, s.y); }
+        case 3u: { return vec3<f32>(s.x, -1.0, -s.y); }
+        case 4u: { return vec3<f32>(s.x, -s.y, 1.0); }
+        default: { return vec3<f32>(-s.x, -s.y, -1.0); }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::types::Pair;
+
+// the six faces in the usual +x, -x, +y, -y, +z, -z order, +z is the front face in the
+// middle of a cross and +y is up
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+}
+
+// how six square faces are arranged in one image
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CubeLayout {
+    // 4x3 cells, -x +z +x -z across the middle with +y above and -y below +z
+    HorizontalCross,
+    // 3x4 cells, like the horizontal cross with -z hanging upside down below -y
+    VerticalCross,
+    // 6x1 cells in face order
+    HorizontalStrip,
+    // 1x6 cells in face order
+    VerticalStrip,
+}
+
+impl CubeLayout {
+    pub const ALL: [CubeLayout; 4] = [
+        CubeLayout::HorizontalCross,
+        CubeLayout::VerticalCross,
+        CubeLayout::HorizontalStrip,
+        CubeLayout::VerticalStrip,
+    ];
+
+    // a cross whose cells without a face are one flat color, like the transparent or black
+    // corners cube maps are saved with, going by the aspect ratio alone would take every
+    // 4:3 photo for one, strips look like any wide image and have to be asked for
+    // explicitly, `texel` gives the texel at a point of the image
+    pub fn detect<T: PartialEq>(size: Pair<u32>, mut texel: impl FnMut(Pair<u32>) -> Option<T>) -> Option<Self> {
+        [CubeLayout::HorizontalCross, CubeLayout::VerticalCross].into_iter().find(|layout| {
+            let Some(face_size) = layout.face_size(size) else {
+                return false;
+            };
+
+            let mut samples = layout.empty_cells().flat_map(|(column, row)| {
+                let origin = (column * face_size, row * face_size);
+                EMPTY_CELL_SAMPLES.map(|(x, y)| (origin.0 + x * (face_size - 1) / 4, origin.1 + y * (face_size - 1) / 4))
+            });
+
+            let Some(first) = samples.next().and_then(&mut texel) else {
+                return false;
+            };
+
+            samples.all(|point| texel(point).is_some_and(|value| value == first))
+        })
+    }
+
+    // cells of the layout that hold no face
+    pub fn empty_cells(&self) -> impl Iterator<Item = Pair<u32>> + '_ {
+        let cells = self.cells();
+        (0..cells.1)
+            .flat_map(move |row| (0..cells.0).map(move |column| (column, row)))
+            .filter(|cell| CubeFace::ALL.iter().all(|face| self.cell(*face) != *cell))
+    }
+
+    // columns and rows of face sized cells
+    pub const fn cells(&self) -> Pair<u32> {
+        match self {
+            CubeLayout::HorizontalCross => (4, 3),
+            CubeLayout::VerticalCross => (3, 4),
+            CubeLayout::HorizontalStrip => (6, 1),
+            CubeLayout::VerticalStrip => (1, 6),
+        }
+    }
+
+    // `None` when an image of `size` doesn't have this layout
+    pub fn face_size(&self, size: Pair<u32>) -> Option<u32> {
+        let cells = self.cells();
+        let face = size.0 / cells.0;
+
+        match face > 0 && size == (face * cells.0, face * cells.1) {
+            true => Some(face),
+            false => None,
+        }
+    }
+
+    // `None` when the image would be wider or higher than a `u32`
+    pub fn size(&self, face_size: u32) -> Option<Pair<u32>> {
+        let cells = self.cells();
+        Some((face_size.checked_mul(cells.0)?, face_size.checked_mul(cells.1)?))
+    }
+
+    // the cell a face sits in
+    pub const fn cell(&self, face: CubeFace) -> Pair<u32> {
+        match self {
+            CubeLayout::HorizontalCross | CubeLayout::VerticalCross => match face {
+                CubeFace::PositiveY => (1, 0),
+                CubeFace::NegativeX => (0, 1),
+                CubeFace::PositiveZ => (1, 1),
+                CubeFace::PositiveX => (2, 1),
+                CubeFace::NegativeY => (1, 2),
+                CubeFace::NegativeZ => match self {
+                    CubeLayout::HorizontalCross => (3, 1),
+                    _ => (1, 3),
+                },
+            },
+            CubeLayout::HorizontalStrip => (face as u32, 0),
+            CubeLayout::VerticalStrip => (0, face as u32),
+        }
+    }
+
+    // the -z face of a vertical cross is stored rotated by 180 degrees
+    pub const fn is_flipped(&self, face: CubeFace) -> bool {
+        matches!((self, face), (CubeLayout::VerticalCross, CubeFace::NegativeZ))
+    }
+}
+
+// the corners, middle and edge centers of a cell in quarters of it
+const EMPTY_CELL_SAMPLES: [Pair<u32>; 9] = [(0, 0), (4, 0), (0, 4), (4, 4), (2, 2), (2, 0), (0, 2), (4, 2), (2, 4)];
+
+// looks around the inside of a cube image instead of showing it flat, angles in radians
+// with yaw turning right and pitch looking up
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkyboxView {
+    pub layout: CubeLayout,
+    pub yaw: f32,
+    pub pitch: f32,
+    // vertical field of view
+    pub fov: f32,
+}
+
+impl SkyboxView {
+    pub const fn new(layout: CubeLayout) -> Self {
+        Self { layout, yaw: 0.0, pitch: 0.0, fov: core::f32::consts::FRAC_PI_2 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // faces in a gradient, empty cells in `empty`
+    fn cross(layout: CubeLayout, face_size: u32, empty: u8) -> impl Fn(Pair<u32>) -> Option<u8> {
+        move |(x, y)| {
+            let cell = (x / face_size, y / face_size);
+            match layout.empty_cells().any(|empty_cell| empty_cell == cell) {
+                true => Some(empty),
+                false => Some((x + y) as u8),
+            }
+        }
+    }
+
+    #[test]
+    fn crosses_are_detected_by_their_empty_cells() {
+        let horizontal = cross(CubeLayout::HorizontalCross, 8, 0);
+        assert_eq!(CubeLayout::detect((32, 24), horizontal), Some(CubeLayout::HorizontalCross));
+
+        let vertical = cross(CubeLayout::VerticalCross, 8, 255);
+        assert_eq!(CubeLayout::detect((24, 32), vertical), Some(CubeLayout::VerticalCross));
+    }
+
+    #[test]
+    fn photos_and_strips_are_not_guessed() {
+        let photo = |(x, y): Pair<u32>| Some((x * 3 + y * 7) as u8);
+        assert_eq!(CubeLayout::detect((32, 24), photo), None);
+        assert_eq!(CubeLayout::detect((48, 8), |_| Some(0)), None);
+        assert_eq!(CubeLayout::detect((32, 25), |_| Some(0)), None);
+        assert_eq!(CubeLayout::detect((32, 24), |_| None::<u8>), None);
+    }
+
+    #[test]
+    fn empty_cells_hold_no_face() {
+        assert_eq!(CubeLayout::HorizontalCross.empty_cells().collect::<Vec<_>>(), [(0, 0), (2, 0), (3, 0), (0, 2), (2, 2), (3, 2)]);
+        assert_eq!(CubeLayout::VerticalStrip.empty_cells().count(), 0);
+    }
+
+    #[test]
+    fn sizes_do_not_overflow() {
+        assert_eq!(CubeLayout::HorizontalCross.size(256), Some((1024, 768)));
+        assert_eq!(CubeLayout::HorizontalStrip.size(u32::MAX / 2), None);
+    }
+}
@@ -0,0 +1,56 @@
+@group(0) @binding(0)
+var source : texture_2d<f32>;
+
+// repeats across the panorama seam, clamps at the poles
+@group(0) @binding(1)
+var source_sampler : sampler;
+
+@group(0) @binding(2)
+var output : texture_storage_2d<rgba8unorm, write>;
+
+@group(0) @binding(3)
+var<uniform> cube : CubeCells;
+
+@compute @workgroup_size(8, 8)
+fn to_equirect(@builtin(global_invocation_id) id : vec3<u32>) {
+    let size = textureDimensions(output);
+    if any(id.xy >= size) {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let source_uv = cube_uv(cube, equirect_dir(uv), textureDimensions(source));
+    textureStore(output, id.xy, textureSampleLevel(source, source_sampler, source_uv, 0.0));
+}
+
+// cells without a face stay transparent
+@compute @workgroup_size(8, 8)
+fn to_cube(@builtin(global_invocation_id) id : vec3<u32>) {
+    let size = textureDimensions(output);
+    if any(id.xy >= size) {
+        return;
+    }
+
+    let face_size = size / cube.grid;
+    let cell = id.xy / face_size;
+
+    var face = 6u;
+    for (var index = 0u; index < 6u; index++) {
+        if all(cube.cells[index].xy == cell) {
+            face = index;
+        }
+    }
+
+    if face == 6u {
+        textureStore(output, id.xy, vec4<f32>(0.0));
+        return;
+    }
+
+    var uv = (vec2<f32>(id.xy - cell * face_size) + 0.5) / vec2<f32>(face_size);
+    if cube.cells[face].z != 0u {
+        uv = 1.0 - uv;
+    }
+
+    let source_uv = equirect_uv(face_dir(face, uv));
+    textureStore(output, id.xy, textureSampleLevel(source, source_sampler, source_uv, 0.0));
+}
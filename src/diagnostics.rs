@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// everything here stays on the local machine, reports are only ever written to `directory`
+#[derive(Debug, Clone)]
+pub struct CrashReportConfig {
+    pub directory: PathBuf,
+    pub log_lines: usize,
+}
+
+impl Default for CrashReportConfig {
+    fn default() -> Self {
+        Self {
+            directory: std::env::temp_dir(),
+            log_lines: 200,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DiagnosticState {
+    config: Option<CrashReportConfig>,
+    adapter: Option<String>,
+    surface_config: Option<String>,
+    source: Option<String>,
+    log_lines: VecDeque<String>,
+}
+
+static STATE: Mutex<DiagnosticState> = Mutex::new(DiagnosticState {
+    config: None,
+    adapter: None,
+    surface_config: None,
+    source: None,
+    log_lines: VecDeque::new(),
+});
+
+fn with_state<T>(update: impl FnOnce(&mut DiagnosticState) -> T) -> T {
+    // a panic while holding the lock must not prevent the report from being written
+    let mut state = STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    update(&mut state)
+}
+
+// enables report writing and chains a panic hook in front of the existing one
+pub fn install(config: CrashReportConfig) {
+    with_state(|state| state.config = Some(config));
+
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(&format!("panic: {info}")) {
+            Ok(Some(path)) => eprintln!("egami: crash report written to {}", path.display()),
+            Ok(None) => (),
+            Err(error) => eprintln!("egami: failed to write crash report: {error}"),
+        }
+
+        previous_hook(info);
+    }));
+}
+
+pub fn set_source_description<S: Into<String>>(description: S) {
+    with_state(|state| state.source = Some(description.into()));
+}
+
+pub(crate) fn record_adapter(info: &wgpu::AdapterInfo) {
+    with_state(|state| state.adapter = Some(format!("{info:#?}")));
+}
+
+pub(crate) fn record_surface_config(config: &wgpu::SurfaceConfiguration) {
+    with_state(|state| state.surface_config = Some(format!("{config:#?}")));
+}
+
+// returns `Ok(None)` when no crash reporter has been installed
+pub fn write_report(reason: &str) -> io::Result<Option<PathBuf>> {
+    let (directory, contents) = match with_state(|state| {
+        state.config.as_ref().map(|config| (config.directory.clone(), render_report(state, reason)))
+    }) {
+        Some(report) => report,
+        None => return Ok(None),
+    };
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+
+    std::fs::create_dir_all(&directory)?;
+    let path = directory.join(format!("egami-crash-{timestamp}.txt"));
+    std::fs::write(&path, contents)?;
+
+    Ok(Some(path))
+}
+
+fn render_report(state: &DiagnosticState, reason: &str) -> String {
+    let unknown = String::from("unknown");
+    let mut report = String::new();
+
+    let _ = writeln!(report, "egami {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "reason: {reason}");
+    let _ = writeln!(report, "os: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+    let _ = writeln!(report, "source: {}", state.source.as_ref().unwrap_or(&unknown));
+    let _ = writeln!(report, "\n[adapter]\n{}", state.adapter.as_ref().unwrap_or(&unknown));
+    let _ = writeln!(report, "\n[surface]\n{}", state.surface_config.as_ref().unwrap_or(&unknown));
+    let _ = writeln!(report, "\n[log]");
+
+    for line in &state.log_lines {
+        let _ = writeln!(report, "{line}");
+    }
+
+    report
+}
+
+// forwards to `inner` while keeping the last lines around for the report
+pub struct DiagnosticLogger<Inner> {
+    inner: Inner,
+}
+
+impl<Inner> DiagnosticLogger<Inner>
+where
+    Inner: log::Log + 'static,
+{
+    pub fn new(inner: Inner) -> Self {
+        Self { inner }
+    }
+
+    pub fn init(self, max_level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl<Inner> log::Log for DiagnosticLogger<Inner>
+where
+    Inner: log::Log,
+{
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        with_state(|state| {
+            let capacity = state.config.as_ref().map_or(0, |config| config.log_lines);
+
+            if capacity > 0 {
+                while state.log_lines.len() >= capacity {
+                    state.log_lines.pop_front();
+                }
+
+                state.log_lines.push_back(format!("{} {}: {}", record.level(), record.target(), record.args()));
+            }
+        });
+
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
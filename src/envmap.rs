@@ -0,0 +1,199 @@
+use std::fmt;
+
+use wgpu::util::DeviceExt;
+
+use crate::cubemap::CubeLayout;
+use crate::readback::{self, ReadbackError};
+use crate::render::{CubeCellsUniform, WgpuFrameRenderContext};
+use crate::types::Pair;
+
+// converts environment maps between cube layouts and equirectangular panoramas on the gpu
+// of a render context, both sides are tightly packed rgba8 without color space conversion
+
+#[derive(Debug)]
+pub enum EnvmapError {
+    // the pixels don't add up to an rgba8 image of the given size
+    PixelCount { expected: usize, actual: usize },
+    // the image can't be split into square faces the way the layout wants
+    LayoutMismatch { size: Pair<u32>, layout: CubeLayout },
+    // empty or larger than the device can hold in one texture
+    InvalidSize(Pair<u32>),
+    Readback(ReadbackError),
+}
+
+impl fmt::Display for EnvmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvmapError::PixelCount { expected, actual } => write!(f, "expected {expected} bytes of rgba8 pixels, got {actual}"),
+            EnvmapError::LayoutMismatch { size, layout } => write!(f, "a {}x{} image is not a {layout:?}", size.0, size.1),
+            EnvmapError::InvalidSize(size) => write!(f, "cannot convert to or from a {}x{} image", size.0, size.1),
+            EnvmapError::Readback(error) => write!(f, "failed to read back the converted image: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for EnvmapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EnvmapError::Readback(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ReadbackError> for EnvmapError {
+    fn from(error: ReadbackError) -> Self {
+        EnvmapError::Readback(error)
+    }
+}
+
+// `output_size` is usually twice as wide as high, e.g. four faces by two
+pub fn cube_to_equirect(
+    context: &WgpuFrameRenderContext,
+    cube: &[u8],
+    size: Pair<u32>,
+    layout: CubeLayout,
+    output_size: Pair<u32>,
+) -> Result<Vec<u8>, EnvmapError> {
+    if layout.face_size(size).is_none() {
+        return Err(EnvmapError::LayoutMismatch { size, layout });
+    }
+
+    convert(context, cube, size, layout, output_size, "to_equirect")
+}
+
+// cells of the layout without a face come out transparent
+pub fn equirect_to_cube(
+    context: &WgpuFrameRenderContext,
+    equirect: &[u8],
+    size: Pair<u32>,
+    layout: CubeLayout,
+    face_size: u32,
+) -> Result<Vec<u8>, EnvmapError> {
+    let cells = layout.cells();
+    let output_size = layout
+        .size(face_size)
+        .ok_or(EnvmapError::InvalidSize((face_size.saturating_mul(cells.0), face_size.saturating_mul(cells.1))))?;
+    convert(context, equirect, size, layout, output_size, "to_cube")
+}
+
+fn convert(
+    context: &WgpuFrameRenderContext,
+    pixels: &[u8],
+    size: Pair<u32>,
+    layout: CubeLayout,
+    output_size: Pair<u32>,
+    entry_point: &str,
+) -> Result<Vec<u8>, EnvmapError> {
+    let (device, queue) = (context.device(), context.queue());
+    let max_size = device.limits().max_texture_dimension_2d;
+
+    for size in [size, output_size] {
+        if size.0 == 0 || size.1 == 0 || size.0 > max_size || size.1 > max_size {
+            return Err(EnvmapError::InvalidSize(size));
+        }
+    }
+
+    let expected = 4 * size.0 as usize * size.1 as usize;
+    if pixels.len() != expected {
+        return Err(EnvmapError::PixelCount { expected, actual: pixels.len() });
+    }
+
+    let source = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some("Envmap Source Texture"),
+            size: wgpu::Extent3d { width: size.0, height: size.1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        wgpu::util::TextureDataOrder::LayerMajor,
+        pixels,
+    );
+
+    let target = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Envmap Target Texture"),
+        size: wgpu::Extent3d { width: output_size.0, height: output_size.1, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Envmap Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let cells = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Envmap Cells Buffer"),
+        usage: wgpu::BufferUsages::UNIFORM,
+        contents: bytemuck::bytes_of(&CubeCellsUniform::of(layout)),
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Envmap Shader"),
+        source: wgpu::ShaderSource::Wgsl(concat!(include_str!("cube.wgsl"), include_str!("cubemap.wgsl")).into()),
+    });
+
+    // the layout is derived from the shader, both entry points use the same bindings
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Envmap Pipeline"),
+        layout: None,
+        module: &shader,
+        entry_point,
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Envmap Bind Group"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&source.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&target.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: cells.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Envmap Encoder"),
+    });
+
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Envmap Pass"),
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(output_size.0.div_ceil(8), output_size.1.div_ceil(8), 1);
+    }
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    Ok(readback::read_texture(device, queue, &target)?)
+}
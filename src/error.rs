@@ -0,0 +1,93 @@
+use std::fmt;
+
+use crate::adapter::{AdapterError, AdapterSelection};
+
+// everything that can go wrong before the first frame, so an embedding application can
+// report it instead of panicking
+#[derive(Debug)]
+pub enum RenderInitError {
+    CreateSurface(wgpu::CreateSurfaceError),
+    NoAdapter(AdapterSelection),
+    RequestDevice(wgpu::RequestDeviceError),
+    // a shared `GpuContext` whose adapter, named here, can't present to the surface
+    UnsupportedSurface(String),
+}
+
+impl fmt::Display for RenderInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderInitError::CreateSurface(error) => write!(f, "failed to create surface: {error}"),
+            RenderInitError::NoAdapter(selection) => write!(f, "no compatible adapter matches {selection:?}"),
+            RenderInitError::RequestDevice(error) => write!(f, "failed to request device: {error}"),
+            RenderInitError::UnsupportedSurface(adapter) => write!(f, "{adapter} can't present to the surface"),
+        }
+    }
+}
+
+impl std::error::Error for RenderInitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderInitError::CreateSurface(error) => Some(error),
+            RenderInitError::NoAdapter(_) | RenderInitError::UnsupportedSurface(_) => None,
+            RenderInitError::RequestDevice(error) => Some(error),
+        }
+    }
+}
+
+impl From<AdapterError> for RenderInitError {
+    fn from(error: AdapterError) -> Self {
+        match error {
+            AdapterError::NotFound(selection) => RenderInitError::NoAdapter(selection),
+            AdapterError::RequestDevice(error) => RenderInitError::RequestDevice(error),
+        }
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for RenderInitError {
+    fn from(error: wgpu::CreateSurfaceError) -> Self {
+        RenderInitError::CreateSurface(error)
+    }
+}
+
+// why a frame didn't reach the screen, lost and outdated surfaces are reconfigured and
+// retried once before they are reported
+#[derive(Debug)]
+pub enum DrawError {
+    // no surface texture in time, the next draw may succeed
+    Timeout,
+    OutOfMemory,
+    // still lost or outdated after reconfiguring the surface
+    SurfaceLost(wgpu::SurfaceError),
+    // a frame the provider got wrong, only in strict mode, otherwise it is skipped
+    InvalidFrame(String),
+}
+
+impl fmt::Display for DrawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawError::Timeout => write!(f, "timed out waiting for a surface texture"),
+            DrawError::OutOfMemory => write!(f, "out of memory acquiring a surface texture"),
+            DrawError::SurfaceLost(error) => write!(f, "surface could not be recovered: {error}"),
+            DrawError::InvalidFrame(problem) => write!(f, "strict validation: {problem}"),
+        }
+    }
+}
+
+impl std::error::Error for DrawError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DrawError::SurfaceLost(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::SurfaceError> for DrawError {
+    fn from(error: wgpu::SurfaceError) -> Self {
+        match error {
+            wgpu::SurfaceError::Timeout => DrawError::Timeout,
+            wgpu::SurfaceError::OutOfMemory => DrawError::OutOfMemory,
+            error => DrawError::SurfaceLost(error),
+        }
+    }
+}
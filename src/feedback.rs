@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::transform::RegionOfInterest;
+
+// shared between the render context and any number of providers, cloning is cheap
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackHandle {
+    inner: Arc<FeedbackState>,
+}
+
+#[derive(Debug, Default)]
+struct FeedbackState {
+    presented: AtomicU64,
+    dropped: AtomicU64,
+    queue_depth: AtomicU32,
+    presents: Mutex<PresentTimes>,
+    visible_region: Mutex<Option<RegionOfInterest>>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct PresentTimes {
+    last: Option<Instant>,
+    interval: Option<Duration>,
+}
+
+// a point in time view of what happened to the frames handed to the renderer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Feedback {
+    pub presented: u64,
+    pub dropped: u64,
+    // submissions the gpu has not finished yet
+    pub queue_depth: u32,
+    pub last_present: Option<Instant>,
+    pub present_interval: Option<Duration>,
+    // what the viewer currently shows, remote sources can forward it so the server only
+    // sends the tiles and quality that are actually visible
+    pub visible_region: Option<RegionOfInterest>,
+}
+
+impl Feedback {
+    // more work in flight than the surface keeps frames in flight, new frames only add latency
+    pub fn is_saturated(&self, max_frame_latency: u32) -> bool {
+        self.queue_depth > max_frame_latency
+    }
+}
+
+impl FeedbackHandle {
+    pub fn snapshot(&self) -> Feedback {
+        let presents = *lock(&self.inner.presents);
+
+        Feedback {
+            presented: self.inner.presented.load(Ordering::Relaxed),
+            dropped: self.inner.dropped.load(Ordering::Relaxed),
+            queue_depth: self.inner.queue_depth.load(Ordering::Relaxed),
+            last_present: presents.last,
+            present_interval: presents.interval,
+            visible_region: *lock(&self.inner.visible_region),
+        }
+    }
+
+    pub(crate) fn record_present(&self) {
+        let now = Instant::now();
+        let mut presents = lock(&self.inner.presents);
+
+        presents.interval = presents.last.map(|last| now - last);
+        presents.last = Some(now);
+        self.inner.presented.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_visible_region(&self, region: Option<RegionOfInterest>) {
+        *lock(&self.inner.visible_region) = region;
+    }
+
+    pub(crate) fn record_drop(&self) {
+        self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_submit(&self) {
+        self.inner.queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_done(&self) {
+        let _ = self.inner.queue_depth.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |depth| depth.checked_sub(1));
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,522 @@
+use crate::memory;
+use crate::overlay::OverlayRect;
+use crate::render::ResourceLabels;
+use crate::types::{self, FrameProvider, HasData, HasFormat, HasOrientation, HasSize, Pair};
+
+// which side of the surface the strip runs along
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FilmstripEdge {
+    Top,
+    #[default]
+    Bottom,
+    Left,
+    Right,
+}
+
+impl FilmstripEdge {
+    // thumbnails run left to right rather than top to bottom
+    fn is_horizontal(self) -> bool {
+        matches!(self, FilmstripEdge::Top | FilmstripEdge::Bottom)
+    }
+}
+
+// a row of downscaled frames along an edge of the surface, e.g. the images of a folder
+// with the one shown picked out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilmstripStyle {
+    pub edge: FilmstripEdge,
+    // the longest side of a thumbnail in surface pixels
+    pub thumbnail_size: u32,
+    // between the thumbnails and around the strip, in surface pixels
+    pub spacing: f32,
+}
+
+impl Default for FilmstripStyle {
+    fn default() -> Self {
+        Self { edge: FilmstripEdge::Bottom, thumbnail_size: 96, spacing: 8.0 }
+    }
+}
+
+// where a thumbnail is on the surface, e.g. to find the one that was clicked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilmstripCell {
+    pub index: usize,
+    // the square the thumbnail is centered in, in surface pixels
+    pub min: Pair<f32>,
+    pub max: Pair<f32>,
+}
+
+impl FilmstripCell {
+    pub fn contains(&self, point: Pair<f32>) -> bool {
+        point.0 >= self.min.0 && point.0 < self.max.0 && point.1 >= self.min.1 && point.1 < self.max.1
+    }
+}
+
+// samples per axis averaged into a thumbnail pixel
+const SAMPLES: u32 = 4;
+
+const OUTLINE_WIDTH: f32 = 2.0;
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+// where a provider hasn't had a frame yet
+const PLACEHOLDER: [f32; 4] = [1.0, 1.0, 1.0, 0.1];
+const SELECTED: [f32; 4] = [1.0, 0.6, 0.05, 1.0];
+
+// an upright srgb rgba8 copy of a frame no larger than a thumbnail
+#[derive(Debug, Clone)]
+pub(crate) struct Thumbnail {
+    pub size: Pair<u32>,
+    pub pixels: Vec<u8>,
+    content: Option<u64>,
+    // tells the gpu side which layers to upload again
+    generation: u64,
+}
+
+impl Thumbnail {
+    // frames are only shrunk, never enlarged
+    fn of<Frame>(frame: &Frame, max_side: u32, generation: u64) -> Self
+    where
+        Frame: HasSize<u32> + HasFormat + HasOrientation + HasData
+    {
+        let stored = frame.size();
+        let orientation = frame.orientation();
+        let shown = orientation.displayed_size(stored);
+        let scale = (max_side as f32 / shown.0.max(shown.1).max(1) as f32).min(1.0);
+        let size = (((shown.0 as f32 * scale).round() as u32).max(1), ((shown.1 as f32 * scale).round() as u32).max(1));
+
+        let mut pixels = Vec::with_capacity(size.0 as usize * size.1 as usize * 4);
+        let count = SAMPLES * SAMPLES;
+
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let mut sum = [0u32; 4];
+
+                for sample in 0..count {
+                    let point = (
+                        (x as f32 + ((sample % SAMPLES) as f32 + 0.5) / SAMPLES as f32) / size.0 as f32,
+                        (y as f32 + ((sample / SAMPLES) as f32 + 0.5) / SAMPLES as f32) / size.1 as f32,
+                    );
+                    let (u, v) = orientation.stored_point(point);
+                    let column = ((u * stored.0 as f32) as u32).min(stored.0 - 1);
+                    let row = ((v * stored.1 as f32) as u32).min(stored.1 - 1);
+
+                    let color = types::rgba8_at(frame, column, row).unwrap_or_default();
+                    for (total, channel) in sum.iter_mut().zip(color) {
+                        *total += channel as u32;
+                    }
+                }
+
+                pixels.extend(sum.map(|total| ((total + count / 2) / count) as u8));
+            }
+        }
+
+        Self { size, pixels, content: frame.content_id(), generation }
+    }
+}
+
+// what the strip shows, kept apart from its gpu side so it survives switching devices
+#[derive(Debug)]
+pub(crate) struct Filmstrip {
+    pub style: FilmstripStyle,
+    // one per provider, `None` until it had a frame
+    pub thumbnails: Vec<Option<Thumbnail>>,
+    pub selected: Option<usize>,
+    next_generation: u64,
+}
+
+impl Filmstrip {
+    pub fn new(style: FilmstripStyle) -> Self {
+        Self { style, thumbnails: Vec::new(), selected: None, next_generation: 0 }
+    }
+
+    // takes a frame from every provider that has one, frames with the content id of their
+    // thumbnail aren't shrunk again, returns whether any thumbnail changed
+    pub fn poll<Provider, Frame>(&mut self, providers: &mut [Provider]) -> bool
+    where
+        Provider: FrameProvider<Frame = Frame>,
+        Frame: HasSize<u32> + HasFormat + HasOrientation + HasData,
+    {
+        let mut changed = self.thumbnails.len() != providers.len();
+        self.thumbnails.resize_with(providers.len(), || None);
+
+        for (thumbnail, provider) in self.thumbnails.iter_mut().zip(providers) {
+            let Some(frame) = provider.poll_frame().into_frame() else {
+                continue;
+            };
+
+            let content = frame.content_id();
+            if content.is_some() && thumbnail.as_ref().is_some_and(|thumbnail| thumbnail.content == content) {
+                continue;
+            }
+
+            *thumbnail = Some(Thumbnail::of(&frame, self.style.thumbnail_size, self.next_generation));
+            self.next_generation += 1;
+            changed = true;
+        }
+
+        changed
+    }
+
+    // the thumbnails at least partly on the surface, the strip scrolls to keep the
+    // selected one in view when they don't all fit
+    pub fn cells(&self, surface_size: Pair<u32>) -> Vec<FilmstripCell> {
+        let side = self.style.thumbnail_size as f32;
+        let spacing = self.style.spacing.max(0.0);
+        let pitch = side + spacing;
+        let horizontal = self.style.edge.is_horizontal();
+
+        let (length, depth) = match horizontal {
+            true => (surface_size.0 as f32, surface_size.1 as f32),
+            false => (surface_size.1 as f32, surface_size.0 as f32),
+        };
+        let across = match self.style.edge {
+            FilmstripEdge::Top | FilmstripEdge::Left => spacing,
+            FilmstripEdge::Bottom | FilmstripEdge::Right => depth - spacing - side,
+        };
+
+        let total = spacing + self.thumbnails.len() as f32 * pitch;
+        let scroll = match self.selected {
+            Some(selected) if total > length => {
+                let center = spacing + selected as f32 * pitch + side / 2.0;
+                (center - length / 2.0).clamp(0.0, total - length).round()
+            },
+            _ => 0.0,
+        };
+
+        (0..self.thumbnails.len())
+            .map(|index| (index, spacing + index as f32 * pitch - scroll))
+            .filter(|&(_, along)| along + side > 0.0 && along < length)
+            .map(|(index, along)| match horizontal {
+                true => FilmstripCell { index, min: (along, across), max: (along + side, across + side) },
+                false => FilmstripCell { index, min: (across, along), max: (across + side, along + side) },
+            })
+            .collect()
+    }
+
+    // the band behind the thumbnails, placeholders and the outline of the selected one
+    pub fn rects(&self, surface_size: Pair<u32>) -> Vec<OverlayRect> {
+        let (width, height) = (surface_size.0 as f32, surface_size.1 as f32);
+        let depth = self.style.thumbnail_size as f32 + 2.0 * self.style.spacing.max(0.0);
+
+        let band = match self.style.edge {
+            FilmstripEdge::Top => OverlayRect { min: (0.0, 0.0), max: (width, depth), color: BACKGROUND },
+            FilmstripEdge::Bottom => OverlayRect { min: (0.0, height - depth), max: (width, height), color: BACKGROUND },
+            FilmstripEdge::Left => OverlayRect { min: (0.0, 0.0), max: (depth, height), color: BACKGROUND },
+            FilmstripEdge::Right => OverlayRect { min: (width - depth, 0.0), max: (width, height), color: BACKGROUND },
+        };
+
+        let mut rects = vec![band];
+
+        for cell in self.cells(surface_size) {
+            if self.thumbnails[cell.index].is_none() {
+                rects.push(OverlayRect { min: cell.min, max: cell.max, color: PLACEHOLDER });
+            }
+
+            if self.selected == Some(cell.index) {
+                let (min, max) = ((cell.min.0 - OUTLINE_WIDTH, cell.min.1 - OUTLINE_WIDTH), (cell.max.0 + OUTLINE_WIDTH, cell.max.1 + OUTLINE_WIDTH));
+                rects.extend([
+                    OverlayRect { min, max: (max.0, cell.min.1), color: SELECTED },
+                    OverlayRect { min: (min.0, cell.max.1), max, color: SELECTED },
+                    OverlayRect { min: (min.0, cell.min.1), max: (cell.min.0, cell.max.1), color: SELECTED },
+                    OverlayRect { min: (cell.max.0, cell.min.1), max: (max.0, cell.max.1), color: SELECTED },
+                ]);
+            }
+        }
+
+        rects
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ThumbnailVertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+    layer: u32,
+}
+
+impl ThumbnailVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Uint32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            attributes: &Self::ATTRIBS,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            array_stride: std::mem::size_of::<ThumbnailVertex>() as wgpu::BufferAddress,
+        }
+    }
+}
+
+// layout of `Strip` in filmstrip.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct StripUniform {
+    encode_output: u32,
+    _padding: [u32; 3],
+}
+
+// the thumbnails in a layer each of a texture array, drawn after the overlay
+#[derive(Debug)]
+struct ThumbnailTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    side: u32,
+    layers: u32,
+    // the generation of the thumbnail each layer holds
+    uploaded: Vec<Option<u64>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct FilmstripPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    texture: Option<ThumbnailTexture>,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    labels: ResourceLabels,
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, labels: &ResourceLabels, vertices: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&labels.get("Filmstrip Vertex Buffer")),
+        size: (vertices.max(6) * std::mem::size_of::<ThumbnailVertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl FilmstripPass {
+    pub fn new(device: &wgpu::Device, labels: &ResourceLabels, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&labels.get("Filmstrip Shader")),
+            source: wgpu::ShaderSource::Wgsl(include_str!("filmstrip.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&labels.get("Filmstrip Bind Group Layout")),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&labels.get("Filmstrip Pipeline Layout")),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&labels.get("Filmstrip Pipeline")),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_thumbnail",
+                buffers: &[ThumbnailVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_thumbnail",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&labels.get("Filmstrip Sampler")),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&labels.get("Filmstrip Uniform Buffer")),
+            size: std::mem::size_of::<StripUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            texture: None,
+            vertex_buffer: create_vertex_buffer(device, labels, 0),
+            vertex_count: 0,
+            labels: labels.clone(),
+        }
+    }
+
+    // at least two layers, gl can only view a texture as an array when it has more than one
+    fn create_texture(&self, device: &wgpu::Device, side: u32, layers: u32) -> ThumbnailTexture {
+        let layers = layers.max(2);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&self.labels.get("Filmstrip Texture")),
+            size: wgpu::Extent3d { width: side, height: side, depth_or_array_layers: layers },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&self.labels.get("Filmstrip Bind Group")),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&self.sampler) },
+                wgpu::BindGroupEntry { binding: 2, resource: self.uniform_buffer.as_entire_binding() },
+            ],
+        });
+
+        ThumbnailTexture { texture, bind_group, side, layers, uploaded: vec![None; layers as usize] }
+    }
+
+    // uploads the thumbnails that changed and places the ones in view, thumbnails past the
+    // device's layer limit are left out
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, filmstrip: &Filmstrip, surface_size: Pair<u32>, encode_output: bool) {
+        let side = filmstrip.style.thumbnail_size.max(1);
+        let max_layers = device.limits().max_texture_array_layers;
+        let layers = (filmstrip.thumbnails.len() as u32).min(max_layers);
+
+        if filmstrip.thumbnails.len() as u32 > max_layers {
+            log::warn!("showing {max_layers} of {} thumbnails, the device holds no more", filmstrip.thumbnails.len());
+        }
+
+        if self.texture.as_ref().is_none_or(|texture| texture.side != side || texture.layers < layers) {
+            self.texture = Some(self.create_texture(device, side, layers.next_power_of_two()));
+        }
+
+        let Some(texture) = self.texture.as_mut() else {
+            return;
+        };
+
+        for (layer, thumbnail) in filmstrip.thumbnails.iter().enumerate().take(layers as usize) {
+            let Some(thumbnail) = thumbnail else {
+                continue;
+            };
+
+            if texture.uploaded[layer] == Some(thumbnail.generation) {
+                continue;
+            }
+
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x: 0, y: 0, z: layer as u32 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &thumbnail.pixels,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * thumbnail.size.0),
+                    rows_per_image: Some(thumbnail.size.1),
+                },
+                wgpu::Extent3d { width: thumbnail.size.0.min(side), height: thumbnail.size.1.min(side), depth_or_array_layers: 1 },
+            );
+            texture.uploaded[layer] = Some(thumbnail.generation);
+        }
+
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+        let to_clip = |point: Pair<f32>| [2.0 * point.0 / width - 1.0, 1.0 - 2.0 * point.1 / height];
+
+        // whole surface pixels so every texel lands on one
+        let vertices: Vec<ThumbnailVertex> = filmstrip
+            .cells(surface_size)
+            .into_iter()
+            .filter(|cell| cell.index < layers as usize)
+            .filter_map(|cell| Some((cell, filmstrip.thumbnails[cell.index].as_ref()?)))
+            .flat_map(|(cell, thumbnail)| {
+                let size = (thumbnail.size.0 as f32, thumbnail.size.1 as f32);
+                let min = (
+                    (cell.min.0 + (side as f32 - size.0) / 2.0).round(),
+                    (cell.min.1 + (side as f32 - size.1) / 2.0).round(),
+                );
+                let max = (min.0 + size.0, min.1 + size.1);
+                let extent = (size.0 / side as f32, size.1 / side as f32);
+
+                let corners = [
+                    (to_clip(min), [0.0, 0.0]),
+                    (to_clip((max.0, min.1)), [extent.0, 0.0]),
+                    (to_clip((min.0, max.1)), [0.0, extent.1]),
+                    (to_clip(max), [extent.0, extent.1]),
+                ];
+
+                [0, 2, 1, 2, 3, 1].map(|corner| ThumbnailVertex {
+                    position: corners[corner].0,
+                    tex_coords: corners[corner].1,
+                    layer: cell.index as u32,
+                })
+            })
+            .collect();
+
+        if (self.vertex_buffer.size() as usize) < std::mem::size_of_val(vertices.as_slice()) {
+            self.vertex_buffer = create_vertex_buffer(device, &self.labels, vertices.len().next_power_of_two());
+        }
+
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&StripUniform { encode_output: encode_output as u32, ..Default::default() }));
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    pub fn allocated_bytes(&self) -> u64 {
+        let texture = self.texture.as_ref().map_or(0, |texture| memory::texture_bytes(&texture.texture));
+        texture + self.uniform_buffer.size() + self.vertex_buffer.size()
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let Some(texture) = self.texture.as_ref().filter(|_| self.vertex_count > 0) {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &texture.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+    }
+}
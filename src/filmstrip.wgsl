@@ -0,0 +1,51 @@
+struct ThumbnailInput {
+    @location(0) position : vec2<f32>,
+    @location(1) tex_coords : vec2<f32>,
+    @location(2) layer : u32,
+}
+
+struct ThumbnailOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) tex_coords : vec2<f32>,
+    @location(1) @interpolate(flat) layer : u32,
+}
+
+struct Strip {
+    // set when the target stores what it's given without srgb encoding it
+    encode_output : u32,
+}
+
+@group(0) @binding(0)
+var t_thumbnails : texture_2d_array<f32>;
+
+@group(0) @binding(1)
+var s_thumbnails : sampler;
+
+@group(0) @binding(2)
+var<uniform> strip : Strip;
+
+fn srgb_encode(linear : vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+@vertex
+fn vs_thumbnail(vertex : ThumbnailInput) -> ThumbnailOutput {
+    var out : ThumbnailOutput;
+    out.clip_position = vec4<f32>(vertex.position, 0.0, 1.0);
+    out.tex_coords = vertex.tex_coords;
+    out.layer = vertex.layer;
+    return out;
+}
+
+@fragment
+fn fs_thumbnail(in : ThumbnailOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(t_thumbnails, s_thumbnails, in.tex_coords, in.layer, 0.0);
+
+    if strip.encode_output != 0u {
+        return vec4<f32>(srgb_encode(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), color.a);
+    }
+
+    return color;
+}
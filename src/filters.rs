@@ -0,0 +1,184 @@
+use wgpu::util::DeviceExt;
+
+use crate::memory;
+use crate::render::ResourceLabels;
+
+// wider blurs cost a tap per pixel of radius, past this they are clamped
+const MAX_BLUR_RADIUS: f32 = 64.0;
+
+// image adjustments applied to the uploaded frames before they are drawn, in the order
+// they were pushed, see `WgpuFrameRenderContext::push_filter`
+// values are in the frame's own encoding, srgb frames are filtered on their srgb values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    // added to every channel, then scaled around the middle gray, 0 and 1 leave the frame as is
+    BrightnessContrast { brightness: f32, contrast: f32 },
+    // rec. 709 luma
+    Grayscale,
+    Invert,
+    // gaussian, `radius` in frame pixels
+    Blur { radius: f32 },
+    // unsharp mask over the four neighbours, 0 leaves the frame as is
+    Sharpen { amount: f32 },
+}
+
+// layout of `Params` in filters.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FilterUniform {
+    kind: u32,
+    srgb: u32,
+    radius: i32,
+    _padding: u32,
+    values: [f32; 4],
+}
+
+impl Filter {
+    // blurs are separable, a horizontal and a vertical pass
+    fn passes(self, srgb: bool) -> Vec<FilterUniform> {
+        let pass = |kind, radius, values| FilterUniform { kind, srgb: srgb as u32, radius, _padding: 0, values };
+
+        match self {
+            Filter::BrightnessContrast { brightness, contrast } => vec![pass(0, 0, [brightness, contrast, 0.0, 0.0])],
+            Filter::Grayscale => vec![pass(1, 0, [0.0; 4])],
+            Filter::Invert => vec![pass(2, 0, [0.0; 4])],
+            Filter::Blur { radius } if radius > 0.0 => {
+                let radius = radius.min(MAX_BLUR_RADIUS);
+                let values = [0.0, 0.0, radius / 2.0, 0.0];
+                vec![pass(3, radius.ceil() as i32, values), pass(4, radius.ceil() as i32, values)]
+            },
+            Filter::Blur { .. } => Vec::new(),
+            Filter::Sharpen { amount } => vec![pass(5, 0, [0.0, 0.0, 0.0, amount])],
+        }
+    }
+}
+
+// the compute passes of a list of filters over one frame texture, they ping-pong between
+// two float textures so the last one always lands in `output`
+#[derive(Debug)]
+pub(crate) struct FilterChain {
+    pipeline: wgpu::ComputePipeline,
+    output: wgpu::Texture,
+    // the second target, only there when a pass reads another pass
+    scratch: Option<wgpu::Texture>,
+    bind_groups: Vec<wgpu::BindGroup>,
+    size: wgpu::Extent3d,
+    labels: ResourceLabels,
+    // the output doesn't hold the filtered frame yet
+    pub stale: bool,
+}
+
+impl FilterChain {
+    // `None` when the filters add up to no passes at all
+    pub fn new(device: &wgpu::Device, labels: &ResourceLabels, source: &wgpu::Texture, filters: &[Filter]) -> Option<Self> {
+        let passes: Vec<_> = filters.iter().flat_map(|filter| filter.passes(source.format().is_srgb())).collect();
+
+        if passes.is_empty() {
+            return None;
+        }
+
+        let size = source.size();
+        let create_target = |label| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(&labels.get(label)),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba16Float,
+                usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+        };
+
+        let output = create_target("Filter Output Texture");
+        let scratch = (passes.len() > 1).then(|| create_target("Filter Scratch Texture"));
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&labels.get("Filter Shader")),
+            source: wgpu::ShaderSource::Wgsl(include_str!("filters.wgsl").into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(&labels.get("Filter Pipeline")),
+            layout: None,
+            module: &shader,
+            entry_point: "filter_pass",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+
+        // a single layer would default to a plain 2d view
+        let array_view = |texture: &wgpu::Texture| texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        // counted back from the last pass, which writes the output
+        let target = |pass: usize| match (passes.len() - 1 - pass) % 2 {
+            0 => &output,
+            _ => scratch.as_ref().unwrap_or(&output),
+        };
+
+        let bind_groups = passes
+            .iter()
+            .enumerate()
+            .map(|(index, pass)| {
+                let input = match index {
+                    0 => source,
+                    _ => target(index - 1),
+                };
+
+                let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&labels.get("Filter Params Buffer")),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                    contents: bytemuck::bytes_of(pass),
+                });
+
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(&labels.get("Filter Bind Group")),
+                    layout: &pipeline.get_bind_group_layout(0),
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&array_view(input)),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&array_view(target(index))),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: params.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        Some(Self { pipeline, output, scratch, bind_groups, size, labels: labels.clone(), stale: true })
+    }
+
+    // the filtered frames, in place of the uploaded texture
+    pub fn output(&self) -> &wgpu::Texture {
+        &self.output
+    }
+
+    pub fn allocated_bytes(&self) -> u64 {
+        memory::texture_bytes(&self.output) + self.scratch.as_ref().map_or(0, memory::texture_bytes)
+    }
+
+    // every pass in order, after the frames were uploaded and before they are drawn
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(&self.labels.get("Filter Pass")),
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+
+        for bind_group in &self.bind_groups {
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(self.size.width.div_ceil(8), self.size.height.div_ceil(8), self.size.depth_or_array_layers);
+        }
+    }
+}
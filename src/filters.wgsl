@@ -0,0 +1,104 @@
+// one filter pass over every layer of the frame texture, `Params.kind` picks the filter
+struct Params {
+    kind : u32,
+    // filter in srgb encoded values, what brightness and contrast mean for 8 bit images
+    srgb : u32,
+    // blur taps on either side
+    radius : i32,
+    _padding : u32,
+    // brightness, contrast, blur sigma, sharpen amount
+    values : vec4<f32>,
+}
+
+@group(0) @binding(0)
+var source : texture_2d_array<f32>;
+@group(0) @binding(1)
+var output : texture_storage_2d_array<rgba16float, write>;
+@group(0) @binding(2)
+var<uniform> params : Params;
+
+const BRIGHTNESS_CONTRAST : u32 = 0u;
+const GRAYSCALE : u32 = 1u;
+const INVERT : u32 = 2u;
+const BLUR_HORIZONTAL : u32 = 3u;
+const BLUR_VERTICAL : u32 = 4u;
+const SHARPEN : u32 = 5u;
+
+fn srgb_encode(linear : vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+fn srgb_decode(encoded : vec3<f32>) -> vec3<f32> {
+    let low = encoded / 12.92;
+    let high = pow((encoded + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, encoded <= vec3<f32>(0.04045));
+}
+
+// edges repeat the outermost texels
+fn load(coords : vec2<i32>, layer : i32) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(source));
+    let texel = textureLoad(source, clamp(coords, vec2<i32>(0), size - 1), layer, 0);
+
+    if params.srgb != 0u {
+        return vec4<f32>(srgb_encode(clamp(texel.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), texel.a);
+    }
+    return texel;
+}
+
+fn blur(coords : vec2<i32>, layer : i32, step : vec2<i32>) -> vec4<f32> {
+    let sigma = max(params.values.z, 0.001);
+    var sum = vec4<f32>(0.0);
+    var weights = 0.0;
+
+    for (var offset = -params.radius; offset <= params.radius; offset++) {
+        let weight = exp(-f32(offset * offset) / (2.0 * sigma * sigma));
+        sum += weight * load(coords + offset * step, layer);
+        weights += weight;
+    }
+
+    return sum / weights;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn filter_pass(@builtin(global_invocation_id) id : vec3<u32>) {
+    let size = textureDimensions(source);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let coords = vec2<i32>(id.xy);
+    let layer = i32(id.z);
+    var color = load(coords, layer);
+
+    switch params.kind {
+        case BRIGHTNESS_CONTRAST: {
+            color = vec4<f32>((color.rgb - 0.5) * params.values.y + 0.5 + params.values.x, color.a);
+        }
+        case GRAYSCALE: {
+            color = vec4<f32>(vec3<f32>(dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722))), color.a);
+        }
+        case INVERT: {
+            color = vec4<f32>(1.0 - color.rgb, color.a);
+        }
+        case BLUR_HORIZONTAL: {
+            color = blur(coords, layer, vec2<i32>(1, 0));
+        }
+        case BLUR_VERTICAL: {
+            color = blur(coords, layer, vec2<i32>(0, 1));
+        }
+        case SHARPEN: {
+            let neighbours = load(coords + vec2<i32>(1, 0), layer) + load(coords - vec2<i32>(1, 0), layer)
+                + load(coords + vec2<i32>(0, 1), layer) + load(coords - vec2<i32>(0, 1), layer);
+            color = vec4<f32>(color.rgb * (1.0 + 4.0 * params.values.w) - neighbours.rgb * params.values.w, color.a);
+        }
+        default: {}
+    }
+
+    if params.srgb != 0u {
+        color = vec4<f32>(srgb_decode(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), color.a);
+    }
+
+    textureStore(output, coords, layer, color);
+}
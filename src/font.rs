@@ -0,0 +1,101 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use ab_glyph::{Font as _, FontArc, GlyphId, InvalidFont, PxScale, ScaleFont};
+
+use crate::types::Pair;
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    Invalid(InvalidFont),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(error) => write!(f, "failed to read font: {error}"),
+            FontError::Invalid(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontError::Io(error) => Some(error),
+            FontError::Invalid(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for FontError {
+    fn from(error: io::Error) -> Self {
+        FontError::Io(error)
+    }
+}
+
+impl From<InvalidFont> for FontError {
+    fn from(error: InvalidFont) -> Self {
+        FontError::Invalid(error)
+    }
+}
+
+// a truetype or opentype font the overlay text is drawn in, see
+// `WgpuFrameRenderContext::set_font`, cheap to clone
+#[derive(Clone, Debug)]
+pub struct Font {
+    font: FontArc,
+}
+
+impl Font {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, FontError> {
+        Ok(Self { font: FontArc::try_from_vec(bytes)? })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FontError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    // characters the font doesn't have come out as its missing glyph, usually a box
+    pub(crate) fn glyph_id(&self, character: char) -> u16 {
+        self.font.glyph_id(character).0
+    }
+
+    // ascent, descent and line gap of text `size` pixels from ascender to descender,
+    // descent is negative
+    pub(crate) fn metrics(&self, size: f32) -> (f32, f32, f32) {
+        let scaled = self.font.as_scaled(PxScale::from(size));
+        (scaled.ascent(), scaled.descent(), scaled.line_gap())
+    }
+
+    pub(crate) fn h_advance(&self, glyph: u16, size: f32) -> f32 {
+        self.font.as_scaled(PxScale::from(size)).h_advance(GlyphId(glyph))
+    }
+
+    pub(crate) fn kern(&self, previous: u16, glyph: u16, size: f32) -> f32 {
+        self.font.as_scaled(PxScale::from(size)).kern(GlyphId(previous), GlyphId(glyph))
+    }
+
+    // the coverage of `glyph` with its pen position at the origin, the offset of its top left
+    // from there and its size, `None` for glyphs without an outline like the space
+    pub(crate) fn rasterize(&self, glyph: u16, size: f32) -> Option<(Pair<f32>, Pair<u32>, Vec<u8>)> {
+        let outlined = self.font.outline_glyph(GlyphId(glyph).with_scale(PxScale::from(size)))?;
+        let bounds = outlined.px_bounds();
+        let extent = (bounds.width() as u32, bounds.height() as u32);
+
+        if extent.0 == 0 || extent.1 == 0 {
+            return None;
+        }
+
+        let mut coverage = vec![0; extent.0 as usize * extent.1 as usize];
+        outlined.draw(|x, y, value| {
+            if let Some(texel) = coverage.get_mut(y as usize * extent.0 as usize + x as usize) {
+                *texel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        });
+
+        Some(((bounds.min.x, bounds.min.y), extent, coverage))
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use crate::memory;
+use crate::render::ResourceLabels;
+use crate::text::{Font, PlacedGlyph};
+use crate::types::Pair;
+
+// the atlas is a single coverage texture, glyphs are packed in rows and everything is
+// rasterized again once it is full
+const ATLAS_SIZE: u32 = 1024;
+
+// between glyphs in the atlas so filtering never picks up a neighbour
+const ATLAS_PADDING: u32 = 1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GlyphVertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+    color: [f32; 4],
+}
+
+impl GlyphVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            attributes: &Self::ATTRIBS,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            array_stride: std::mem::size_of::<GlyphVertex>() as wgpu::BufferAddress,
+        }
+    }
+}
+
+// where a rasterized glyph is in the atlas
+#[derive(Copy, Clone, Debug)]
+struct AtlasGlyph {
+    // of its top left from the pen position
+    offset: Pair<f32>,
+    origin: Pair<u32>,
+    size: Pair<u32>,
+}
+
+// rows of glyphs from the top, each as tall as its tallest glyph
+#[derive(Debug, Default)]
+struct Shelves {
+    cursor: Pair<u32>,
+    row_height: u32,
+}
+
+impl Shelves {
+    fn place(&mut self, size: Pair<u32>) -> Option<Pair<u32>> {
+        let padded = (size.0 + ATLAS_PADDING, size.1 + ATLAS_PADDING);
+
+        if self.cursor.0 + padded.0 > ATLAS_SIZE {
+            self.cursor = (0, self.cursor.1 + self.row_height);
+            self.row_height = 0;
+        }
+
+        if self.cursor.0 + padded.0 > ATLAS_SIZE || self.cursor.1 + padded.1 > ATLAS_SIZE {
+            return None;
+        }
+
+        let origin = self.cursor;
+        self.cursor.0 += padded.0;
+        self.row_height = self.row_height.max(padded.1);
+        Some(origin)
+    }
+}
+
+// the glyphs of a loaded font, drawn in a pass of their own over the frames and the overlay
+#[derive(Debug)]
+pub(crate) struct GlyphPass {
+    pipeline: wgpu::RenderPipeline,
+    atlas: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    shelves: Shelves,
+    // by glyph and the bits of its size, `None` for glyphs without an outline
+    cached: HashMap<(u16, u32), Option<AtlasGlyph>>,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    labels: ResourceLabels,
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, labels: &ResourceLabels, vertices: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&labels.get("Glyph Vertex Buffer")),
+        size: (vertices.max(6) * std::mem::size_of::<GlyphVertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl GlyphPass {
+    pub(crate) fn new(device: &wgpu::Device, labels: &ResourceLabels, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&labels.get("Glyph Shader")),
+            source: wgpu::ShaderSource::Wgsl(include_str!("glyphs.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&labels.get("Glyph Bind Group Layout")),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&labels.get("Glyph Pipeline Layout")),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&labels.get("Glyph Pipeline")),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_glyph",
+                buffers: &[GlyphVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_glyph",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let atlas = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&labels.get("Glyph Atlas")),
+            size: wgpu::Extent3d { width: ATLAS_SIZE, height: ATLAS_SIZE, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        // glyphs land on whole surface pixels, so they are sampled as they are
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&labels.get("Glyph Sampler")),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&labels.get("Glyph Bind Group")),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&sampler) },
+            ],
+        });
+
+        Self {
+            pipeline,
+            atlas,
+            bind_group,
+            shelves: Shelves::default(),
+            cached: HashMap::new(),
+            vertex_buffer: create_vertex_buffer(device, labels, 0),
+            vertex_count: 0,
+            labels: labels.clone(),
+        }
+    }
+
+    // the glyph in the atlas, rasterized and uploaded the first time it is drawn, `Err` when
+    // the atlas has no room left for it
+    fn atlas_glyph(&mut self, queue: &wgpu::Queue, font: &Font, glyph: u16, size: f32) -> Result<Option<AtlasGlyph>, ()> {
+        let key = (glyph, size.to_bits());
+
+        if let Some(cached) = self.cached.get(&key) {
+            return Ok(*cached);
+        }
+
+        let Some((offset, extent, coverage)) = font.rasterize(glyph, size) else {
+            self.cached.insert(key, None);
+            return Ok(None);
+        };
+
+        let origin = self.shelves.place(extent).ok_or(())?;
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.atlas,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: origin.0, y: origin.1, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &coverage,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(extent.0),
+                rows_per_image: Some(extent.1),
+            },
+            wgpu::Extent3d { width: extent.0, height: extent.1, depth_or_array_layers: 1 },
+        );
+
+        let placed = AtlasGlyph { offset, origin, size: extent };
+        self.cached.insert(key, Some(placed));
+        Ok(Some(placed))
+    }
+
+    // two triangles per glyph, `None` when the atlas runs out of room unless glyphs that
+    // don't fit are to be left out
+    fn vertices(&mut self, queue: &wgpu::Queue, font: &Font, glyphs: &[PlacedGlyph], surface_size: Pair<u32>, leave_out: bool) -> Option<Vec<GlyphVertex>> {
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+        let to_clip = |point: Pair<f32>| [2.0 * point.0 / width - 1.0, 1.0 - 2.0 * point.1 / height];
+        let to_atlas = |texel: Pair<u32>| [texel.0 as f32 / ATLAS_SIZE as f32, texel.1 as f32 / ATLAS_SIZE as f32];
+
+        let mut vertices = Vec::with_capacity(6 * glyphs.len());
+        let mut left_out = 0;
+
+        for placed in glyphs {
+            let atlas_glyph = match self.atlas_glyph(queue, font, placed.glyph, placed.size) {
+                Ok(Some(atlas_glyph)) => atlas_glyph,
+                Ok(None) => continue,
+                Err(()) if leave_out => {
+                    left_out += 1;
+                    continue;
+                },
+                Err(()) => return None,
+            };
+
+            // whole surface pixels so every texel lands on one
+            let min = ((placed.position.0 + atlas_glyph.offset.0).round(), (placed.position.1 + atlas_glyph.offset.1).round());
+            let max = (min.0 + atlas_glyph.size.0 as f32, min.1 + atlas_glyph.size.1 as f32);
+            let texel_min = atlas_glyph.origin;
+            let texel_max = (texel_min.0 + atlas_glyph.size.0, texel_min.1 + atlas_glyph.size.1);
+
+            let corners = [
+                (to_clip(min), to_atlas(texel_min)),
+                (to_clip((max.0, min.1)), to_atlas((texel_max.0, texel_min.1))),
+                (to_clip((min.0, max.1)), to_atlas((texel_min.0, texel_max.1))),
+                (to_clip(max), to_atlas(texel_max)),
+            ];
+
+            vertices.extend([0, 2, 1, 2, 3, 1].map(|corner| GlyphVertex { position: corners[corner].0, tex_coords: corners[corner].1, color: placed.color }));
+        }
+
+        if left_out > 0 {
+            log::warn!("leaving out {left_out} glyphs, they don't fit the glyph atlas");
+        }
+
+        Some(vertices)
+    }
+
+    // rasterizes the glyphs not in the atlas yet and places all of them, when they don't
+    // all fit the atlas starts over once and what still doesn't fit is left out
+    pub(crate) fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, font: &Font, glyphs: &[PlacedGlyph], surface_size: Pair<u32>) {
+        let vertices = match self.vertices(queue, font, glyphs, surface_size, false) {
+            Some(vertices) => vertices,
+            None => {
+                self.shelves = Shelves::default();
+                self.cached.clear();
+                self.vertices(queue, font, glyphs, surface_size, true).unwrap_or_default()
+            },
+        };
+
+        if (self.vertex_buffer.size() as usize) < std::mem::size_of_val(vertices.as_slice()) {
+            self.vertex_buffer = create_vertex_buffer(device, &self.labels, vertices.len().next_power_of_two());
+        }
+
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        memory::texture_bytes(&self.atlas) + self.vertex_buffer.size()
+    }
+
+    // a pass of its own over whatever `view` holds
+    pub(crate) fn encode(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&self.labels.get("Glyph Pass")),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelves_fill_rows_and_run_out() {
+        let mut shelves = Shelves::default();
+
+        assert_eq!(shelves.place((600, 10)), Some((0, 0)));
+        assert_eq!(shelves.place((300, 20)), Some((601, 0)));
+        // doesn't fit the rest of the row, the next starts below the tallest
+        assert_eq!(shelves.place((200, 5)), Some((0, 21)));
+        assert_eq!(shelves.place((ATLAS_SIZE, 1)), None);
+        assert_eq!(shelves.place((10, ATLAS_SIZE)), None);
+    }
+}
@@ -0,0 +1,33 @@
+struct GlyphInput {
+    @location(0) position : vec2<f32>,
+    @location(1) tex_coords : vec2<f32>,
+    @location(2) color : vec4<f32>,
+}
+
+struct GlyphOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) tex_coords : vec2<f32>,
+    @location(1) color : vec4<f32>,
+}
+
+// coverage of the glyphs in the red channel
+@group(0) @binding(0)
+var t_atlas : texture_2d<f32>;
+
+@group(0) @binding(1)
+var s_atlas : sampler;
+
+@vertex
+fn vs_glyph(vertex : GlyphInput) -> GlyphOutput {
+    var out : GlyphOutput;
+    out.clip_position = vec4<f32>(vertex.position, 0.0, 1.0);
+    out.tex_coords = vertex.tex_coords;
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fs_glyph(in : GlyphOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSampleLevel(t_atlas, s_atlas, in.tex_coords, 0.0).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::adapter::{self, AdapterError, AdapterSelection};
+use crate::{diagnostics, executor};
+
+// the instance, adapter, device and queue render contexts draw with, clones share them, so
+// several windows can be backed by one device instead of each requesting its own and
+// holding its own copies of everything in vram
+//
+//     let gpu = GpuContext::request(&AdapterSelection::Default, false)?;
+//     let first = RenderContextBuilder::new(first_window).gpu(gpu.clone()).build()?;
+//     let second = RenderContextBuilder::new(second_window).gpu(gpu).build()?;
+//
+// or take the one a context was built with from `WgpuFrameRenderContext::gpu`
+#[derive(Debug, Clone)]
+pub struct GpuContext {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    // also shared with contexts that switched to another adapter, their surfaces were
+    // created from it
+    instance: Arc<wgpu::Instance>,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    strict_validation: bool,
+}
+
+impl GpuContext {
+    // not tied to any surface, check `supports` before drawing a window with it on platforms
+    // where adapters can't present everywhere
+    pub fn request(selection: &AdapterSelection, strict_validation: bool) -> Result<Self, AdapterError> {
+        executor::block_on(Self::request_async(selection, strict_validation))
+    }
+
+    pub async fn request_async(selection: &AdapterSelection, strict_validation: bool) -> Result<Self, AdapterError> {
+        Self::with_instance(Arc::new(create_instance(strict_validation)), None, selection, strict_validation).await
+    }
+
+    pub(crate) async fn with_instance(
+        instance: Arc<wgpu::Instance>,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+        selection: &AdapterSelection,
+        strict_validation: bool,
+    ) -> Result<Self, AdapterError> {
+        let adapter = adapter::select_adapter(&instance, compatible_surface, selection)
+            .await
+            .ok_or_else(|| AdapterError::NotFound(selection.clone()))?;
+
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_limits: wgpu::Limits::default(),
+                // for `RenderStats::gpu_time`, where the adapter has it
+                required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+            },
+            None,
+        ).await.map_err(AdapterError::RequestDevice)?;
+
+        diagnostics::record_adapter(&adapter.get_info());
+
+        device.set_device_lost_callback(|reason, message| {
+            log::error!("device lost ({reason:?}): {message}");
+
+            if let Err(error) = diagnostics::write_report(&format!("device lost ({reason:?}): {message}")) {
+                log::error!("failed to write crash report: {error}");
+            }
+        });
+
+        if strict_validation {
+            device.on_uncaptured_error(Box::new(|error| {
+                let _ = diagnostics::write_report(&format!("validation error: {error}"));
+                panic!("strict validation: {error}");
+            }));
+        }
+
+        Ok(Self { shared: Arc::new(Shared { instance, adapter, device, queue, strict_validation }) })
+    }
+
+    pub fn instance(&self) -> &wgpu::Instance {
+        &self.shared.instance
+    }
+
+    pub(crate) fn shared_instance(&self) -> Arc<wgpu::Instance> {
+        Arc::clone(&self.shared.instance)
+    }
+
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.shared.adapter
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.shared.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.shared.queue
+    }
+
+    pub fn strict_validation(&self) -> bool {
+        self.shared.strict_validation
+    }
+
+    // whether the adapter can present to a surface created from `instance`
+    pub fn supports(&self, surface: &wgpu::Surface) -> bool {
+        self.shared.adapter.is_surface_supported(surface)
+    }
+
+    // the same device, not merely an equal one
+    pub fn ptr_eq(&self, other: &GpuContext) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+pub(crate) fn create_instance(strict_validation: bool) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        flags: match strict_validation {
+            true => wgpu::InstanceFlags::advanced_debugging(),
+            false => wgpu::InstanceFlags::from_build_config(),
+        },
+        ..Default::default()
+    })
+}
@@ -0,0 +1,104 @@
+use crate::adapter::AdapterSelection;
+use crate::error::{DrawError, RenderInitError};
+use crate::gpu::GpuContext;
+use crate::readback::ReadbackError;
+use crate::render::{AlphaBehavior, WgpuFrameRenderContext};
+use crate::stats::RenderStats;
+use crate::types::{FrameProvider, FrameRenderContext, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair};
+
+// renders into a texture instead of a window, for screenshot tests, thumbnails and
+// server side rendering
+pub struct HeadlessFrameRenderContextInit {
+    pub size: Pair<u32>,
+    // anything `read_back` can copy, rgba8 srgb matches what a window would show
+    pub format: wgpu::TextureFormat,
+    pub clear_color: Option<wgpu::Color>,
+    pub alpha: AlphaBehavior,
+    pub adapter: AdapterSelection,
+    pub strict_validation: bool,
+    // see `WgpuFrameRenderContext::set_label_prefix`
+    pub label_prefix: Option<String>,
+    // e.g. the one of a window's context, `adapter` and `strict_validation` don't apply then
+    pub gpu: Option<GpuContext>,
+}
+
+impl Default for HeadlessFrameRenderContextInit {
+    fn default() -> Self {
+        Self {
+            size: (1280, 720),
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            clear_color: None,
+            alpha: AlphaBehavior::default(),
+            adapter: AdapterSelection::Default,
+            strict_validation: false,
+            label_prefix: None,
+            gpu: None,
+        }
+    }
+}
+
+pub struct HeadlessFrameRenderContext {
+    context: WgpuFrameRenderContext,
+}
+
+impl HeadlessFrameRenderContext {
+    pub fn open(init: HeadlessFrameRenderContextInit) -> Result<Self, RenderInitError> {
+        let mut context = WgpuFrameRenderContext::offscreen(
+            init.size,
+            init.format,
+            init.clear_color,
+            init.alpha,
+            &init.adapter,
+            init.strict_validation,
+            init.gpu,
+        )?;
+
+        if init.label_prefix.is_some() {
+            context.set_label_prefix(init.label_prefix.as_deref());
+        }
+
+        Ok(Self { context })
+    }
+
+    // zoom, pan and the other view controls work the same as on a window
+    pub fn render_context(&mut self) -> &mut WgpuFrameRenderContext {
+        &mut self.context
+    }
+
+    // the last drawn frame as tightly packed rows in the target format
+    pub fn read_back(&self) -> Result<Vec<u8>, ReadbackError> {
+        self.context.read_target().expect("headless contexts always render to a texture")
+    }
+}
+
+impl HasSize<u32> for HeadlessFrameRenderContext {
+    fn size(&self) -> Pair<u32> {
+        self.context.size()
+    }
+}
+
+impl TryFrom<HeadlessFrameRenderContextInit> for HeadlessFrameRenderContext {
+    type Error = RenderInitError;
+
+    fn try_from(init: HeadlessFrameRenderContextInit) -> Result<Self, Self::Error> {
+        Self::open(init)
+    }
+}
+
+impl FrameRenderContext for HeadlessFrameRenderContext {
+    type RenderError = DrawError;
+    type Init = HeadlessFrameRenderContextInit;
+    type Stats = RenderStats;
+
+    // reallocates the target texture
+    fn configure(&mut self, size: Pair<u32>) {
+        self.context.configure(size);
+    }
+
+    fn draw_frame<Frame>(&mut self, frame_provider: impl FrameProvider<Frame = Frame>) -> Result<Self::Stats, Self::RenderError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        self.context.draw_frame(frame_provider)
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+
+use crate::types::Pair;
+
+// a float per pixel over a frame as it was decoded, e.g. attention or saliency, at any
+// resolution, it is stretched over the frame and interpolated
+// nan marks pixels without a value, they are left untinted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    size: Pair<u32>,
+    values: Vec<f32>,
+    // of the finite values, `None` when there are none
+    range: Option<Pair<f32>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapError {
+    pub reason: &'static str,
+}
+
+impl fmt::Display for HeatmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid heatmap: {}", self.reason)
+    }
+}
+
+impl std::error::Error for HeatmapError {}
+
+impl Heatmap {
+    // rows top to bottom
+    pub fn new(size: Pair<u32>, values: Vec<f32>) -> Result<Self, HeatmapError> {
+        if size.0 == 0 || size.1 == 0 {
+            return Err(HeatmapError { reason: "empty size" });
+        }
+
+        if values.len() != size.0 as usize * size.1 as usize {
+            return Err(HeatmapError { reason: "values don't match the size" });
+        }
+
+        let range = values.iter().filter(|value| value.is_finite()).fold(None, |range: Option<Pair<f32>>, &value| {
+            Some(range.map_or((value, value), |(min, max)| (min.min(value), max.max(value))))
+        });
+
+        Ok(Self { size, values, range })
+    }
+
+    pub fn size(&self) -> Pair<u32> {
+        self.size
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    // smallest and largest finite value
+    pub fn range(&self) -> Option<Pair<f32>> {
+        self.range
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Colormap {
+    #[default]
+    Turbo,
+    Viridis,
+    Inferno,
+    Gray,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [Colormap::Turbo, Colormap::Viridis, Colormap::Inferno, Colormap::Gray];
+
+    // the srgb color at `t` in 0..1, from polynomial fits of google's turbo and matplotlib's maps
+    pub fn rgb(self, t: f32) -> [u8; 3] {
+        let t = t.clamp(0.0, 1.0) as f64;
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        let coefficients = match self {
+            Colormap::Turbo => return turbo(t).map(channel),
+            Colormap::Gray => return [channel(t); 3],
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Inferno => &INFERNO,
+        };
+
+        [0, 1, 2].map(|index| channel(coefficients.iter().rev().fold(0.0, |sum, coefficient| sum * t + coefficient[index])))
+    }
+}
+
+fn turbo(t: f64) -> [f64; 3] {
+    let (t2, t3) = (t * t, t * t * t);
+    let (t4, t5) = (t3 * t, t3 * t2);
+
+    [
+        0.13572138 + 4.61539260 * t - 42.66032258 * t2 + 132.13108234 * t3 - 152.94239396 * t4 + 59.28637943 * t5,
+        0.09140261 + 2.19418839 * t + 4.84296658 * t2 - 14.18503333 * t3 + 4.27729857 * t4 + 2.82956604 * t5,
+        0.10667330 + 12.64194608 * t - 60.58204836 * t2 + 110.36276771 * t3 - 89.90310912 * t4 + 27.34824973 * t5,
+    ]
+}
+
+// lowest power first
+const VIRIDIS: [[f64; 3]; 7] = [
+    [0.2777273272234177, 0.005407344544966578, 0.3340998053353061],
+    [0.1050930431085774, 1.404613529898575, 1.384590162594685],
+    [-0.3308618287255563, 0.214847559468213, 0.09509516302823659],
+    [-4.634230498983486, -5.799100973351585, -19.33244095627987],
+    [6.228269936347081, 14.17993336680509, 56.69055260068105],
+    [4.776384997670288, -13.74514537774601, -65.35303263337234],
+    [-5.435455855934631, 4.645852612178535, 26.3124352495832],
+];
+
+const INFERNO: [[f64; 3]; 7] = [
+    [0.0002189403691192265, 0.001651004631001012, -0.01948089843709184],
+    [0.1065134194856116, 0.5639564367884091, 3.932712388889277],
+    [11.60249308247187, -3.972853965665698, -15.9423941062914],
+    [-41.70399613139459, 17.43639888205313, 44.35414519872813],
+    [77.162935699427, -33.40235894210092, -81.80730925738993],
+    [-71.31942824499214, 32.62606426397723, 73.20951985803202],
+    [25.13112622477341, -12.24266895238567, -23.07032500287172],
+];
+
+// how a heatmap is colored over the frame, independent of the frame's own shading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatmapStyle {
+    pub colormap: Colormap,
+    // 0 hides the heatmap, 1 covers the frame
+    pub opacity: f32,
+    // values mapped to the ends of the colormap, `None` uses the heatmap's own range
+    pub range: Option<Pair<f32>>,
+}
+
+impl Default for HeatmapStyle {
+    fn default() -> Self {
+        Self { colormap: Colormap::default(), opacity: 0.5, range: None }
+    }
+}
+
+impl HeatmapStyle {
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+}
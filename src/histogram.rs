@@ -0,0 +1,190 @@
+use wgpu::util::DeviceExt;
+
+use crate::readback::{self, ReadbackError};
+use crate::render::ResourceLabels;
+use crate::shading::Channel;
+use crate::types::Pair;
+
+// values a channel is counted in, one per 8 bit step
+pub const BINS: usize = 256;
+
+// the 16x16 workgroups of `histogram.wgsl`
+const WORKGROUP_SIZE: u32 = 16;
+
+// how many pixels have each value, per channel, see
+// `WgpuFrameRenderContext::read_histogram`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    counts: [[u32; BINS]; 4],
+}
+
+impl Histogram {
+    pub fn from_counts(counts: [[u32; BINS]; 4]) -> Self {
+        Self { counts }
+    }
+
+    pub fn channel(&self, channel: Channel) -> &[u32; BINS] {
+        &self.counts[channel.index()]
+    }
+
+    // the pixels counted, the same for every channel
+    pub fn total(&self) -> u64 {
+        self.counts[0].iter().map(|&count| count as u64).sum()
+    }
+
+    pub fn mean(&self, channel: Channel) -> Option<f32> {
+        let total = self.total();
+        let sum: u64 = self.channel(channel).iter().enumerate().map(|(value, &count)| value as u64 * count as u64).sum();
+
+        (total > 0).then(|| sum as f32 / total as f32)
+    }
+
+    // the lowest value at least `fraction` of the pixels don't exceed, e.g. 0.01 and 0.99
+    // for the range an auto contrast stretches, `None` without pixels
+    pub fn percentile(&self, channel: Channel, fraction: f32) -> Option<u8> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+
+        let target = (fraction.clamp(0.0, 1.0) as f64 * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+
+        for (value, &count) in self.channel(channel).iter().enumerate() {
+            seen += count as u64;
+            if seen >= target {
+                return Some(value as u8);
+            }
+        }
+
+        Some(u8::MAX)
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct RegionUniform {
+    origin: [u32; 2],
+    size: [u32; 2],
+    layer: u32,
+    srgb: u32,
+    _padding: [u32; 2],
+}
+
+// a region of a layer to count, origin and size in texels
+pub(crate) struct Region {
+    pub(crate) layer: u32,
+    pub(crate) origin: Pair<u32>,
+    pub(crate) size: Pair<u32>,
+}
+
+// built on the first histogram a context reads
+#[derive(Debug)]
+pub(crate) struct HistogramPass {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl HistogramPass {
+    pub(crate) fn new(device: &wgpu::Device, labels: &ResourceLabels) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&labels.get("Histogram Shader")),
+            source: wgpu::ShaderSource::Wgsl(include_str!("histogram.wgsl").into()),
+        });
+
+        // the layout is derived from the shader
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(&labels.get("Histogram Pipeline")),
+            layout: None,
+            module: &shader,
+            entry_point: "main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+
+        Self { pipeline }
+    }
+
+    // counts the texels of every region into one histogram, the texture has to be some
+    // rgba format and is counted as stored, srgb textures srgb encoded
+    pub(crate) fn run(&self, device: &wgpu::Device, queue: &wgpu::Queue, labels: &ResourceLabels, texture: &wgpu::Texture, regions: &[Region]) -> Result<Histogram, ReadbackError> {
+        let counts = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&labels.get("Histogram Buffer")),
+            size: (4 * BINS * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let regions: Vec<_> = regions.iter().filter(|region| region.size.0 > 0 && region.size.1 > 0).collect();
+        let bind_groups: Vec<_> = regions
+            .iter()
+            .map(|region| {
+                let uniform = RegionUniform {
+                    origin: [region.origin.0, region.origin.1],
+                    size: [region.size.0, region.size.1],
+                    layer: region.layer,
+                    srgb: texture.format().is_srgb() as u32,
+                    ..Default::default()
+                };
+
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&labels.get("Histogram Region Buffer")),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                    contents: bytemuck::bytes_of(&uniform),
+                });
+
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(&labels.get("Histogram Bind Group")),
+                    layout: &self.pipeline.get_bind_group_layout(0),
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: counts.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(&labels.get("Histogram Encoder")),
+        });
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(&labels.get("Histogram Pass")),
+                timestamp_writes: None,
+            });
+
+            pass.set_pipeline(&self.pipeline);
+
+            for (region, bind_group) in regions.iter().zip(&bind_groups) {
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(region.size.0.div_ceil(WORKGROUP_SIZE), region.size.1.div_ceil(WORKGROUP_SIZE), 1);
+            }
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let bytes = readback::read_buffer(device, queue, &counts)?;
+        let values: &[u32] = bytemuck::cast_slice(&bytes);
+        let mut histogram = [[0; BINS]; 4];
+
+        for (channel, values) in histogram.iter_mut().zip(values.chunks_exact(BINS)) {
+            channel.copy_from_slice(values);
+        }
+
+        Ok(Histogram::from_counts(histogram))
+    }
+}
@@ -0,0 +1,56 @@
+// counts the stored values of every channel in a region of a layer, 256 bins a channel
+
+struct Region {
+    origin : vec2<u32>,
+    size : vec2<u32>,
+    layer : u32,
+    // set for textures whose loads are decoded from srgb
+    srgb : u32,
+}
+
+@group(0) @binding(0)
+var t_source: texture_2d_array<f32>;
+
+@group(0) @binding(1)
+var<uniform> region: Region;
+
+// red, green, blue and alpha bins after each other
+@group(0) @binding(2)
+var<storage, read_write> counts: array<atomic<u32>, 1024>;
+
+// counted per workgroup first, so few adds contend across the dispatch
+var<workgroup> local_counts: array<atomic<u32>, 1024>;
+
+fn srgb_encode(linear : vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+@compute @workgroup_size(16, 16)
+fn main(@builtin(global_invocation_id) id : vec3<u32>, @builtin(local_invocation_index) index : u32) {
+    // every invocation has to reach the barrier
+    if all(id.xy < region.size) {
+        var texel = clamp(textureLoad(t_source, region.origin + id.xy, region.layer, 0), vec4<f32>(0.0), vec4<f32>(1.0));
+        if region.srgb != 0u {
+            texel = vec4<f32>(srgb_encode(texel.rgb), texel.a);
+        }
+
+        let bins = vec4<u32>(round(texel * 255.0));
+        atomicAdd(&local_counts[bins.r], 1u);
+        atomicAdd(&local_counts[256u + bins.g], 1u);
+        atomicAdd(&local_counts[512u + bins.b], 1u);
+        atomicAdd(&local_counts[768u + bins.a], 1u);
+    }
+
+    workgroupBarrier();
+
+    // a bin of each channel per invocation
+    for (var channel = 0u; channel < 4u; channel++) {
+        let bin = channel * 256u + index;
+        let count = atomicLoad(&local_counts[bin]);
+        if count != 0u {
+            atomicAdd(&counts[bin], count);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::types::Pair;
+
+// the way the view moves, so the frame moves the other way, e.g. `Left` shows more of
+// what is left of the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// what the viewer does for a key, whichever key it is bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ZoomIn,
+    ZoomOut,
+    Pan(Direction),
+    ResetView,
+    ToggleFullscreen,
+    // see `FrameProvider::next_image`, providers with a single image get the key instead
+    NextImage,
+    PreviousImage,
+    NextAdapter,
+    NextScalingMode,
+    NextFilterPreset,
+    ToggleTiling,
+    NextPresentMode,
+    // switches the left button between panning and editing boxes
+    ToggleEditing,
+    ToggleMask,
+    ToggleHeatmap,
+    TogglePixelGrid,
+    ToggleReadout,
+    ToggleNormalMap,
+    NextChannel,
+    ToggleSkybox,
+    NextCoverageMode,
+    ToggleCheckerboard,
+    // the window title, zoom and frame rate in the top right corner
+    ToggleHud,
+    // of the next frame, by the capture tool the app runs under, e.g. renderdoc
+    CaptureFrame,
+    // the ones below need a scrubber or subtitles, without them the key goes to the key
+    // handler, home rewinds any provider that can
+    TogglePause,
+    Rewind,
+    NextMarker,
+    PreviousMarker,
+}
+
+// which key triggers which action in the viewer, keys without an action go to its key
+// handler
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputBindings {
+    keys: HashMap<KeyCode, Action>,
+}
+
+impl InputBindings {
+    // nothing bound, not even a way to quit besides closing the window
+    pub fn empty() -> Self {
+        Self { keys: HashMap::new() }
+    }
+
+    // returns the action `key` was bound to before
+    pub fn bind(&mut self, key: KeyCode, action: Action) -> Option<Action> {
+        self.keys.insert(key, action)
+    }
+
+    // hands `key` to the key handler
+    pub fn unbind(&mut self, key: KeyCode) -> Option<Action> {
+        self.keys.remove(&key)
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.keys.get(&key).copied()
+    }
+
+    // the keys bound to `action`, in no particular order
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys.iter().filter(move |(_, bound)| **bound == action).map(|(key, _)| *key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (KeyCode, Action)> + '_ {
+        self.keys.iter().map(|(key, action)| (*key, *action))
+    }
+
+    // the action of a key press, releases and events other than keys have none
+    pub fn action_for(&self, event: &WindowEvent) -> Option<Action> {
+        pressed_key(event).and_then(|key| self.action(key))
+    }
+}
+
+// escape quits, + and - zoom, the number pad arrows pan, 0 resets the view, f11 goes
+// fullscreen, page down and page up step through the provider's images, g switches
+// adapters, s cycles through the scaling modes, f through the filter presets, t toggles a
+// tiling check, v cycles vsync, mailbox and immediate presentation, e switches between
+// panning and editing boxes with the left button, m and h show or hide the segmentation
+// mask and the heatmap, p the pixel grid, i the position and value of the pixel under the
+// cursor, n shows the frame as a normal map, c steps through its channels as gray, k looks
+// around a cube map, d steps through coverage views, b puts transparent pixels on a
+// checkerboard, f1 shows the title, zoom and frame rate, f9 captures the next frame with
+// renderdoc or the like, space pauses, home rewinds and [ and ] jump between markers
+// the arrows are left to the key handler, e.g. `DatasetReview` steps through images with
+// them
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keys = [
+            (KeyCode::Escape, Action::Quit),
+            (KeyCode::Equal, Action::ZoomIn),
+            (KeyCode::NumpadAdd, Action::ZoomIn),
+            (KeyCode::Minus, Action::ZoomOut),
+            (KeyCode::NumpadSubtract, Action::ZoomOut),
+            (KeyCode::Numpad4, Action::Pan(Direction::Left)),
+            (KeyCode::Numpad6, Action::Pan(Direction::Right)),
+            (KeyCode::Numpad8, Action::Pan(Direction::Up)),
+            (KeyCode::Numpad2, Action::Pan(Direction::Down)),
+            (KeyCode::Digit0, Action::ResetView),
+            (KeyCode::F11, Action::ToggleFullscreen),
+            (KeyCode::PageDown, Action::NextImage),
+            (KeyCode::PageUp, Action::PreviousImage),
+            (KeyCode::KeyG, Action::NextAdapter),
+            (KeyCode::KeyS, Action::NextScalingMode),
+            (KeyCode::KeyF, Action::NextFilterPreset),
+            (KeyCode::KeyT, Action::ToggleTiling),
+            (KeyCode::KeyV, Action::NextPresentMode),
+            (KeyCode::KeyE, Action::ToggleEditing),
+            (KeyCode::KeyM, Action::ToggleMask),
+            (KeyCode::KeyH, Action::ToggleHeatmap),
+            (KeyCode::KeyP, Action::TogglePixelGrid),
+            (KeyCode::KeyI, Action::ToggleReadout),
+            (KeyCode::KeyN, Action::ToggleNormalMap),
+            (KeyCode::KeyC, Action::NextChannel),
+            (KeyCode::KeyK, Action::ToggleSkybox),
+            (KeyCode::KeyD, Action::NextCoverageMode),
+            (KeyCode::KeyB, Action::ToggleCheckerboard),
+            (KeyCode::F1, Action::ToggleHud),
+            (KeyCode::F9, Action::CaptureFrame),
+            (KeyCode::Space, Action::TogglePause),
+            (KeyCode::Home, Action::Rewind),
+            (KeyCode::BracketRight, Action::NextMarker),
+            (KeyCode::BracketLeft, Action::PreviousMarker),
+        ];
+
+        Self { keys: keys.into_iter().collect() }
+    }
+}
+
+// the key of a press, `None` for releases and keys winit can't name
+pub fn pressed_key(event: &WindowEvent) -> Option<KeyCode> {
+    match event {
+        WindowEvent::KeyboardInput { event: KeyEvent { state: ElementState::Pressed, physical_key: PhysicalKey::Code(key), .. }, .. } => Some(*key),
+        _ => None,
+    }
+}
+
+// the window events the viewer acts on, in physical pixels, what `InteractionRecorder`
+// keeps and `InteractionPlayer` plays back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Resized(Pair<u32>),
+    ScaleFactorChanged(f64),
+    // releases do nothing in the viewer
+    KeyPressed(KeyCode),
+    CursorMoved(Pair<f32>),
+    CursorLeft,
+    LeftButton { pressed: bool },
+    Wheel(MouseScrollDelta),
+    CloseRequested,
+}
+
+impl InputEvent {
+    // `None` for the events the viewer ignores
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        if let Some(key) = pressed_key(event) {
+            return Some(InputEvent::KeyPressed(key));
+        }
+
+        match event {
+            WindowEvent::Resized(size) => Some(InputEvent::Resized((size.width, size.height))),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => Some(InputEvent::ScaleFactorChanged(*scale_factor)),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved((position.x as f32, position.y as f32))),
+            WindowEvent::CursorLeft { .. } => Some(InputEvent::CursorLeft),
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => Some(InputEvent::LeftButton { pressed: *state == ElementState::Pressed }),
+            WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::Wheel(*delta)),
+            WindowEvent::CloseRequested => Some(InputEvent::CloseRequested),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::{AsFd, BorrowedFd};
+use std::path::PathBuf;
+
+use drm::buffer::DrmFourcc;
+use drm::control::{connector, crtc, dumbbuffer::DumbBuffer, framebuffer, Device as ControlDevice, Mode};
+
+use crate::adapter::{AdapterError, AdapterSelection};
+use crate::error::DrawError;
+use crate::readback::ReadbackError;
+use crate::render::{AlphaBehavior, WgpuFrameRenderContext};
+use crate::stats::RenderStats;
+use crate::types::{FrameProvider, FrameRenderContext, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair};
+
+// presents on a bare display through DRM/KMS: frames are rendered offscreen with the
+// regular pipeline and copied into a dumb buffer scanned out by the first connected output
+#[derive(Debug)]
+struct Card(File);
+
+impl AsFd for Card {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl drm::Device for Card {}
+impl ControlDevice for Card {}
+
+#[derive(Debug)]
+pub enum KmsError {
+    Io(io::Error),
+    Adapter(AdapterError),
+    NoConnectedOutput,
+    NoCrtc,
+    NotOffscreen,
+    Draw(DrawError),
+    Readback(ReadbackError),
+}
+
+impl fmt::Display for KmsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KmsError::Io(error) => write!(f, "drm error: {error}"),
+            KmsError::Adapter(error) => write!(f, "{error}"),
+            KmsError::NoConnectedOutput => write!(f, "no connected output with a usable mode"),
+            KmsError::NoCrtc => write!(f, "no crtc available for the connected output"),
+            KmsError::NotOffscreen => write!(f, "render context does not target a texture"),
+            KmsError::Draw(error) => write!(f, "{error}"),
+            KmsError::Readback(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for KmsError {}
+
+impl From<io::Error> for KmsError {
+    fn from(error: io::Error) -> Self {
+        KmsError::Io(error)
+    }
+}
+
+pub struct KmsFrameRenderContextInit {
+    pub device_path: PathBuf,
+    pub clear_color: Option<wgpu::Color>,
+    pub adapter: AdapterSelection,
+    pub strict_validation: bool,
+}
+
+impl Default for KmsFrameRenderContextInit {
+    fn default() -> Self {
+        Self {
+            device_path: PathBuf::from("/dev/dri/card0"),
+            clear_color: None,
+            adapter: AdapterSelection::Default,
+            strict_validation: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KmsDisplay {
+    card: Card,
+    mode: Mode,
+    crtc: crtc::Handle,
+    connector: connector::Handle,
+    framebuffer: framebuffer::Handle,
+    dumb_buffer: Option<DumbBuffer>,
+    saved_crtc: Option<crtc::Info>,
+}
+
+impl KmsDisplay {
+    fn open(device_path: &PathBuf) -> Result<Self, KmsError> {
+        let card = Card(OpenOptions::new().read(true).write(true).open(device_path)?);
+        let resources = card.resource_handles()?;
+
+        let connector = resources
+            .connectors()
+            .iter()
+            .filter_map(|handle| card.get_connector(*handle, true).ok())
+            .find(|info| info.state() == connector::State::Connected && !info.modes().is_empty())
+            .ok_or(KmsError::NoConnectedOutput)?;
+
+        // the first mode is the preferred one
+        let mode = connector.modes()[0];
+
+        let crtc = connector
+            .current_encoder()
+            .and_then(|encoder| card.get_encoder(encoder).ok())
+            .and_then(|encoder| encoder.crtc())
+            .or_else(|| resources.crtcs().first().copied())
+            .ok_or(KmsError::NoCrtc)?;
+
+        let (width, height) = mode.size();
+        let dumb_buffer = card.create_dumb_buffer((width.into(), height.into()), DrmFourcc::Xrgb8888, 32)?;
+        let framebuffer = card.add_framebuffer(&dumb_buffer, 24, 32)?;
+        let saved_crtc = card.get_crtc(crtc).ok();
+
+        card.set_crtc(crtc, Some(framebuffer), (0, 0), &[connector.handle()], Some(mode))?;
+
+        Ok(Self {
+            card,
+            mode,
+            crtc,
+            framebuffer,
+            saved_crtc,
+            connector: connector.handle(),
+            dumb_buffer: Some(dumb_buffer),
+        })
+    }
+
+    fn size(&self) -> Pair<u32> {
+        let (width, height) = self.mode.size();
+        (width.into(), height.into())
+    }
+
+    // `pixels` are tightly packed bgra rows, which is xrgb8888 in memory
+    fn scan_out(&mut self, pixels: &[u8]) -> Result<(), KmsError> {
+        let row_len = 4 * self.size().0 as usize;
+        let dumb_buffer = self.dumb_buffer.as_mut().expect("dumb buffer is only taken on drop");
+        let pitch = drm::buffer::Buffer::pitch(dumb_buffer) as usize;
+        let mut mapping = self.card.map_dumb_buffer(dumb_buffer)?;
+
+        for (target, source) in mapping.as_mut().chunks_exact_mut(pitch).zip(pixels.chunks_exact(row_len)) {
+            target[..row_len].copy_from_slice(source);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for KmsDisplay {
+    fn drop(&mut self) {
+        // hand the display back in the state we found it
+        if let Some(saved) = self.saved_crtc.as_ref() {
+            let _ = self.card.set_crtc(self.crtc, saved.framebuffer(), saved.position(), &[self.connector], saved.mode());
+        }
+
+        let _ = self.card.destroy_framebuffer(self.framebuffer);
+
+        if let Some(dumb_buffer) = self.dumb_buffer.take() {
+            let _ = self.card.destroy_dumb_buffer(dumb_buffer);
+        }
+    }
+}
+
+pub struct KmsFrameRenderContext {
+    display: KmsDisplay,
+    context: WgpuFrameRenderContext,
+}
+
+impl KmsFrameRenderContext {
+    pub fn open(init: KmsFrameRenderContextInit) -> Result<Self, KmsError> {
+        let display = KmsDisplay::open(&init.device_path)?;
+
+        let context = WgpuFrameRenderContext::offscreen(
+            display.size(),
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            init.clear_color,
+            AlphaBehavior::default(),
+            &init.adapter,
+            init.strict_validation,
+            None,
+        ).map_err(KmsError::Adapter)?;
+
+        Ok(Self { display, context })
+    }
+
+    pub fn render_context(&mut self) -> &mut WgpuFrameRenderContext {
+        &mut self.context
+    }
+}
+
+impl HasSize<u32> for KmsFrameRenderContext {
+    fn size(&self) -> Pair<u32> {
+        self.display.size()
+    }
+}
+
+impl TryFrom<KmsFrameRenderContextInit> for KmsFrameRenderContext {
+    type Error = KmsError;
+
+    fn try_from(init: KmsFrameRenderContextInit) -> Result<Self, Self::Error> {
+        Self::open(init)
+    }
+}
+
+impl FrameRenderContext for KmsFrameRenderContext {
+    type RenderError = KmsError;
+    type Init = KmsFrameRenderContextInit;
+    type Stats = RenderStats;
+
+    // the output resolution is fixed by the display mode
+    fn configure(&mut self, size: Pair<u32>) {
+        if size != self.display.size() {
+            log::warn!("ignoring resize to {size:?}, kms output is {:?}", self.display.size());
+        }
+
+        self.context.configure(self.display.size());
+    }
+
+    // the stats cover the draw into the offscreen target, not the scan out
+    fn draw_frame<Frame>(&mut self, frame_provider: impl FrameProvider<Frame = Frame>) -> Result<Self::Stats, Self::RenderError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let stats = self.context.draw_frame(frame_provider).map_err(KmsError::Draw)?;
+
+        let pixels = self
+            .context
+            .read_target()
+            .ok_or(KmsError::NotOffscreen)?
+            .map_err(KmsError::Readback)?;
+
+        self.display.scan_out(&pixels)?;
+        Ok(stats)
+    }
+}
@@ -1,4 +1,140 @@
-mod viewport;
-mod vertex;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// the core (types, viewport math and quad generation) only depends on bytemuck and log,
+// everything touching wgpu, winit or image sits behind the matching feature
 pub mod types;
+pub mod vertex;
+pub mod transform;
+pub mod viewport;
+pub mod shading;
+pub mod cubemap;
+pub mod convert;
+pub mod source;
+pub mod transport;
+
+#[cfg(feature = "std")]
+mod executor;
+
+#[cfg(feature = "std")]
+pub mod annotation;
+
+#[cfg(feature = "std")]
+pub mod color;
+
+#[cfg(feature = "std")]
+pub mod mask;
+
+#[cfg(feature = "std")]
+pub mod heatmap;
+
+#[cfg(feature = "std")]
+pub mod subtitles;
+
+#[cfg(feature = "render")]
 pub mod render;
+
+#[cfg(feature = "render")]
+pub mod adapter;
+
+#[cfg(feature = "render")]
+pub mod gpu;
+
+#[cfg(feature = "render")]
+pub mod filters;
+
+#[cfg(feature = "render")]
+pub mod error;
+
+#[cfg(feature = "render")]
+pub mod readback;
+
+#[cfg(feature = "render")]
+pub mod memory;
+
+#[cfg(feature = "render")]
+pub mod stats;
+
+#[cfg(feature = "render")]
+pub mod builder;
+
+#[cfg(feature = "render")]
+pub mod feedback;
+
+#[cfg(feature = "render")]
+pub mod headless;
+
+#[cfg(feature = "compute")]
+pub mod envmap;
+
+#[cfg(feature = "compute")]
+pub mod histogram;
+
+#[cfg(feature = "render")]
+mod overlay;
+
+#[cfg(feature = "render")]
+pub mod scrubber;
+
+#[cfg(feature = "render")]
+pub mod filmstrip;
+
+#[cfg(feature = "render")]
+pub mod scale_bar;
+
+#[cfg(feature = "render")]
+mod text;
+
+#[cfg(feature = "render")]
+mod glyphs;
+
+#[cfg(feature = "fonts")]
+pub mod font;
+
+#[cfg(feature = "render")]
+mod tiling;
+
+#[cfg(feature = "render")]
+mod upload;
+
+#[cfg(feature = "render")]
+pub mod diagnostics;
+
+#[cfg(all(feature = "kms", target_os = "linux"))]
+pub mod kms;
+
+#[cfg(feature = "renderdoc")]
+pub mod renderdoc;
+
+#[cfg(feature = "software")]
+pub mod software;
+
+#[cfg(feature = "providers")]
+pub mod providers;
+
+// saves and loads the golden images as png
+#[cfg(all(feature = "render", feature = "providers"))]
+pub mod snapshot;
+
+#[cfg(feature = "viewer")]
+pub mod input;
+
+#[cfg(feature = "viewer")]
+pub mod recording;
+
+#[cfg(feature = "viewer")]
+pub mod viewer;
+
+#[cfg(all(feature = "viewer", feature = "providers"))]
+pub mod review;
+
+#[cfg(all(feature = "viewer", feature = "providers"))]
+pub mod viewer_builder;
+
+#[cfg(all(feature = "viewer", feature = "providers"))]
+pub mod region_selector;
+
+#[cfg(all(feature = "viewer", feature = "providers"))]
+pub use viewer_builder::{ViewerBuilder, ViewerError};
+
+#[cfg(all(feature = "viewer", feature = "providers"))]
+pub use region_selector::RegionSelector;
@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::types::Pair;
+
+// a class per pixel of a frame as it was decoded, 0 is background and isn't drawn
+// instance masks from a dataset are painted into it one after another, later ones on top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentationMask {
+    size: Pair<u32>,
+    classes: Vec<u8>,
+}
+
+// coco style run lengths, alternating background and foreground runs starting with
+// background, counted down the columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rle {
+    pub size: Pair<u32>,
+    pub counts: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskError {
+    pub reason: &'static str,
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid mask: {}", self.reason)
+    }
+}
+
+impl std::error::Error for MaskError {}
+
+fn error(reason: &'static str) -> MaskError {
+    MaskError { reason }
+}
+
+impl Rle {
+    // the compressed `counts` string pycocotools writes, six bit groups offset from '0'
+    // with every count past the second stored as the difference to the one two before
+    pub fn from_coco_string(size: Pair<u32>, counts: &str) -> Result<Self, MaskError> {
+        let mut bytes = counts.bytes().peekable();
+        let mut decoded: Vec<u32> = Vec::new();
+
+        while bytes.peek().is_some() {
+            let mut value = 0i64;
+            let mut shift = 0;
+
+            loop {
+                let group = bytes.next().ok_or(error("truncated count"))?.wrapping_sub(b'0') as i64;
+                if group > 0x3f || shift > 30 {
+                    return Err(error("malformed count"));
+                }
+
+                value |= (group & 0x1f) << shift;
+                shift += 5;
+
+                if group & 0x20 == 0 {
+                    // the last group carries the sign
+                    if group & 0x10 != 0 {
+                        value |= -1 << shift;
+                    }
+                    break;
+                }
+            }
+
+            if decoded.len() > 2 {
+                value += decoded[decoded.len() - 2] as i64;
+            }
+
+            decoded.push(u32::try_from(value).map_err(|_| error("negative count"))?);
+        }
+
+        Ok(Self { size, counts: decoded })
+    }
+}
+
+impl SegmentationMask {
+    pub fn new(size: Pair<u32>) -> Self {
+        Self { size, classes: vec![0; size.0 as usize * size.1 as usize] }
+    }
+
+    // one class per pixel, rows top to bottom
+    pub fn from_classes(size: Pair<u32>, classes: Vec<u8>) -> Result<Self, MaskError> {
+        match classes.len() == size.0 as usize * size.1 as usize {
+            true => Ok(Self { size, classes }),
+            false => Err(error("class map doesn't match the size")),
+        }
+    }
+
+    pub fn size(&self) -> Pair<u32> {
+        self.size
+    }
+
+    pub fn classes(&self) -> &[u8] {
+        &self.classes
+    }
+
+    pub fn class_at(&self, position: Pair<u32>) -> Option<u8> {
+        match position.0 < self.size.0 && position.1 < self.size.1 {
+            true => Some(self.classes[position.1 as usize * self.size.0 as usize + position.0 as usize]),
+            false => None,
+        }
+    }
+
+    // sets `class` wherever `bitmap` isn't 0, rows top to bottom
+    pub fn paint_bitmap(&mut self, bitmap: &[u8], class: u8) -> Result<(), MaskError> {
+        if bitmap.len() != self.classes.len() {
+            return Err(error("bitmap doesn't match the size"));
+        }
+
+        for (target, _) in self.classes.iter_mut().zip(bitmap).filter(|(_, value)| **value != 0) {
+            *target = class;
+        }
+
+        Ok(())
+    }
+
+    pub fn paint_rle(&mut self, rle: &Rle, class: u8) -> Result<(), MaskError> {
+        if rle.size != self.size {
+            return Err(error("run lengths don't match the size"));
+        }
+
+        let (width, height) = (self.size.0 as usize, self.size.1 as usize);
+        let mut start = 0usize;
+
+        for (index, count) in rle.counts.iter().enumerate() {
+            let end = start.saturating_add(*count as usize);
+            if end > self.classes.len() {
+                return Err(error("runs cover more than the image"));
+            }
+
+            if index % 2 == 1 {
+                for position in start..end {
+                    let (x, y) = (position / height, position % height);
+                    self.classes[y * width + x] = class;
+                }
+            }
+
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    // fills a polygon in pixel coordinates, e.g. a coco segmentation, pixels whose centers
+    // are inside it by the even-odd rule are set
+    pub fn paint_polygon(&mut self, points: &[Pair<f32>], class: u8) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let width = self.size.0 as usize;
+        let mut crossings = Vec::new();
+
+        for y in 0..self.size.1 {
+            let center = y as f32 + 0.5;
+            crossings.clear();
+
+            for (index, a) in points.iter().enumerate() {
+                let b = points[(index + 1) % points.len()];
+                if (a.1 <= center) != (b.1 <= center) {
+                    crossings.push(a.0 + (center - a.1) / (b.1 - a.1) * (b.0 - a.0));
+                }
+            }
+
+            crossings.sort_by(f32::total_cmp);
+
+            for span in crossings.chunks_exact(2) {
+                // first and last pixel centers inside the span
+                let first = (span[0] - 0.5).ceil().max(0.0) as usize;
+                let last = ((span[1] - 0.5).floor() + 1.0).clamp(0.0, self.size.0 as f32) as usize;
+
+                for target in self.classes[y as usize * width..][..width].iter_mut().take(last).skip(first) {
+                    *target = class;
+                }
+            }
+        }
+    }
+}
+
+// how a mask is tinted over the frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskStyle {
+    // 0 hides the mask, 1 covers the frame
+    pub opacity: f32,
+    // srgb colors by class, classes past the end get a color of their own
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl Default for MaskStyle {
+    fn default() -> Self {
+        Self { opacity: 0.5, colors: Vec::new() }
+    }
+}
+
+impl MaskStyle {
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_color(mut self, class: u8, color: [u8; 3]) -> Self {
+        let class = class as usize;
+        while self.colors.len() <= class {
+            self.colors.push(generated_color(self.colors.len() as u8));
+        }
+
+        self.colors[class] = color;
+        self
+    }
+
+    // transparent for the background
+    pub fn color(&self, class: u8) -> [u8; 4] {
+        let [r, g, b] = self.colors.get(class as usize).copied().unwrap_or_else(|| generated_color(class));
+
+        match class {
+            0 => [0, 0, 0, 0],
+            _ => [r, g, b, 255],
+        }
+    }
+}
+
+// hues a golden angle apart so neighboring classes are easy to tell apart
+fn generated_color(class: u8) -> [u8; 3] {
+    let hue = (class as f32 * 137.508).rem_euclid(360.0) / 60.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+
+    // lifted off black so every class shows up on dark frames
+    let channel = |value: f32| (40.0 + value * 215.0) as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pycocotools' `rleToString`
+    fn coco_string(counts: &[u32]) -> String {
+        let mut string = String::new();
+
+        for (index, count) in counts.iter().enumerate() {
+            let mut value = *count as i64;
+            if index > 2 {
+                value -= counts[index - 2] as i64;
+            }
+
+            loop {
+                let mut group = value & 0x1f;
+                value >>= 5;
+                let more = match group & 0x10 {
+                    0 => value != 0,
+                    _ => value != -1,
+                };
+                if more {
+                    group |= 0x20;
+                }
+                string.push((group as u8 + b'0') as char);
+                if !more {
+                    break;
+                }
+            }
+        }
+
+        string
+    }
+
+    #[test]
+    fn decodes_coco_strings() {
+        let counts = [0, 7, 100_000, 3, 1, 40_000_000, 2, 1];
+        assert_eq!(Rle::from_coco_string((1, 1), &coco_string(&counts)).unwrap().counts, counts);
+        assert!(Rle::from_coco_string((2, 2), "").unwrap().counts.is_empty());
+
+        assert_eq!(Rle::from_coco_string((1, 1), "0P").unwrap_err().reason, "truncated count");
+        assert_eq!(Rle::from_coco_string((1, 1), "0~").unwrap_err().reason, "malformed count");
+        assert_eq!(Rle::from_coco_string((1, 1), "PPPPPPPP0").unwrap_err().reason, "malformed count");
+        // the sign bit on a count that isn't a difference
+        assert_eq!(Rle::from_coco_string((1, 1), "@").unwrap_err().reason, "negative count");
+    }
+
+    #[test]
+    fn paints_runs_down_the_columns() {
+        let mut mask = SegmentationMask::new((3, 2));
+        mask.paint_rle(&Rle { size: (3, 2), counts: vec![1, 2, 2, 1] }, 5).unwrap();
+
+        assert_eq!(mask.classes(), [0, 5, 0, 5, 0, 5]);
+        assert_eq!(mask.class_at((0, 1)), Some(5));
+        assert_eq!(mask.class_at((3, 0)), None);
+
+        assert!(mask.paint_rle(&Rle { size: (3, 2), counts: vec![6, 1] }, 1).is_err());
+        assert!(mask.paint_rle(&Rle { size: (3, 2), counts: vec![u32::MAX, u32::MAX] }, 1).is_err());
+        assert!(mask.paint_rle(&Rle { size: (2, 3), counts: vec![1] }, 1).is_err());
+    }
+
+    #[test]
+    fn paints_bitmaps_and_polygons() {
+        let mut mask = SegmentationMask::new((4, 4));
+        assert!(mask.paint_bitmap(&[1; 15], 1).is_err());
+        assert!(SegmentationMask::from_classes((4, 4), vec![0; 15]).is_err());
+
+        mask.paint_bitmap(&[[1, 0, 0, 0], [0; 4], [0; 4], [0; 4]].concat(), 2).unwrap();
+        // covers the centers of the middle four pixels and leaves the corner alone
+        mask.paint_polygon(&[(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)], 3);
+        // outside the mask and degenerate
+        mask.paint_polygon(&[(-10.0, -10.0), (20.0, -10.0), (20.0, -5.0)], 4);
+        mask.paint_polygon(&[(0.0, 0.0), (4.0, 4.0)], 4);
+
+        assert_eq!(mask.classes(), [[2, 0, 0, 0], [0, 3, 3, 0], [0, 3, 3, 0], [0; 4]].concat());
+    }
+}
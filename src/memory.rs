@@ -0,0 +1,83 @@
+use std::fmt;
+
+// bytes of gpu memory a render context holds on to, counted from the textures and buffers
+// it keeps alive, drivers add their own padding and alignment on top
+// transient buffers, e.g. those of a capture, are left out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    // the frames drawn and everything sampled along with them: palettes, masks, heatmaps
+    pub source_textures: u64,
+    // the texture holding the tiles in view while a frame too large for a texture is drawn
+    pub tiles: u64,
+    // the targets the filters render into
+    pub filters: u64,
+    // annotations, captions, the scrub bar and the filmstrip with its thumbnails
+    pub overlays: u64,
+    // the buffers frames are uploaded through, see `UploadPath::StagingBelt`
+    pub staging: u64,
+    // offscreen targets, vertex and uniform buffers
+    pub other: u64,
+}
+
+impl MemoryReport {
+    pub fn total(&self) -> u64 {
+        self.source_textures + self.tiles + self.filters + self.overlays + self.staging + self.other
+    }
+}
+
+// e.g. "12.0 MiB (source 8.0 MiB, tiles 0 B, filters 4.0 MiB, overlays 1.2 KiB, staging 0 B, other 3.1 KiB)"
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (source {}, tiles {}, filters {}, overlays {}, staging {}, other {})",
+            Bytes(self.total()),
+            Bytes(self.source_textures),
+            Bytes(self.tiles),
+            Bytes(self.filters),
+            Bytes(self.overlays),
+            Bytes(self.staging),
+            Bytes(self.other),
+        )
+    }
+}
+
+struct Bytes(u64);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+
+        while value >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        write!(f, "{value:.1} {}", UNITS[unit])
+    }
+}
+
+// every mip level of every layer, formats without a fixed block size count as 4 bytes a
+// texel
+pub(crate) fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+    let size = texture.size();
+
+    (0..texture.mip_level_count())
+        .map(|level| {
+            let extent = size.mip_level_size(level, texture.dimension());
+            let blocks = extent.width.div_ceil(block_width) as u64 * extent.height.div_ceil(block_height) as u64;
+            blocks * extent.depth_or_array_layers as u64 * block_size
+        })
+        .sum::<u64>()
+        * texture.sample_count() as u64
+}
@@ -0,0 +1,193 @@
+use crate::annotation::{Annotation, AnnotationId, Handle};
+use crate::render::ResourceLabels;
+use crate::types::Pair;
+
+// solid rectangles drawn over the frames in surface pixels, the same size at any zoom
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct OverlayRect {
+    pub min: Pair<f32>,
+    pub max: Pair<f32>,
+    // linear rgba, blended over the frame
+    pub color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct OverlayVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl OverlayVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            attributes: &Self::ATTRIBS,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            array_stride: std::mem::size_of::<OverlayVertex>() as wgpu::BufferAddress,
+        }
+    }
+}
+
+const OUTLINE_WIDTH: f32 = 2.0;
+const HANDLE_SIZE: f32 = 8.0;
+
+// outlines of the boxes and the handles of the selected one, `to_surface` maps frame
+// pixels to surface pixels
+pub(crate) fn annotation_rects(annotations: &[Annotation], selected: Option<AnnotationId>, to_surface: impl Fn(Pair<f32>) -> Pair<f32>) -> Vec<OverlayRect> {
+    let mut rects = Vec::new();
+
+    for annotation in annotations {
+        let (min, max) = (to_surface(annotation.rect.min), to_surface(annotation.rect.max));
+        let half = OUTLINE_WIDTH / 2.0;
+        let color = annotation.color;
+
+        rects.extend([
+            OverlayRect { min: (min.0 - half, min.1 - half), max: (max.0 + half, min.1 + half), color },
+            OverlayRect { min: (min.0 - half, max.1 - half), max: (max.0 + half, max.1 + half), color },
+            OverlayRect { min: (min.0 - half, min.1 + half), max: (min.0 + half, max.1 - half), color },
+            OverlayRect { min: (max.0 - half, min.1 + half), max: (max.0 + half, max.1 - half), color },
+        ]);
+    }
+
+    // a dark rim keeps the handles visible on light frames
+    let selected = selected.and_then(|id| annotations.iter().find(|annotation| annotation.id == id));
+    if let Some(annotation) = selected {
+        for handle in Handle::ALL {
+            let center = to_surface(handle.position(&annotation.rect));
+            let half = HANDLE_SIZE / 2.0;
+
+            rects.push(OverlayRect {
+                min: (center.0 - half - 1.0, center.1 - half - 1.0),
+                max: (center.0 + half + 1.0, center.1 + half + 1.0),
+                color: [0.0, 0.0, 0.0, 0.8],
+            });
+            rects.push(OverlayRect { min: (center.0 - half, center.1 - half), max: (center.0 + half, center.1 + half), color: [1.0; 4] });
+        }
+    }
+
+    rects
+}
+
+const SPOTLIGHT_DIM: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+
+// darkens the surface around `rect`, in surface pixels
+pub(crate) fn spotlight_rects(min: Pair<f32>, max: Pair<f32>, surface_size: Pair<u32>) -> Vec<OverlayRect> {
+    let (width, height) = (surface_size.0 as f32, surface_size.1 as f32);
+    let color = SPOTLIGHT_DIM;
+
+    vec![
+        OverlayRect { min: (0.0, 0.0), max: (width, min.1), color },
+        OverlayRect { min: (0.0, max.1), max: (width, height), color },
+        OverlayRect { min: (0.0, min.1), max: (min.0, max.1), color },
+        OverlayRect { min: (max.0, min.1), max: (width, max.1), color },
+    ]
+}
+
+#[derive(Debug)]
+pub(crate) struct Overlay {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    labels: ResourceLabels,
+}
+
+fn create_vertex_buffer(device: &wgpu::Device, labels: &ResourceLabels, vertices: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&labels.get("Overlay Vertex Buffer")),
+        size: (vertices.max(6) * std::mem::size_of::<OverlayVertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl Overlay {
+    pub(crate) fn new(device: &wgpu::Device, labels: &ResourceLabels, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&labels.get("Overlay Shader")),
+            source: wgpu::ShaderSource::Wgsl(include_str!("overlay.wgsl").into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&labels.get("Overlay Pipeline Layout")),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&labels.get("Overlay Pipeline")),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_overlay",
+                buffers: &[OverlayVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_overlay",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer: create_vertex_buffer(device, labels, 0),
+            vertex_count: 0,
+            labels: labels.clone(),
+        }
+    }
+
+    // two triangles per rectangle, the buffer grows when it runs out of room
+    pub(crate) fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, rects: &[OverlayRect], surface_size: Pair<u32>) {
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+        let to_clip = |point: Pair<f32>| [2.0 * point.0 / width - 1.0, 1.0 - 2.0 * point.1 / height];
+
+        let vertices: Vec<OverlayVertex> = rects
+            .iter()
+            .flat_map(|rect| {
+                let corners = [
+                    to_clip(rect.min),
+                    to_clip((rect.max.0, rect.min.1)),
+                    to_clip((rect.min.0, rect.max.1)),
+                    to_clip(rect.max),
+                ];
+
+                [0, 2, 1, 2, 3, 1].map(|corner| OverlayVertex { position: corners[corner], color: rect.color })
+            })
+            .collect();
+
+        if (self.vertex_buffer.size() as usize) < std::mem::size_of_val(vertices.as_slice()) {
+            self.vertex_buffer = create_vertex_buffer(device, &self.labels, vertices.len().next_power_of_two());
+        }
+
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.vertex_buffer.size()
+    }
+
+    pub(crate) fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.vertex_count > 0 {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+struct OverlayInput {
+    @location(0) position : vec2<f32>,
+    @location(1) color : vec4<f32>,
+}
+
+struct OverlayOutput {
+    @builtin(position) clip_position : vec4<f32>,
+    @location(0) color : vec4<f32>,
+}
+
+@vertex
+fn vs_overlay(vertex: OverlayInput) -> OverlayOutput {
+    var out : OverlayOutput;
+    out.clip_position = vec4<f32>(vertex.position, 0.0, 1.0);
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fs_overlay(in: OverlayOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,265 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::decode::{self, DecodeError, DecodeLimits, DecodedAnimation};
+use crate::transport::Transport;
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat};
+
+// browsers treat shorter gif delays as "as fast as possible" and slow them down to this
+const MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+// a decoded gif or apng played back in real time: `next` yields a frame when it is due and
+// `None` in between, so a render loop running at any rate keeps showing the current frame
+#[derive(Debug, Clone)]
+pub struct AnimatedImageProvider {
+    size: Pair<u32>,
+    source_size: Pair<u32>,
+    frames: Arc<[AnimatedImageFrame]>,
+    playback: Option<Playback>,
+    // the frame of a seek is yielded even when the index didn't change
+    sought: bool,
+    // the clock stands still at this instant
+    paused_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Playback {
+    index: usize,
+    shown_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimatedImageFrame {
+    size: Pair<u32>,
+    index: usize,
+    delay: Duration,
+    buffer: Arc<[u8]>,
+}
+
+impl AnimatedImageProvider {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        Ok(Self::from_decoded(decode::decode_animation(bytes.to_vec(), limits)?))
+    }
+
+    fn from_decoded(DecodedAnimation { frames, source_size }: DecodedAnimation) -> Self {
+        let size = frames.first().map_or(source_size, |frame| frame.image.dimensions());
+
+        let frames = frames
+            .into_iter()
+            .enumerate()
+            .map(|(index, frame)| AnimatedImageFrame {
+                size,
+                index,
+                delay: match frame.delay {
+                    Duration::ZERO => DEFAULT_DELAY,
+                    delay => delay.max(MIN_DELAY),
+                },
+                buffer: frame.image.into_raw().into(),
+            })
+            .collect();
+
+        Self { size, source_size, frames, playback: None, sought: false, paused_at: None }
+    }
+
+    pub fn source_size(&self) -> Pair<u32> {
+        self.source_size
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    // how long each frame stays on screen, in playback order
+    pub fn delays(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.frames.iter().map(|frame| frame.delay)
+    }
+
+    pub fn frames(&self) -> &[AnimatedImageFrame] {
+        &self.frames
+    }
+
+    // playback restarts from the first frame on the next call to `next`
+    pub fn rewind(&mut self) {
+        self.playback = None;
+    }
+}
+
+impl AnimatedImageFrame {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+}
+
+impl HasSize<u32> for AnimatedImageProvider {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasSize<u32> for AnimatedImageFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for AnimatedImageFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for AnimatedImageFrame {
+    fn format(&self) -> PixelFormat {
+        PixelFormat::Rgba8
+    }
+}
+
+impl HasOrientation for AnimatedImageFrame {}
+
+impl HasData for AnimatedImageFrame {
+    fn data(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+// loops forever, frames that were due while nobody polled are skipped so playback
+// keeps wall clock speed
+impl Iterator for AnimatedImageProvider {
+    type Item = AnimatedImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+
+        let Some(mut playback) = self.playback else {
+            self.playback = Some(Playback { index: 0, shown_at: now });
+            return self.frames.first().cloned();
+        };
+
+        if self.frames.len() < 2 {
+            return None;
+        }
+
+        let sought = std::mem::take(&mut self.sought);
+        let previous = playback.index;
+
+        // whole loops missed while nobody polled
+        let loop_duration = self.loop_duration();
+        let loops = (now.duration_since(playback.shown_at).as_nanos() / loop_duration.as_nanos()) as u32;
+        playback.shown_at += loop_duration * loops;
+
+        while now.duration_since(playback.shown_at) >= self.frames[playback.index].delay {
+            playback.shown_at += self.frames[playback.index].delay;
+            playback.index = (playback.index + 1) % self.frames.len();
+        }
+
+        self.playback = Some(playback);
+
+        match playback.index == previous && !sought {
+            true => None,
+            false => Some(self.frames[playback.index].clone()),
+        }
+    }
+}
+
+// loops forever, so it never finishes
+impl FrameProvider for AnimatedImageProvider {
+    type Frame = AnimatedImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size)
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        match self.playback {
+            _ if self.paused_at.is_some() || self.frames.len() < 2 => FrameSchedule::Still,
+            Some(playback) if !self.sought => FrameSchedule::At(playback.shown_at + self.frames[playback.index].delay),
+            _ => FrameSchedule::Soon,
+        }
+    }
+
+    fn rewind(&mut self) -> bool {
+        AnimatedImageProvider::rewind(self);
+        true
+    }
+}
+
+impl AnimatedImageProvider {
+    fn loop_duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delay).sum()
+    }
+
+    // when frame `index` starts within a loop
+    fn start_of(&self, index: usize) -> Duration {
+        self.frames[..index].iter().map(|frame| frame.delay).sum()
+    }
+}
+
+impl Transport for AnimatedImageProvider {
+    // between frame starts too, so a seek reads back where it went
+    fn position(&self) -> Option<Duration> {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+
+        self.playback.map(|playback| {
+            let shown_for = now.saturating_duration_since(playback.shown_at).min(self.frames[playback.index].delay);
+            self.start_of(playback.index) + shown_for
+        })
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(self.loop_duration())
+    }
+
+    // positions past the end wrap around like playback does
+    fn seek(&mut self, position: Duration) -> bool {
+        let loop_duration = self.loop_duration();
+        if loop_duration.is_zero() {
+            return false;
+        }
+
+        let position = Duration::from_nanos((position.as_nanos() % loop_duration.as_nanos()) as u64);
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        let mut start = Duration::ZERO;
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            if start + frame.delay > position {
+                self.playback = Some(Playback { index, shown_at: now.checked_sub(position - start).unwrap_or(now) });
+                self.sought = true;
+                break;
+            }
+
+            start += frame.delay;
+        }
+
+        true
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        match (self.paused_at, paused) {
+            (None, true) => self.paused_at = Some(Instant::now()),
+            (Some(paused_at), false) => {
+                if let Some(playback) = self.playback.as_mut() {
+                    playback.shown_at += paused_at.elapsed();
+                }
+                self.paused_at = None;
+            },
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use std::ffi::c_void;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::convert;
+use crate::types::{FramePoll, FrameProvider, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat, PlaneLayout};
+
+// frames the driver fills while the last one is converted
+const BUFFER_COUNT: u32 = 4;
+
+// how long the worker waits for a frame before it checks whether it should stop
+const POLL_TIMEOUT_MS: i32 = 100;
+
+#[derive(Debug)]
+pub enum CameraError {
+    Io(io::Error),
+    // the device can't capture video or can't stream it through mapped buffers
+    NotACamera,
+    // the device offers neither yuyv nor mjpeg at the requested size, the fourcc it
+    // picked instead
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraError::Io(error) => write!(f, "failed to open camera: {error}"),
+            CameraError::NotACamera => write!(f, "device doesn't stream video"),
+            CameraError::UnsupportedFormat(fourcc) => write!(f, "unsupported camera format {fourcc}"),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CameraError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CameraError {
+    fn from(error: io::Error) -> Self {
+        CameraError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CameraFrame {
+    size: Pair<u32>,
+    // counted by the driver, gaps are frames nobody polled in time
+    sequence: u32,
+    // when the driver took it, on the monotonic clock
+    timestamp: Duration,
+    // rgba8, or nv12 as the driver wrote it with rows `row_stride` apart
+    format: PixelFormat,
+    row_stride: Option<usize>,
+    chroma: Option<PlaneLayout>,
+    buffer: Arc<[u8]>,
+}
+
+impl CameraFrame {
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+}
+
+impl HasSize<u32> for CameraFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for CameraFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for CameraFrame {
+    fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        self.row_stride
+    }
+}
+
+impl HasOrientation for CameraFrame {}
+
+impl HasData for CameraFrame {
+    fn data(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn plane_layouts(&self) -> &[PlaneLayout] {
+        self.chroma.as_slice()
+    }
+}
+
+// a video4linux camera, e.g. a usb webcam at /dev/video0, frames are dequeued and
+// converted to rgba (nv12 is left to the gpu) on a worker thread and polling yields the
+// latest one, so a viewer that falls behind skips frames rather than showing them late
+#[derive(Debug)]
+pub struct CameraProvider {
+    size: Pair<u32>,
+    latest: Arc<Mutex<Option<CameraFrame>>>,
+    stop: Arc<AtomicBool>,
+    // set by the worker when the device went away, e.g. unplugged
+    failed: Arc<AtomicBool>,
+    worker: Option<thread::JoinHandle<()>>,
+}
+
+impl CameraProvider {
+    // `size` is a request, drivers pick the closest size they have, see `size`
+    pub fn open<P: AsRef<Path>>(path: P, size: Pair<u32>) -> Result<Self, CameraError> {
+        let stream = Stream::open(path.as_ref(), size)?;
+        let size = stream.size;
+        let latest = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+        let failed = Arc::new(AtomicBool::new(false));
+
+        let worker = thread::Builder::new().name(String::from("egami-camera")).spawn({
+            let (latest, stop, failed) = (Arc::clone(&latest), Arc::clone(&stop), Arc::clone(&failed));
+
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match stream.next_frame() {
+                        Ok(Some(frame)) => *latest.lock().unwrap() = Some(frame),
+                        Ok(None) => (),
+                        Err(error) => {
+                            log::error!("camera stopped: {error}");
+                            failed.store(true, Ordering::Relaxed);
+                            break;
+                        },
+                    }
+                }
+            }
+        })?;
+
+        log::info!("capturing {}x{} from {}", size.0, size.1, path.as_ref().display());
+        Ok(Self { size, latest, stop, failed, worker: Some(worker) })
+    }
+
+    // the size the driver settled on
+    pub fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl Drop for CameraProvider {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Iterator for CameraProvider {
+    type Item = CameraFrame;
+
+    // only frames that weren't yielded before
+    fn next(&mut self) -> Option<Self::Item> {
+        self.latest.lock().unwrap().take()
+    }
+}
+
+// live, so it can't rewind, it finishes when the device goes away
+impl FrameProvider for CameraProvider {
+    type Frame = CameraFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match (self.next(), self.failed.load(Ordering::Relaxed)) {
+            (Some(frame), _) => FramePoll::Ready(frame),
+            (None, true) => FramePoll::Finished,
+            (None, false) => FramePoll::Pending,
+        }
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Yuyv,
+    Mjpeg,
+    Nv12,
+}
+
+// a streaming device and the driver's buffers mapped into the process, owned by the worker
+struct Stream {
+    file: File,
+    buffers: Vec<(*mut c_void, usize)>,
+    size: Pair<u32>,
+    bytes_per_line: u32,
+    encoding: Encoding,
+}
+
+// the mappings are only touched by the thread owning the stream
+unsafe impl Send for Stream {}
+
+impl Stream {
+    fn open(path: &Path, size: Pair<u32>) -> Result<Self, CameraError> {
+        let file = OpenOptions::new().read(true).write(true).custom_flags(libc::O_NONBLOCK).open(path)?;
+        let fd = file.as_raw_fd();
+
+        let mut capability: v4l2::Capability = unsafe { mem::zeroed() };
+        ioctl(fd, v4l2::VIDIOC_QUERYCAP, &mut capability)?;
+
+        let caps = match capability.capabilities & v4l2::CAP_DEVICE_CAPS != 0 {
+            true => capability.device_caps,
+            false => capability.capabilities,
+        };
+
+        if caps & v4l2::CAP_VIDEO_CAPTURE == 0 || caps & v4l2::CAP_STREAMING == 0 {
+            return Err(CameraError::NotACamera);
+        }
+
+        // yuyv is what every uvc camera offers, larger sizes often only come as mjpeg, which
+        // the driver picks instead when asked for a size it has no yuyv of, devices behind
+        // an isp may answer with nv12, which is passed on for the gpu to convert
+        let mut format: v4l2::Format = unsafe { mem::zeroed() };
+        format.kind = v4l2::BUF_TYPE_VIDEO_CAPTURE;
+        format.data.pix = v4l2::PixFormat { width: size.0, height: size.1, pixel_format: v4l2::PIX_FMT_YUYV, ..unsafe { mem::zeroed() } };
+        ioctl(fd, v4l2::VIDIOC_S_FMT, &mut format)?;
+
+        let pix = unsafe { format.data.pix };
+        let encoding = match pix.pixel_format {
+            v4l2::PIX_FMT_YUYV => Encoding::Yuyv,
+            v4l2::PIX_FMT_MJPEG => Encoding::Mjpeg,
+            v4l2::PIX_FMT_NV12 => Encoding::Nv12,
+            other => return Err(CameraError::UnsupportedFormat(String::from_utf8_lossy(&other.to_le_bytes()).into_owned())),
+        };
+
+        let mut request = v4l2::RequestBuffers { count: BUFFER_COUNT, kind: v4l2::BUF_TYPE_VIDEO_CAPTURE, memory: v4l2::MEMORY_MMAP, ..unsafe { mem::zeroed() } };
+        ioctl(fd, v4l2::VIDIOC_REQBUFS, &mut request)?;
+
+        let mut stream = Self {
+            file,
+            buffers: Vec::new(),
+            size: (pix.width, pix.height),
+            bytes_per_line: match encoding {
+                Encoding::Nv12 => pix.bytes_per_line.max(pix.width),
+                _ => pix.bytes_per_line.max(2 * pix.width),
+            },
+            encoding,
+        };
+
+        for index in 0..request.count {
+            let mut buffer = v4l2::Buffer::new(index);
+            ioctl(fd, v4l2::VIDIOC_QUERYBUF, &mut buffer)?;
+
+            let length = buffer.length as usize;
+            let offset = unsafe { buffer.location.offset } as libc::off_t;
+            let mapping = unsafe { libc::mmap(ptr::null_mut(), length, libc::PROT_READ, libc::MAP_SHARED, fd, offset) };
+
+            if mapping == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error().into());
+            }
+
+            stream.buffers.push((mapping, length));
+            ioctl(fd, v4l2::VIDIOC_QBUF, &mut buffer)?;
+        }
+
+        let mut kind = v4l2::BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        ioctl(fd, v4l2::VIDIOC_STREAMON, &mut kind)?;
+
+        Ok(stream)
+    }
+
+    // `None` when nothing came within `POLL_TIMEOUT_MS` or the frame was damaged
+    fn next_frame(&self) -> io::Result<Option<CameraFrame>> {
+        let fd = self.file.as_raw_fd();
+        let mut poll = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+
+        match unsafe { libc::poll(&mut poll, 1, POLL_TIMEOUT_MS) } {
+            0 => return Ok(None),
+            result if result < 0 => {
+                let error = io::Error::last_os_error();
+                return match error.kind() {
+                    io::ErrorKind::Interrupted => Ok(None),
+                    _ => Err(error),
+                };
+            },
+            _ => (),
+        }
+
+        let mut buffer = v4l2::Buffer::new(0);
+        match ioctl(fd, v4l2::VIDIOC_DQBUF, &mut buffer) {
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            result => result?,
+        }
+
+        let frame = self.convert(&buffer);
+        ioctl(fd, v4l2::VIDIOC_QBUF, &mut buffer)?;
+        Ok(frame)
+    }
+
+    fn convert(&self, buffer: &v4l2::Buffer) -> Option<CameraFrame> {
+        let &(mapping, length) = self.buffers.get(buffer.index as usize)?;
+        let data = unsafe { std::slice::from_raw_parts(mapping as *const u8, length) };
+        let data = &data[..(buffer.bytes_used as usize).min(length)];
+
+        if buffer.flags & v4l2::BUF_FLAG_ERROR != 0 {
+            return None;
+        }
+
+        let timestamp = Duration::new(buffer.timestamp.tv_sec as u64, buffer.timestamp.tv_usec as u32 * 1000);
+
+        let pixels = match self.encoding {
+            Encoding::Yuyv => self.yuyv_to_rgba(data)?,
+            Encoding::Nv12 => return self.nv12_frame(data, buffer.sequence, timestamp),
+            Encoding::Mjpeg => match image::load_from_memory_with_format(data, image::ImageFormat::Jpeg) {
+                Ok(image) if (image.width(), image.height()) == self.size => image.into_rgba8().into_vec(),
+                Ok(image) => {
+                    log::warn!("skipping a {}x{} camera frame in a {}x{} stream", image.width(), image.height(), self.size.0, self.size.1);
+                    return None;
+                },
+                Err(error) => {
+                    log::warn!("skipping a camera frame: {error}");
+                    return None;
+                },
+            },
+        };
+
+        Some(CameraFrame {
+            size: self.size,
+            sequence: buffer.sequence,
+            timestamp,
+            format: PixelFormat::Rgba8,
+            row_stride: None,
+            chroma: None,
+            buffer: pixels.into(),
+        })
+    }
+
+    // copied out of the driver's buffer as it is, the chroma plane follows the luma rows
+    fn nv12_frame(&self, data: &[u8], sequence: u32, timestamp: Duration) -> Option<CameraFrame> {
+        let (width, height) = (self.size.0 as usize, self.size.1 as usize);
+        let stride = self.bytes_per_line as usize;
+        let chroma = PlaneLayout { offset: stride * height, row_stride: stride };
+
+        if data.len() < chroma.offset + stride * (height.div_ceil(2).max(1) - 1) + 2 * width.div_ceil(2) {
+            return None;
+        }
+
+        Some(CameraFrame {
+            size: self.size,
+            sequence,
+            timestamp,
+            format: PixelFormat::Nv12,
+            row_stride: Some(stride),
+            chroma: Some(chroma),
+            buffer: data.into(),
+        })
+    }
+
+    // pairs of pixels share their chroma: y0 u y1 v
+    fn yuyv_to_rgba(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let (width, height) = (self.size.0 as usize, self.size.1 as usize);
+        let stride = self.bytes_per_line as usize;
+
+        if data.len() < stride * (height.max(1) - 1) + 2 * width {
+            return None;
+        }
+
+        let mut pixels = vec![0; width * height * 4];
+
+        for (row, target) in pixels.chunks_exact_mut(width * 4).enumerate() {
+            let source = &data[row * stride..row * stride + 2 * width];
+
+            for (pair, target) in source.chunks_exact(4).zip(target.chunks_mut(8)) {
+                let first = convert::yuv_to_rgba(pair[0], pair[1], pair[3]);
+                let second = convert::yuv_to_rgba(pair[2], pair[1], pair[3]);
+                target[..4].copy_from_slice(&first);
+                if target.len() == 8 {
+                    target[4..].copy_from_slice(&second);
+                }
+            }
+        }
+
+        Some(pixels)
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        let mut kind = v4l2::BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        let _ = ioctl(self.file.as_raw_fd(), v4l2::VIDIOC_STREAMOFF, &mut kind);
+
+        for &(mapping, length) in &self.buffers {
+            unsafe { libc::munmap(mapping, length) };
+        }
+    }
+}
+
+fn ioctl<T>(fd: RawFd, request: libc::c_ulong, argument: &mut T) -> io::Result<()> {
+    loop {
+        match unsafe { libc::ioctl(fd, request as _, argument as *mut T) } {
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+            -1 => return Err(io::Error::last_os_error()),
+            _ => return Ok(()),
+        }
+    }
+}
+
+// the parts of linux/videodev2.h the provider uses
+#[allow(dead_code)]
+mod v4l2 {
+    use std::ffi::c_void;
+    use std::mem::size_of;
+
+    pub const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+    pub const CAP_STREAMING: u32 = 0x0400_0000;
+    pub const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+
+    pub const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+    pub const MEMORY_MMAP: u32 = 1;
+    pub const BUF_FLAG_ERROR: u32 = 0x0000_0040;
+
+    pub const PIX_FMT_YUYV: u32 = fourcc(b"YUYV");
+    pub const PIX_FMT_MJPEG: u32 = fourcc(b"MJPG");
+    pub const PIX_FMT_NV12: u32 = fourcc(b"NV12");
+
+    pub const VIDIOC_QUERYCAP: libc::c_ulong = request(READ, 0, size_of::<Capability>());
+    pub const VIDIOC_S_FMT: libc::c_ulong = request(READ | WRITE, 5, size_of::<Format>());
+    pub const VIDIOC_REQBUFS: libc::c_ulong = request(READ | WRITE, 8, size_of::<RequestBuffers>());
+    pub const VIDIOC_QUERYBUF: libc::c_ulong = request(READ | WRITE, 9, size_of::<Buffer>());
+    pub const VIDIOC_QBUF: libc::c_ulong = request(READ | WRITE, 15, size_of::<Buffer>());
+    pub const VIDIOC_DQBUF: libc::c_ulong = request(READ | WRITE, 17, size_of::<Buffer>());
+    pub const VIDIOC_STREAMON: libc::c_ulong = request(WRITE, 18, size_of::<libc::c_int>());
+    pub const VIDIOC_STREAMOFF: libc::c_ulong = request(WRITE, 19, size_of::<libc::c_int>());
+
+    const WRITE: libc::c_ulong = 1;
+    const READ: libc::c_ulong = 2;
+
+    // _IOC with 'V' as the type
+    const fn request(direction: libc::c_ulong, number: libc::c_ulong, size: usize) -> libc::c_ulong {
+        (direction << 30) | ((size as libc::c_ulong) << 16) | ((b'V' as libc::c_ulong) << 8) | number
+    }
+
+    const fn fourcc(code: &[u8; 4]) -> u32 {
+        u32::from_le_bytes(*code)
+    }
+
+    #[repr(C)]
+    pub struct Capability {
+        pub driver: [u8; 16],
+        pub card: [u8; 32],
+        pub bus_info: [u8; 32],
+        pub version: u32,
+        pub capabilities: u32,
+        pub device_caps: u32,
+        pub reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct PixFormat {
+        pub width: u32,
+        pub height: u32,
+        pub pixel_format: u32,
+        pub field: u32,
+        pub bytes_per_line: u32,
+        pub size_image: u32,
+        pub colorspace: u32,
+        pub private: u32,
+        pub flags: u32,
+        pub ycbcr_encoding: u32,
+        pub quantization: u32,
+        pub transfer_function: u32,
+    }
+
+    // the union of every buffer type's format, only the capture one is used
+    #[repr(C)]
+    pub struct Format {
+        pub kind: u32,
+        pub data: FormatData,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub union FormatData {
+        pub pix: PixFormat,
+        pub raw: [u8; 200],
+        // the kernel's union holds pointers, which sets its alignment
+        pub align: *mut c_void,
+    }
+
+    #[repr(C)]
+    pub struct RequestBuffers {
+        pub count: u32,
+        pub kind: u32,
+        pub memory: u32,
+        pub capabilities: u32,
+        pub flags: u8,
+        pub reserved: [u8; 3],
+    }
+
+    #[repr(C)]
+    pub struct Timecode {
+        pub kind: u32,
+        pub flags: u32,
+        pub frames: u8,
+        pub seconds: u8,
+        pub minutes: u8,
+        pub hours: u8,
+        pub user_bits: [u8; 4],
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub union BufferLocation {
+        pub offset: u32,
+        pub user_pointer: libc::c_ulong,
+        pub planes: *mut c_void,
+        pub fd: i32,
+    }
+
+    #[repr(C)]
+    pub struct Buffer {
+        pub index: u32,
+        pub kind: u32,
+        pub bytes_used: u32,
+        pub flags: u32,
+        pub field: u32,
+        pub timestamp: libc::timeval,
+        pub timecode: Timecode,
+        pub sequence: u32,
+        pub memory: u32,
+        pub location: BufferLocation,
+        pub length: u32,
+        pub reserved2: u32,
+        pub request_fd: i32,
+    }
+
+    impl Buffer {
+        pub fn new(index: u32) -> Self {
+            Self { index, kind: BUF_TYPE_VIDEO_CAPTURE, memory: MEMORY_MMAP, ..unsafe { std::mem::zeroed() } }
+        }
+    }
+}
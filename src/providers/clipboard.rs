@@ -0,0 +1,115 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use super::image::{ImageFrame, ImageProvider};
+use crate::types::{FramePoll, FrameProvider, HasSize, Pair};
+
+// reading an image off the clipboard converts it on most platforms, so it isn't done on
+// every poll of the viewer
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+
+// the image on the clipboard, switching to every new one copied, e.g. for a window that
+// previews whatever was copied last
+// yields nothing until there is an image, text and other content is skipped and the
+// previous image stays
+#[derive(Debug)]
+pub struct ClipboardProvider {
+    receiver: mpsc::Receiver<ImageProvider>,
+    image: Option<ImageProvider>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ClipboardProvider {
+    pub fn watch() -> Result<Self, arboard::Error> {
+        Self::watch_every(DEFAULT_INTERVAL)
+    }
+
+    // the clipboard is read on a worker thread once per `interval`
+    pub fn watch_every(interval: Duration) -> Result<Self, arboard::Error> {
+        // fails right away rather than on the worker when there is no clipboard at all
+        let mut clipboard = arboard::Clipboard::new()?;
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+
+        thread::Builder::new()
+            .name(String::from("egami-clipboard"))
+            .spawn(move || {
+                let mut last = None;
+
+                while !stopped.load(Ordering::Relaxed) {
+                    if let Ok(image) = clipboard.get_image() {
+                        let mut hasher = DefaultHasher::new();
+                        (image.width, image.height, &image.bytes).hash(&mut hasher);
+                        let hash = hasher.finish();
+
+                        if last != Some(hash) {
+                            last = Some(hash);
+
+                            if let Some(provider) = to_provider(image) {
+                                if sender.send(provider).is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    thread::sleep(interval);
+                }
+            })
+            .expect("failed to spawn the clipboard thread");
+
+        Ok(Self { receiver, image: None, stop })
+    }
+
+    // the image shown now, `None` until one was copied
+    pub fn image(&self) -> Option<&ImageProvider> {
+        self.image.as_ref()
+    }
+}
+
+fn to_provider(image: arboard::ImageData) -> Option<ImageProvider> {
+    let (width, height) = (image.width as u32, image.height as u32);
+    let pixels = image::RgbaImage::from_raw(width, height, image.bytes.into_owned());
+
+    if pixels.is_none() {
+        log::warn!("skipping a {width}x{height} clipboard image with the wrong amount of data");
+    }
+
+    pixels.map(|pixels| ImageProvider::from_image(image::DynamicImage::ImageRgba8(pixels)))
+}
+
+impl Drop for ClipboardProvider {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for ClipboardProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(image) = self.receiver.try_iter().last() {
+            self.image = Some(image);
+        }
+
+        self.image.as_ref().and_then(|mut image| image.next())
+    }
+}
+
+// keeps watching, so it never finishes
+impl FrameProvider for ClipboardProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.image.as_ref().map(ImageProvider::size)
+    }
+}
@@ -0,0 +1,487 @@
+use std::time::{Duration, Instant};
+
+use super::image::ImageFrame;
+use super::next_content_id;
+use crate::types::{self, FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasSize, Pair, Rect};
+
+// samples per axis averaged into an output pixel when shrinking
+const SAMPLES: u32 = 4;
+
+// building blocks for processing and preview chains, e.g.
+// `camera.throttle(10.0).crop(region).scale(512)`
+// the combinators touching pixels hand out upright srgb rgba8 frames, a frame with the
+// content id of the one before isn't processed again, so a still costs nothing after its
+// first draw
+pub trait FrameProviderExt: FrameProvider + Sized {
+    // runs `map` over every pixel
+    fn map_pixels<F>(self, map: F) -> MapPixels<Self, F>
+    where
+        F: FnMut([u8; 4]) -> [u8; 4],
+    {
+        MapPixels { provider: self, map, processed: Processed::default() }
+    }
+
+    // at most `fps` frames a second, the provider isn't polled in between, so a live
+    // source skips frames rather than queueing them
+    fn throttle(self, fps: f32) -> Throttle<Self> {
+        let interval = match fps.is_finite() && fps > 0.0 {
+            true => Duration::from_secs_f32(1.0 / fps),
+            false => {
+                log::warn!("not throttling to {fps} fps");
+                Duration::ZERO
+            },
+        };
+
+        Throttle { provider: self, interval, last: None }
+    }
+
+    // finishes after `count` frames
+    fn take_frames(self, count: usize) -> TakeFrames<Self> {
+        TakeFrames { provider: self, count, remaining: count }
+    }
+
+    // `next` once this provider finished, or when stepping past its last image
+    // not `chain` to stay clear of `Iterator::chain`, which most providers also have
+    fn chain_frames<Next>(self, next: Next) -> Chain<Self, Next>
+    where
+        Next: FrameProvider<Frame = Self::Frame>,
+    {
+        Chain { first: self, second: next, on_second: false }
+    }
+
+    // the part of every frame inside `rect`, in upright frame pixels, clamped to the frame
+    fn crop(self, rect: Rect<u32>) -> Crop<Self> {
+        Crop { provider: self, rect, processed: Processed::default() }
+    }
+
+    // frames with a side longer than `max_dimension` shrunk to fit, smaller ones are
+    // only made upright
+    fn scale(self, max_dimension: u32) -> Scale<Self> {
+        Scale { provider: self, max_dimension: max_dimension.max(1), processed: Processed::default() }
+    }
+}
+
+impl<P: FrameProvider> FrameProviderExt for P {}
+
+// the last processed frame and the content id of the frame it came from
+#[derive(Debug, Default)]
+struct Processed {
+    source: Option<u64>,
+    frame: Option<ImageFrame>,
+}
+
+impl Processed {
+    fn get<Frame>(&mut self, frame: &Frame, process: impl FnOnce(&Frame) -> (Pair<u32>, Vec<u8>, f32)) -> ImageFrame
+    where
+        Frame: HasData,
+    {
+        if let (Some(source), Some(processed)) = (frame.content_id(), self.frame.as_ref()) {
+            if self.source == Some(source) {
+                return processed.clone();
+            }
+        }
+
+        let (size, pixels, scale) = process(frame);
+        let processed = ImageFrame::rgba8(size, pixels, scale, next_content_id());
+        self.source = frame.content_id();
+        self.frame = Some(processed.clone());
+        processed
+    }
+
+    fn size(&self) -> Option<Pair<u32>> {
+        self.frame.as_ref().map(HasSize::size)
+    }
+}
+
+// `area` of the upright frame as `size` upright rgba8 pixels, averaging a few samples per
+// pixel when shrinking
+fn resample<Frame>(frame: &Frame, area: Rect<u32>, size: Pair<u32>) -> Vec<u8>
+where
+    Frame: HasSize<u32> + HasFormat + HasOrientation + HasData,
+{
+    let stored = frame.size();
+    let orientation = frame.orientation();
+    let shown = orientation.displayed_size(stored);
+    let step = (area.size.0 as f32 / size.0 as f32, area.size.1 as f32 / size.1 as f32);
+    let samples = (step.0.max(step.1).ceil() as u32).clamp(1, SAMPLES);
+    let count = samples * samples;
+
+    let mut pixels = Vec::with_capacity(size.0 as usize * size.1 as usize * 4);
+
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let mut sum = [0u32; 4];
+
+            for sample in 0..count {
+                let point = (
+                    (area.position.0 as f32 + (x as f32 + ((sample % samples) as f32 + 0.5) / samples as f32) * step.0) / shown.0 as f32,
+                    (area.position.1 as f32 + (y as f32 + ((sample / samples) as f32 + 0.5) / samples as f32) * step.1) / shown.1 as f32,
+                );
+                let (u, v) = orientation.stored_point(point);
+                let column = ((u * stored.0 as f32) as u32).min(stored.0 - 1);
+                let row = ((v * stored.1 as f32) as u32).min(stored.1 - 1);
+
+                for (total, channel) in sum.iter_mut().zip(types::rgba8_at(frame, column, row).unwrap_or_default()) {
+                    *total += channel as u32;
+                }
+            }
+
+            pixels.extend(sum.map(|total| ((total + count / 2) / count) as u8));
+        }
+    }
+
+    pixels
+}
+
+fn upright<Frame: HasSize<u32> + HasOrientation>(frame: &Frame) -> Rect<u32> {
+    Rect::new((0, 0), frame.orientation().displayed_size(frame.size()))
+}
+
+pub struct MapPixels<P, F> {
+    provider: P,
+    map: F,
+    processed: Processed,
+}
+
+impl<P, F> FrameProvider for MapPixels<P, F>
+where
+    P: FrameProvider,
+    P::Frame: HasSize<u32> + HasFormat + HasOrientation + HasData,
+    F: FnMut([u8; 4]) -> [u8; 4],
+{
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        let (map, processed) = (&mut self.map, &mut self.processed);
+
+        self.provider.poll_frame().map(|frame| {
+            processed.get(&frame, |frame| {
+                let area = upright(frame);
+                let mut pixels = resample(frame, area, area.size);
+
+                for pixel in pixels.chunks_exact_mut(4) {
+                    let mapped = map([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                    pixel.copy_from_slice(&mapped);
+                }
+
+                (area.size, pixels, 1.0)
+            })
+        })
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.processed.size().or_else(|| self.provider.current_size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        self.provider.schedule()
+    }
+
+    fn rewind(&mut self) -> bool {
+        self.provider.rewind()
+    }
+
+    fn next_image(&mut self) -> bool {
+        self.provider.next_image()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        self.provider.previous_image()
+    }
+}
+
+pub struct Throttle<P> {
+    provider: P,
+    interval: Duration,
+    // when the last frame came through
+    last: Option<Instant>,
+}
+
+impl<P: FrameProvider> FrameProvider for Throttle<P> {
+    type Frame = P::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return FramePoll::Pending;
+        }
+
+        let poll = self.provider.poll_frame();
+        if let FramePoll::Ready(_) = poll {
+            self.last = Some(now);
+        }
+        poll
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.provider.current_size()
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        let schedule = self.provider.schedule();
+
+        // the provider isn't even asked until the interval is over
+        match self.last.map(|last| last + self.interval).filter(|next| *next > Instant::now()) {
+            Some(next) => match schedule {
+                FrameSchedule::At(at) => FrameSchedule::At(at.max(next)),
+                FrameSchedule::Soon | FrameSchedule::Still => FrameSchedule::At(next),
+            },
+            None => schedule,
+        }
+    }
+
+    // the first frame comes through right away
+    fn rewind(&mut self) -> bool {
+        self.last = None;
+        self.provider.rewind()
+    }
+
+    fn next_image(&mut self) -> bool {
+        self.last = None;
+        self.provider.next_image()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        self.last = None;
+        self.provider.previous_image()
+    }
+}
+
+pub struct TakeFrames<P> {
+    provider: P,
+    count: usize,
+    remaining: usize,
+}
+
+impl<P: FrameProvider> FrameProvider for TakeFrames<P> {
+    type Frame = P::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        if self.remaining == 0 {
+            return FramePoll::Finished;
+        }
+
+        let poll = self.provider.poll_frame();
+        if let FramePoll::Ready(_) = poll {
+            self.remaining -= 1;
+        }
+        poll
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.provider.current_size()
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        match self.remaining {
+            0 => FrameSchedule::Still,
+            _ => self.provider.schedule(),
+        }
+    }
+
+    // takes `count` frames again
+    fn rewind(&mut self) -> bool {
+        let rewound = self.provider.rewind();
+        if rewound {
+            self.remaining = self.count;
+        }
+        rewound
+    }
+
+    fn next_image(&mut self) -> bool {
+        self.provider.next_image()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        self.provider.previous_image()
+    }
+}
+
+pub struct Chain<First, Second> {
+    first: First,
+    second: Second,
+    on_second: bool,
+}
+
+impl<First, Second> FrameProvider for Chain<First, Second>
+where
+    First: FrameProvider,
+    Second: FrameProvider<Frame = First::Frame>,
+{
+    type Frame = First::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        if !self.on_second {
+            match self.first.poll_frame() {
+                FramePoll::Finished => self.on_second = true,
+                poll => return poll,
+            }
+        }
+
+        self.second.poll_frame()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        match self.on_second {
+            true => self.second.current_size(),
+            false => self.first.current_size(),
+        }
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        match self.on_second {
+            true => self.second.schedule(),
+            false => self.first.schedule(),
+        }
+    }
+
+    // back to the start of the first, the second starts over when it comes up again
+    fn rewind(&mut self) -> bool {
+        let rewound = self.first.rewind();
+        if rewound {
+            self.second.rewind();
+            self.on_second = false;
+        }
+        rewound
+    }
+
+    // past the first's last image is the second from its start
+    fn next_image(&mut self) -> bool {
+        match self.on_second {
+            true => self.second.next_image(),
+            false if self.first.next_image() => true,
+            false => {
+                self.second.rewind();
+                self.on_second = true;
+                true
+            },
+        }
+    }
+
+    // before the second's first image is the first from its start
+    fn previous_image(&mut self) -> bool {
+        match self.on_second {
+            false => self.first.previous_image(),
+            true if self.second.previous_image() => true,
+            true => {
+                self.first.rewind();
+                self.on_second = false;
+                true
+            },
+        }
+    }
+}
+
+pub struct Crop<P> {
+    provider: P,
+    rect: Rect<u32>,
+    processed: Processed,
+}
+
+impl<P> Crop<P> {
+    // `rect` inside a frame of `size`, at least a pixel large
+    fn area(&self, size: Pair<u32>) -> Rect<u32> {
+        let clamp = |position: u32, len: u32, total: u32| {
+            let start = position.min(total.saturating_sub(1));
+            (start, len.min(total - start).max(1))
+        };
+        let (x, width) = clamp(self.rect.position.0, self.rect.size.0, size.0);
+        let (y, height) = clamp(self.rect.position.1, self.rect.size.1, size.1);
+
+        Rect::new((x, y), (width, height))
+    }
+}
+
+impl<P> FrameProvider for Crop<P>
+where
+    P: FrameProvider,
+    P::Frame: HasSize<u32> + HasFormat + HasOrientation + HasData,
+{
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self.provider.poll_frame() {
+            FramePoll::Ready(frame) => {
+                let area = self.area(upright(&frame).size);
+                FramePoll::Ready(self.processed.get(&frame, |frame| (area.size, resample(frame, area, area.size), 1.0)))
+            },
+            FramePoll::Pending => FramePoll::Pending,
+            FramePoll::Finished => FramePoll::Finished,
+        }
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.processed.size().or_else(|| self.provider.current_size().map(|size| self.area(size).size))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        self.provider.schedule()
+    }
+
+    fn rewind(&mut self) -> bool {
+        self.provider.rewind()
+    }
+
+    fn next_image(&mut self) -> bool {
+        self.provider.next_image()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        self.provider.previous_image()
+    }
+}
+
+pub struct Scale<P> {
+    provider: P,
+    max_dimension: u32,
+    processed: Processed,
+}
+
+impl<P> Scale<P> {
+    // frames are only shrunk, never enlarged
+    fn fit(&self, size: Pair<u32>) -> (Pair<u32>, f32) {
+        let scale = (self.max_dimension as f32 / size.0.max(size.1).max(1) as f32).min(1.0);
+        let fitted = (((size.0 as f32 * scale).round() as u32).max(1), ((size.1 as f32 * scale).round() as u32).max(1));
+        (fitted, scale)
+    }
+}
+
+impl<P> FrameProvider for Scale<P>
+where
+    P: FrameProvider,
+    P::Frame: HasSize<u32> + HasFormat + HasOrientation + HasData,
+{
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self.provider.poll_frame() {
+            FramePoll::Ready(frame) => {
+                let area = upright(&frame);
+                let (size, scale) = self.fit(area.size);
+                FramePoll::Ready(self.processed.get(&frame, |frame| (size, resample(frame, area, size), scale)))
+            },
+            FramePoll::Pending => FramePoll::Pending,
+            FramePoll::Finished => FramePoll::Finished,
+        }
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.processed.size().or_else(|| self.provider.current_size().map(|size| self.fit(size).0))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        self.provider.schedule()
+    }
+
+    fn rewind(&mut self) -> bool {
+        self.provider.rewind()
+    }
+
+    fn next_image(&mut self) -> bool {
+        self.provider.next_image()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        self.provider.previous_image()
+    }
+}
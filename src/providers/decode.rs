@@ -0,0 +1,791 @@
+use std::fmt;
+use std::io::{self, BufRead, Cursor, Read, Seek, SeekFrom};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, ImageDecoder};
+
+use super::{icon, portable, qoi};
+use super::sniff::Diagnosis;
+use crate::types::{Orientation, Pair};
+
+#[cfg(feature = "heic")]
+use super::heic;
+
+// shared flag a caller sets to abandon a load it no longer needs, e.g. because the user
+// moved on to another image, clones share the flag
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    // a decode holding the token gives up past this, as timed out
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn with_deadline(&self, deadline: Instant) -> Self {
+        Self { cancelled: Arc::clone(&self.cancelled), deadline: Some(deadline) }
+    }
+
+    fn is_past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+// tokens are equal when they share a flag
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+// caps applied before and during decoding so a hostile file can't exhaust memory or hang the caller
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeLimits {
+    pub max_input_bytes: usize,
+    // both apply to the size an image is decoded at, which for a jpeg decoded at a reduced
+    // scale is smaller than the stored one
+    pub max_size: Pair<u32>,
+    pub max_alloc: u64,
+    // decoded images over either budget are downscaled instead of rejected, jpegs are
+    // decoded at 1/2, 1/4 or 1/8 scale on the way, other formats still need the full-size
+    // image to fit within `max_alloc`
+    pub max_pixels: u64,
+    pub max_dimension: u32,
+    // decoding runs on a worker thread, `None` waits forever, a cancellable decode runs on
+    // the calling thread instead and gives up once it notices the time is up
+    pub timeout: Option<Duration>,
+    // a truncated or corrupt still keeps the rows decoded before the failure instead of
+    // failing, see `DecodedImage::salvage`
+    pub salvage: bool,
+    // a cancelled load fails with `DecodeError::Cancelled`, decoders check the token as
+    // they read their input, so most formats stop within a few rows, and between stages
+    pub cancel: Option<CancelToken>,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: 256 * 1024 * 1024,
+            max_size: (16384, 16384),
+            max_alloc: 1024 * 1024 * 1024,
+            max_pixels: 64 * 1024 * 1024,
+            // wgpu's default max_texture_dimension_2d
+            max_dimension: 8192,
+            timeout: Some(Duration::from_secs(10)),
+            salvage: false,
+            cancel: None,
+        }
+    }
+}
+
+impl DecodeLimits {
+    pub fn none() -> Self {
+        Self {
+            max_input_bytes: usize::MAX,
+            max_size: (u32::MAX, u32::MAX),
+            max_alloc: u64::MAX,
+            max_pixels: u64::MAX,
+            max_dimension: u32::MAX,
+            timeout: None,
+            salvage: false,
+            cancel: None,
+        }
+    }
+
+    // these limits with loads abandoned once `token` is cancelled
+    pub fn cancellable(&self, token: CancelToken) -> Self {
+        Self { cancel: Some(token), ..self.clone() }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    fn should_stop(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|token| token.is_cancelled() || token.is_past_deadline())
+    }
+
+    // fails a cancelled or timed out decode between two stages, `run_limited` turns the
+    // error into the right `DecodeError`
+    pub(crate) fn check_stop(&self) -> Result<(), image::ImageError> {
+        match self.should_stop() {
+            true => Err(image::ImageError::IoError(stopped())),
+            false => Ok(()),
+        }
+    }
+
+    // `bytes` to hand to a decoder, reads fail once the decode should stop
+    pub(crate) fn reader<'a>(&'a self, bytes: &'a [u8]) -> StoppableReader<'a> {
+        StoppableReader { cursor: Cursor::new(bytes), limits: self }
+    }
+
+    // the size a source of `size` is decoded to under the pixel and dimension budgets
+    pub fn budgeted_size(&self, size: Pair<u32>) -> Pair<u32> {
+        let pixels = size.0 as u64 * size.1 as u64;
+        let largest = size.0.max(size.1);
+
+        let pixel_scale = match pixels > self.max_pixels {
+            true => (self.max_pixels as f64 / pixels as f64).sqrt(),
+            false => 1.0,
+        };
+
+        let dimension_scale = match largest > self.max_dimension {
+            true => self.max_dimension as f64 / largest as f64,
+            false => 1.0,
+        };
+
+        let scale = pixel_scale.min(dimension_scale);
+
+        (
+            ((size.0 as f64 * scale).floor() as u32).max(1),
+            ((size.1 as f64 * scale).floor() as u32).max(1),
+        )
+    }
+
+    pub(crate) fn image_limits(&self) -> image::Limits {
+        let mut limits = image::Limits::no_limits();
+        limits.max_image_width = Some(self.max_size.0);
+        limits.max_image_height = Some(self.max_size.1);
+        limits.max_alloc = Some(self.max_alloc);
+        limits
+    }
+}
+
+fn stopped() -> io::Error {
+    io::Error::other("decoding was stopped")
+}
+
+// the input of a decode, checking its limits on every read
+pub(crate) struct StoppableReader<'a> {
+    cursor: Cursor<&'a [u8]>,
+    limits: &'a DecodeLimits,
+}
+
+impl Read for StoppableReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.limits.should_stop() {
+            true => Err(stopped()),
+            false => self.cursor.read(buf),
+        }
+    }
+}
+
+impl BufRead for StoppableReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self.limits.should_stop() {
+            true => Err(stopped()),
+            false => self.cursor.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.cursor.consume(amount);
+    }
+}
+
+impl Seek for StoppableReader<'_> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.cursor.seek(position)
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    InputTooLarge { len: usize, max: usize },
+    Image(image::ImageError),
+    TimedOut(Duration),
+    // the load's `CancelToken` was cancelled
+    Cancelled,
+    WorkerPanicked,
+    // `error` with what the file's name and first bytes say about it
+    Diagnosed { diagnosis: Diagnosis, error: Box<DecodeError> },
+}
+
+impl DecodeError {
+    // why the file may not have decoded, e.g. it is a webp named .png
+    pub fn diagnosis(&self) -> Option<&Diagnosis> {
+        match self {
+            DecodeError::Diagnosed { diagnosis, .. } => Some(diagnosis),
+            _ => None,
+        }
+    }
+
+    // limits and timeouts don't depend on the format, they are left as they are
+    pub(crate) fn diagnosed(self, diagnosis: Option<Diagnosis>) -> Self {
+        match (self, diagnosis) {
+            (error @ DecodeError::Image(_), Some(diagnosis)) => DecodeError::Diagnosed { diagnosis, error: Box::new(error) },
+            (error, _) => error,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InputTooLarge { len, max } => write!(f, "input is {len} bytes, limit is {max}"),
+            DecodeError::Image(error) => write!(f, "{error}"),
+            DecodeError::TimedOut(timeout) => write!(f, "decoding did not finish within {timeout:?}"),
+            DecodeError::Cancelled => write!(f, "decoding was cancelled"),
+            DecodeError::WorkerPanicked => write!(f, "decoder panicked"),
+            DecodeError::Diagnosed { diagnosis, error } => write!(f, "{diagnosis} ({error})"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Image(error) => Some(error),
+            DecodeError::Diagnosed { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for DecodeError {
+    fn from(error: image::ImageError) -> Self {
+        DecodeError::Image(error)
+    }
+}
+
+// what is known about an image that didn't decode in full, e.g. a recovered file cut short
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Salvage {
+    // rows from the top holding decoded pixels, the rest are black, `None` when the
+    // decoder filled them in itself
+    pub decoded_rows: Option<u32>,
+    pub rows: u32,
+    // what stopped the decoder
+    pub reason: String,
+}
+
+impl fmt::Display for Salvage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.decoded_rows {
+            Some(decoded_rows) => write!(f, "damaged, {decoded_rows} of {} rows decoded: {}", self.rows, self.reason),
+            None => write!(f, "damaged: {}", self.reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    pub image: image::DynamicImage,
+    pub source_size: Pair<u32>,
+    // the embedded icc profile, `None` means srgb
+    pub icc_profile: Option<Vec<u8>>,
+    // from the exif block, the pixels are left as stored
+    pub orientation: Orientation,
+    // set when only part of the image could be decoded
+    pub salvage: Option<Salvage>,
+}
+
+impl DecodedImage {
+    pub fn scale(&self) -> f32 {
+        self.image.width() as f32 / self.source_size.0 as f32
+    }
+}
+
+// pixels, the icc profile and what is known about missing parts
+type Decoded = (image::DynamicImage, Option<Vec<u8>>, Option<Salvage>);
+
+// the image with its embedded icc profile, a profile that can't be read is ignored
+fn decode_with_profile(bytes: &[u8], limits: &DecodeLimits) -> Result<Decoded, image::ImageError> {
+    let mut decoder = guarded_decoder(bytes, limits)?;
+
+    let icc_profile = decoder.icc_profile().unwrap_or_else(|error| {
+        log::debug!("ignoring the icc profile: {error}");
+        None
+    });
+
+    match image::DynamicImage::from_decoder(decoder) {
+        Ok(image) => {
+            let salvage = missing_jpeg_end(bytes, image.height());
+            Ok((image, icc_profile, salvage))
+        },
+        Err(error @ (image::ImageError::Decoding(_) | image::ImageError::IoError(_))) if limits.salvage => {
+            let (image, salvage) = salvage(bytes, limits, error)?;
+            Ok((image, icc_profile, Some(salvage)))
+        },
+        Err(error) => Err(error),
+    }
+}
+
+// from the exif of the formats the image crate reads, only the headers are parsed so no
+// limits apply, anything else is upright
+fn read_orientation(bytes: &[u8]) -> Orientation {
+    let mut reader = image::ImageReader::new(Cursor::new(bytes));
+    reader.no_limits();
+
+    let orientation = reader.with_guessed_format().ok().and_then(|reader| reader.into_decoder().ok()?.orientation().ok());
+    orientation.and_then(|orientation| Orientation::from_exif(orientation.to_exif().into())).unwrap_or_default()
+}
+
+fn guarded_decoder<'a>(bytes: &'a [u8], limits: &'a DecodeLimits) -> Result<Box<dyn ImageDecoder + 'a>, image::ImageError> {
+    let mut reader = image::ImageReader::new(limits.reader(bytes)).with_guessed_format()?;
+    reader.limits(limits.image_limits());
+
+    let decoder = reader.into_decoder()?;
+    if decoder.total_bytes() > limits.max_alloc {
+        return Err(image::ImageError::Limits(image::error::LimitError::from_kind(image::error::LimitErrorKind::InsufficientMemory)));
+    }
+
+    Ok(Box::new(decoder))
+}
+
+// decodes again into a zeroed buffer, decoders write rows as they go so whatever came
+// before `error` is there, rows past the last one with a set byte count as missing
+fn salvage(bytes: &[u8], limits: &DecodeLimits, error: image::ImageError) -> Result<(image::DynamicImage, Salvage), image::ImageError> {
+    let decoder = guarded_decoder(bytes, limits)?;
+    let (width, height) = decoder.dimensions();
+    let color = decoder.color_type();
+
+    let mut buffer = vec![0; decoder.total_bytes() as usize];
+    let _ = decoder.read_image(&mut buffer);
+
+    let row_bytes = (width as usize * color.bytes_per_pixel() as usize).max(1);
+    let decoded_rows = buffer.chunks(row_bytes).rposition(|row| row.iter().any(|&byte| byte != 0)).map_or(0, |last| last + 1);
+
+    let image = match decoded_rows {
+        0 => None,
+        _ => image_from_buffer((width, height), color, buffer),
+    };
+    let Some(image) = image else {
+        return Err(error);
+    };
+
+    log::warn!("showing {decoded_rows} of {height} rows, {error}");
+
+    Ok((image, Salvage { decoded_rows: Some(decoded_rows as u32), rows: height, reason: error.to_string() }))
+}
+
+fn image_from_buffer(size: Pair<u32>, color: image::ColorType, buffer: Vec<u8>) -> Option<image::DynamicImage> {
+    use image::{ColorType, DynamicImage, ImageBuffer};
+
+    let (width, height) = size;
+    let u16s = |buffer: Vec<u8>| buffer.chunks_exact(2).map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]])).collect::<Vec<_>>();
+    let f32s = |buffer: Vec<u8>| buffer.chunks_exact(4).map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).collect::<Vec<_>>();
+
+    match color {
+        ColorType::L8 => ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageLuma8),
+        ColorType::La8 => ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageLumaA8),
+        ColorType::Rgb8 => ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageRgb8),
+        ColorType::Rgba8 => ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageRgba8),
+        ColorType::L16 => ImageBuffer::from_raw(width, height, u16s(buffer)).map(DynamicImage::ImageLuma16),
+        ColorType::La16 => ImageBuffer::from_raw(width, height, u16s(buffer)).map(DynamicImage::ImageLumaA16),
+        ColorType::Rgb16 => ImageBuffer::from_raw(width, height, u16s(buffer)).map(DynamicImage::ImageRgb16),
+        ColorType::Rgba16 => ImageBuffer::from_raw(width, height, u16s(buffer)).map(DynamicImage::ImageRgba16),
+        ColorType::Rgb32F => ImageBuffer::from_raw(width, height, f32s(buffer)).map(DynamicImage::ImageRgb32F),
+        ColorType::Rgba32F => ImageBuffer::from_raw(width, height, f32s(buffer)).map(DynamicImage::ImageRgba32F),
+        _ => None,
+    }
+}
+
+// the jpeg decoder fills in what is missing after a cut without failing, only the end of
+// image marker gives it away, zeros recovery tools pad files with are skipped
+fn missing_jpeg_end(bytes: &[u8], rows: u32) -> Option<Salvage> {
+    let end = bytes.iter().rposition(|&byte| byte != 0).map_or(0, |last| last + 1);
+
+    match bytes.starts_with(&[0xff, 0xd8]) && !bytes[..end].ends_with(&[0xff, 0xd9]) {
+        true => Some(Salvage { decoded_rows: None, rows, reason: String::from("the file ends before the image does") }),
+        false => None,
+    }
+}
+
+// a jpeg over the pixel budget at the scale that brings it closest to the budget without
+// going under, the decoder skips the high frequencies instead of decoding every pixel only
+// to shrink the image afterwards, `None` when it fits or isn't gray or rgb
+fn decode_jpeg_reduced(bytes: &[u8], limits: &DecodeLimits) -> Result<Option<(Pair<u32>, Decoded)>, image::ImageError> {
+    use image::error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind};
+    use jpeg_decoder::PixelFormat;
+
+    fn error(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> image::ImageError {
+        image::ImageError::Decoding(DecodingError::new(ImageFormatHint::Exact(image::ImageFormat::Jpeg), error))
+    }
+
+    // jpegs this decoder can't read, e.g. arithmetic coded ones, are left to the full decode
+    let mut decoder = jpeg_decoder::Decoder::new(limits.reader(bytes));
+    let Some(info) = decoder.read_info().ok().and_then(|()| decoder.info()).filter(|info| matches!(info.pixel_format, PixelFormat::L8 | PixelFormat::RGB24)) else {
+        return Ok(None);
+    };
+
+    let source_size = (info.width as u32, info.height as u32);
+    let budgeted_size = limits.budgeted_size(source_size);
+    if budgeted_size == source_size {
+        return Ok(None);
+    }
+
+    let (width, height) = decoder.scale(budgeted_size.0 as u16, budgeted_size.1 as u16).map_err(error)?;
+    let size = (width as u32, height as u32);
+    let len = size.0 as u64 * size.1 as u64 * info.pixel_format.pixel_bytes() as u64;
+
+    if size.0 > limits.max_size.0 || size.1 > limits.max_size.1 {
+        return Err(image::ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+
+    if len > limits.max_alloc {
+        return Err(image::ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)));
+    }
+
+    // progressive jpegs keep their coefficients at full size
+    decoder.set_max_decoding_buffer_size(usize::try_from(limits.max_alloc).unwrap_or(usize::MAX));
+    let pixels = decoder.decode().map_err(error)?;
+
+    let image = match info.pixel_format {
+        PixelFormat::L8 => image::ImageBuffer::from_raw(size.0, size.1, pixels).map(image::DynamicImage::ImageLuma8),
+        _ => image::ImageBuffer::from_raw(size.0, size.1, pixels).map(image::DynamicImage::ImageRgb8),
+    };
+    let image = image.ok_or_else(|| error("decoded fewer pixels than the frame has"))?;
+
+    log::info!("decoded the {source_size:?} jpeg at {size:?}");
+
+    Ok(Some((source_size, (image, decoder.icc_profile(), missing_jpeg_end(bytes, size.1)))))
+}
+
+pub(super) fn decode_unguarded(bytes: &[u8], limits: &DecodeLimits) -> Result<DecodedImage, image::ImageError> {
+    let reduced = match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Jpeg) => decode_jpeg_reduced(bytes, limits)?,
+        _ => None,
+    };
+
+    let (source_size, (image, icc_profile, salvage)) = match reduced {
+        Some(reduced) => reduced,
+        None => {
+            let decoded = match () {
+                _ if portable::is_pfm(bytes) => (portable::decode_pfm(bytes, limits)?, None, None),
+                _ if qoi::is_qoi(bytes) => (qoi::decode_qoi(bytes, limits)?, None, None),
+                #[cfg(feature = "heic")]
+                _ if heic::is_heif(bytes) => (heic::decode_heif(bytes, limits)?, None, None),
+                _ => decode_with_profile(bytes, limits)?,
+            };
+
+            ((decoded.0.width(), decoded.0.height()), decoded)
+        },
+    };
+
+    limits.check_stop()?;
+    let orientation = read_orientation(bytes);
+    let budgeted_size = limits.budgeted_size(source_size);
+
+    if budgeted_size == (image.width(), image.height()) {
+        return Ok(DecodedImage { image, source_size, icc_profile, orientation, salvage });
+    }
+
+    log::info!("downscaling {source_size:?} to {budgeted_size:?} to stay within the decode budget");
+
+    Ok(DecodedImage {
+        source_size,
+        image: image.resize_exact(budgeted_size.0, budgeted_size.1, image::imageops::FilterType::Triangle),
+        icc_profile,
+        orientation,
+        salvage,
+    })
+}
+
+// every decode in the crate goes through here
+pub fn decode(bytes: Vec<u8>, limits: &DecodeLimits) -> Result<DecodedImage, DecodeError> {
+    run_limited(bytes, limits, decode_unguarded)
+}
+
+// gif, apng and ani cursors, still pngs decode to a single frame
+pub fn decode_animation(bytes: Vec<u8>, limits: &DecodeLimits) -> Result<DecodedAnimation, DecodeError> {
+    run_limited(bytes, limits, decode_animation_unguarded)
+}
+
+pub(crate) fn run_limited<T: Send + 'static>(
+    bytes: Vec<u8>,
+    limits: &DecodeLimits,
+    decode: fn(&[u8], &DecodeLimits) -> Result<T, image::ImageError>,
+) -> Result<T, DecodeError> {
+    if bytes.len() > limits.max_input_bytes {
+        return Err(DecodeError::InputTooLarge { len: bytes.len(), max: limits.max_input_bytes });
+    }
+
+    if limits.is_cancelled() {
+        return Err(DecodeError::Cancelled);
+    }
+
+    match (&limits.cancel, limits.timeout) {
+        (None, Some(timeout)) => run_on_worker(bytes, limits, timeout, decode),
+        (Some(token), Some(timeout)) => {
+            let limits = DecodeLimits { cancel: Some(token.with_deadline(Instant::now() + timeout)), ..limits.clone() };
+            run_here(&bytes, &limits, decode)
+        },
+        (_, None) => run_here(&bytes, limits, decode),
+    }
+}
+
+// cancellable decodes run on the caller's thread, e.g. a `Scheduler` worker, so the
+// number of decodes running is the number of threads asking for them
+fn run_here<T>(bytes: &[u8], limits: &DecodeLimits, decode: fn(&[u8], &DecodeLimits) -> Result<T, image::ImageError>) -> Result<T, DecodeError> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| decode(bytes, limits))).map_err(|_| DecodeError::WorkerPanicked)?;
+    let timeout = limits.timeout.unwrap_or_default();
+
+    match limits.cancel.as_ref() {
+        Some(token) if token.is_cancelled() => Err(DecodeError::Cancelled),
+        Some(token) if result.is_err() && token.is_past_deadline() => Err(DecodeError::TimedOut(timeout)),
+        _ => Ok(result?),
+    }
+}
+
+// the worker's decode stops reading at the timeout like a cancellable one, a decoder stuck
+// past it anyway is left to finish on its own thread, its result is dropped
+fn run_on_worker<T: Send + 'static>(
+    bytes: Vec<u8>,
+    limits: &DecodeLimits,
+    timeout: Duration,
+    decode: fn(&[u8], &DecodeLimits) -> Result<T, image::ImageError>,
+) -> Result<T, DecodeError> {
+    let worker_limits = DecodeLimits { cancel: Some(CancelToken::new().with_deadline(Instant::now() + timeout)), ..limits.clone() };
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+
+    std::thread::Builder::new()
+        .name(String::from("egami-decode"))
+        .spawn(move || {
+            let _ = sender.send(run_here(&bytes, &worker_limits, decode));
+        })
+        .map_err(|error| DecodeError::Image(image::ImageError::IoError(error)))?;
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(DecodeError::WorkerPanicked),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(DecodeError::TimedOut(timeout)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedFrame {
+    pub image: image::RgbaImage,
+    pub delay: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedAnimation {
+    pub frames: Vec<DecodedFrame>,
+    pub source_size: Pair<u32>,
+}
+
+fn decode_animation_unguarded(bytes: &[u8], limits: &DecodeLimits) -> Result<DecodedAnimation, image::ImageError> {
+    if icon::is_ani(bytes) {
+        return icon::decode_ani(bytes, limits);
+    }
+
+    let frames = match image::guess_format(bytes)? {
+        image::ImageFormat::Gif => {
+            let mut decoder = GifDecoder::new(limits.reader(bytes))?;
+            decoder.set_limits(limits.image_limits())?;
+            decoder.into_frames()
+        },
+        image::ImageFormat::Png => {
+            let decoder = PngDecoder::with_limits(limits.reader(bytes), limits.image_limits())?;
+
+            match decoder.is_apng()? {
+                true => decoder.apng()?.into_frames(),
+                false => {
+                    let image = image::DynamicImage::from_decoder(decoder)?.into_rgba8();
+                    let source_size = image.dimensions();
+                    let image = fit_budget(image, limits);
+
+                    return Ok(DecodedAnimation { frames: vec![DecodedFrame { image, delay: Duration::ZERO }], source_size });
+                },
+            }
+        },
+        format => return Err(image::ImageError::Unsupported(image::error::ImageFormatHint::Exact(format).into())),
+    };
+
+    // the per-image limits don't cover the sum of all frames
+    let mut allocated = 0u64;
+    let mut source_size = (0, 0);
+    let mut decoded = Vec::new();
+
+    for frame in frames {
+        limits.check_stop()?;
+        let frame = frame?;
+        let delay = Duration::from(frame.delay());
+        let image = frame.into_buffer();
+
+        source_size = image.dimensions();
+        let image = fit_budget(image, limits);
+
+        allocated += image.as_raw().len() as u64;
+        if allocated > limits.max_alloc {
+            return Err(image::ImageError::Limits(image::error::LimitError::from_kind(
+                image::error::LimitErrorKind::InsufficientMemory,
+            )));
+        }
+
+        decoded.push(DecodedFrame { image, delay });
+    }
+
+    Ok(DecodedAnimation { frames: decoded, source_size })
+}
+
+fn fit_budget(image: image::RgbaImage, limits: &DecodeLimits) -> image::RgbaImage {
+    let budgeted_size = limits.budgeted_size(image.dimensions());
+
+    match budgeted_size == image.dimensions() {
+        true => image,
+        false => image::imageops::resize(&image, budgeted_size.0, budgeted_size.1, image::imageops::FilterType::Triangle),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg(size: Pair<u32>) -> Vec<u8> {
+        let image = image::RgbImage::from_fn(size.0, size.1, |x, y| image::Rgb([x as u8, y as u8, 128]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Jpeg).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn default_limits_cap_the_size() {
+        assert_eq!(DecodeLimits::default().max_size, (16384, 16384));
+    }
+
+    #[test]
+    fn budgeted_size_keeps_the_aspect() {
+        let limits = DecodeLimits { max_pixels: 100 * 50, max_dimension: 80, ..DecodeLimits::default() };
+
+        assert_eq!(limits.budgeted_size((64, 32)), (64, 32));
+        assert_eq!(limits.budgeted_size((400, 200)), (80, 40));
+        assert_eq!(limits.budgeted_size((200, 800)), (20, 80));
+        assert_eq!(limits.budgeted_size((100_000, 1)), (80, 1));
+    }
+
+    #[test]
+    fn large_jpeg_is_decoded_at_a_reduced_scale() {
+        let bytes = jpeg((512, 256));
+        // too little for the full image, enough for an eighth of it
+        let limits = DecodeLimits { max_pixels: 40 * 20, max_alloc: 512 * 256 * 3 / 4, timeout: None, ..DecodeLimits::default() };
+
+        let decoded = decode(bytes.clone(), &limits).unwrap();
+        assert_eq!(decoded.source_size, (512, 256));
+        assert_eq!((decoded.image.width(), decoded.image.height()), (40, 20));
+
+        let full = DecodeLimits { max_pixels: u64::MAX, ..limits };
+        assert!(matches!(decode(bytes, &full), Err(DecodeError::Image(image::ImageError::Limits(_)))));
+    }
+
+    #[test]
+    fn reduced_jpeg_still_respects_the_size_limit() {
+        let limits = DecodeLimits { max_pixels: 100 * 50, max_size: (32, 32), timeout: None, ..DecodeLimits::default() };
+
+        assert!(decode(jpeg((512, 256)), &limits).is_err());
+    }
+
+    #[test]
+    fn orientation_comes_from_the_exif() {
+        use image::codecs::png::PngEncoder;
+        use image::{ExtendedColorType, ImageEncoder};
+
+        // a big endian tiff with just the orientation, rotated by 90 degrees
+        let exif = [&b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0"[..], &[0; 4]].concat();
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        encoder.set_exif_metadata(exif).unwrap();
+        encoder.write_image(&[0; 3 * 8], 4, 2, ExtendedColorType::Rgb8).unwrap();
+
+        assert_eq!(read_orientation(&png), Orientation::Rotate90);
+        assert_eq!(read_orientation(&jpeg((4, 2))), Orientation::Normal);
+        assert_eq!(read_orientation(b"not an image"), Orientation::Normal);
+    }
+
+    fn thread(_: &[u8], _: &DecodeLimits) -> Result<std::thread::ThreadId, image::ImageError> {
+        Ok(std::thread::current().id())
+    }
+
+    #[test]
+    fn cancellable_decodes_run_on_the_calling_thread() {
+        let limits = DecodeLimits::default().cancellable(CancelToken::new());
+
+        assert_eq!(run_limited(Vec::new(), &limits, thread).unwrap(), std::thread::current().id());
+        assert_ne!(run_limited(Vec::new(), &DecodeLimits::default(), thread).unwrap(), std::thread::current().id());
+    }
+
+    #[test]
+    fn cancellable_decodes_stop_reading_past_the_timeout() {
+        let limits = DecodeLimits { timeout: Some(Duration::ZERO), ..DecodeLimits::default() }.cancellable(CancelToken::new());
+
+        assert!(matches!(decode(jpeg((64, 64)), &limits), Err(DecodeError::TimedOut(Duration::ZERO))));
+    }
+
+    #[test]
+    fn timed_out_workers_stop_decoding() {
+        static STOPPED: AtomicBool = AtomicBool::new(false);
+
+        fn spins(_: &[u8], limits: &DecodeLimits) -> Result<(), image::ImageError> {
+            while limits.check_stop().is_ok() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+
+            STOPPED.store(true, Ordering::Relaxed);
+            limits.check_stop()
+        }
+
+        let timeout = Duration::from_millis(20);
+        let limits = DecodeLimits { timeout: Some(timeout), ..DecodeLimits::default() };
+        assert!(matches!(run_limited(Vec::new(), &limits, spins), Err(DecodeError::TimedOut(_))));
+
+        let started = Instant::now();
+        while !STOPPED.load(Ordering::Relaxed) && started.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(STOPPED.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn panicking_decodes_are_reported() {
+        fn panics(_: &[u8], _: &DecodeLimits) -> Result<(), image::ImageError> {
+            panic!("decoder bug");
+        }
+
+        let limits = DecodeLimits::default().cancellable(CancelToken::new());
+        assert!(matches!(run_limited(Vec::new(), &limits, panics), Err(DecodeError::WorkerPanicked)));
+    }
+
+    #[test]
+    fn stopped_reader_fails_reads() {
+        let token = CancelToken::new();
+        let limits = DecodeLimits::default().cancellable(token.clone());
+        let mut reader = limits.reader(&[1, 2, 3]);
+
+        let mut byte = [0];
+        assert_eq!(reader.read(&mut byte).unwrap(), 1);
+
+        token.cancel();
+        assert!(reader.read(&mut byte).is_err());
+        assert!(limits.check_stop().is_err());
+    }
+
+    #[test]
+    fn input_limit_and_cancellation() {
+        let bytes = jpeg((8, 8));
+
+        let small = DecodeLimits { max_input_bytes: 4, ..DecodeLimits::default() };
+        assert!(matches!(decode(bytes.clone(), &small), Err(DecodeError::InputTooLarge { max: 4, .. })));
+
+        let token = CancelToken::new();
+        token.cancel();
+        assert!(matches!(decode(bytes, &DecodeLimits::default().cancellable(token)), Err(DecodeError::Cancelled)));
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::SystemTime;
+use std::{fs, io};
+
+use super::decode::{DecodeError, DecodeLimits};
+use super::file::FileImageProvider;
+use super::image::ImageFrame;
+use super::scheduler::{JobHandle, Priority, Scheduler};
+use super::sniff::Signature;
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasOrientation, HasSize, Pair};
+
+// images this far before and after the current one are decoded ahead of time
+const PRELOAD_DISTANCE: usize = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    // by file name, bytes compared as they are
+    #[default]
+    Name,
+    // oldest first
+    Modified,
+    // smallest first
+    Size,
+}
+
+type Decoded = (usize, Result<FileImageProvider, DecodeError>);
+
+// the images in a directory one at a time, the neighbours of the one shown are decoded on
+// a `Scheduler` as prefetches so stepping to them doesn't stall on a decode, the one shown
+// is decoded ahead of them
+// stepping past an image cancels its decode, so skipping through a directory doesn't queue
+// up decodes of images that are no longer wanted
+// subdirectories and files of formats the crate can't decode are left out
+#[derive(Debug)]
+pub struct DirectoryProvider {
+    paths: Vec<PathBuf>,
+    index: usize,
+    images: HashMap<usize, Result<FileImageProvider, DecodeError>>,
+    // decodes queued that haven't come back yet
+    requested: Vec<(usize, JobHandle)>,
+    scheduler: Scheduler,
+    limits: DecodeLimits,
+    sender: mpsc::Sender<Decoded>,
+    receiver: mpsc::Receiver<Decoded>,
+}
+
+impl DirectoryProvider {
+    pub fn new<P: AsRef<Path>>(path: P, sort: SortOrder) -> io::Result<Self> {
+        Self::with_limits(path, sort, DecodeLimits::default())
+    }
+
+    pub fn with_limits<P: AsRef<Path>>(path: P, sort: SortOrder, limits: DecodeLimits) -> io::Result<Self> {
+        Self::with_scheduler(path, sort, limits, Scheduler::global()?)
+    }
+
+    // decodes on `scheduler` instead of the global one, e.g. to share workers with other
+    // providers
+    pub fn with_scheduler<P: AsRef<Path>>(path: P, sort: SortOrder, limits: DecodeLimits, scheduler: Scheduler) -> io::Result<Self> {
+        let paths = list_images(path.as_ref(), sort)?;
+        let (sender, receiver) = mpsc::channel();
+
+        let mut provider = Self { paths, index: 0, images: HashMap::new(), requested: Vec::new(), scheduler, limits, sender, receiver };
+        provider.preload();
+        Ok(provider)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    // index of the image shown now
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn current_path(&self) -> Option<&Path> {
+        self.paths.get(self.index).map(PathBuf::as_path)
+    }
+
+    // `None` while the image shown now is still being decoded
+    pub fn current(&self) -> Option<&Result<FileImageProvider, DecodeError>> {
+        self.images.get(&self.index)
+    }
+
+    // false at the last image
+    pub fn advance(&mut self) -> bool {
+        self.index + 1 < self.len() && self.show(self.index + 1)
+    }
+
+    // false at the first image
+    pub fn back(&mut self) -> bool {
+        self.index > 0 && self.show(self.index - 1)
+    }
+
+    // false when there is no image at `index`
+    pub fn show(&mut self, index: usize) -> bool {
+        if index >= self.len() {
+            return false;
+        }
+
+        self.index = index;
+        self.preload();
+        true
+    }
+
+    // takes in what the worker finished, drops images too far away to be shown next and
+    // cancels their decodes, then asks for the missing neighbours, the current one first
+    fn preload(&mut self) {
+        self.receive();
+
+        if self.is_empty() {
+            return;
+        }
+
+        let first = self.index.saturating_sub(PRELOAD_DISTANCE);
+        let last = (self.index + PRELOAD_DISTANCE).min(self.len() - 1);
+        self.images.retain(|&index, _| (first..=last).contains(&index));
+        self.requested.retain(|(index, job)| {
+            let wanted = (first..=last).contains(index);
+            if !wanted {
+                job.cancel();
+            }
+            wanted
+        });
+
+        let current = self.index;
+        let wanted = std::iter::once(current).chain((first..=last).filter(|&index| index != current));
+
+        for index in wanted {
+            if self.images.contains_key(&index) {
+                continue;
+            }
+
+            let priority = match index == current {
+                true => Priority::Visible,
+                false => Priority::Prefetch,
+            };
+
+            if let Some((_, job)) = self.requested.iter_mut().find(|(requested, _)| *requested == index) {
+                job.promote(priority);
+                continue;
+            }
+
+            let job = self.decode(index, priority);
+            self.requested.push((index, job));
+        }
+    }
+
+    fn decode(&self, index: usize, priority: Priority) -> JobHandle {
+        let path = self.paths[index].clone();
+        let (limits, sender) = (self.limits.clone(), self.sender.clone());
+
+        self.scheduler.spawn(priority, move |token| {
+            let image = FileImageProvider::open_with_limits(&path, &limits.cancellable(token.clone()));
+
+            match &image {
+                Err(DecodeError::Cancelled) => {
+                    log::debug!("cancelled decoding {}", path.display());
+                    return;
+                },
+                Err(error) => log::warn!("failed to decode {}: {error}", path.display()),
+                Ok(_) => (),
+            }
+
+            let _ = sender.send((index, image));
+        })
+    }
+
+    fn receive(&mut self) {
+        for (index, image) in self.receiver.try_iter() {
+            // a decode of the same index asked for again after a cancel isn't needed anymore
+            self.requested.retain(|(requested, job)| {
+                if *requested == index {
+                    job.cancel();
+                }
+                *requested != index
+            });
+
+            // the user may have moved on while it was decoding
+            if index.abs_diff(self.index) <= PRELOAD_DISTANCE {
+                self.images.insert(index, image);
+            }
+        }
+    }
+}
+
+fn list_images(dir: &Path, sort: SortOrder) -> io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let supported = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Signature::from_extension)
+            .is_some_and(Signature::is_supported);
+
+        if supported && entry.file_type()?.is_file() {
+            entries.push((path, entry.metadata()?));
+        }
+    }
+
+    match sort {
+        SortOrder::Name => entries.sort_by(|a, b| a.0.cmp(&b.0)),
+        SortOrder::Modified => entries.sort_by_key(|(path, metadata)| (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), path.clone())),
+        SortOrder::Size => entries.sort_by_key(|(path, metadata)| (metadata.len(), path.clone())),
+    }
+
+    Ok(entries.into_iter().map(|(path, _)| path).collect())
+}
+
+// a broken image yields nothing, so the previous one stays on screen
+impl Iterator for DirectoryProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receive();
+        self.current()?.as_ref().ok().and_then(|mut image| image.next())
+    }
+}
+
+impl FrameProvider for DirectoryProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    // the image shown now, once it was decoded
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.current()?.as_ref().ok().map(|image| image.orientation().displayed_size(image.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        // until the image being shown is decoded
+        match self.current() {
+            Some(_) => FrameSchedule::Still,
+            None => FrameSchedule::Soon,
+        }
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+
+    fn next_image(&mut self) -> bool {
+        self.advance()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        self.back()
+    }
+}
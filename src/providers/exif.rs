@@ -0,0 +1,229 @@
+use image::{ImageDecoder, ImageFormat};
+
+// just the exif fields dataset tools care about, read from the tiff structure embedded in
+// jpeg, png, webp and tiff files
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Exif {
+    // 1 to 8 as in the tiff spec, 1 is upright
+    pub orientation: Option<u16>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    // when the photo was taken, or when the file was last changed if that's all there is
+    pub date_time: Option<String>,
+}
+
+const TAG_MAKE: u16 = 0x010f;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+
+// the exif block of a file the image crate reads, which finds it in jpeg, png and webp
+// containers, a tiff file is one exif structure already, `None` when there is none or it
+// can't be parsed
+pub(crate) fn read<D: ImageDecoder + ?Sized>(decoder: &mut D, format: ImageFormat, bytes: &[u8]) -> Option<Exif> {
+    match format {
+        ImageFormat::Tiff => parse(bytes),
+        _ => parse(&decoder.exif_metadata().ok()??),
+    }
+}
+
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = [*self.bytes.get(offset)?, *self.bytes.get(offset + 1)?];
+
+        Some(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.bytes.get(offset..offset + 4)?.try_into().ok()?;
+
+        Some(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    // (tag, type, count, offset of the value) for every entry of the ifd at `offset`
+    fn entries(&self, offset: usize) -> impl Iterator<Item = (u16, u16, u32, usize)> + '_ {
+        let count = self.u16(offset).unwrap_or(0) as usize;
+
+        (0..count).map_while(move |index| {
+            let entry = offset + 2 + 12 * index;
+            let (tag, kind, count) = (self.u16(entry)?, self.u16(entry + 2)?, self.u32(entry + 4)?);
+
+            let size = match kind {
+                TYPE_SHORT => 2,
+                TYPE_LONG => 4,
+                _ => 1,
+            } * count as usize;
+
+            // values of up to four bytes sit in the entry itself
+            let value = match size <= 4 {
+                true => entry + 8,
+                false => self.u32(entry + 8)? as usize,
+            };
+
+            Some((tag, kind, count, value))
+        })
+    }
+
+    fn ascii(&self, kind: u16, count: u32, offset: usize) -> Option<String> {
+        if kind != TYPE_ASCII {
+            return None;
+        }
+
+        let bytes = self.bytes.get(offset..offset.checked_add(count as usize)?)?;
+        let text = String::from_utf8_lossy(bytes.split(|&byte| byte == 0).next()?).trim().to_string();
+
+        match text.is_empty() {
+            true => None,
+            false => Some(text),
+        }
+    }
+}
+
+fn parse(bytes: &[u8]) -> Option<Exif> {
+    let big_endian = match bytes.get(..4)? {
+        [b'I', b'I', 42, 0] => false,
+        [b'M', b'M', 0, 42] => true,
+        _ => return None,
+    };
+
+    let tiff = Tiff { bytes, big_endian };
+    let ifd0 = tiff.u32(4)? as usize;
+
+    let mut exif = Exif::default();
+    let mut exif_ifd = None;
+
+    for (tag, kind, count, value) in tiff.entries(ifd0) {
+        match tag {
+            TAG_MAKE => exif.make = tiff.ascii(kind, count, value),
+            TAG_MODEL => exif.model = tiff.ascii(kind, count, value),
+            TAG_DATE_TIME => exif.date_time = tiff.ascii(kind, count, value),
+            TAG_ORIENTATION if kind == TYPE_SHORT => exif.orientation = tiff.u16(value).filter(|orientation| (1..=8).contains(orientation)),
+            TAG_EXIF_IFD if kind == TYPE_LONG => exif_ifd = tiff.u32(value),
+            _ => (),
+        }
+    }
+
+    // a pointer back to ifd0 would otherwise be read twice, that's all the looping there is
+    if let Some(offset) = exif_ifd.map(|offset| offset as usize).filter(|&offset| offset != ifd0) {
+        for (tag, kind, count, value) in tiff.entries(offset) {
+            if tag == TAG_DATE_TIME_ORIGINAL {
+                exif.date_time = tiff.ascii(kind, count, value).or(exif.date_time.take());
+            }
+        }
+    }
+
+    Some(exif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a tiff with orientation, make and model in ifd0 and the capture date in the exif ifd
+    fn tiff(big_endian: bool, orientation: u16) -> Vec<u8> {
+        let u16 = |value: u16| match big_endian {
+            true => value.to_be_bytes().to_vec(),
+            false => value.to_le_bytes().to_vec(),
+        };
+        let u32 = |value: u32| match big_endian {
+            true => value.to_be_bytes().to_vec(),
+            false => value.to_le_bytes().to_vec(),
+        };
+        let entry = |tag: u16, kind: u16, count: u32, value: Vec<u8>| [u16(tag), u16(kind), u32(count), value].concat();
+
+        // header, ifd0 of 4 entries, exif ifd of 1 entry, then the strings
+        let exif_ifd = 8 + 2 + 4 * 12 + 4;
+        let strings = exif_ifd + 2 + 12 + 4;
+        let date = b"2024:05:06 07:08:09\0";
+
+        [
+            match big_endian {
+                true => b"MM\0\x2a".to_vec(),
+                false => b"II\x2a\0".to_vec(),
+            },
+            u32(8),
+            u16(4),
+            entry(TAG_MAKE, TYPE_ASCII, 4, b"Foo\0".to_vec()),
+            entry(TAG_MODEL, TYPE_ASCII, 8, u32(strings as u32)),
+            entry(TAG_ORIENTATION, TYPE_SHORT, 1, [u16(orientation), vec![0, 0]].concat()),
+            entry(TAG_EXIF_IFD, TYPE_LONG, 1, u32(exif_ifd as u32)),
+            u32(0),
+            u16(1),
+            entry(TAG_DATE_TIME_ORIGINAL, TYPE_ASCII, date.len() as u32, u32(strings as u32 + 8)),
+            u32(0),
+            b"Bar 100\0".to_vec(),
+            date.to_vec(),
+        ]
+        .concat()
+    }
+
+    fn expected(orientation: Option<u16>) -> Exif {
+        Exif {
+            orientation,
+            make: Some(String::from("Foo")),
+            model: Some(String::from("Bar 100")),
+            date_time: Some(String::from("2024:05:06 07:08:09")),
+        }
+    }
+
+    #[test]
+    fn reads_both_byte_orders() {
+        assert_eq!(parse(&tiff(false, 6)), Some(expected(Some(6))));
+        assert_eq!(parse(&tiff(true, 8)), Some(expected(Some(8))));
+    }
+
+    #[test]
+    fn drops_invalid_orientations() {
+        assert_eq!(parse(&tiff(false, 9)), Some(expected(None)));
+        assert_eq!(parse(&tiff(true, 0)), Some(expected(None)));
+    }
+
+    #[test]
+    fn reads_the_block_the_decoder_found() {
+        use std::io::Cursor;
+
+        use image::codecs::png::PngEncoder;
+        use image::{ExtendedColorType, ImageEncoder, ImageReader};
+
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        encoder.set_exif_metadata(tiff(true, 3)).unwrap();
+        encoder.write_image(&[0; 4], 1, 1, ExtendedColorType::Rgba8).unwrap();
+
+        let reader = ImageReader::new(Cursor::new(&png)).with_guessed_format().unwrap();
+        let mut decoder = reader.into_decoder().unwrap();
+        assert_eq!(read(&mut decoder, ImageFormat::Png, &png), Some(expected(Some(3))));
+
+        let tiff = tiff(false, 6);
+        assert_eq!(read(&mut decoder, ImageFormat::Tiff, &tiff), Some(expected(Some(6))));
+    }
+
+    #[test]
+    fn ignores_garbage() {
+        assert_eq!(parse(b""), None);
+        assert_eq!(parse(b"GIF89a"), None);
+        assert_eq!(parse(b"II\x2a\0\xff\xff\xff\xff"), Some(Exif::default()));
+
+        let mut truncated = tiff(false, 1);
+        truncated.truncate(30);
+        assert_eq!(parse(&truncated).and_then(|exif| exif.model), None);
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::decode::{self, DecodeError, DecodeLimits, Salvage};
+use super::image::{ImageFrame, ImageProvider};
+use super::sniff::Diagnosis;
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasOrientation, HasScale, HasSize, Orientation, Pair};
+
+// a still image opened at runtime, it is decoded by what the file contents say it is, the
+// extension is only consulted for `format` when the image crate doesn't recognise them
+#[derive(Debug, Clone)]
+pub struct FileImageProvider {
+    path: PathBuf,
+    format: Option<image::ImageFormat>,
+    image: ImageProvider,
+}
+
+impl FileImageProvider {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DecodeError> {
+        Self::open_with_limits(path, &DecodeLimits::default())
+    }
+
+    pub fn open_with_limits<P: AsRef<Path>>(path: P, limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        let path = path.as_ref();
+        let io_error = |error| DecodeError::Image(image::ImageError::IoError(error));
+
+        // checked before reading so an oversized file is never loaded
+        let len = fs::metadata(path).map_err(io_error)?.len();
+        if len > limits.max_input_bytes as u64 {
+            return Err(DecodeError::InputTooLarge { len: len as usize, max: limits.max_input_bytes });
+        }
+
+        if limits.is_cancelled() {
+            return Err(DecodeError::Cancelled);
+        }
+
+        let bytes = fs::read(path).map_err(io_error)?;
+        let format = image::guess_format(&bytes).ok().or_else(|| image::ImageFormat::from_path(path).ok());
+        let diagnosis = Diagnosis::of(Some(path), &bytes);
+
+        log::debug!("opening {} as {format:?}", path.display());
+
+        // decoding goes by the contents, a wrong name only matters to other programs
+        if let Some(mismatch @ Diagnosis::Mismatch { actual, .. }) = &diagnosis {
+            if actual.is_supported() {
+                log::warn!("{}: {mismatch}", path.display());
+            }
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            format,
+            image: ImageProvider::from_decoded(decode::decode(bytes, limits).map_err(|error| error.diagnosed(diagnosis))?),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // from the contents, or the extension when the contents aren't one the `image` crate
+    // knows, `None` when neither is, like pfm
+    pub fn format(&self) -> Option<image::ImageFormat> {
+        self.format
+    }
+
+    pub fn source_size(&self) -> Pair<u32> {
+        self.image.source_size()
+    }
+
+    // see `DecodeLimits::salvage`
+    pub fn salvage(&self) -> Option<&Salvage> {
+        self.image.salvage()
+    }
+}
+
+impl HasSize<u32> for FileImageProvider {
+    fn size(&self) -> Pair<u32> {
+        self.image.size()
+    }
+}
+
+impl HasOrientation for FileImageProvider {
+    fn orientation(&self) -> Orientation {
+        self.image.orientation()
+    }
+}
+
+impl HasScale for FileImageProvider {
+    fn scale(&self) -> f32 {
+        self.image.scale()
+    }
+}
+
+impl Iterator for &FileImageProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&self.image).next()
+    }
+}
+
+impl Iterator for FileImageProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&*self).next()
+    }
+}
+
+// a still is always at its first frame
+impl FrameProvider for &FileImageProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.orientation().displayed_size(self.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+impl FrameProvider for FileImageProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        (&*self).poll_frame()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.orientation().displayed_size(self.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,47 @@
+use image::error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind};
+use image::{DynamicImage, ImageError, RgbaImage};
+use libheif_rs::{ColorSpace, HeifContext, HeifError, LibHeif, RgbChroma};
+
+use super::decode::DecodeLimits;
+use crate::convert;
+
+// brands of the iso base media `ftyp` box that libheif reads
+const BRANDS: [&[u8; 4]; 8] = [b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1"];
+
+pub(crate) fn is_heif(bytes: &[u8]) -> bool {
+    bytes.get(4..8) == Some(b"ftyp") && bytes.get(8..12).is_some_and(|brand| BRANDS.iter().any(|known| known.as_slice() == brand))
+}
+
+fn heif_error(error: HeifError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Name(String::from("heif")), error))
+}
+
+// the primary image with rotation, mirroring and cropping from the file already applied
+pub(crate) fn decode_heif(bytes: &[u8], limits: &DecodeLimits) -> Result<DynamicImage, ImageError> {
+    let context = HeifContext::read_from_bytes(bytes).map_err(heif_error)?;
+    let handle = context.primary_image_handle().map_err(heif_error)?;
+    let (width, height) = (handle.width(), handle.height());
+
+    if width == 0 || height == 0 || width > limits.max_size.0 || height > limits.max_size.1 {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+
+    if 4 * width as u64 * height as u64 > limits.max_alloc {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)));
+    }
+
+    let image = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(heif_error)?;
+
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| ImageError::Decoding(DecodingError::new(ImageFormatHint::Name(String::from("heif")), "no interleaved rgba plane")))?;
+
+    let mut pixels = vec![0; 4 * plane.width as usize * plane.height as usize];
+    convert::remove_stride(plane.data, 4 * plane.width as usize, plane.stride, &mut pixels);
+
+    let image = RgbaImage::from_raw(plane.width, plane.height, pixels).expect("buffer matches the plane size");
+    Ok(DynamicImage::ImageRgba8(image))
+}
@@ -0,0 +1,426 @@
+use std::io::Cursor;
+use std::ops::Range;
+use std::time::Duration;
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::{ImageError, RgbaImage};
+
+use super::decode::{self, DecodeError, DecodeLimits, DecodedAnimation, DecodedFrame};
+use super::image::{ImageFrame, ImageProvider};
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasSize, Pair};
+
+// one image of an ico or cur file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconEntry {
+    pub size: Pair<u32>,
+    pub bit_count: u16,
+    // cursors only
+    pub hotspot: Option<Pair<u16>>,
+}
+
+// every resolution of an ico/cur file, frames come from the selected one (the largest
+// by default) so a viewer can flip between sizes without reloading
+#[derive(Debug, Clone)]
+pub struct IconProvider {
+    entries: Vec<IconEntry>,
+    images: Vec<ImageProvider>,
+    selected: usize,
+}
+
+struct DirectoryEntry {
+    entry: IconEntry,
+    range: Range<usize>,
+}
+
+fn malformed(format: &str, reason: &str) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Name(format.to_owned()), reason.to_owned()))
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_directory(bytes: &[u8]) -> Result<Vec<DirectoryEntry>, ImageError> {
+    let truncated = || malformed("ico", "truncated directory");
+
+    let kind = read_u16(bytes, 2).ok_or_else(truncated)?;
+    let count = read_u16(bytes, 4).ok_or_else(truncated)? as usize;
+
+    if read_u16(bytes, 0) != Some(0) || !(kind == 1 || kind == 2) || count == 0 {
+        return Err(malformed("ico", "not an icon or cursor directory"));
+    }
+
+    (0..count)
+        .map(|index| {
+            let at = 6 + 16 * index;
+            let header = bytes.get(at..at + 16).ok_or_else(truncated)?;
+
+            // 0 stands for 256
+            let dimension = |value: u8| match value {
+                0 => 256,
+                value => value as u32,
+            };
+
+            let len = read_u32(header, 8).ok_or_else(truncated)? as usize;
+            let offset = read_u32(header, 12).ok_or_else(truncated)? as usize;
+            let data = bytes.get(offset..offset.saturating_add(len)).ok_or_else(|| malformed("ico", "entry outside of the file"))?;
+
+            // the directory's bit count is unreliable, the image header isn't
+            let bit_count = match data.starts_with(b"\x89PNG") {
+                true => 32,
+                false => read_u16(data, 14).ok_or_else(truncated)?,
+            };
+
+            Ok(DirectoryEntry {
+                entry: IconEntry {
+                    size: (dimension(header[0]), dimension(header[1])),
+                    bit_count,
+                    hotspot: match kind {
+                        2 => Some((read_u16(header, 4).unwrap_or(0), read_u16(header, 6).unwrap_or(0))),
+                        _ => None,
+                    },
+                },
+                range: offset..offset + len,
+            })
+        })
+        .collect()
+}
+
+// the `image` crate only decodes the best entry, so each entry is handed to it as a
+// single entry icon of its own
+fn decode_entry(bytes: &[u8], directory_entry: &DirectoryEntry, limits: &DecodeLimits) -> Result<RgbaImage, ImageError> {
+    let data = &bytes[directory_entry.range.clone()];
+    let (width, height) = directory_entry.entry.size;
+
+    let mut icon = Vec::with_capacity(22 + data.len());
+    icon.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    icon.extend_from_slice(&[width as u8, height as u8, 0, 0, 1, 0, 0, 0]);
+    icon.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    icon.extend_from_slice(&22u32.to_le_bytes());
+    icon.extend_from_slice(data);
+
+    let mut reader = image::ImageReader::with_format(Cursor::new(icon), image::ImageFormat::Ico);
+    reader.limits(limits.image_limits());
+
+    Ok(reader.decode()?.into_rgba8())
+}
+
+fn decode_icon_unguarded(bytes: &[u8], limits: &DecodeLimits) -> Result<Vec<(IconEntry, RgbaImage)>, ImageError> {
+    read_directory(bytes)?
+        .iter()
+        .map(|directory_entry| Ok((directory_entry.entry, decode_entry(bytes, directory_entry, limits)?)))
+        .collect()
+}
+
+impl IconProvider {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        let decoded = decode::run_limited(bytes.to_vec(), limits, decode_icon_unguarded)?;
+
+        let (entries, images): (Vec<_>, Vec<_>) = decoded
+            .into_iter()
+            .map(|(entry, image)| (entry, ImageProvider::from_image(image.into())))
+            .unzip();
+
+        let selected = largest(&entries);
+        Ok(Self { entries, images, selected })
+    }
+
+    pub fn entries(&self) -> &[IconEntry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    // returns false and keeps the current entry when `index` is out of range
+    pub fn select(&mut self, index: usize) -> bool {
+        let valid = index < self.entries.len();
+
+        if valid {
+            self.selected = index;
+        }
+
+        valid
+    }
+
+    // the smallest entry covering `size`, or the largest one when none does, deeper
+    // colors win between entries of the same size
+    pub fn select_size(&mut self, size: Pair<u32>) -> IconEntry {
+        self.selected = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.size.0 >= size.0 && entry.size.1 >= size.1)
+            .min_by_key(|(_, entry)| (entry.size.0 * entry.size.1, u16::MAX - entry.bit_count))
+            .map_or_else(|| largest(&self.entries), |(index, _)| index);
+
+        self.entries[self.selected]
+    }
+}
+
+fn largest(entries: &[IconEntry]) -> usize {
+    entries
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, entry)| (entry.size.0 * entry.size.1, entry.bit_count))
+        .map_or(0, |(index, _)| index)
+}
+
+impl HasSize<u32> for IconProvider {
+    fn size(&self) -> Pair<u32> {
+        self.entries[self.selected].size
+    }
+}
+
+impl Iterator for &IconProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&self.images[self.selected]).next()
+    }
+}
+
+impl Iterator for IconProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&*self).next()
+    }
+}
+
+// a still is always at its first frame
+impl FrameProvider for &IconProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+impl FrameProvider for IconProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        (&*self).poll_frame()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+pub(crate) fn is_ani(bytes: &[u8]) -> bool {
+    bytes.get(..4) == Some(b"RIFF") && bytes.get(8..12) == Some(b"ACON")
+}
+
+// riff chunks as (id, data), data is padded to an even length in the file
+fn chunks(mut bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    std::iter::from_fn(move || {
+        let id = bytes.get(..4)?;
+        let len = read_u32(bytes, 4)? as usize;
+        let data = bytes.get(8..8usize.checked_add(len)?)?;
+
+        bytes = bytes.get(8 + len + len % 2..).unwrap_or(&[]);
+        Some((id, data))
+    })
+}
+
+// animated cursors: a riff file with one ico/cur per frame, a default rate in jiffies
+// (1/60 s) and optional per step rates and frame sequence
+pub(crate) fn decode_ani(bytes: &[u8], limits: &DecodeLimits) -> Result<DecodedAnimation, ImageError> {
+    let mut default_rate = None;
+    let mut rates = None;
+    let mut sequence = None;
+    let mut icons = Vec::new();
+
+    for (id, data) in chunks(&bytes[12..]) {
+        match id {
+            b"anih" => default_rate = read_u32(data, 28),
+            b"rate" => rates = Some(data.chunks_exact(4).map(|rate| read_u32(rate, 0).unwrap_or(0)).collect::<Vec<_>>()),
+            b"seq " => sequence = Some(data.chunks_exact(4).map(|index| read_u32(index, 0).unwrap_or(0) as usize).collect::<Vec<_>>()),
+            b"LIST" if data.starts_with(b"fram") => icons.extend(chunks(&data[4..]).filter(|(id, _)| *id == b"icon").map(|(_, icon)| icon)),
+            _ => (),
+        }
+    }
+
+    if icons.is_empty() {
+        return Err(malformed("ani", "no frames"));
+    }
+
+    let images = icons
+        .iter()
+        .map(|icon| {
+            let directory = read_directory(icon)?;
+            let sizes: Vec<_> = directory.iter().map(|directory_entry| directory_entry.entry).collect();
+            decode_entry(icon, &directory[largest(&sizes)], limits)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let sequence = sequence.unwrap_or_else(|| (0..images.len()).collect());
+    let size = images[0].dimensions();
+    let mut allocated = 0u64;
+
+    let frames = sequence
+        .iter()
+        .enumerate()
+        .map(|(step, index)| {
+            let image = images.get(*index).ok_or_else(|| malformed("ani", "sequence refers to a missing frame"))?;
+
+            allocated += 4 * size.0 as u64 * size.1 as u64;
+            if allocated > limits.max_alloc {
+                return Err(ImageError::Limits(image::error::LimitError::from_kind(
+                    image::error::LimitErrorKind::InsufficientMemory,
+                )));
+            }
+
+            let jiffies = rates.as_ref().and_then(|rates| rates.get(step).copied()).or(default_rate).unwrap_or(6);
+
+            // frames of a different size are fitted to the first one
+            let image = match image.dimensions() == size {
+                true => image.clone(),
+                false => image::imageops::resize(image, size.0, size.1, image::imageops::FilterType::Triangle),
+            };
+
+            Ok(DecodedFrame { image, delay: Duration::from_secs(jiffies as u64) / 60 })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(DecodedAnimation { frames, source_size: size })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(size: u32, pixel: [u8; 4]) -> Vec<u8> {
+        let mut png = Cursor::new(Vec::new());
+        RgbaImage::from_pixel(size, size, image::Rgba(pixel)).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    // an ico (kind 1) or cur (kind 2) of png entries, hotspots are (size / 2, size / 4)
+    fn icon(kind: u16, entries: &[(u32, [u8; 4])]) -> Vec<u8> {
+        let images: Vec<_> = entries.iter().map(|(size, pixel)| png(*size, *pixel)).collect();
+        let mut bytes = [0, 0].iter().chain(&kind.to_le_bytes()).chain(&(entries.len() as u16).to_le_bytes()).copied().collect::<Vec<_>>();
+        let mut offset = 6 + 16 * entries.len();
+
+        for ((size, _), image) in entries.iter().zip(&images) {
+            bytes.extend_from_slice(&[*size as u8, *size as u8, 0, 0]);
+            bytes.extend_from_slice(&(*size as u16 / 2).to_le_bytes());
+            bytes.extend_from_slice(&(*size as u16 / 4).to_le_bytes());
+            bytes.extend_from_slice(&(image.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += image.len();
+        }
+
+        images.iter().for_each(|image| bytes.extend_from_slice(image));
+        bytes
+    }
+
+    fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut bytes = [id, &(data.len() as u32).to_le_bytes(), data].concat();
+        if data.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn ani(icons: &[Vec<u8>], rate: u32, sequence: Option<&[u32]>) -> Vec<u8> {
+        let mut anih = vec![0; 36];
+        anih[28..32].copy_from_slice(&rate.to_le_bytes());
+
+        let frames: Vec<u8> = icons.iter().flat_map(|icon| chunk(b"icon", icon)).collect();
+        let mut body = [&b"ACON"[..], &chunk(b"anih", &anih), &chunk(b"LIST", &[&b"fram"[..], &frames].concat())].concat();
+        if let Some(sequence) = sequence {
+            body.extend(chunk(b"seq ", &sequence.iter().flat_map(|index| index.to_le_bytes()).collect::<Vec<_>>()));
+        }
+
+        chunk(b"RIFF", &body)
+    }
+
+    #[test]
+    fn selects_between_entries() {
+        let mut provider = IconProvider::from_bytes(&icon(1, &[(16, [255, 0, 0, 255]), (32, [0, 0, 255, 255])])).unwrap();
+
+        assert_eq!(provider.entries().iter().map(|entry| entry.size).collect::<Vec<_>>(), [(16, 16), (32, 32)]);
+        assert!(provider.entries().iter().all(|entry| entry.bit_count == 32 && entry.hotspot.is_none()));
+        assert_eq!(provider.selected(), 1);
+        assert_eq!(provider.current_size(), Some((32, 32)));
+
+        assert_eq!(provider.select_size((10, 10)).size, (16, 16));
+        assert_eq!(provider.selected(), 0);
+        assert_eq!(provider.select_size((64, 64)).size, (32, 32));
+
+        assert!(!provider.select(2));
+        assert!(provider.select(0));
+        assert_eq!(provider.next().unwrap().size(), (16, 16));
+    }
+
+    #[test]
+    fn reads_cursor_hotspots() {
+        let provider = IconProvider::from_bytes(&icon(2, &[(32, [0; 4])])).unwrap();
+        assert_eq!(provider.entries()[0].hotspot, Some((16, 8)));
+    }
+
+    #[test]
+    fn rejects_malformed_directories() {
+        let limits = DecodeLimits::default();
+
+        assert!(read_directory(&[0, 0, 1]).is_err());
+        assert!(read_directory(&[0, 0, 3, 0, 1, 0]).is_err());
+        assert!(read_directory(&[0, 0, 1, 0, 0, 0]).is_err());
+        // the directory promises a second entry
+        assert!(read_directory(&[&icon(1, &[(16, [0; 4])])[..22], &[0, 0]].concat()).is_err());
+
+        let mut outside = icon(1, &[(16, [0; 4])]);
+        outside[18..22].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_directory(&outside).is_err());
+        assert!(decode_icon_unguarded(&outside, &limits).is_err());
+    }
+
+    #[test]
+    fn decodes_animated_cursors() {
+        let icons = [icon(2, &[(16, [255, 0, 0, 255])]), icon(2, &[(8, [0, 255, 0, 255])])];
+        let bytes = ani(&icons, 30, Some(&[1, 0, 1]));
+        assert!(is_ani(&bytes));
+
+        let animation = decode_ani(&bytes, &DecodeLimits::default()).unwrap();
+        assert_eq!(animation.source_size, (16, 16));
+        assert_eq!(animation.frames.len(), 3);
+        assert!(animation.frames.iter().all(|frame| frame.delay == Duration::from_millis(500) && frame.image.dimensions() == (16, 16)));
+        assert_eq!(animation.frames[0].image.get_pixel(8, 8).0, [0, 255, 0, 255]);
+        assert_eq!(animation.frames[1].image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+
+        assert!(decode_ani(&ani(&icons, 30, Some(&[2])), &DecodeLimits::default()).is_err());
+        assert!(decode_ani(&ani(&[], 30, None), &DecodeLimits::default()).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use super::next_content_id;
+use super::decode::{self, DecodeError, DecodeLimits, DecodedImage, Salvage};
+use crate::color::{ColorTransform, IccProfile};
+use crate::convert;
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasScale, HasSize, Orientation, Pair, PixelFormat};
+
+// a decoded still image, yields the same frame forever without copying the pixels
+#[derive(Debug, Clone)]
+pub struct ImageProvider {
+    size: Pair<u32>,
+    source_size: Pair<u32>,
+    format: PixelFormat,
+    orientation: Orientation,
+    buffer: Arc<[u8]>,
+    content_id: u64,
+    salvage: Option<Salvage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageFrame {
+    size: Pair<u32>,
+    scale: f32,
+    format: PixelFormat,
+    orientation: Orientation,
+    buffer: Arc<[u8]>,
+    content_id: u64,
+}
+
+impl ImageProvider {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        Ok(Self::from_decoded(decode::decode(bytes.to_vec(), limits)?))
+    }
+
+    pub fn from_image(image: ::image::DynamicImage) -> Self {
+        let source_size = (image.width(), image.height());
+        Self::from_decoded(DecodedImage { image, source_size, icc_profile: None, orientation: Orientation::Normal, salvage: None })
+    }
+
+    // 16 bit and float sources (pnm, pfm, farbfeld, 16 bit png) keep their precision in an
+    // rgba16float texture, everything else becomes rgba8
+    // images tagged with a profile other than srgb are converted to extended linear srgb in
+    // rgba16float, which an srgb surface clips to its gamut and a float surface shows in full
+    pub(crate) fn from_decoded(DecodedImage { image, source_size, icc_profile, orientation, salvage }: DecodedImage) -> Self {
+        let size = (image.width(), image.height());
+
+        let transform = icc_profile.as_deref().and_then(|bytes| match IccProfile::parse(bytes) {
+            Ok(profile) => Some(ColorTransform::to_linear_srgb(&profile)).filter(|transform| !transform.is_srgb()),
+            Err(error) => {
+                log::warn!("showing as srgb, {error}");
+                None
+            },
+        });
+
+        let (format, buffer) = match (transform, image.color().bytes_per_pixel() / image.color().channel_count()) {
+            (Some(transform), _) => (PixelFormat::Rgba16Float, to_rgba16_float_with(image, &transform)),
+            (None, 1) => (PixelFormat::Rgba8, image.into_rgba8().into_vec()),
+            (None, _) => (PixelFormat::Rgba16Float, to_rgba16_float(image)),
+        };
+
+        Self { size, source_size, format, orientation, buffer: buffer.into(), content_id: next_content_id(), salvage }
+    }
+
+    // an image shown sideways or mirrored, e.g. a phone photo, reports its stored size and
+    // is turned upright when drawn
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    // dimensions of the file before any budget downscale
+    pub fn source_size(&self) -> Pair<u32> {
+        self.source_size
+    }
+
+    // set when the file was damaged and only part of it is shown
+    pub fn salvage(&self) -> Option<&Salvage> {
+        self.salvage.as_ref()
+    }
+}
+
+impl ImageFrame {
+    // upright rgba8 pixels made by the crate, e.g. a combinator's output
+    pub(crate) fn rgba8(size: Pair<u32>, pixels: Vec<u8>, scale: f32, content_id: u64) -> Self {
+        Self { size, scale, format: PixelFormat::Rgba8, orientation: Orientation::Normal, buffer: pixels.into(), content_id }
+    }
+}
+
+fn to_rgba16_float(image: ::image::DynamicImage) -> Vec<u8> {
+    // integer samples are gamma encoded, float ones are linear by convention
+    let encoded = !matches!(image, ::image::DynamicImage::ImageRgb32F(_) | ::image::DynamicImage::ImageRgba32F(_));
+
+    image
+        .into_rgba32f()
+        .pixels()
+        .flat_map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            let linear = |value: f32| match encoded {
+                true => srgb_to_linear(value),
+                false => value,
+            };
+
+            [linear(r), linear(g), linear(b), a]
+        })
+        .flat_map(|value| convert::f32_to_f16(value).to_ne_bytes())
+        .collect()
+}
+
+fn to_rgba16_float_with(image: ::image::DynamicImage, transform: &ColorTransform) -> Vec<u8> {
+    image
+        .into_rgba32f()
+        .pixels()
+        .flat_map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            let [r, g, b] = transform.apply([r, g, b]);
+            [r, g, b, a]
+        })
+        .flat_map(|value| convert::f32_to_f16(value).to_ne_bytes())
+        .collect()
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+impl HasScale for ImageProvider {
+    fn scale(&self) -> f32 {
+        self.size.0 as f32 / self.source_size.0 as f32
+    }
+}
+
+impl HasScale for ImageFrame {
+    fn scale(&self) -> f32 {
+        self.scale
+    }
+}
+
+impl HasSize<u32> for ImageProvider {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for ImageFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasSize<u32> for ImageFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasFormat for ImageFrame {
+    fn format(&self) -> PixelFormat {
+        self.format
+    }
+}
+
+impl HasOrientation for ImageProvider {
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+}
+
+impl HasOrientation for ImageFrame {
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+}
+
+impl HasData for ImageFrame {
+    fn data(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn content_id(&self) -> Option<u64> {
+        Some(self.content_id)
+    }
+}
+
+impl Iterator for &ImageProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(ImageFrame {
+            size: self.size,
+            scale: self.scale(),
+            format: self.format,
+            orientation: self.orientation,
+            buffer: Arc::clone(&self.buffer),
+            content_id: self.content_id,
+        })
+    }
+}
+
+impl Iterator for ImageProvider {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&*self).next()
+    }
+}
+
+// a still is always at its first frame
+impl FrameProvider for &ImageProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.orientation().displayed_size(self.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+impl FrameProvider for ImageProvider {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        (&*self).poll_frame()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.orientation().displayed_size(self.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
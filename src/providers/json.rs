@@ -0,0 +1,72 @@
+use std::fmt;
+
+// `line` and `column` count from 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid json at line {}, column {}: {}", self.line, self.column, self.reason)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        // the message carries the position too, so it is dropped from it
+        let reason = error.to_string();
+        let reason = reason.split(" at line ").next().unwrap_or_default().to_owned();
+
+        JsonError { line: error.line(), column: error.column(), reason }
+    }
+}
+
+// objects keep their key order
+pub(crate) fn parse(text: &str) -> Result<serde_json::Value, JsonError> {
+    Ok(serde_json::from_str(text)?)
+}
+
+// `text` as a json string literal
+pub(crate) fn quote(text: &str) -> String {
+    serde_json::Value::from(text).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keeps_key_order() {
+        let value = parse(r#"{"b": 1, "a": [true, null, "x"]}"#).unwrap();
+        let keys: Vec<_> = value.as_object().unwrap().keys().collect();
+
+        assert_eq!(keys, ["b", "a"]);
+    }
+
+    #[test]
+    fn parse_rejects_lone_surrogates() {
+        for text in [r#""\ud800\u0000""#, r#""\ud800""#, r#""\udc00""#, r#""\ud800\ud800""#] {
+            assert!(parse(text).is_err(), "{text}");
+        }
+
+        assert_eq!(parse(r#""😀""#).unwrap(), "\u{1f600}");
+    }
+
+    #[test]
+    fn parse_reports_the_position() {
+        let error = parse("{\n  \"a\": ]\n}").unwrap_err();
+
+        assert_eq!((error.line, error.column), (2, 8));
+        assert!(!error.reason.contains("line"));
+    }
+
+    #[test]
+    fn quote_escapes() {
+        assert_eq!(quote("a\"b\\c\n\u{1}é"), r#""a\"b\\c\n\u0001é""#);
+    }
+}
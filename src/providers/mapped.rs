@@ -0,0 +1,402 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+use super::next_content_id;
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat, RowOrder};
+
+// uncompressed images served straight from a read-only file mapping, the pixels are never
+// copied on the cpu side and only the pages the gpu upload touches are read from disk
+// opening one is unsafe, the file has to keep its length and contents while it is mapped,
+// load files other processes may write with `ImageProvider` instead
+#[derive(Debug, Clone)]
+pub struct MappedImageProvider {
+    file: Arc<File>,
+    map: Arc<Mmap>,
+    layout: RawLayout,
+    content_id: u64,
+}
+
+// where the pixels of a raw dump sit inside the file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawLayout {
+    pub size: Pair<u32>,
+    pub format: PixelFormat,
+    pub offset: usize,
+    // `None` for tightly packed rows
+    pub row_stride: Option<usize>,
+    pub row_order: RowOrder,
+}
+
+#[derive(Debug, Clone)]
+pub struct MappedFrame {
+    map: Arc<Mmap>,
+    range: Range<usize>,
+    layout: RawLayout,
+    content_id: u64,
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Io(io::Error),
+    Unsupported(String),
+    Truncated { expected: usize, len: usize },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(error) => write!(f, "failed to map file: {error}"),
+            MapError::Unsupported(reason) => write!(f, "cannot map image: {reason}"),
+            MapError::Truncated { expected, len } => write!(f, "file is {len} bytes, the pixel data needs {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<io::Error> for MapError {
+    fn from(error: io::Error) -> Self {
+        MapError::Io(error)
+    }
+}
+
+impl MappedImageProvider {
+    // uncompressed 24/32 bit bmp and binary 8 bit pgm/ppm, recognised by their magic bytes
+    /// # Safety
+    ///
+    /// The file at `path` must not be truncated or written to, by this process or any
+    /// other, while the provider or a frame of it is alive, reading pages a truncation
+    /// removed faults and changed pages change pixels handed out as `&[u8]`.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self, MapError> {
+        let (file, map) = map_file(path.as_ref())?;
+
+        let layout = match map.get(..2) {
+            Some(b"BM") => bmp_layout(&map)?,
+            Some(b"P5") | Some(b"P6") => pnm_layout(&map)?,
+            _ => return Err(MapError::Unsupported(String::from("not an uncompressed bmp, pgm or ppm file"))),
+        };
+
+        Self::with_layout(file, map, layout)
+    }
+
+    /// # Safety
+    ///
+    /// See `open`.
+    pub unsafe fn raw<P: AsRef<Path>>(path: P, layout: RawLayout) -> Result<Self, MapError> {
+        let (file, map) = map_file(path.as_ref())?;
+        Self::with_layout(file, map, layout)
+    }
+
+    fn with_layout(file: File, map: Mmap, layout: RawLayout) -> Result<Self, MapError> {
+        let expected = layout.end().ok_or_else(|| MapError::Unsupported(String::from("the pixel data doesn't fit in memory")))?;
+
+        match map.len() < expected {
+            true => Err(MapError::Truncated { expected, len: map.len() }),
+            false => Ok(Self { file: Arc::new(file), map: Arc::new(map), layout, content_id: next_content_id() }),
+        }
+    }
+
+    // whether the file still holds all of the pixels, a file shrunk between frames despite
+    // the contract of `open` yields no more frames
+    fn is_intact(&self) -> bool {
+        match self.file.metadata() {
+            Ok(metadata) => metadata.len() >= self.layout.offset as u64 + self.layout.data_len() as u64,
+            Err(error) => {
+                log::warn!("failed to check the mapped file: {error}");
+                false
+            },
+        }
+    }
+
+    pub fn layout(&self) -> &RawLayout {
+        &self.layout
+    }
+}
+
+impl RawLayout {
+    fn row_len(&self) -> usize {
+        self.format.bytes_per_pixel() * self.size.0 as usize
+    }
+
+    // the last row doesn't need its padding
+    fn data_len(&self) -> usize {
+        let row_len = self.row_len();
+        let stride = self.row_stride.unwrap_or(row_len);
+        stride * (self.size.1 as usize).saturating_sub(1) + row_len
+    }
+
+    // past the last byte of the pixels, `None` when that overflows
+    fn end(&self) -> Option<usize> {
+        let row_len = self.format.bytes_per_pixel().checked_mul(self.size.0 as usize)?;
+        let stride = self.row_stride.unwrap_or(row_len);
+        let len = stride.checked_mul((self.size.1 as usize).saturating_sub(1))?.checked_add(row_len)?;
+        self.offset.checked_add(len)
+    }
+}
+
+// the caller keeps the file as it is, see `MappedImageProvider::open`
+unsafe fn map_file(path: &Path) -> Result<(File, Mmap), MapError> {
+    let file = File::open(path)?;
+
+    // SAFETY: the mapping is read-only and only ever exposed as `&[u8]`, the caller
+    // guarantees the file doesn't change while it is mapped
+    let map = unsafe { Mmap::map(&file)? };
+    Ok((file, map))
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn bmp_layout(bytes: &[u8]) -> Result<RawLayout, MapError> {
+    let truncated = || MapError::Unsupported(String::from("truncated bmp header"));
+
+    let offset = read_u32(bytes, 10).ok_or_else(truncated)? as usize;
+    let header_len = read_u32(bytes, 14).ok_or_else(truncated)?;
+    let width = read_u32(bytes, 18).ok_or_else(truncated)? as i32;
+    let height = read_u32(bytes, 22).ok_or_else(truncated)? as i32;
+    let bits_per_pixel = read_u16(bytes, 28).ok_or_else(truncated)?;
+    let compression = read_u32(bytes, 30).ok_or_else(truncated)?;
+
+    if header_len < 40 || width <= 0 || height == 0 {
+        return Err(MapError::Unsupported(format!("bmp with header size {header_len} and size {width}x{height}")));
+    }
+
+    // masks follow a 40 byte header and are part of the larger ones
+    let bgra_masks = || (0..3).map(|index| read_u32(bytes, 54 + 4 * index)).eq([0x00ff0000, 0x0000ff00, 0x000000ff].map(Some));
+
+    let format = match (bits_per_pixel, compression) {
+        (24, 0) => PixelFormat::Bgr8,
+        (32, 0) => PixelFormat::Bgra8,
+        (32, 3) if bgra_masks() => PixelFormat::Bgra8,
+        _ => return Err(MapError::Unsupported(format!("{bits_per_pixel} bit bmp with compression {compression}"))),
+    };
+
+    Ok(RawLayout {
+        size: (width as u32, height.unsigned_abs()),
+        format,
+        offset,
+        // rows are padded to 4 bytes
+        row_stride: Some((bits_per_pixel as usize * width as usize).div_ceil(32) * 4),
+        row_order: match height < 0 {
+            true => RowOrder::TopDown,
+            false => RowOrder::BottomUp,
+        },
+    })
+}
+
+fn pnm_layout(bytes: &[u8]) -> Result<RawLayout, MapError> {
+    let mut position = 2;
+    let mut fields = [0u32; 3];
+
+    for field in fields.iter_mut() {
+        // whitespace and comments running to the end of the line
+        loop {
+            match bytes.get(position) {
+                Some(byte) if byte.is_ascii_whitespace() => position += 1,
+                Some(b'#') => while bytes.get(position).is_some_and(|byte| *byte != b'\n') {
+                    position += 1;
+                },
+                _ => break,
+            }
+        }
+
+        let start = position;
+        while bytes.get(position).is_some_and(u8::is_ascii_digit) {
+            position += 1;
+        }
+
+        *field = std::str::from_utf8(&bytes[start..position])
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(|| MapError::Unsupported(String::from("malformed pnm header")))?;
+    }
+
+    let [width, height, max_value] = fields;
+
+    if max_value != 255 || width == 0 || height == 0 {
+        return Err(MapError::Unsupported(format!("pnm of {width}x{height} with maximum value {max_value}")));
+    }
+
+    Ok(RawLayout {
+        size: (width, height),
+        format: match &bytes[..2] {
+            b"P5" => PixelFormat::Gray8,
+            _ => PixelFormat::Rgb8,
+        },
+        // a single whitespace byte separates the header from the pixels
+        offset: position + 1,
+        row_stride: None,
+        row_order: RowOrder::TopDown,
+    })
+}
+
+impl HasSize<u32> for MappedImageProvider {
+    fn size(&self) -> Pair<u32> {
+        self.layout.size
+    }
+}
+
+impl HasSize<u32> for MappedFrame {
+    fn size(&self) -> Pair<u32> {
+        self.layout.size
+    }
+}
+
+impl HasPosition<u32> for MappedFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for MappedFrame {
+    fn format(&self) -> PixelFormat {
+        self.layout.format
+    }
+
+    fn row_order(&self) -> RowOrder {
+        self.layout.row_order
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        self.layout.row_stride
+    }
+}
+
+impl HasOrientation for MappedFrame {}
+
+impl HasData for MappedFrame {
+    fn data(&self) -> &[u8] {
+        &self.map[self.range.clone()]
+    }
+
+    fn content_id(&self) -> Option<u64> {
+        Some(self.content_id)
+    }
+}
+
+impl Iterator for &MappedImageProvider {
+    type Item = MappedFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.is_intact() {
+            log::warn!("the mapped file was truncated, not reading it anymore");
+            return None;
+        }
+
+        Some(MappedFrame {
+            map: Arc::clone(&self.map),
+            range: self.layout.offset..self.layout.offset + self.layout.data_len(),
+            layout: self.layout,
+            content_id: self.content_id,
+        })
+    }
+}
+
+impl Iterator for MappedImageProvider {
+    type Item = MappedFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&*self).next()
+    }
+}
+
+// a still is always at its first frame
+impl FrameProvider for &MappedImageProvider {
+    type Frame = MappedFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+impl FrameProvider for MappedImageProvider {
+    type Frame = MappedFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        (&*self).poll_frame()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn temp_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("egami-mapped-{}-{name}", std::process::id()));
+        File::create(&path).unwrap().write_all(bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn maps_a_ppm() {
+        let path = temp_file("ppm", b"P6\n# comment\n2 1\n255\n\x01\x02\x03\x04\x05\x06");
+        let provider = unsafe { MappedImageProvider::open(&path) }.unwrap();
+        let frame = (&provider).next().unwrap();
+
+        assert_eq!(provider.layout().size, (2, 1));
+        assert_eq!(frame.format(), PixelFormat::Rgb8);
+        assert_eq!(frame.data(), [1, 2, 3, 4, 5, 6]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_short_files_and_overflowing_layouts() {
+        let path = temp_file("short", b"P5 4 4 255\n\x00");
+        assert!(matches!(unsafe { MappedImageProvider::open(&path) }, Err(MapError::Truncated { expected: 27, len: 12 })));
+
+        let layout = RawLayout { size: (u32::MAX, u32::MAX), format: PixelFormat::Rgba8, offset: usize::MAX, row_stride: None, row_order: RowOrder::TopDown };
+        assert!(matches!(unsafe { MappedImageProvider::raw(&path, layout) }, Err(MapError::Unsupported(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncated_file_yields_no_frames() {
+        let path = temp_file("truncated", &[7; 64]);
+        let layout = RawLayout { size: (4, 4), format: PixelFormat::Rgba8, offset: 0, row_stride: None, row_order: RowOrder::TopDown };
+        let provider = unsafe { MappedImageProvider::raw(&path, layout) }.unwrap();
+
+        assert!((&provider).next().is_some());
+
+        // breaks the contract of `raw`, but no frame is alive to read the lost pages
+        File::options().write(true).open(&path).unwrap().set_len(16).unwrap();
+        assert!((&provider).next().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
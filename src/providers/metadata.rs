@@ -0,0 +1,217 @@
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use image::error::{ImageFormatHint, UnsupportedError};
+use image::{ImageDecoder, ImageError};
+
+use super::decode::{self, DecodeError, DecodeLimits};
+use super::exif::{self, Exif};
+use super::json;
+use super::sniff::Signature;
+use super::{portable, qoi};
+use crate::types::Pair;
+
+#[cfg(feature = "heic")]
+use super::heic;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageMetadata {
+    pub format: String,
+    pub size: Pair<u32>,
+    // the color type stored in the file, e.g. `Rgb8` or `L16`
+    pub color: String,
+    pub exif: Option<Exif>,
+}
+
+#[derive(Debug)]
+pub struct MetadataEntry {
+    pub path: PathBuf,
+    pub file_len: u64,
+    pub metadata: Result<ImageMetadata, DecodeError>,
+}
+
+// dimensions, format and exif of every image in a directory tree, for auditing datasets
+#[derive(Debug, Default)]
+pub struct MetadataReport {
+    pub entries: Vec<MetadataEntry>,
+}
+
+fn read_unguarded(bytes: &[u8], limits: &DecodeLimits) -> Result<ImageMetadata, ImageError> {
+    // formats the image crate doesn't know have no header-only path and are decoded in full
+    let decoded_format = match () {
+        _ if portable::is_pfm(bytes) => Some("Pfm"),
+        _ if qoi::is_qoi(bytes) => Some("Qoi"),
+        #[cfg(feature = "heic")]
+        _ if heic::is_heif(bytes) => Some("Heif"),
+        _ => None,
+    };
+
+    if let Some(format) = decoded_format {
+        let decoded = decode::decode_unguarded(bytes, limits)?;
+
+        return Ok(ImageMetadata {
+            format: String::from(format),
+            size: decoded.source_size,
+            color: format!("{:?}", decoded.image.color()),
+            exif: None,
+        });
+    }
+
+    let mut reader = image::ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    reader.limits(limits.image_limits());
+
+    let format = reader.format().ok_or_else(|| ImageError::Unsupported(UnsupportedError::from(ImageFormatHint::Unknown)))?;
+    let mut decoder = reader.into_decoder()?;
+    let exif = exif::read(&mut decoder, format, bytes);
+
+    Ok(ImageMetadata {
+        format: format!("{format:?}"),
+        size: decoder.dimensions(),
+        color: format!("{:?}", decoder.original_color_type()),
+        exif,
+    })
+}
+
+// only the headers are parsed where the format allows it, on the same limited decode
+// workers as full decodes
+pub fn read_metadata(bytes: Vec<u8>, limits: &DecodeLimits) -> Result<ImageMetadata, DecodeError> {
+    decode::run_limited(bytes, limits, read_unguarded)
+}
+
+// files `MetadataReport::scan` looks at, the formats this crate decodes except cursors,
+// which the image crate doesn't know
+fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Signature::from_extension)
+        .is_some_and(|signature| signature.is_supported() && !matches!(signature, Signature::Cur | Signature::Ani))
+}
+
+// symlinked directories are not followed, so a link back up the tree can't loop
+fn collect_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            collect_paths(&entry.path(), paths)?;
+        } else if is_image_path(&entry.path()) {
+            paths.push(entry.path());
+        }
+    }
+
+    Ok(())
+}
+
+// every image below `dir` in path order
+pub(crate) fn image_paths(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    collect_paths(dir, &mut paths)?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn read_entry(path: PathBuf, limits: &DecodeLimits) -> MetadataEntry {
+    let io_error = |error| DecodeError::Image(ImageError::IoError(error));
+    let file_len = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+
+    let metadata = match file_len > limits.max_input_bytes as u64 {
+        true => Err(DecodeError::InputTooLarge { len: file_len as usize, max: limits.max_input_bytes }),
+        false => fs::read(&path).map_err(io_error).and_then(|bytes| read_metadata(bytes, limits)),
+    };
+
+    MetadataEntry { path, file_len, metadata }
+}
+
+// csv can't hold a comma, quote or line break in a field unless it's quoted
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+impl MetadataReport {
+    // every image below `dir` sorted by path, files that fail to read get an entry with
+    // the error instead of failing the scan
+    pub fn scan<P: AsRef<Path>>(dir: P, limits: &DecodeLimits) -> io::Result<Self> {
+        let paths = image_paths(dir.as_ref())?;
+
+        Ok(Self {
+            entries: paths.into_iter().map(|path| read_entry(path, limits)).collect(),
+        })
+    }
+
+    // one row per image with a header row, empty fields for what a file doesn't have
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "path,file_bytes,format,width,height,color,orientation,make,model,date_time,error")?;
+
+        for entry in &self.entries {
+            let path = csv_field(&entry.path.to_string_lossy());
+
+            match &entry.metadata {
+                Ok(metadata) => {
+                    let exif = metadata.exif.clone().unwrap_or_default();
+                    let text = |value: Option<String>| value.as_deref().map(csv_field).unwrap_or_default();
+
+                    writeln!(
+                        writer,
+                        "{path},{},{},{},{},{},{},{},{},{},",
+                        entry.file_len,
+                        csv_field(&metadata.format),
+                        metadata.size.0,
+                        metadata.size.1,
+                        csv_field(&metadata.color),
+                        exif.orientation.map(|orientation| orientation.to_string()).unwrap_or_default(),
+                        text(exif.make),
+                        text(exif.model),
+                        text(exif.date_time),
+                    )?;
+                },
+                Err(error) => writeln!(writer, "{path},{},,,,,,,,,{}", entry.file_len, csv_field(&error.to_string()))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    // an array with one object per image, absent values are null
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let text = |value: Option<&str>| value.map(json::quote).unwrap_or_else(|| String::from("null"));
+
+        writeln!(writer, "[")?;
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let path = json::quote(&entry.path.to_string_lossy());
+
+            let fields = match &entry.metadata {
+                Ok(metadata) => {
+                    let exif = metadata.exif.clone().unwrap_or_default();
+
+                    format!(
+                        "\"format\": {}, \"width\": {}, \"height\": {}, \"color\": {}, \"orientation\": {}, \"make\": {}, \"model\": {}, \"date_time\": {}",
+                        json::quote(&metadata.format),
+                        metadata.size.0,
+                        metadata.size.1,
+                        json::quote(&metadata.color),
+                        exif.orientation.map_or(String::from("null"), |orientation| orientation.to_string()),
+                        text(exif.make.as_deref()),
+                        text(exif.model.as_deref()),
+                        text(exif.date_time.as_deref()),
+                    )
+                },
+                Err(error) => format!("\"error\": {}", json::quote(&error.to_string())),
+            };
+
+            let separator = match index + 1 == self.entries.len() {
+                true => "",
+                false => ",",
+            };
+
+            writeln!(writer, "  {{\"path\": {path}, \"file_bytes\": {}, {fields}}}{separator}", entry.file_len)?;
+        }
+
+        writeln!(writer, "]")
+    }
+}
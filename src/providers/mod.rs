@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+mod image;
+mod animated;
+mod combinators;
+mod directory;
+mod exif;
+mod file;
+mod icon;
+mod json;
+mod metadata;
+mod paletted;
+mod portable;
+mod qoi;
+mod scheduler;
+mod sniff;
+mod sprite;
+mod stream;
+mod y4m;
+pub mod decode;
+pub mod plugin;
+
+#[cfg(feature = "mmap")]
+mod mapped;
+
+#[cfg(feature = "heic")]
+mod heic;
+
+#[cfg(feature = "clipboard")]
+mod clipboard;
+
+#[cfg(all(feature = "camera", target_os = "linux"))]
+mod camera;
+
+#[cfg(feature = "video")]
+mod video;
+
+pub use self::animated::{AnimatedImageFrame, AnimatedImageProvider};
+pub use self::combinators::{Chain, Crop, FrameProviderExt, MapPixels, Scale, TakeFrames, Throttle};
+pub use self::directory::{DirectoryProvider, SortOrder};
+pub use self::file::FileImageProvider;
+pub use self::icon::{IconEntry, IconProvider};
+pub use self::exif::Exif;
+pub use self::json::JsonError;
+pub use self::metadata::{read_metadata, ImageMetadata, MetadataEntry, MetadataReport};
+#[cfg(feature = "viewer")]
+pub(crate) use self::metadata::image_paths;
+pub use self::paletted::{PalettedFrame, PalettedImageProvider};
+pub use self::sniff::{sniff, Diagnosis, Signature};
+pub use self::sprite::{AtlasError, GridSpec, SpriteFrame, SpriteRegion, SpriteSheet, SpriteSheetProvider, SpriteView};
+pub use self::scheduler::{JobHandle, Priority, Scheduler};
+pub use self::stream::{ExternalClock, StreamPlayer, StreamingFrameProvider, TimedFrame};
+pub use self::y4m::{Y4mError, Y4mFrame, Y4mProvider};
+pub use self::decode::{CancelToken, DecodeError, DecodeLimits, DecodedAnimation, DecodedFrame, DecodedImage, Salvage};
+pub use self::image::{ImageFrame, ImageProvider};
+
+#[cfg(feature = "mmap")]
+pub use self::mapped::{MapError, MappedFrame, MappedImageProvider, RawLayout};
+
+#[cfg(feature = "clipboard")]
+pub use self::clipboard::ClipboardProvider;
+
+#[cfg(all(feature = "camera", target_os = "linux"))]
+pub use self::camera::{CameraError, CameraFrame, CameraProvider};
+
+#[cfg(feature = "video")]
+pub use self::video::{FfmpegProvider, VideoError, VideoFrame};
+
+// ids are never reused, so a new image never passes for one that was dropped
+pub(crate) fn next_content_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
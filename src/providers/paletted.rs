@@ -0,0 +1,334 @@
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+use image::error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind};
+use image::{ImageError, ImageFormat};
+
+use super::next_content_id;
+use super::decode::{self, DecodeError, DecodeLimits};
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat};
+
+// an indexed png or gif kept as palette indices, the palette can be swapped at any time
+// and the next frame is drawn with it, e.g. to preview recolors of pixel art
+#[derive(Debug, Clone)]
+pub struct PalettedImageProvider {
+    size: Pair<u32>,
+    indices: Arc<[u8]>,
+    palette: Arc<[[u8; 4]]>,
+    // changes with the palette
+    content_id: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PalettedFrame {
+    size: Pair<u32>,
+    indices: Arc<[u8]>,
+    palette: Arc<[[u8; 4]]>,
+    content_id: u64,
+}
+
+struct Indexed {
+    size: Pair<u32>,
+    indices: Vec<u8>,
+    palette: Vec<[u8; 4]>,
+}
+
+fn decoding_error(format: ImageFormat, error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Exact(format), error))
+}
+
+fn check_size(size: Pair<u32>, limits: &DecodeLimits) -> Result<(), ImageError> {
+    if size.0 == 0 || size.1 == 0 || size.0 > limits.max_size.0 || size.1 > limits.max_size.1 {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+
+    if size.0 as u64 * size.1 as u64 > limits.max_alloc {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)));
+    }
+
+    Ok(())
+}
+
+fn rgb_palette(rgb: &[u8], alpha: impl Fn(usize) -> u8) -> Vec<[u8; 4]> {
+    rgb.chunks_exact(3).take(256).enumerate().map(|(index, color)| [color[0], color[1], color[2], alpha(index)]).collect()
+}
+
+fn decode_png(bytes: &[u8], limits: &DecodeLimits) -> Result<Indexed, ImageError> {
+    let error = |error| decoding_error(ImageFormat::Png, error);
+
+    let mut decoder = png::Decoder::new_with_limits(limits.reader(bytes), png::Limits { bytes: limits.max_alloc as usize });
+    decoder.set_transformations(png::Transformations::IDENTITY);
+
+    let mut reader = decoder.read_info().map_err(error)?;
+    let info = reader.info();
+    let size = (info.width, info.height);
+
+    check_size(size, limits)?;
+
+    let (png::ColorType::Indexed, Some(palette)) = (info.color_type, info.palette.as_ref()) else {
+        return Err(decoding_error(ImageFormat::Png, "not an indexed png"));
+    };
+
+    let transparency = info.trns.as_deref().unwrap_or(&[]);
+    let palette = rgb_palette(palette, |index| transparency.get(index).copied().unwrap_or(u8::MAX));
+    let bits = info.bit_depth as usize;
+
+    let mut packed = vec![0; reader.output_buffer_size()];
+    let output = reader.next_frame(&mut packed).map_err(error)?;
+
+    // rows of 1, 2 and 4 bit indices are packed from the high bits down
+    let mut indices = Vec::with_capacity(size.0 as usize * size.1 as usize);
+    for row in packed.chunks(output.line_size).take(size.1 as usize) {
+        indices.extend((0..size.0 as usize).map(|x| {
+            let bit = x * bits;
+            (row[bit / 8] >> (8 - bits - bit % 8)) & ((1u16 << bits) - 1) as u8
+        }));
+    }
+
+    Ok(Indexed { size, indices, palette })
+}
+
+// the first frame drawn onto the logical screen, uncovered pixels use the transparent
+// index when there is one
+fn decode_gif(bytes: &[u8], limits: &DecodeLimits) -> Result<Indexed, ImageError> {
+    let error = |error| decoding_error(ImageFormat::Gif, error);
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    options.set_memory_limit(gif::MemoryLimit::Bytes(NonZeroU64::new(limits.max_alloc).unwrap_or(NonZeroU64::MIN)));
+
+    let mut decoder = options.read_info(limits.reader(bytes)).map_err(error)?;
+    let size = (decoder.width() as u32, decoder.height() as u32);
+
+    check_size(size, limits)?;
+
+    let global_palette = decoder.global_palette().map(<[u8]>::to_vec);
+    let frame = decoder.read_next_frame().map_err(error)?.ok_or_else(|| decoding_error(ImageFormat::Gif, "no frames"))?;
+
+    let palette = frame
+        .palette
+        .as_deref()
+        .or(global_palette.as_deref())
+        .ok_or_else(|| decoding_error(ImageFormat::Gif, "no palette"))?;
+    let palette = rgb_palette(palette, |index| match Some(index) == frame.transparent.map(usize::from) {
+        true => 0,
+        false => u8::MAX,
+    });
+
+    let mut indices = vec![frame.transparent.unwrap_or(0); size.0 as usize * size.1 as usize];
+    let (width, height) = (size.0 as usize, size.1 as usize);
+    // a frame may reach past the logical screen, or start past it, what is outside is dropped
+    let (left, top) = ((frame.left as usize).min(width), (frame.top as usize).min(height));
+    let len = (frame.width as usize).min(width - left);
+
+    if len > 0 {
+        for (y, row) in frame.buffer.chunks(frame.width as usize).take(height - top).enumerate() {
+            let start = (top + y) * width + left;
+            let len = len.min(row.len());
+            indices[start..start + len].copy_from_slice(&row[..len]);
+        }
+    }
+
+    Ok(Indexed { size, indices, palette })
+}
+
+fn decode_indexed(bytes: &[u8], limits: &DecodeLimits) -> Result<Indexed, ImageError> {
+    match image::guess_format(bytes)? {
+        ImageFormat::Png => decode_png(bytes, limits),
+        ImageFormat::Gif => decode_gif(bytes, limits),
+        format => Err(ImageError::Unsupported(image::error::UnsupportedError::from(ImageFormatHint::Exact(format)))),
+    }
+}
+
+impl PalettedImageProvider {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        let Indexed { size, indices, palette } = decode::run_limited(bytes.to_vec(), limits, decode_indexed)?;
+
+        Ok(Self {
+            size,
+            indices: indices.into(),
+            palette: palette.into(),
+            content_id: next_content_id(),
+        })
+    }
+
+    pub fn palette(&self) -> &[[u8; 4]] {
+        &self.palette
+    }
+
+    // entries past 256 are ignored
+    pub fn set_palette(&mut self, palette: Vec<[u8; 4]>) {
+        self.palette = palette.into_iter().take(256).collect();
+        self.content_id = next_content_id();
+    }
+
+    // returns false and leaves the palette alone when `index` is past its end
+    pub fn set_color(&mut self, index: u8, color: [u8; 4]) -> bool {
+        let mut palette = self.palette.to_vec();
+
+        match palette.get_mut(index as usize) {
+            Some(entry) => {
+                *entry = color;
+                self.palette = palette.into();
+                self.content_id = next_content_id();
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl HasSize<u32> for PalettedImageProvider {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for PalettedFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasSize<u32> for PalettedFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasFormat for PalettedFrame {
+    fn format(&self) -> PixelFormat {
+        PixelFormat::Indexed8
+    }
+
+    fn palette(&self) -> Option<&[[u8; 4]]> {
+        Some(&self.palette)
+    }
+}
+
+impl HasOrientation for PalettedFrame {}
+
+impl HasData for PalettedFrame {
+    fn data(&self) -> &[u8] {
+        &self.indices
+    }
+
+    fn content_id(&self) -> Option<u64> {
+        Some(self.content_id)
+    }
+}
+
+impl Iterator for &PalettedImageProvider {
+    type Item = PalettedFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(PalettedFrame {
+            size: self.size,
+            indices: Arc::clone(&self.indices),
+            palette: Arc::clone(&self.palette),
+            content_id: self.content_id,
+        })
+    }
+}
+
+impl Iterator for PalettedImageProvider {
+    type Item = PalettedFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (&*self).next()
+    }
+}
+
+// a still is always at its first frame
+impl FrameProvider for &PalettedImageProvider {
+    type Frame = PalettedFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+impl FrameProvider for PalettedImageProvider {
+    type Frame = PalettedFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        (&*self).poll_frame()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        Some(self.size())
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+
+    fn rewind(&mut self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn gif(screen: (u16, u16), frame: gif::Frame) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = gif::Encoder::new(&mut bytes, screen.0, screen.1, &[0, 0, 0, 255, 255, 255]).unwrap();
+        encoder.write_frame(&frame).unwrap();
+        drop(encoder);
+        bytes
+    }
+
+    fn frame(left: u16, top: u16, width: u16, height: u16) -> gif::Frame<'static> {
+        gif::Frame {
+            left,
+            top,
+            width,
+            height,
+            buffer: Cow::Owned(vec![1; width as usize * height as usize]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gif_frame_inside_the_screen() {
+        let indexed = decode_gif(&gif((4, 3), frame(1, 1, 2, 1)), &DecodeLimits::default()).unwrap();
+
+        assert_eq!(indexed.size, (4, 3));
+        assert_eq!(indexed.indices, [0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn gif_frame_reaching_past_the_screen_is_clipped() {
+        let indexed = decode_gif(&gif((4, 3), frame(2, 1, 5, 4)), &DecodeLimits::default()).unwrap();
+
+        assert_eq!(indexed.indices, [0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn gif_frame_starting_past_the_screen_is_dropped() {
+        for (left, top) in [(4, 0), (200, 2), (0, 3), (60000, 60000)] {
+            let indexed = decode_gif(&gif((4, 3), frame(left, top, 2, 2)), &DecodeLimits::default()).unwrap();
+            assert!(indexed.indices.iter().all(|index| *index == 0), "frame at {left}, {top}");
+        }
+    }
+}
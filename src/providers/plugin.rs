@@ -0,0 +1,399 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::types::{FramePoll, FrameProvider, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat};
+
+// bumped whenever the layout of `ProviderPluginVTable` or `RawFrame` changes
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+
+// symbol a shared library exports, with the signature of `PluginEntry`
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"egami_provider_plugin\0";
+
+pub type PluginEntry = unsafe extern "C" fn() -> *const ProviderPluginVTable;
+
+// values of `RawFrame::format`
+pub const RAW_FORMAT_RGBA8: u32 = 0;
+pub const RAW_FORMAT_BGRA8: u32 = 1;
+pub const RAW_FORMAT_RGB8: u32 = 2;
+pub const RAW_FORMAT_BGR8: u32 = 3;
+pub const RAW_FORMAT_GRAY8: u32 = 4;
+
+// a frame handed over by a plugin, `data` stays valid until the next call
+// to `next_frame` or `close` on the same handle, `stride` is the distance between
+// row starts in bytes and 0 for tightly packed rows
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawFrame {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+    pub format: u32,
+    pub stride: usize,
+    pub data: *const u8,
+    pub len: usize,
+}
+
+impl Default for RawFrame {
+    fn default() -> Self {
+        Self { width: 0, height: 0, x: 0, y: 0, format: RAW_FORMAT_RGBA8, stride: 0, data: std::ptr::null(), len: 0 }
+    }
+}
+
+impl RawFrame {
+    fn pixel_format(&self) -> Option<PixelFormat> {
+        match self.format {
+            RAW_FORMAT_RGBA8 => Some(PixelFormat::Rgba8),
+            RAW_FORMAT_BGRA8 => Some(PixelFormat::Bgra8),
+            RAW_FORMAT_RGB8 => Some(PixelFormat::Rgb8),
+            RAW_FORMAT_BGR8 => Some(PixelFormat::Bgr8),
+            RAW_FORMAT_GRAY8 => Some(PixelFormat::Gray8),
+            _ => None,
+        }
+    }
+
+    // the format, stride and the number of bytes the pixels take up, `None` when the frame
+    // doesn't describe pixels that fit in `len`
+    fn layout(&self) -> Option<(PixelFormat, usize, usize)> {
+        let format = self.pixel_format()?;
+        let row_len = format.bytes_per_pixel().checked_mul(self.width as usize)?;
+        let stride = match self.stride {
+            0 => row_len,
+            stride if stride >= row_len => stride,
+            _ => return None,
+        };
+
+        let data_len = match self.height {
+            0 => 0,
+            height => stride.checked_mul(height as usize - 1)?.checked_add(row_len)?,
+        };
+
+        (data_len <= self.len).then_some((format, stride, data_len))
+    }
+}
+
+#[repr(C)]
+pub struct ProviderPluginVTable {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub probe: extern "C" fn(source: *const c_char) -> bool,
+    pub open: extern "C" fn(source: *const c_char) -> *mut c_void,
+    pub next_frame: extern "C" fn(handle: *mut c_void, frame: *mut RawFrame) -> bool,
+    pub close: extern "C" fn(handle: *mut c_void),
+}
+
+// the vtable is immutable static data owned by the plugin
+unsafe impl Sync for ProviderPluginVTable {}
+unsafe impl Send for ProviderPluginVTable {}
+
+#[derive(Debug)]
+pub enum PluginError {
+    #[cfg(feature = "plugins")]
+    Load(libloading::Error),
+    NullVTable,
+    AbiMismatch { expected: u32, found: u32 },
+    InvalidSource,
+    NoProvider(String),
+    OpenFailed(String),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "plugins")]
+            PluginError::Load(error) => write!(f, "failed to load plugin: {error}"),
+            PluginError::NullVTable => write!(f, "plugin returned a null vtable"),
+            PluginError::AbiMismatch { expected, found } => write!(f, "plugin abi version {found}, expected {expected}"),
+            PluginError::InvalidSource => write!(f, "source contains an interior nul byte"),
+            PluginError::NoProvider(source) => write!(f, "no plugin can open {source}"),
+            PluginError::OpenFailed(name) => write!(f, "plugin {name} failed to open source"),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+pub struct ProviderPlugin {
+    name: String,
+    vtable: *const ProviderPluginVTable,
+    // keeps the shared library mapped for as long as the vtable is reachable
+    #[cfg(feature = "plugins")]
+    _library: Option<libloading::Library>,
+}
+
+unsafe impl Send for ProviderPlugin {}
+unsafe impl Sync for ProviderPlugin {}
+
+impl fmt::Debug for ProviderPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProviderPlugin").field("name", &self.name).finish()
+    }
+}
+
+impl ProviderPlugin {
+    fn from_vtable(vtable: *const ProviderPluginVTable) -> Result<Self, PluginError> {
+        let table = unsafe { vtable.as_ref() }.ok_or(PluginError::NullVTable)?;
+
+        if table.abi_version != PLUGIN_ABI_VERSION {
+            return Err(PluginError::AbiMismatch { expected: PLUGIN_ABI_VERSION, found: table.abi_version });
+        }
+
+        let name = if table.name.is_null() {
+            String::from("unnamed")
+        } else {
+            unsafe { CStr::from_ptr(table.name) }.to_string_lossy().into_owned()
+        };
+
+        Ok(Self {
+            name,
+            vtable,
+            #[cfg(feature = "plugins")]
+            _library: None,
+        })
+    }
+
+    fn vtable(&self) -> &ProviderPluginVTable {
+        unsafe { &*self.vtable }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn probe(&self, source: &CStr) -> bool {
+        (self.vtable().probe)(source.as_ptr())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Arc<ProviderPlugin>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // for plugins linked into the application instead of loaded at runtime
+    pub fn register(&mut self, vtable: &'static ProviderPluginVTable) -> Result<(), PluginError> {
+        self.plugins.push(Arc::new(ProviderPlugin::from_vtable(vtable)?));
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// The library at `path` runs arbitrary code on load and must export
+    /// `PLUGIN_ENTRY_SYMBOL` with the `PluginEntry` signature.
+    #[cfg(feature = "plugins")]
+    pub unsafe fn load<P: AsRef<std::ffi::OsStr>>(&mut self, path: P) -> Result<(), PluginError> {
+        let library = libloading::Library::new(path).map_err(PluginError::Load)?;
+        let entry = library.get::<PluginEntry>(PLUGIN_ENTRY_SYMBOL).map_err(PluginError::Load)?;
+
+        let mut plugin = ProviderPlugin::from_vtable(entry())?;
+        plugin._library = Some(library);
+
+        log::info!("loaded provider plugin {}", plugin.name);
+        self.plugins.push(Arc::new(plugin));
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// See `load`, applies to every shared library found in `dir`.
+    #[cfg(feature = "plugins")]
+    pub unsafe fn load_dir<P: AsRef<std::path::Path>>(&mut self, dir: P) -> std::io::Result<usize> {
+        let mut loaded = 0;
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_library = path
+                .extension()
+                .is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION);
+
+            if !is_library {
+                continue;
+            }
+
+            match self.load(&path) {
+                Ok(()) => loaded += 1,
+                Err(error) => log::warn!("skipping plugin {}: {error}", path.display()),
+            }
+        }
+
+        Ok(loaded)
+    }
+
+    pub fn plugins(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|plugin| plugin.name())
+    }
+
+    // the first registered plugin whose probe accepts `source` opens it
+    pub fn open(&self, source: &str) -> Result<PluginProvider, PluginError> {
+        let c_source = CString::new(source).map_err(|_| PluginError::InvalidSource)?;
+
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|plugin| plugin.probe(&c_source))
+            .ok_or_else(|| PluginError::NoProvider(source.to_owned()))?;
+
+        let handle = (plugin.vtable().open)(c_source.as_ptr());
+
+        if handle.is_null() {
+            return Err(PluginError::OpenFailed(plugin.name.clone()));
+        }
+
+        Ok(PluginProvider { plugin: Arc::clone(plugin), handle })
+    }
+}
+
+#[derive(Debug)]
+pub struct PluginProvider {
+    plugin: Arc<ProviderPlugin>,
+    handle: *mut c_void,
+}
+
+impl PluginProvider {
+    pub fn plugin_name(&self) -> &str {
+        self.plugin.name()
+    }
+}
+
+impl Drop for PluginProvider {
+    fn drop(&mut self) {
+        (self.plugin.vtable().close)(self.handle);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginFrame {
+    size: Pair<u32>,
+    position: Pair<u32>,
+    format: PixelFormat,
+    stride: usize,
+    buffer: Vec<u8>,
+}
+
+impl HasSize<u32> for PluginFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for PluginFrame {
+    fn position(&self) -> Pair<u32> {
+        self.position
+    }
+}
+
+impl HasFormat for PluginFrame {
+    fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        Some(self.stride)
+    }
+}
+
+impl HasOrientation for PluginFrame {}
+
+impl HasData for PluginFrame {
+    fn data(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Iterator for PluginProvider {
+    type Item = PluginFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut raw = RawFrame::default();
+
+        if !(self.plugin.vtable().next_frame)(self.handle, &mut raw) || raw.data.is_null() {
+            return None;
+        }
+
+        let Some((format, stride, data_len)) = raw.layout() else {
+            log::warn!("plugin {} handed out a {}x{} frame of format {} with a stride of {} in {} bytes, dropping it", self.plugin.name, raw.width, raw.height, raw.format, raw.stride, raw.len);
+            return None;
+        };
+
+        // copy out, the plugin may reuse its buffer on the next call
+        let buffer = unsafe { std::slice::from_raw_parts(raw.data, data_len) }.to_vec();
+
+        Some(PluginFrame {
+            buffer,
+            size: (raw.width, raw.height),
+            position: (raw.x, raw.y),
+            format,
+            stride,
+        })
+    }
+}
+
+// the abi has no end of stream, a plugin without a frame is waiting for one
+impl FrameProvider for PluginProvider {
+    type Frame = PluginFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // what the test plugin hands out next, its pixels are `PIXELS`
+    static NEXT: Mutex<(u32, u32, u32, usize, usize)> = Mutex::new((0, 0, RAW_FORMAT_RGBA8, 0, 0));
+    static PIXELS: [u8; 64] = [9; 64];
+
+    extern "C" fn probe(_source: *const c_char) -> bool {
+        true
+    }
+
+    extern "C" fn open(_source: *const c_char) -> *mut c_void {
+        std::ptr::NonNull::dangling().as_ptr()
+    }
+
+    extern "C" fn next_frame(_handle: *mut c_void, frame: *mut RawFrame) -> bool {
+        let (width, height, format, stride, len) = *NEXT.lock().unwrap();
+        let frame = unsafe { &mut *frame };
+        *frame = RawFrame { width, height, format, stride, data: PIXELS.as_ptr(), len, ..RawFrame::default() };
+        true
+    }
+
+    extern "C" fn close(_handle: *mut c_void) {}
+
+    static VTABLE: ProviderPluginVTable = ProviderPluginVTable { abi_version: PLUGIN_ABI_VERSION, name: std::ptr::null(), probe, open, next_frame, close };
+
+    #[test]
+    fn validates_the_frame_layout() {
+        let mut registry = PluginRegistry::new();
+        registry.register(&VTABLE).unwrap();
+        let mut provider = registry.open("test").unwrap();
+
+        let mut next = |frame| {
+            *NEXT.lock().unwrap() = frame;
+            provider.next()
+        };
+
+        let frame = next((2, 2, RAW_FORMAT_RGBA8, 0, 16)).unwrap();
+        assert_eq!((frame.format(), frame.row_stride(), frame.data().len()), (PixelFormat::Rgba8, Some(8), 16));
+
+        // the padding after the last row isn't needed
+        let frame = next((3, 2, RAW_FORMAT_RGB8, 12, 21)).unwrap();
+        assert_eq!((frame.format(), frame.row_stride(), frame.data().len()), (PixelFormat::Rgb8, Some(12), 21));
+
+        // too short, a stride shorter than a row, an unknown format and an overflowing size
+        assert!(next((4, 4, RAW_FORMAT_RGBA8, 0, 63)).is_none());
+        assert!(next((4, 1, RAW_FORMAT_RGBA8, 8, 64)).is_none());
+        assert!(next((1, 1, 99, 0, 64)).is_none());
+        assert!(next((u32::MAX, u32::MAX, RAW_FORMAT_RGBA8, 0, 64)).is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use image::error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind};
+use image::{DynamicImage, ImageError, Rgb32FImage};
+
+use super::decode::DecodeLimits;
+
+// pfm, the float member of the netpbm family that the `image` crate doesn't read:
+// "PF" (rgb) or "Pf" (gray), width, height and a scale whose sign gives the byte order,
+// followed by bottom-up rows of f32 samples
+pub(crate) fn is_pfm(bytes: &[u8]) -> bool {
+    matches!(bytes, [b'P', b'F' | b'f', next, ..] if next.is_ascii_whitespace())
+}
+
+fn malformed(reason: &str) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Name(String::from("pfm")), reason.to_owned()))
+}
+
+pub(crate) fn decode_pfm(bytes: &[u8], limits: &DecodeLimits) -> Result<DynamicImage, ImageError> {
+    let channels = match bytes[1] {
+        b'F' => 3,
+        _ => 1,
+    };
+
+    let mut position = 2;
+    let mut fields = [""; 3];
+
+    for field in fields.iter_mut() {
+        while bytes.get(position).is_some_and(u8::is_ascii_whitespace) {
+            position += 1;
+        }
+
+        let start = position;
+        while bytes.get(position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            position += 1;
+        }
+
+        *field = std::str::from_utf8(&bytes[start..position]).map_err(|_| malformed("header is not ascii"))?;
+    }
+
+    let width: u32 = fields[0].parse().map_err(|_| malformed("invalid width"))?;
+    let height: u32 = fields[1].parse().map_err(|_| malformed("invalid height"))?;
+    let scale: f32 = fields[2].parse().map_err(|_| malformed("invalid scale"))?;
+
+    if width == 0 || height == 0 || width > limits.max_size.0 || height > limits.max_size.1 {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+
+    let samples = width as usize * height as usize * channels;
+
+    // the decoded rgb image is the largest allocation
+    if width as u64 * height as u64 * 12 > limits.max_alloc {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)));
+    }
+
+    // a single whitespace byte ends the header
+    let data = bytes
+        .get(position + 1..position + 1 + 4 * samples)
+        .ok_or_else(|| malformed("pixel data is truncated"))?;
+
+    let read = match scale < 0.0 {
+        true => f32::from_le_bytes,
+        false => f32::from_be_bytes,
+    };
+
+    let row_len = 4 * channels * width as usize;
+    let mut image = Rgb32FImage::new(width, height);
+
+    for (target, source) in image.rows_mut().zip(data.chunks_exact(row_len).rev()) {
+        for (pixel, sample) in target.zip(source.chunks_exact(4 * channels)) {
+            let value = |channel: usize| read([sample[4 * channel], sample[4 * channel + 1], sample[4 * channel + 2], sample[4 * channel + 3]]);
+
+            pixel.0 = match channels {
+                3 => [value(0), value(1), value(2)],
+                _ => [value(0); 3],
+            };
+        }
+    }
+
+    Ok(DynamicImage::ImageRgb32F(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pfm(header: &str, samples: &[f32], little_endian: bool) -> Vec<u8> {
+        let mut bytes = header.as_bytes().to_vec();
+        for sample in samples {
+            bytes.extend_from_slice(&match little_endian {
+                true => sample.to_le_bytes(),
+                false => sample.to_be_bytes(),
+            });
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_gray_little_endian() {
+        let bytes = pfm("Pf\n2 1\n-1.0\n", &[0.25, 2.0], true);
+        assert!(is_pfm(&bytes));
+
+        let image = decode_pfm(&bytes, &DecodeLimits::default()).unwrap().into_rgb32f();
+        assert_eq!(image.get_pixel(0, 0).0, [0.25; 3]);
+        assert_eq!(image.get_pixel(1, 0).0, [2.0; 3]);
+    }
+
+    #[test]
+    fn flips_big_endian_rgb_rows() {
+        // the bottom row comes first
+        let bytes = pfm("PF 1 2 1.0\n", &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], false);
+
+        let image = decode_pfm(&bytes, &DecodeLimits::default()).unwrap().into_rgb32f();
+        assert_eq!(image.get_pixel(0, 0).0, [4.0, 5.0, 6.0]);
+        assert_eq!(image.get_pixel(0, 1).0, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn rejects_bad_headers_and_data() {
+        let limits = DecodeLimits::default();
+
+        assert!(!is_pfm(b"PF"));
+        assert!(!is_pfm(b"P6\n1 1\n255\n"));
+        assert!(matches!(decode_pfm(b"PF\nx 1\n1.0\n", &limits), Err(ImageError::Decoding(_))));
+        assert!(matches!(decode_pfm(b"PF\n1 1\n", &limits), Err(ImageError::Decoding(_))));
+        assert!(matches!(decode_pfm(b"PF\n0 1\n1.0\n", &limits), Err(ImageError::Limits(_))));
+        assert!(matches!(decode_pfm(&pfm("PF\n2 2\n1.0\n", &[0.0; 11], false), &limits), Err(ImageError::Decoding(_))));
+    }
+}
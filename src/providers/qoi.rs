@@ -0,0 +1,154 @@
+use image::error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind};
+use image::{DynamicImage, ImageError, RgbImage, RgbaImage};
+
+use super::decode::DecodeLimits;
+
+// qoi, the "quite ok image format": a 14 byte header ("qoif", big-endian width and height,
+// channel count and colorspace) followed by a byte oriented stream of pixel ops
+const HEADER_LEN: usize = 14;
+
+const OP_RGB: u8 = 0xfe;
+const OP_RGBA: u8 = 0xff;
+const OP_INDEX: u8 = 0b00;
+const OP_DIFF: u8 = 0b01;
+const OP_LUMA: u8 = 0b10;
+
+pub(crate) fn is_qoi(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"qoif")
+}
+
+fn malformed(reason: &str) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Name(String::from("qoi")), reason.to_owned()))
+}
+
+fn hash([r, g, b, a]: [u8; 4]) -> usize {
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+// the colorspace byte is informative only, linear files decode to the same samples
+pub(crate) fn decode_qoi(bytes: &[u8], limits: &DecodeLimits) -> Result<DynamicImage, ImageError> {
+    let header = bytes.get(..HEADER_LEN).ok_or_else(|| malformed("truncated header"))?;
+    let width = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let height = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+    let channels = header[12] as usize;
+
+    if !(channels == 3 || channels == 4) {
+        return Err(malformed("channel count must be 3 or 4"));
+    }
+
+    if width == 0 || height == 0 || width > limits.max_size.0 || height > limits.max_size.1 {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+
+    if channels as u64 * width as u64 * height as u64 > limits.max_alloc {
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory)));
+    }
+
+    let mut ops = bytes[HEADER_LEN..].iter().copied();
+    let mut next = || ops.next().ok_or_else(|| malformed("pixel data is truncated"));
+
+    let mut pixels = vec![0; channels * width as usize * height as usize];
+    let mut index = [[0u8; 4]; 64];
+    let mut pixel = [0, 0, 0, 255];
+    let mut run = 0;
+
+    for target in pixels.chunks_exact_mut(channels) {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let op = next()?;
+
+            match op {
+                OP_RGB => pixel[..3].copy_from_slice(&[next()?, next()?, next()?]),
+                OP_RGBA => pixel = [next()?, next()?, next()?, next()?],
+                _ => match op >> 6 {
+                    OP_INDEX => pixel = index[op as usize],
+                    OP_DIFF => {
+                        pixel[0] = pixel[0].wrapping_add((op >> 4) & 3).wrapping_sub(2);
+                        pixel[1] = pixel[1].wrapping_add((op >> 2) & 3).wrapping_sub(2);
+                        pixel[2] = pixel[2].wrapping_add(op & 3).wrapping_sub(2);
+                    },
+                    OP_LUMA => {
+                        let green = (op & 0x3f).wrapping_sub(32);
+                        let red_blue = next()?;
+
+                        pixel[0] = pixel[0].wrapping_add(green).wrapping_add(red_blue >> 4).wrapping_sub(8);
+                        pixel[1] = pixel[1].wrapping_add(green);
+                        pixel[2] = pixel[2].wrapping_add(green).wrapping_add(red_blue & 0x0f).wrapping_sub(8);
+                    },
+                    // OP_RUN, the pixel itself is repeated `run` more times
+                    _ => run = op & 0x3f,
+                },
+            }
+
+            index[hash(pixel)] = pixel;
+        }
+
+        target.copy_from_slice(&pixel[..channels]);
+    }
+
+    Ok(match channels {
+        3 => DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, pixels).expect("buffer matches the header size")),
+        _ => DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, pixels).expect("buffer matches the header size")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Pair;
+
+    fn qoi(size: Pair<u32>, channels: u8, ops: &[u8]) -> Vec<u8> {
+        let mut bytes = b"qoif".to_vec();
+        bytes.extend_from_slice(&size.0.to_be_bytes());
+        bytes.extend_from_slice(&size.1.to_be_bytes());
+        bytes.extend_from_slice(&[channels, 0]);
+        bytes.extend_from_slice(ops);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        bytes
+    }
+
+    #[test]
+    fn decodes_every_op() {
+        let first = [10, 20, 30, 255];
+        let ops = [
+            &[OP_RGBA, 10, 20, 30, 255][..],
+            // red + 1, green as is, blue - 1
+            &[0b01_11_10_01],
+            // green + 4, red and blue + 4 - 1
+            &[0b10_100100, 0x77],
+            // the previous pixel once more
+            &[0b11_000000],
+            &[OP_INDEX << 6 | hash(first) as u8],
+            &[OP_RGB, 1, 2, 3],
+        ]
+        .concat();
+
+        let image = decode_qoi(&qoi((6, 1), 4, &ops), &DecodeLimits::default()).unwrap().into_rgba8();
+        let pixels: Vec<[u8; 4]> = image.pixels().map(|pixel| pixel.0).collect();
+
+        assert_eq!(pixels, [first, [11, 20, 29, 255], [14, 24, 32, 255], [14, 24, 32, 255], first, [1, 2, 3, 255]]);
+    }
+
+    #[test]
+    fn runs_fill_rgb_images() {
+        let image = decode_qoi(&qoi((2, 2), 3, &[OP_RGB, 5, 6, 7, 0b11_000010]), &DecodeLimits::default()).unwrap();
+
+        assert_eq!(image.color(), image::ColorType::Rgb8);
+        assert_eq!(image.into_bytes(), [5, 6, 7].repeat(4));
+    }
+
+    #[test]
+    fn rejects_bad_headers_and_data() {
+        let limits = DecodeLimits::default();
+
+        assert!(matches!(decode_qoi(b"qoif\0\0", &limits), Err(ImageError::Decoding(_))));
+        assert!(matches!(decode_qoi(&qoi((1, 1), 2, &[]), &limits), Err(ImageError::Decoding(_))));
+        assert!(matches!(decode_qoi(&qoi((0, 1), 4, &[]), &limits), Err(ImageError::Limits(_))));
+        assert!(matches!(decode_qoi(&qoi((u32::MAX, 1), 4, &[]), &limits), Err(ImageError::Limits(_))));
+
+        // two pixels, one op and the end marker read as ops
+        let truncated = &qoi((4, 4), 4, &[OP_RGB, 1, 2, 3])[..HEADER_LEN + 4];
+        assert!(matches!(decode_qoi(truncated, &limits), Err(ImageError::Decoding(_))));
+    }
+}
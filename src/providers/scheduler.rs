@@ -0,0 +1,331 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::{io, thread};
+
+use super::decode::CancelToken;
+
+type Job = Box<dyn FnOnce(&CancelToken) + Send>;
+
+// the pool and worker index of the thread, so jobs spawned by a job stay on its worker
+thread_local! {
+    static CURRENT_WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+// what a job is for, workers always take the most urgent job there is, so prefetches and
+// thumbnails only ever run on workers the image being waited for doesn't need
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Thumbnail,
+    Prefetch,
+    // the image the user is waiting for
+    Visible,
+}
+
+impl Priority {
+    const COUNT: usize = 3;
+
+    // queue index, the most urgent first
+    fn index(self) -> usize {
+        match self {
+            Priority::Visible => 0,
+            Priority::Prefetch => 1,
+            Priority::Thumbnail => 2,
+        }
+    }
+}
+
+// a queued job, taken by whichever of its tickets runs first
+struct JobCell {
+    job: Mutex<Option<Job>>,
+    token: CancelToken,
+    finished: AtomicBool,
+}
+
+impl JobCell {
+    fn run(&self) {
+        let Some(job) = self.job.lock().unwrap().take() else {
+            return;
+        };
+
+        // a panicking job would take its worker with it, and with a single worker every
+        // job after it would wait forever
+        if !self.token.is_cancelled() && panic::catch_unwind(AssertUnwindSafe(|| job(&self.token))).is_err() {
+            log::error!("a scheduled job panicked");
+        }
+
+        self.finished.store(true, Ordering::Relaxed);
+    }
+}
+
+type Ticket = Arc<JobCell>;
+
+struct Shared {
+    // a deque per worker with a queue per priority, a worker takes from the front of its
+    // own and steals from the back of the others
+    queues: Vec<Mutex<[VecDeque<Ticket>; Priority::COUNT]>>,
+    // tickets queued and not taken yet, wakes idle workers
+    pending: Mutex<usize>,
+    wake: Condvar,
+    shutdown: AtomicBool,
+    // the worker the next job spawned from outside the pool is queued on
+    next: AtomicUsize,
+}
+
+impl Shared {
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn push(&self, ticket: Ticket, priority: Priority) {
+        let own = CURRENT_WORKER.get().filter(|(pool, _)| *pool == self.id()).map(|(_, worker)| worker);
+        let worker = own.unwrap_or_else(|| self.next.fetch_add(1, Ordering::Relaxed) % self.queues.len());
+
+        // counted before it can be taken, a worker awake for another ticket may take it and
+        // count it down right away
+        *self.pending.lock().unwrap() += 1;
+        self.queues[worker].lock().unwrap()[priority.index()].push_back(ticket);
+        self.wake.notify_one();
+    }
+
+    // the most urgent ticket, from the worker's own deque before the others'
+    fn take(&self, worker: usize) -> Option<Ticket> {
+        let len = self.queues.len();
+
+        for priority in 0..Priority::COUNT {
+            if let Some(ticket) = self.queues[worker].lock().unwrap()[priority].pop_front() {
+                return Some(ticket);
+            }
+
+            for other in (1..len).map(|offset| (worker + offset) % len) {
+                if let Some(ticket) = self.queues[other].lock().unwrap()[priority].pop_back() {
+                    return Some(ticket);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn work(&self, worker: usize) {
+        CURRENT_WORKER.set(Some((self.id(), worker)));
+
+        loop {
+            {
+                let mut pending = self.pending.lock().unwrap();
+                while *pending == 0 && !self.shutdown.load(Ordering::Relaxed) {
+                    pending = self.wake.wait(pending).unwrap();
+                }
+
+                if self.shutdown.load(Ordering::Relaxed) {
+                    return;
+                }
+            }
+
+            // another worker may have taken the ticket this one was woken for
+            if let Some(ticket) = self.take(worker) {
+                *self.pending.lock().unwrap() -= 1;
+                ticket.run();
+            } else {
+                thread::yield_now();
+            }
+        }
+    }
+}
+
+// ends the workers once the last `Scheduler` clone is gone, a job at hand is finished first
+struct Pool {
+    shared: Arc<Shared>,
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        let _pending = self.shared.pending.lock().unwrap();
+        self.shared.wake.notify_all();
+    }
+}
+
+// worker threads shared by decodes, prefetches and thumbnails, cloning is cheap and
+// clones queue on the same workers
+// every worker has its own deque and steals from the others when it runs dry, so no job
+// waits behind a busy worker while another one is idle
+#[derive(Clone)]
+pub struct Scheduler {
+    pool: Arc<Pool>,
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler").field("workers", &self.workers()).finish()
+    }
+}
+
+impl Scheduler {
+    // at least one worker
+    pub fn new(workers: usize) -> io::Result<Self> {
+        let workers = workers.max(1);
+        let shared = Arc::new(Shared {
+            queues: (0..workers).map(|_| Mutex::new(Default::default())).collect(),
+            pending: Mutex::new(0),
+            wake: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            next: AtomicUsize::new(0),
+        });
+
+        for worker in 0..workers {
+            let shared = Arc::clone(&shared);
+            thread::Builder::new().name(format!("egami-worker-{worker}")).spawn(move || shared.work(worker))?;
+        }
+
+        Ok(Self { pool: Arc::new(Pool { shared }) })
+    }
+
+    // the pool the providers use unless they are given one, a worker per core but one, so
+    // the render thread keeps a core
+    pub fn global() -> io::Result<Self> {
+        static GLOBAL: OnceLock<Scheduler> = OnceLock::new();
+
+        if let Some(scheduler) = GLOBAL.get() {
+            return Ok(scheduler.clone());
+        }
+
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+        let scheduler = Scheduler::new(cores.saturating_sub(1))?;
+        Ok(GLOBAL.get_or_init(|| scheduler).clone())
+    }
+
+    pub fn workers(&self) -> usize {
+        self.pool.shared.queues.len()
+    }
+
+    // queues `job`, it gets the handle's token to pass on, e.g. to
+    // `DecodeLimits::cancellable`, and doesn't run at all when cancelled before it started
+    // decodes with a cancellable limit run right on the worker, so the workers bound how
+    // many decodes run at once
+    pub fn spawn<F>(&self, priority: Priority, job: F) -> JobHandle
+    where
+        F: FnOnce(&CancelToken) + Send + 'static,
+    {
+        let cell = Arc::new(JobCell {
+            job: Mutex::new(Some(Box::new(job))),
+            token: CancelToken::new(),
+            finished: AtomicBool::new(false),
+        });
+
+        self.pool.shared.push(Arc::clone(&cell), priority);
+        JobHandle { cell, priority, shared: Arc::clone(&self.pool.shared) }
+    }
+}
+
+// a job queued on a `Scheduler`, dropping the handle leaves the job queued
+pub struct JobHandle {
+    cell: Arc<JobCell>,
+    priority: Priority,
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for JobHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobHandle").field("priority", &self.priority).field("finished", &self.is_finished()).finish()
+    }
+}
+
+impl JobHandle {
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    // queues the job again at a more urgent priority, e.g. when a prefetched image is
+    // stepped to before it was decoded, whichever turn comes first runs it
+    pub fn promote(&mut self, priority: Priority) {
+        if priority <= self.priority || self.cell.job.lock().unwrap().is_none() {
+            return;
+        }
+
+        self.priority = priority;
+        self.shared.push(Arc::clone(&self.cell), priority);
+    }
+
+    pub fn cancel(&self) {
+        self.cell.token.cancel();
+    }
+
+    pub fn token(&self) -> &CancelToken {
+        &self.cell.token
+    }
+
+    // it ran to the end, or was cancelled before it started and was dropped
+    pub fn is_finished(&self) -> bool {
+        self.cell.finished.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use super::*;
+
+    const WAIT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn jobs_run_most_urgent_first() {
+        let scheduler = Scheduler::new(1).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let (blocker, blocked) = mpsc::channel::<()>();
+
+        // holds the only worker until every job is queued
+        scheduler.spawn(Priority::Visible, move |_| {
+            let _ = blocked.recv();
+        });
+
+        for priority in [Priority::Thumbnail, Priority::Prefetch, Priority::Visible] {
+            let sender = sender.clone();
+            scheduler.spawn(priority, move |_| sender.send(priority).unwrap());
+        }
+
+        blocker.send(()).unwrap();
+        let order: Vec<_> = (0..3).map(|_| receiver.recv_timeout(WAIT).unwrap()).collect();
+
+        assert_eq!(order, [Priority::Visible, Priority::Prefetch, Priority::Thumbnail]);
+    }
+
+    #[test]
+    fn cancelled_jobs_dont_run() {
+        let scheduler = Scheduler::new(1).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let (blocker, blocked) = mpsc::channel::<()>();
+
+        scheduler.spawn(Priority::Visible, move |_| {
+            let _ = blocked.recv();
+        });
+
+        let cancelled = {
+            let sender = sender.clone();
+            scheduler.spawn(Priority::Visible, move |_| sender.send("cancelled").unwrap())
+        };
+        scheduler.spawn(Priority::Visible, move |_| sender.send("kept").unwrap());
+
+        cancelled.cancel();
+        blocker.send(()).unwrap();
+
+        assert_eq!(receiver.recv_timeout(WAIT).unwrap(), "kept");
+        assert!(cancelled.is_finished());
+    }
+
+    #[test]
+    fn panicking_job_keeps_its_worker() {
+        let scheduler = Scheduler::new(1).unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        let panicking = scheduler.spawn(Priority::Visible, |_| panic!("job bug"));
+        scheduler.spawn(Priority::Visible, move |_| sender.send(()).unwrap());
+
+        assert!(receiver.recv_timeout(WAIT).is_ok());
+        assert!(panicking.is_finished());
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+use std::path::Path;
+
+use super::{icon, portable, qoi};
+
+// markup is looked for this far into a file, past a byte order mark, comments and whitespace
+const MARKUP_WINDOW: usize = 1024;
+
+// what a file is by its first bytes, whatever its name says, including formats that can't
+// be shown so failures can name them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signature {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Bmp,
+    Tiff,
+    Ico,
+    Cur,
+    Ani,
+    Qoi,
+    Pnm,
+    Pfm,
+    Farbfeld,
+    Heif,
+    Avif,
+    JpegXl,
+    OpenExr,
+    RadianceHdr,
+    Dds,
+    Psd,
+    Pdf,
+    Svg,
+    Html,
+}
+
+impl Signature {
+    pub fn name(self) -> &'static str {
+        match self {
+            Signature::Png => "PNG",
+            Signature::Jpeg => "JPEG",
+            Signature::Gif => "GIF",
+            Signature::WebP => "WebP",
+            Signature::Bmp => "BMP",
+            Signature::Tiff => "TIFF",
+            Signature::Ico => "ICO",
+            Signature::Cur => "CUR",
+            Signature::Ani => "ANI",
+            Signature::Qoi => "QOI",
+            Signature::Pnm => "PNM",
+            Signature::Pfm => "PFM",
+            Signature::Farbfeld => "farbfeld",
+            Signature::Heif => "HEIF",
+            Signature::Avif => "AVIF",
+            Signature::JpegXl => "JPEG XL",
+            Signature::OpenExr => "OpenEXR",
+            Signature::RadianceHdr => "Radiance HDR",
+            Signature::Dds => "DDS",
+            Signature::Psd => "Photoshop",
+            Signature::Pdf => "PDF",
+            Signature::Svg => "SVG",
+            Signature::Html => "HTML",
+        }
+    }
+
+    // the usual one first
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Signature::Png => &["png", "apng"],
+            Signature::Jpeg => &["jpg", "jpeg", "jpe", "jfif"],
+            Signature::Gif => &["gif"],
+            Signature::WebP => &["webp"],
+            Signature::Bmp => &["bmp", "dib"],
+            Signature::Tiff => &["tif", "tiff"],
+            Signature::Ico => &["ico"],
+            Signature::Cur => &["cur"],
+            Signature::Ani => &["ani"],
+            Signature::Qoi => &["qoi"],
+            Signature::Pnm => &["ppm", "pgm", "pbm", "pam", "pnm"],
+            Signature::Pfm => &["pfm"],
+            Signature::Farbfeld => &["ff", "farbfeld"],
+            Signature::Heif => &["heic", "heif", "hif"],
+            Signature::Avif => &["avif"],
+            Signature::JpegXl => &["jxl"],
+            Signature::OpenExr => &["exr"],
+            Signature::RadianceHdr => &["hdr"],
+            Signature::Dds => &["dds"],
+            Signature::Psd => &["psd"],
+            Signature::Pdf => &["pdf"],
+            Signature::Svg => &["svg"],
+            Signature::Html => &["html", "htm"],
+        }
+    }
+
+    // the format an extension stands for, case is ignored
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.to_ascii_lowercase();
+        ALL.iter().copied().find(|signature| signature.extensions().contains(&extension.as_str()))
+    }
+
+    // whether the decoders built into this crate read it
+    pub fn is_supported(self) -> bool {
+        match self {
+            Signature::Heif => cfg!(feature = "heic"),
+            _ => !matches!(
+                self,
+                Signature::Avif
+                    | Signature::JpegXl
+                    | Signature::OpenExr
+                    | Signature::RadianceHdr
+                    | Signature::Dds
+                    | Signature::Psd
+                    | Signature::Pdf
+                    | Signature::Svg
+                    | Signature::Html
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+const ALL: [Signature; 23] = [
+    Signature::Png,
+    Signature::Jpeg,
+    Signature::Gif,
+    Signature::WebP,
+    Signature::Bmp,
+    Signature::Tiff,
+    Signature::Ico,
+    Signature::Cur,
+    Signature::Ani,
+    Signature::Qoi,
+    Signature::Pnm,
+    Signature::Pfm,
+    Signature::Farbfeld,
+    Signature::Heif,
+    Signature::Avif,
+    Signature::JpegXl,
+    Signature::OpenExr,
+    Signature::RadianceHdr,
+    Signature::Dds,
+    Signature::Psd,
+    Signature::Pdf,
+    Signature::Svg,
+    Signature::Html,
+];
+
+// `None` when the bytes match no signature, text that isn't svg or html included
+pub fn sniff(bytes: &[u8]) -> Option<Signature> {
+    let brand = bytes.get(4..8).filter(|&kind| kind == b"ftyp").and(bytes.get(8..12));
+
+    let signature = match bytes {
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Signature::Png,
+        [0xff, 0xd8, 0xff, ..] => Signature::Jpeg,
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Signature::Gif,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Signature::WebP,
+        _ if icon::is_ani(bytes) => Signature::Ani,
+        [b'B', b'M', ..] => Signature::Bmp,
+        [b'I', b'I', 0x2a, 0x00, ..] | [b'M', b'M', 0x00, 0x2a, ..] => Signature::Tiff,
+        [0x00, 0x00, 0x01, 0x00, ..] => Signature::Ico,
+        [0x00, 0x00, 0x02, 0x00, ..] => Signature::Cur,
+        _ if qoi::is_qoi(bytes) => Signature::Qoi,
+        _ if portable::is_pfm(bytes) => Signature::Pfm,
+        [b'P', b'1'..=b'7', next, ..] if next.is_ascii_whitespace() => Signature::Pnm,
+        _ if bytes.starts_with(b"farbfeld") => Signature::Farbfeld,
+        _ if matches!(brand, Some(b"avif" | b"avis")) => Signature::Avif,
+        _ if matches!(brand, Some(b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis" | b"mif1" | b"msf1")) => Signature::Heif,
+        [0xff, 0x0a, ..] => Signature::JpegXl,
+        _ if bytes.starts_with(b"\0\0\0\x0cJXL \r\n\x87\n") => Signature::JpegXl,
+        [0x76, 0x2f, 0x31, 0x01, ..] => Signature::OpenExr,
+        _ if bytes.starts_with(b"#?RADIANCE") || bytes.starts_with(b"#?RGBE") => Signature::RadianceHdr,
+        _ if bytes.starts_with(b"DDS ") => Signature::Dds,
+        _ if bytes.starts_with(b"8BPS") => Signature::Psd,
+        _ if bytes.starts_with(b"%PDF-") => Signature::Pdf,
+        _ => return sniff_markup(bytes),
+    };
+
+    Some(signature)
+}
+
+fn sniff_markup(bytes: &[u8]) -> Option<Signature> {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(MARKUP_WINDOW)]).to_ascii_lowercase();
+    let head = head.trim_start();
+
+    if !head.starts_with('<') {
+        return None;
+    }
+
+    match () {
+        _ if head.contains("<svg") => Some(Signature::Svg),
+        _ if head.starts_with("<!doctype html") || head.contains("<html") => Some(Signature::Html),
+        _ => None,
+    }
+}
+
+// why a file that failed to decode may have failed, from its name and first bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    Empty,
+    // the extension names another format than the contents are
+    Mismatch { extension: String, actual: Signature },
+    // a format none of the decoders read
+    Unsupported(Signature),
+    // no signature matched, `extension` is what the name claims
+    Unrecognized { extension: Option<String> },
+}
+
+impl Diagnosis {
+    // `None` when the file is what it claims to be and could be decoded, the failure is
+    // then in the data itself
+    pub fn of(path: Option<&Path>, bytes: &[u8]) -> Option<Self> {
+        let extension = path.and_then(Path::extension).map(|extension| extension.to_string_lossy().into_owned());
+        let claimed = extension.as_deref().and_then(Signature::from_extension);
+
+        match (sniff(bytes), extension) {
+            _ if bytes.is_empty() => Some(Diagnosis::Empty),
+            (Some(actual), Some(extension)) if claimed.is_some_and(|claimed| claimed != actual) => Some(Diagnosis::Mismatch { extension, actual }),
+            (Some(actual), _) if !actual.is_supported() => Some(Diagnosis::Unsupported(actual)),
+            (Some(_), _) => None,
+            (None, extension) => Some(Diagnosis::Unrecognized { extension }),
+        }
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnosis::Empty => write!(f, "this file is empty"),
+            Diagnosis::Mismatch { extension, actual: Signature::Html } => {
+                write!(f, "this file claims .{extension} but is actually an HTML page, likely an error page saved by a download")
+            },
+            Diagnosis::Mismatch { extension, actual } if actual.is_supported() => {
+                write!(f, "this file claims .{extension} but is actually {actual}, renaming it to .{} helps other programs", actual.extensions()[0])
+            },
+            Diagnosis::Mismatch { extension, actual } => write!(f, "this file claims .{extension} but is actually {actual}, which isn't supported"),
+            Diagnosis::Unsupported(Signature::Html) => write!(f, "this file is an HTML page, not an image"),
+            Diagnosis::Unsupported(actual) => write!(f, "this file is {actual}, which isn't supported"),
+            Diagnosis::Unrecognized { extension: Some(extension) } => write!(f, "this file claims .{extension} but doesn't start like any known image format"),
+            Diagnosis::Unrecognized { extension: None } => write!(f, "the data doesn't start like any known image format"),
+        }
+    }
+}
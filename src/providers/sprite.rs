@@ -0,0 +1,514 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::decode::{self, DecodeError, DecodeLimits};
+use serde_json::Value;
+
+use super::json::{self, JsonError};
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat};
+
+const OUTLINE: [u8; 4] = [255, 0, 255, 255];
+const SELECTED_OUTLINE: [u8; 4] = [255, 255, 0, 255];
+
+// one sprite of a sheet, `size` is the size of the area in the sheet, which is the
+// sprite turned by 90 degrees when `rotated`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteRegion {
+    pub name: Option<String>,
+    pub origin: Pair<u32>,
+    pub size: Pair<u32>,
+    pub rotated: bool,
+    // per sprite frame time from the atlas, if it has one
+    pub duration: Option<Duration>,
+}
+
+// equally sized cells in rows, `margin` is the border around the grid and `spacing` the
+// gap between cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GridSpec {
+    pub cell_size: Pair<u32>,
+    pub margin: Pair<u32>,
+    pub spacing: Pair<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtlasError {
+    Json(JsonError),
+    // the document parsed but isn't a TexturePacker atlas
+    Invalid(&'static str),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Json(error) => write!(f, "{error}"),
+            AtlasError::Invalid(reason) => write!(f, "invalid atlas: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+impl From<JsonError> for AtlasError {
+    fn from(error: JsonError) -> Self {
+        AtlasError::Json(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpriteSheet {
+    pub regions: Vec<SpriteRegion>,
+}
+
+impl SpriteSheet {
+    // cells that don't fit entirely inside the image are left out, fails when a cell with
+    // its spacing or margins doesn't fit into a `u32`
+    pub fn grid(image_size: Pair<u32>, spec: GridSpec) -> Result<Self, AtlasError> {
+        let (cell_width, cell_height) = (spec.cell_size.0.max(1), spec.cell_size.1.max(1));
+
+        for (cell_len, margin, spacing) in [(cell_width, spec.margin.0, spec.spacing.0), (cell_height, spec.margin.1, spec.spacing.1)] {
+            let fits = cell_len.checked_add(spacing).is_some() && cell_len.checked_add(margin).and_then(|len| len.checked_add(margin)).is_some();
+
+            if !fits {
+                return Err(AtlasError::Invalid("grid cells are too large"));
+            }
+        }
+
+        let cells = |image_len: u32, margin: u32, cell_len: u32, spacing: u32| {
+            std::iter::successors(Some(margin), move |start| start.checked_add(cell_len + spacing))
+                .take_while(move |start| start.checked_add(cell_len + margin).is_some_and(|end| end <= image_len))
+        };
+
+        let regions = cells(image_size.1, spec.margin.1, cell_height, spec.spacing.1)
+            .flat_map(|y| cells(image_size.0, spec.margin.0, cell_width, spec.spacing.0).map(move |x| (x, y)))
+            .map(|origin| SpriteRegion {
+                name: None,
+                origin,
+                size: (cell_width, cell_height),
+                rotated: false,
+                duration: None,
+            })
+            .collect();
+
+        Ok(Self { regions })
+    }
+
+    // the json hash and json array flavors, sprites keep the order of the file
+    pub fn from_texture_packer(json: &str) -> Result<Self, AtlasError> {
+        let document = json::parse(json)?;
+
+        let entries: Vec<(Option<&str>, &Value)> = match document.get("frames") {
+            Some(Value::Object(frames)) => frames.iter().map(|(name, frame)| (Some(name.as_str()), frame)).collect(),
+            Some(Value::Array(frames)) => frames.iter().map(|frame| (frame.get("filename").and_then(Value::as_str), frame)).collect(),
+            _ => return Err(AtlasError::Invalid("no frames")),
+        };
+
+        let regions = entries
+            .into_iter()
+            .map(|(name, entry)| {
+                let frame = entry.get("frame").ok_or(AtlasError::Invalid("sprite without a frame"))?;
+                let field = |key| frame.get(key).and_then(as_u32).ok_or(AtlasError::Invalid("frame fields must be whole numbers"));
+                let rotated = entry.get("rotated").and_then(Value::as_bool).unwrap_or(false);
+
+                // frame sizes are the sprite's, rotated sprites take up the transposed area
+                let size = match rotated {
+                    true => (field("h")?, field("w")?),
+                    false => (field("w")?, field("h")?),
+                };
+
+                Ok(SpriteRegion {
+                    name: name.map(str::to_owned),
+                    origin: (field("x")?, field("y")?),
+                    size,
+                    rotated,
+                    duration: entry.get("duration").and_then(as_u32).map(|ms| Duration::from_millis(ms as u64)),
+                })
+            })
+            .collect::<Result<_, AtlasError>>()?;
+
+        Ok(Self { regions })
+    }
+}
+
+// whole numbers only, `2.0` is accepted like `2`
+fn as_u32(value: &Value) -> Option<u32> {
+    value.as_f64().filter(|number| number.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(number)).map(|number| number as u32)
+}
+
+// what a `SpriteSheetProvider` shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpriteView {
+    // the whole sheet with every region outlined, the selected one highlighted
+    #[default]
+    Sheet,
+    // only the selected sprite, or the playing range
+    Sprite,
+}
+
+#[derive(Debug, Clone)]
+struct SpritePlayback {
+    range: Range<usize>,
+    delay: Duration,
+    index: usize,
+    shown_at: Instant,
+}
+
+// a sprite sheet with its regions, frames are only yielded when what is shown changes
+#[derive(Debug, Clone)]
+pub struct SpriteSheetProvider {
+    size: Pair<u32>,
+    sheet: Arc<[u8]>,
+    regions: Arc<[SpriteRegion]>,
+    view: SpriteView,
+    selected: usize,
+    playback: Option<SpritePlayback>,
+    changed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpriteFrame {
+    size: Pair<u32>,
+    buffer: Arc<[u8]>,
+    offset: usize,
+    stride: usize,
+}
+
+impl SpriteSheetProvider {
+    pub fn from_bytes(bytes: &[u8], sheet: SpriteSheet) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_limits(bytes, sheet, &DecodeLimits::default())
+    }
+
+    // regions reaching outside of the image are dropped
+    pub fn from_bytes_with_limits(bytes: &[u8], sheet: SpriteSheet, limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        let decoded = decode::decode(bytes.to_vec(), limits)?;
+
+        if decoded.scale() != 1.0 {
+            log::warn!("sprite sheet was downscaled to {:?}, regions no longer line up", (decoded.image.width(), decoded.image.height()));
+        }
+
+        let image = decoded.image.into_rgba8();
+        let size = image.dimensions();
+
+        let regions: Arc<[SpriteRegion]> = sheet
+            .regions
+            .into_iter()
+            .filter(|region| {
+                let inside = region.size.0 > 0
+                    && region.size.1 > 0
+                    && region.origin.0.checked_add(region.size.0).is_some_and(|end| end <= size.0)
+                    && region.origin.1.checked_add(region.size.1).is_some_and(|end| end <= size.1);
+
+                if !inside {
+                    log::warn!("dropping sprite region {:?} at {:?}, it is outside of the {size:?} sheet", region.name, region.origin);
+                }
+
+                inside
+            })
+            .collect();
+
+        Ok(Self {
+            size,
+            sheet: image.into_raw().into(),
+            regions,
+            view: SpriteView::default(),
+            selected: 0,
+            playback: None,
+            changed: true,
+        })
+    }
+
+    pub fn regions(&self) -> &[SpriteRegion] {
+        &self.regions
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn view(&self) -> SpriteView {
+        self.view
+    }
+
+    pub fn set_view(&mut self, view: SpriteView) {
+        self.changed |= self.view != view;
+        self.view = view;
+    }
+
+    // returns false and keeps the selection when `index` is out of range, stops playback
+    pub fn select(&mut self, index: usize) -> bool {
+        let valid = index < self.regions.len();
+
+        if valid {
+            self.selected = index;
+            self.playback = None;
+            self.changed = true;
+        }
+
+        valid
+    }
+
+    // wraps around at either end
+    pub fn step(&mut self, forward: bool) {
+        let count = self.regions.len().max(1);
+
+        self.select(match forward {
+            true => (self.selected + 1) % count,
+            false => (self.selected + count - 1) % count,
+        });
+    }
+
+    // loops over `range` in the sprite view, sprites with a duration in the atlas use it
+    // instead of `delay`
+    pub fn play(&mut self, range: Range<usize>, delay: Duration) {
+        let range = range.start.min(self.regions.len())..range.end.min(self.regions.len());
+
+        self.playback = match range.is_empty() {
+            true => None,
+            false => Some(SpritePlayback { index: range.start, range, delay, shown_at: Instant::now() }),
+        };
+
+        self.view = SpriteView::Sprite;
+        self.changed = true;
+    }
+
+    // keeps showing the sprite that was on screen
+    pub fn stop(&mut self) {
+        if let Some(playback) = self.playback.take() {
+            self.selected = playback.index;
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    fn delay(&self, index: usize, fallback: Duration) -> Duration {
+        self.regions[index].duration.unwrap_or(fallback).max(Duration::from_millis(1))
+    }
+
+    // advances playback to the wall clock, true when another sprite is due
+    fn advance(&mut self) -> bool {
+        let Some(mut playback) = self.playback.clone() else {
+            return false;
+        };
+
+        let now = Instant::now();
+        let previous = playback.index;
+
+        while now.duration_since(playback.shown_at) >= self.delay(playback.index, playback.delay) {
+            playback.shown_at += self.delay(playback.index, playback.delay);
+            playback.index = match playback.index + 1 < playback.range.end {
+                true => playback.index + 1,
+                false => playback.range.start,
+            };
+        }
+
+        let advanced = playback.index != previous;
+        self.playback = Some(playback);
+        advanced
+    }
+
+    fn sheet_frame(&self) -> SpriteFrame {
+        let mut pixels = self.sheet.to_vec();
+        let row_len = 4 * self.size.0 as usize;
+
+        // the selected region is drawn last so it stays visible where outlines overlap
+        let selected = self.regions.get(self.selected).map(|region| (region, SELECTED_OUTLINE));
+
+        for (region, color) in self.regions.iter().map(|region| (region, OUTLINE)).chain(selected) {
+            let (x0, y0) = (region.origin.0 as usize, region.origin.1 as usize);
+            let (x1, y1) = (x0 + region.size.0 as usize - 1, y0 + region.size.1 as usize - 1);
+
+            for x in x0..=x1 {
+                pixels[y0 * row_len + 4 * x..][..4].copy_from_slice(&color);
+                pixels[y1 * row_len + 4 * x..][..4].copy_from_slice(&color);
+            }
+
+            for y in y0..=y1 {
+                pixels[y * row_len + 4 * x0..][..4].copy_from_slice(&color);
+                pixels[y * row_len + 4 * x1..][..4].copy_from_slice(&color);
+            }
+        }
+
+        SpriteFrame { size: self.size, buffer: pixels.into(), offset: 0, stride: row_len }
+    }
+
+    // upright sprites are shown straight out of the sheet, rotated ones are turned back
+    fn sprite_frame(&self, index: usize) -> Option<SpriteFrame> {
+        let region = self.regions.get(index)?;
+        let row_len = 4 * self.size.0 as usize;
+        let offset = region.origin.1 as usize * row_len + 4 * region.origin.0 as usize;
+
+        if !region.rotated {
+            return Some(SpriteFrame { size: region.size, buffer: Arc::clone(&self.sheet), offset, stride: row_len });
+        }
+
+        // packed 90 degrees clockwise, the sprite's rows are the area's columns from the right
+        let (width, height) = (region.size.1 as usize, region.size.0 as usize);
+        let mut pixels = Vec::with_capacity(4 * width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let source = offset + x * row_len + 4 * (height - 1 - y);
+                pixels.extend_from_slice(&self.sheet[source..source + 4]);
+            }
+        }
+
+        Some(SpriteFrame { size: (width as u32, height as u32), buffer: pixels.into(), offset: 0, stride: 4 * width })
+    }
+}
+
+impl HasSize<u32> for SpriteSheetProvider {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasSize<u32> for SpriteFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for SpriteFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for SpriteFrame {
+    fn format(&self) -> PixelFormat {
+        PixelFormat::Rgba8
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        Some(self.stride)
+    }
+}
+
+impl HasOrientation for SpriteFrame {}
+
+impl HasData for SpriteFrame {
+    fn data(&self) -> &[u8] {
+        let len = self.stride * (self.size.1 as usize - 1) + 4 * self.size.0 as usize;
+        &self.buffer[self.offset..self.offset + len]
+    }
+}
+
+impl Iterator for SpriteSheetProvider {
+    type Item = SpriteFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let advanced = self.view == SpriteView::Sprite && self.advance();
+
+        if !std::mem::take(&mut self.changed) && !advanced {
+            return None;
+        }
+
+        match (self.view, &self.playback) {
+            (SpriteView::Sheet, _) => Some(self.sheet_frame()),
+            (SpriteView::Sprite, Some(playback)) => self.sprite_frame(playback.index),
+            (SpriteView::Sprite, None) => self.sprite_frame(self.selected),
+        }
+    }
+}
+
+impl FrameProvider for SpriteSheetProvider {
+    type Frame = SpriteFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        let index = self.playback.as_ref().map_or(self.selected, |playback| playback.index);
+
+        match self.view {
+            SpriteView::Sheet => Some(self.size),
+            SpriteView::Sprite => self.regions.get(index).map(|region| region.size),
+        }
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        match (self.view, &self.playback) {
+            _ if self.changed => FrameSchedule::Soon,
+            (SpriteView::Sprite, Some(playback)) => FrameSchedule::At(playback.shown_at + self.delay(playback.index, playback.delay)),
+            _ => FrameSchedule::Still,
+        }
+    }
+
+    // playback starts over at the beginning of its range, a single sprite or the sheet is
+    // shown again
+    fn rewind(&mut self) -> bool {
+        if let Some(playback) = self.playback.as_mut() {
+            playback.index = playback.range.start;
+            playback.shown_at = Instant::now();
+        }
+
+        self.changed = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origins(sheet: &SpriteSheet) -> Vec<Pair<u32>> {
+        sheet.regions.iter().map(|region| region.origin).collect()
+    }
+
+    #[test]
+    fn grid_leaves_out_partial_cells() {
+        let spec = GridSpec { cell_size: (4, 4), margin: (1, 1), spacing: (2, 0) };
+        let sheet = SpriteSheet::grid((17, 10), spec).unwrap();
+
+        assert_eq!(origins(&sheet), [(1, 1), (7, 1), (1, 5), (7, 5)]);
+        assert!(sheet.regions.iter().all(|region| region.size == (4, 4)));
+    }
+
+    #[test]
+    fn grid_rejects_overflowing_cells() {
+        let large = [
+            GridSpec { cell_size: (u32::MAX, 1), margin: (0, 0), spacing: (1, 0) },
+            GridSpec { cell_size: (1, u32::MAX - 1), margin: (0, 1), spacing: (0, 0) },
+            GridSpec { cell_size: (1, 1), margin: (u32::MAX / 2 + 1, 0), spacing: (0, 0) },
+        ];
+
+        for spec in large {
+            assert_eq!(SpriteSheet::grid((u32::MAX, u32::MAX), spec), Err(AtlasError::Invalid("grid cells are too large")), "{spec:?}");
+        }
+    }
+
+    #[test]
+    fn grid_stops_at_the_end_of_u32() {
+        let spec = GridSpec { cell_size: (u32::MAX / 2, 1), margin: (0, 0), spacing: (0, 0) };
+        let sheet = SpriteSheet::grid((u32::MAX, 1), spec).unwrap();
+
+        assert_eq!(origins(&sheet), [(0, 0), (u32::MAX / 2, 0)]);
+    }
+
+    #[test]
+    fn texture_packer_hash_keeps_the_file_order() {
+        let json = r#"{"frames": {
+            "b": {"frame": {"x": 0, "y": 0, "w": 2, "h": 3}, "duration": 40},
+            "a": {"frame": {"x": 2, "y": 0, "w": 2, "h": 3}, "rotated": true}
+        }}"#;
+        let sheet = SpriteSheet::from_texture_packer(json).unwrap();
+
+        let names: Vec<_> = sheet.regions.iter().map(|region| region.name.as_deref().unwrap()).collect();
+        assert_eq!(names, ["b", "a"]);
+        assert_eq!(sheet.regions[0].duration, Some(Duration::from_millis(40)));
+        assert_eq!(sheet.regions[1].size, (3, 2));
+    }
+
+    #[test]
+    fn texture_packer_rejects_fractional_fields() {
+        let json = r#"{"frames": [{"filename": "a", "frame": {"x": 0.5, "y": 0, "w": 2, "h": 3}}]}"#;
+
+        assert!(matches!(SpriteSheet::from_texture_packer(json), Err(AtlasError::Invalid(_))));
+        assert!(matches!(SpriteSheet::from_texture_packer("{"), Err(AtlasError::Json(_))));
+    }
+}
@@ -0,0 +1,333 @@
+use std::time::{Duration, Instant};
+
+use crate::transport::Transport;
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair};
+
+// a frame and when it should be on screen, counted from the start of the stream
+#[derive(Debug, Clone)]
+pub struct TimedFrame<Frame> {
+    pub frame: Frame,
+    pub timestamp: Duration,
+}
+
+// a pull based source of timed frames such as a video decoder, frames come out in
+// presentation order and `Ok(None)` marks the end of the stream
+// wrapping one in a `StreamPlayer` turns it into a frame provider for `draw_frame`
+pub trait StreamingFrameProvider {
+    type Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData;
+    type Error;
+
+    fn next_frame(&mut self) -> Result<Option<TimedFrame<Self::Frame>>, Self::Error>;
+
+    // starts over at the first frame, `Ok(false)` for sources that can't go back
+    fn rewind(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    // from the start of the first frame to the end of the last, when known up front
+    fn duration(&self) -> Option<Duration> {
+        None
+    }
+
+    // makes the frame showing at `timestamp` the next one, `Ok(false)` for sources that
+    // can't jump, players then rewind and skip ahead instead
+    fn seek(&mut self, _timestamp: Duration) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+// the media time of another player, e.g. the position of the audio a library is playing
+// alongside the video, it may jump when that player seeks or starts over
+pub type ExternalClock = Box<dyn FnMut() -> Duration>;
+
+// a clock that jumps further ahead than this is followed by seeking instead of decoding
+// and dropping every frame in between
+const CLOCK_JUMP: Duration = Duration::from_secs(1);
+
+// plays a stream against the wall clock: `next` yields the latest frame that is due and
+// `None` in between, frames that were late are decoded and dropped so playback keeps speed
+pub struct StreamPlayer<Stream: StreamingFrameProvider> {
+    stream: Stream,
+    looping: bool,
+    started: Option<Instant>,
+    pending: Option<TimedFrame<Stream::Frame>>,
+    // decoded past a seek target, comes after `pending`
+    queued: Option<TimedFrame<Stream::Frame>>,
+    position: Option<Duration>,
+    // of the frame last yielded
+    size: Option<Pair<u32>>,
+    // time between the last two frames, how long the last one stays up before looping
+    interval: Duration,
+    finished: bool,
+    error: Option<Stream::Error>,
+    // the clock stands still at this instant
+    paused_at: Option<Instant>,
+    clock: Option<ExternalClock>,
+}
+
+impl<Stream: StreamingFrameProvider> StreamPlayer<Stream> {
+    pub fn new(stream: Stream) -> Self {
+        Self {
+            stream,
+            looping: false,
+            started: None,
+            pending: None,
+            queued: None,
+            position: None,
+            size: None,
+            interval: Duration::ZERO,
+            finished: false,
+            error: None,
+            paused_at: None,
+            clock: None,
+        }
+    }
+
+    // frames are due by `clock` instead of the wall clock, so video stays in sync with audio
+    // played elsewhere, pausing, seeking and looping are then up to the clock's owner and the
+    // player follows when the clock stops or jumps
+    pub fn external_clock<F>(mut self, clock: F) -> Self
+    where
+        F: FnMut() -> Duration + 'static,
+    {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    // rewinds at the end of the stream instead of stopping, when the stream can
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn stream(&self) -> &Stream {
+        &self.stream
+    }
+
+    // timestamp of the frame last yielded
+    pub fn position(&self) -> Option<Duration> {
+        self.position
+    }
+
+    // no more frames will come, either at the end or after an error
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // the error that stopped playback, once
+    pub fn take_error(&mut self) -> Option<Stream::Error> {
+        self.error.take()
+    }
+
+    fn stop(&mut self, error: Stream::Error) {
+        self.error = Some(error);
+        self.finished = true;
+    }
+
+    // `false` when the stream ended for good
+    fn fill_pending(&mut self, now: Instant) -> bool {
+        if let Some(frame) = self.queued.take() {
+            self.pending = Some(frame);
+            return true;
+        }
+
+        match self.stream.next_frame() {
+            Ok(Some(frame)) => {
+                self.pending = Some(frame);
+                true
+            },
+            Ok(None) if self.looping && self.clock.is_none() && self.position.is_some() => {
+                match self.stream.rewind() {
+                    Ok(true) => (),
+                    Ok(false) => {
+                        self.finished = true;
+                        return false;
+                    },
+                    Err(error) => {
+                        self.stop(error);
+                        return false;
+                    },
+                }
+
+                // the next loop starts once the last frame had its time on screen, or right
+                // away when that has passed already
+                let last = self.position.unwrap_or_default() + self.interval;
+                self.started = self.started.map(|started| (started + last).max(now));
+                self.position = None;
+
+                match self.stream.next_frame() {
+                    Ok(Some(frame)) => {
+                        self.pending = Some(frame);
+                        true
+                    },
+                    Ok(None) => {
+                        self.finished = true;
+                        false
+                    },
+                    Err(error) => {
+                        self.stop(error);
+                        false
+                    },
+                }
+            },
+            Ok(None) => {
+                self.finished = true;
+                false
+            },
+            Err(error) => {
+                self.stop(error);
+                false
+            },
+        }
+    }
+}
+
+impl<Stream: StreamingFrameProvider> Iterator for StreamPlayer<Stream> {
+    type Item = Stream::Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        let clock_time = self.clock.as_mut().map(|clock| clock());
+        let mut due = None;
+
+        if let Some(time) = clock_time {
+            let position = self.position.unwrap_or_default();
+            if time < position || (time > position + CLOCK_JUMP && !self.finished) {
+                self.seek(time);
+            }
+        }
+
+        while !self.finished || self.pending.is_some() {
+            if self.pending.is_none() && !self.fill_pending(now) {
+                break;
+            }
+
+            let started = *self.started.get_or_insert(now);
+            let pending = self.pending.as_ref().expect("filled above");
+
+            let is_due = match clock_time {
+                Some(time) => pending.timestamp <= time,
+                None => started + pending.timestamp <= now,
+            };
+
+            if !is_due {
+                break;
+            }
+
+            let frame = self.pending.take().expect("checked above");
+            if let Some(position) = self.position {
+                self.interval = frame.timestamp.saturating_sub(position);
+            }
+
+            self.position = Some(frame.timestamp);
+            self.size = Some(frame.frame.size());
+            due = Some(frame.frame);
+        }
+
+        due
+    }
+}
+
+impl<Stream: StreamingFrameProvider> FrameProvider for StreamPlayer<Stream> {
+    type Frame = Stream::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self.next() {
+            Some(frame) => FramePoll::Ready(frame),
+            None if self.finished && self.pending.is_none() => FramePoll::Finished,
+            None => FramePoll::Pending,
+        }
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.size.or_else(|| self.pending.as_ref().map(|pending| pending.frame.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        match (&self.pending, self.started) {
+            _ if self.paused_at.is_some() => FrameSchedule::Still,
+            (Some(pending), Some(started)) if self.clock.is_none() => FrameSchedule::At(started + pending.timestamp),
+            _ => FrameSchedule::Soon,
+        }
+    }
+
+    // a seek to the start, the first frame is due right away
+    fn rewind(&mut self) -> bool {
+        self.seek(Duration::ZERO)
+    }
+}
+
+impl<Stream: StreamingFrameProvider> StreamPlayer<Stream> {
+    // rewinds and decodes up to `timestamp` when the stream can't seek on its own
+    fn seek_stream(&mut self, timestamp: Duration) -> Result<bool, Stream::Error> {
+        self.pending = None;
+        self.queued = None;
+
+        if self.stream.seek(timestamp)? {
+            return Ok(true);
+        }
+
+        if !self.stream.rewind()? {
+            return Ok(false);
+        }
+
+        while let Some(frame) = self.stream.next_frame()? {
+            if frame.timestamp > timestamp {
+                match self.pending {
+                    Some(_) => self.queued = Some(frame),
+                    None => self.pending = Some(frame),
+                }
+                break;
+            }
+
+            self.pending = Some(frame);
+        }
+
+        Ok(true)
+    }
+}
+
+impl<Stream: StreamingFrameProvider> Transport for StreamPlayer<Stream> {
+    fn position(&self) -> Option<Duration> {
+        self.position
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.stream.duration()
+    }
+
+    fn seek(&mut self, position: Duration) -> bool {
+        let position = self.duration().map_or(position, |duration| position.min(duration));
+
+        match self.seek_stream(position) {
+            Ok(true) => {
+                // the frame at `position` is due right away
+                let now = self.paused_at.unwrap_or_else(Instant::now);
+                self.started = Some(now.checked_sub(position).unwrap_or(now));
+                self.position = Some(position);
+                self.finished = false;
+                true
+            },
+            Ok(false) => false,
+            Err(error) => {
+                self.stop(error);
+                false
+            },
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        match (self.paused_at, paused) {
+            (None, true) => self.paused_at = Some(Instant::now()),
+            (Some(paused_at), false) => {
+                self.started = self.started.map(|started| started + paused_at.elapsed());
+                self.paused_at = None;
+            },
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg::format::context::Input;
+use ffmpeg::format::Pixel;
+use ffmpeg::software::scaling;
+use ffmpeg::{decoder, frame, Rational};
+
+use super::decode::DecodeLimits;
+use super::stream::{StreamingFrameProvider, TimedFrame};
+use crate::types::{HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat};
+
+#[derive(Debug)]
+pub enum VideoError {
+    Ffmpeg(ffmpeg::Error),
+    NoVideoStream,
+    TooLarge(Pair<u32>),
+}
+
+impl fmt::Display for VideoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VideoError::Ffmpeg(error) => write!(f, "failed to decode video: {error}"),
+            VideoError::NoVideoStream => write!(f, "no video stream"),
+            VideoError::TooLarge(size) => write!(f, "video frames of {}x{} exceed the decode limits", size.0, size.1),
+        }
+    }
+}
+
+impl std::error::Error for VideoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VideoError::Ffmpeg(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ffmpeg::Error> for VideoError {
+    fn from(error: ffmpeg::Error) -> Self {
+        VideoError::Ffmpeg(error)
+    }
+}
+
+// the best video stream of any container and codec the system ffmpeg reads, frames are
+// converted to rgba8 on the cpu
+pub struct FfmpegProvider {
+    input: Input,
+    stream_index: usize,
+    time_base: Rational,
+    // in `time_base`, timestamps count from here
+    start_time: i64,
+    decoder: decoder::Video,
+    // for the format and size of the decoded frames it was made for
+    scaler: Option<(scaling::Context, Pixel, Pair<u32>)>,
+    size: Pair<u32>,
+    limits: DecodeLimits,
+    duration: Option<Duration>,
+    sent_eof: bool,
+    // decoded while seeking, come before anything else
+    pending: VecDeque<TimedFrame<VideoFrame>>,
+    // of the last frame, for frames without a timestamp
+    last_timestamp: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoFrame {
+    size: Pair<u32>,
+    stride: usize,
+    buffer: Arc<[u8]>,
+}
+
+impl fmt::Debug for FfmpegProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FfmpegProvider").field("stream_index", &self.stream_index).field("size", &self.size).field("duration", &self.duration).finish()
+    }
+}
+
+// ffmpeg's marker for an unknown timestamp
+const NO_TIMESTAMP: i64 = i64::MIN;
+
+impl FfmpegProvider {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, VideoError> {
+        Self::open_with_limits(path, &DecodeLimits::default())
+    }
+
+    // only the size limits apply, frames are decoded one at a time
+    pub fn open_with_limits<P: AsRef<Path>>(path: P, limits: &DecodeLimits) -> Result<Self, VideoError> {
+        ffmpeg::init()?;
+
+        let input = ffmpeg::format::input(path.as_ref())?;
+        let stream = input.streams().best(ffmpeg::media::Type::Video).ok_or(VideoError::NoVideoStream)?;
+
+        let stream_index = stream.index();
+        let time_base = stream.time_base();
+        let start_time = Some(stream.start_time()).filter(|start| *start != NO_TIMESTAMP).unwrap_or(0);
+
+        let duration = match stream.duration() {
+            NO_TIMESTAMP => Some(input.duration()).filter(|duration| *duration > 0).map(|micros| Duration::from_micros(micros as u64)),
+            duration => Some(to_duration(duration, time_base)),
+        };
+
+        let decoder = ffmpeg::codec::Context::from_parameters(stream.parameters())?.decoder().video()?;
+        let size = (decoder.width(), decoder.height());
+
+        let provider = Self {
+            input,
+            stream_index,
+            time_base,
+            start_time,
+            decoder,
+            scaler: None,
+            size,
+            limits: limits.clone(),
+            duration,
+            sent_eof: false,
+            pending: VecDeque::new(),
+            last_timestamp: Duration::ZERO,
+        };
+
+        provider.check_size(size)?;
+        Ok(provider)
+    }
+
+    fn check_size(&self, size: Pair<u32>) -> Result<(), VideoError> {
+        let limits = &self.limits;
+
+        match size.0 > limits.max_size.0 || size.1 > limits.max_size.1 || 4 * size.0 as u64 * size.1 as u64 > limits.max_alloc {
+            true => Err(VideoError::TooLarge(size)),
+            false => Ok(()),
+        }
+    }
+
+    // the next decoded frame, sending packets as the decoder asks for them
+    fn decode(&mut self) -> Result<Option<frame::Video>, VideoError> {
+        let mut decoded = frame::Video::empty();
+
+        loop {
+            match self.decoder.receive_frame(&mut decoded) {
+                Ok(()) => return Ok(Some(decoded)),
+                Err(ffmpeg::Error::Eof) => return Ok(None),
+                Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN && !self.sent_eof => (),
+                Err(error) => return Err(error.into()),
+            }
+
+            let stream_index = self.stream_index;
+            let packet = self.input.packets().find(|(stream, _)| stream.index() == stream_index).map(|(_, packet)| packet);
+
+            match packet {
+                Some(packet) => self.decoder.send_packet(&packet)?,
+                None => {
+                    self.decoder.send_eof()?;
+                    self.sent_eof = true;
+                },
+            }
+        }
+    }
+
+    fn convert(&mut self, decoded: &frame::Video) -> Result<TimedFrame<VideoFrame>, VideoError> {
+        let size = (decoded.width(), decoded.height());
+        self.check_size(size)?;
+
+        let stale = self.scaler.as_ref().is_none_or(|(_, format, scaled)| *format != decoded.format() || *scaled != size);
+        if stale {
+            let context = scaling::Context::get(decoded.format(), size.0, size.1, Pixel::RGBA, size.0, size.1, scaling::Flags::BILINEAR)?;
+            self.scaler = Some((context, decoded.format(), size));
+        }
+
+        let mut rgba = frame::Video::empty();
+        if let Some((scaler, ..)) = self.scaler.as_mut() {
+            scaler.run(decoded, &mut rgba)?;
+        }
+
+        let timestamp = match decoded.timestamp() {
+            Some(timestamp) => to_duration(timestamp - self.start_time, self.time_base),
+            None => self.last_timestamp,
+        };
+
+        self.size = size;
+        self.last_timestamp = timestamp;
+
+        let frame = VideoFrame { size, stride: rgba.stride(0), buffer: rgba.data(0).into() };
+        Ok(TimedFrame { frame, timestamp })
+    }
+}
+
+fn to_duration(timestamp: i64, time_base: Rational) -> Duration {
+    Duration::from_secs_f64((timestamp.max(0) as f64 * f64::from(time_base)).max(0.0))
+}
+
+impl HasSize<u32> for FfmpegProvider {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl StreamingFrameProvider for FfmpegProvider {
+    type Frame = VideoFrame;
+    type Error = VideoError;
+
+    fn next_frame(&mut self) -> Result<Option<TimedFrame<Self::Frame>>, Self::Error> {
+        if let Some(frame) = self.pending.pop_front() {
+            return Ok(Some(frame));
+        }
+
+        match self.decode()? {
+            Some(decoded) => self.convert(&decoded).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn rewind(&mut self) -> Result<bool, Self::Error> {
+        self.seek(Duration::ZERO)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    // seeks to the key frame before `timestamp` and decodes up to the frame showing at it
+    fn seek(&mut self, timestamp: Duration) -> Result<bool, Self::Error> {
+        // seeking is in microseconds from the start of the container
+        let target = timestamp + to_duration(self.start_time, self.time_base);
+        let micros = i64::try_from(target.as_micros()).unwrap_or(i64::MAX);
+        self.input.seek(micros, ..micros)?;
+        self.decoder.flush();
+        self.sent_eof = false;
+        self.pending.clear();
+
+        let mut showing = None;
+
+        while let Some(decoded) = self.decode()? {
+            let frame = self.convert(&decoded)?;
+
+            match showing.take() {
+                Some(previous) if frame.timestamp > timestamp => {
+                    self.pending.extend([previous, frame]);
+                    return Ok(true);
+                },
+                _ => showing = Some(frame),
+            }
+        }
+
+        self.pending.extend(showing);
+        Ok(true)
+    }
+}
+
+impl HasSize<u32> for VideoFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for VideoFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for VideoFrame {
+    fn format(&self) -> PixelFormat {
+        PixelFormat::Rgba8
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        Some(self.stride)
+    }
+}
+
+impl HasOrientation for VideoFrame {}
+
+impl HasData for VideoFrame {
+    fn data(&self) -> &[u8] {
+        let len = self.stride * (self.size.1 as usize).saturating_sub(1) + 4 * self.size.0 as usize;
+        &self.buffer[..len.min(self.buffer.len())]
+    }
+}
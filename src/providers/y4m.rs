@@ -0,0 +1,398 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::decode::DecodeLimits;
+use super::stream::{StreamingFrameProvider, TimedFrame};
+use crate::convert;
+use crate::types::{HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat};
+
+// headers longer than this aren't y4m
+const MAX_HEADER_LEN: u64 = 1024;
+
+const FRAME_HEADER: &[u8] = b"FRAME\n";
+
+#[derive(Debug)]
+pub enum Y4mError {
+    Io(io::Error),
+    InvalidHeader(&'static str),
+    // only 8 bit 4:2:0, 4:2:2, 4:4:4 and mono are decoded
+    UnsupportedColorspace(String),
+    TooLarge(Pair<u32>),
+}
+
+impl fmt::Display for Y4mError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Y4mError::Io(error) => write!(f, "failed to read y4m stream: {error}"),
+            Y4mError::InvalidHeader(reason) => write!(f, "invalid y4m header: {reason}"),
+            Y4mError::UnsupportedColorspace(colorspace) => write!(f, "unsupported y4m colorspace {colorspace}"),
+            Y4mError::TooLarge(size) => write!(f, "y4m frames of {}x{} exceed the decode limits", size.0, size.1),
+        }
+    }
+}
+
+impl std::error::Error for Y4mError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Y4mError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Y4mError {
+    fn from(error: io::Error) -> Self {
+        Y4mError::Io(error)
+    }
+}
+
+// chroma planes are this many luma pixels wide and high
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Subsampling {
+    Both,
+    Horizontal,
+    None,
+    Mono,
+}
+
+// uncompressed yuv4mpeg2 video as written by `ffmpeg -f yuv4mpegpipe`, the reference
+// `StreamingFrameProvider`: real codecs plug in by implementing the same trait
+#[derive(Debug)]
+pub struct Y4mProvider<R> {
+    reader: BufReader<R>,
+    size: Pair<u32>,
+    frame_rate: (u32, u32),
+    subsampling: Subsampling,
+    data_start: u64,
+    index: u32,
+    // frames can be found by offset when the first one has a bare header, which is how
+    // practically every encoder writes them
+    frame_count: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Y4mFrame {
+    size: Pair<u32>,
+    index: u32,
+    // 4:2:0 planes as they are read, the gpu converts them, other subsamplings as rgba8
+    format: PixelFormat,
+    buffer: Arc<[u8]>,
+}
+
+fn parse_pair(value: &str, separator: char) -> Option<(u32, u32)> {
+    let (first, second) = value.split_once(separator)?;
+    Some((first.parse().ok()?, second.parse().ok()?))
+}
+
+fn read_line<R: Read>(reader: &mut BufReader<R>) -> Result<Option<String>, Y4mError> {
+    let mut line = Vec::new();
+    reader.by_ref().take(MAX_HEADER_LEN).read_until(b'\n', &mut line)?;
+
+    match line.last() {
+        None => Ok(None),
+        Some(b'\n') => {
+            line.pop();
+            String::from_utf8(line).map(Some).map_err(|_| Y4mError::InvalidHeader("not ascii"))
+        },
+        Some(_) => Err(Y4mError::InvalidHeader("header line is unterminated or too long")),
+    }
+}
+
+impl<R: Read + Seek> Y4mProvider<R> {
+    pub fn new(reader: R) -> Result<Self, Y4mError> {
+        Self::with_limits(reader, &DecodeLimits::default())
+    }
+
+    // only the size limits apply, frames are decoded one at a time
+    pub fn with_limits(reader: R, limits: &DecodeLimits) -> Result<Self, Y4mError> {
+        let mut reader = BufReader::new(reader);
+        let header = read_line(&mut reader)?.ok_or(Y4mError::InvalidHeader("empty stream"))?;
+
+        let mut params = header.split(' ');
+        if params.next() != Some("YUV4MPEG2") {
+            return Err(Y4mError::InvalidHeader("missing YUV4MPEG2 signature"));
+        }
+
+        let (mut width, mut height, mut frame_rate) = (None, None, (25, 1));
+        let mut subsampling = Subsampling::Both;
+
+        for param in params {
+            // tags are a single character, which doesn't have to be ascii in a broken file
+            let Some(tag) = param.chars().next() else {
+                continue;
+            };
+            let value = &param[tag.len_utf8()..];
+
+            match tag {
+                'W' => width = value.parse().ok(),
+                'H' => height = value.parse().ok(),
+                'F' => frame_rate = parse_pair(value, ':').filter(|rate| rate.0 > 0 && rate.1 > 0).ok_or(Y4mError::InvalidHeader("invalid frame rate"))?,
+                'C' => {
+                    subsampling = match value {
+                        "420" | "420jpeg" | "420paldv" | "420mpeg2" => Subsampling::Both,
+                        "422" => Subsampling::Horizontal,
+                        "444" => Subsampling::None,
+                        "mono" => Subsampling::Mono,
+                        _ => return Err(Y4mError::UnsupportedColorspace(value.to_string())),
+                    }
+                },
+                // interlacing, pixel aspect and comments don't change how frames are read
+                _ => (),
+            }
+        }
+
+        let size = match (width, height) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(Y4mError::InvalidHeader("missing frame size")),
+        };
+
+        if size.0 > limits.max_size.0 || size.1 > limits.max_size.1 || 4 * size.0 as u64 * size.1 as u64 > limits.max_alloc {
+            return Err(Y4mError::TooLarge(size));
+        }
+
+        let data_start = reader.stream_position()?;
+
+        let mut provider = Self { reader, size, frame_rate, subsampling, data_start, index: 0, frame_count: None };
+        provider.frame_count = provider.count_frames()?;
+        Ok(provider)
+    }
+
+    fn count_frames(&mut self) -> Result<Option<u32>, Y4mError> {
+        let bare = read_line(&mut self.reader).ok().flatten().as_deref() == Some("FRAME");
+        let len = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(self.data_start))?;
+
+        Ok(bare.then(|| ((len - self.data_start) / self.frame_stride()) as u32))
+    }
+
+    // a bare frame header and the planes
+    fn frame_stride(&self) -> u64 {
+        let chroma = self.chroma_size();
+        FRAME_HEADER.len() as u64 + self.size.0 as u64 * self.size.1 as u64 + 2 * chroma.0 as u64 * chroma.1 as u64
+    }
+
+    fn timestamp(&self, index: u32) -> Duration {
+        let (numerator, denominator) = self.frame_rate;
+        Duration::from_secs(index as u64 * denominator as u64) / numerator
+    }
+
+    // frames per second as a fraction
+    pub fn frame_rate(&self) -> (u32, u32) {
+        self.frame_rate
+    }
+
+    fn chroma_size(&self) -> Pair<u32> {
+        let (width, height) = self.size;
+
+        match self.subsampling {
+            Subsampling::Both => (width.div_ceil(2), height.div_ceil(2)),
+            Subsampling::Horizontal => (width.div_ceil(2), height),
+            Subsampling::None => (width, height),
+            Subsampling::Mono => (0, 0),
+        }
+    }
+
+    fn to_rgba(&self, planes: &[u8]) -> Vec<u8> {
+        let (width, height) = (self.size.0 as usize, self.size.1 as usize);
+        let chroma = self.chroma_size();
+        let (chroma_width, chroma_len) = (chroma.0 as usize, chroma.0 as usize * chroma.1 as usize);
+        let (luma, chroma_planes) = planes.split_at(width * height);
+        let (u_plane, v_plane) = chroma_planes.split_at(chroma_len);
+
+        let (shift_x, shift_y) = match self.subsampling {
+            Subsampling::Both => (1, 1),
+            Subsampling::Horizontal => (1, 0),
+            Subsampling::None | Subsampling::Mono => (0, 0),
+        };
+
+        let mut rgba = vec![0; 4 * width * height];
+
+        for (index, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (index % width, index / width);
+            let (u, v) = match self.subsampling {
+                Subsampling::Mono => (128, 128),
+                _ => {
+                    let chroma_index = (y >> shift_y) * chroma_width + (x >> shift_x);
+                    (u_plane[chroma_index], v_plane[chroma_index])
+                },
+            };
+
+            pixel.copy_from_slice(&convert::yuv_to_rgba(luma[index], u, v));
+        }
+
+        rgba
+    }
+}
+
+impl<R> HasSize<u32> for Y4mProvider<R> {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl<R: Read + Seek> StreamingFrameProvider for Y4mProvider<R> {
+    type Frame = Y4mFrame;
+    type Error = Y4mError;
+
+    // a truncated last frame ends the stream like a complete one would
+    fn next_frame(&mut self) -> Result<Option<TimedFrame<Self::Frame>>, Self::Error> {
+        let Some(line) = read_line(&mut self.reader)? else {
+            return Ok(None);
+        };
+
+        if !line.starts_with("FRAME") {
+            return Err(Y4mError::InvalidHeader("missing FRAME marker"));
+        }
+
+        let chroma = self.chroma_size();
+        let len = self.size.0 as usize * self.size.1 as usize + 2 * chroma.0 as usize * chroma.1 as usize;
+        let mut planes = vec![0; len];
+
+        match self.reader.read_exact(&mut planes) {
+            Ok(()) => (),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error.into()),
+        }
+
+        let timestamp = self.timestamp(self.index);
+
+        let (format, buffer) = match self.subsampling {
+            Subsampling::Both => (PixelFormat::I420, planes.into()),
+            _ => (PixelFormat::Rgba8, self.to_rgba(&planes).into()),
+        };
+
+        let frame = Y4mFrame { size: self.size, index: self.index, format, buffer };
+
+        self.index += 1;
+        Ok(Some(TimedFrame { frame, timestamp }))
+    }
+
+    fn rewind(&mut self) -> Result<bool, Self::Error> {
+        self.reader.seek(SeekFrom::Start(self.data_start))?;
+        self.index = 0;
+        Ok(true)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.frame_count.map(|count| self.timestamp(count))
+    }
+
+    fn seek(&mut self, timestamp: Duration) -> Result<bool, Self::Error> {
+        let Some(count) = self.frame_count else {
+            return Ok(false);
+        };
+
+        let (numerator, denominator) = self.frame_rate;
+        let index = (timestamp.as_nanos() * numerator as u128 / (denominator as u128 * 1_000_000_000)) as u32;
+        let index = index.min(count.saturating_sub(1));
+
+        self.reader.seek(SeekFrom::Start(self.data_start + index as u64 * self.frame_stride()))?;
+        self.index = index;
+        Ok(true)
+    }
+}
+
+impl Y4mFrame {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl HasSize<u32> for Y4mFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for Y4mFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for Y4mFrame {
+    fn format(&self) -> PixelFormat {
+        self.format
+    }
+}
+
+impl HasOrientation for Y4mFrame {}
+
+impl HasData for Y4mFrame {
+    fn data(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn stream(header: &str, frames: &[&[u8]]) -> Cursor<Vec<u8>> {
+        let mut bytes = format!("{header}\n").into_bytes();
+
+        for frame in frames {
+            bytes.extend_from_slice(FRAME_HEADER);
+            bytes.extend_from_slice(frame);
+        }
+
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn reads_420_frames() {
+        let frame: &[u8] = &[16, 16, 235, 235, 128, 128];
+        let mut provider = Y4mProvider::new(stream("YUV4MPEG2 W2 H2 F30:1 Ip A1:1", &[frame, frame])).unwrap();
+
+        assert_eq!(provider.size(), (2, 2));
+        assert_eq!(provider.frame_rate(), (30, 1));
+        assert_eq!(provider.duration(), Some(Duration::from_secs(2) / 30));
+
+        let first = provider.next_frame().unwrap().unwrap();
+        assert_eq!(first.frame.format(), PixelFormat::I420);
+        assert_eq!(first.frame.data(), frame);
+
+        let second = provider.next_frame().unwrap().unwrap();
+        assert_eq!((second.frame.index(), second.timestamp), (1, Duration::from_secs(1) / 30));
+        assert!(provider.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn converts_444_frames() {
+        let mut provider = Y4mProvider::new(stream("YUV4MPEG2 W1 H1 C444", &[&[235, 128, 128]])).unwrap();
+        let frame = provider.next_frame().unwrap().unwrap().frame;
+
+        assert_eq!(frame.format(), PixelFormat::Rgba8);
+        assert_eq!(frame.data(), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn truncated_frame_ends_the_stream() {
+        let mut provider = Y4mProvider::new(stream("YUV4MPEG2 W2 H2", &[&[0, 0, 0]])).unwrap();
+
+        assert!(provider.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn multibyte_parameters_are_ignored() {
+        let provider = Y4mProvider::new(stream("YUV4MPEG2 W2 H2 é XÿÆ  Ü1", &[])).unwrap();
+
+        assert_eq!(provider.size(), (2, 2));
+    }
+
+    #[test]
+    fn invalid_headers() {
+        let header = |header: &str| Y4mProvider::new(stream(header, &[])).unwrap_err();
+
+        assert!(matches!(header("MPEG2 W2 H2"), Y4mError::InvalidHeader(_)));
+        assert!(matches!(header("YUV4MPEG2 W2"), Y4mError::InvalidHeader(_)));
+        assert!(matches!(header("YUV4MPEG2 W2 H2 F0:1"), Y4mError::InvalidHeader(_)));
+        assert!(matches!(header("YUV4MPEG2 W2 H2 C420p10"), Y4mError::UnsupportedColorspace(_)));
+        assert!(matches!(header("YUV4MPEG2 W100000 H100000"), Y4mError::TooLarge(_)));
+        assert!(matches!(Y4mProvider::new(Cursor::new(vec![b'Y'; 2048])).unwrap_err(), Y4mError::InvalidHeader(_)));
+    }
+}
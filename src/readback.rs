@@ -0,0 +1,341 @@
+use std::fmt;
+
+use crate::convert;
+use crate::types::Pair;
+
+#[derive(Debug)]
+pub enum ReadbackError {
+    Map(wgpu::BufferAsyncError),
+    Disconnected,
+    UnsupportedFormat(wgpu::TextureFormat),
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadbackError::Map(error) => write!(f, "failed to map readback buffer: {error}"),
+            ReadbackError::Disconnected => write!(f, "device dropped the readback before it completed"),
+            ReadbackError::UnsupportedFormat(format) => write!(f, "cannot read back textures in {format:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ReadbackError {}
+
+// a frame pixel as it was uploaded, before filters and shading, see
+// `WgpuFrameRenderContext::pixel_at`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelValue {
+    // srgb encoded like the file stores it, frames without alpha read back opaque
+    Rgba8([u8; 4]),
+    // linear and unclipped
+    Rgba16Float([f32; 4]),
+    Indexed { index: u8, color: [u8; 4] },
+    // the samples of a yuv frame, chroma shared by a square of 4 pixels, and the srgb
+    // color they make
+    Yuv { y: u8, u: u8, v: u8, color: [u8; 4] },
+}
+
+impl fmt::Display for PixelValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PixelValue::Rgba8([r, g, b, a]) => write!(f, "{r} {g} {b} {a}"),
+            PixelValue::Rgba16Float([r, g, b, a]) => write!(f, "{r:.3} {g:.3} {b:.3} {a:.3}"),
+            PixelValue::Indexed { index, color: [r, g, b, a] } => write!(f, "#{index} {r} {g} {b} {a}"),
+            PixelValue::Yuv { y, u, v, color: [r, g, b, _] } => write!(f, "y{y} u{u} v{v} {r} {g} {b}"),
+        }
+    }
+}
+
+// fnv-1a, the same for the same pixels on every platform and release, unlike std's hasher
+const HASH_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const HASH_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// a hash of the size and of every byte of `pixels` with its `dropped_bits` lowest bits
+// cleared, so bytes that differ only within one bucket of `1 << dropped_bits` values hash
+// the same, neighbours across a bucket edge like 0x7f and 0x80 still don't, anything above
+// 8 counts as 8
+pub fn hash_pixels(pixels: &[u8], size: Pair<u32>, dropped_bits: u32) -> u64 {
+    let mask = 0xffu8.checked_shl(dropped_bits.min(8)).unwrap_or(0);
+    let size = size.0.to_le_bytes().into_iter().chain(size.1.to_le_bytes());
+
+    size.chain(pixels.iter().map(|byte| byte & mask))
+        .fold(HASH_OFFSET, |hash, byte| (hash ^ byte as u64).wrapping_mul(HASH_PRIME))
+}
+
+// tightly packed rows of a render target as srgb encoded rgba8, what image files hold,
+// float targets are linear and clipped to srgb
+pub(crate) fn to_srgb_rgba8(mut pixels: Vec<u8>, format: wgpu::TextureFormat) -> Result<Vec<u8>, ReadbackError> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => Ok(pixels),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+            convert::swap_red_blue(&mut pixels);
+            Ok(pixels)
+        },
+        wgpu::TextureFormat::Rgba16Float => Ok(pixels
+            .chunks_exact(8)
+            .flat_map(|pixel| {
+                let channel = |index: usize| convert::f16_to_f32(u16::from_le_bytes([pixel[2 * index], pixel[2 * index + 1]]));
+                let alpha = (channel(3).clamp(0.0, 1.0) * 255.0).round() as u8;
+                [linear_to_srgb8(channel(0)), linear_to_srgb8(channel(1)), linear_to_srgb8(channel(2)), alpha]
+            })
+            .collect()),
+        format => Err(ReadbackError::UnsupportedFormat(format)),
+    }
+}
+
+// a linear channel clamped to 0..1 and encoded with the srgb transfer function
+fn linear_to_srgb8(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let encoded = match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
+// the largest region whose padded copy fits in a single buffer, the full width whenever
+// one row fits so most textures are copied in horizontal bands
+fn tile_size(texture_size: Pair<u32>, bytes_per_pixel: u32, max_buffer_size: u64) -> Pair<u32> {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+    let max_row_bytes = (max_buffer_size / alignment * alignment).min(u32::MAX as u64);
+    let tile_width = (max_row_bytes / bytes_per_pixel as u64).clamp(1, texture_size.0 as u64) as u32;
+
+    let padded_bytes_per_row = padded_bytes_per_row(tile_width, bytes_per_pixel) as u64;
+    let tile_height = (max_buffer_size / padded_bytes_per_row).clamp(1, texture_size.1 as u64) as u32;
+
+    (tile_width, tile_height)
+}
+
+fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    (bytes_per_pixel * width).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+// copies a whole texture back as tightly packed rows, tiling the copy through one staging
+// buffer so textures larger than `max_buffer_size` (16k panoramas) still fit
+pub(crate) fn read_texture(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Result<Vec<u8>, ReadbackError> {
+    let format = texture.format();
+    let bytes_per_pixel = format.block_copy_size(None).ok_or(ReadbackError::UnsupportedFormat(format))?;
+    let (width, height) = (texture.width(), texture.height());
+    let row_len = (bytes_per_pixel * width) as usize;
+
+    let (tile_width, tile_height) = tile_size((width, height), bytes_per_pixel, device.limits().max_buffer_size);
+    let tile_bytes_per_row = padded_bytes_per_row(tile_width, bytes_per_pixel);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: tile_bytes_per_row as u64 * tile_height as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut pixels = vec![0; row_len * height as usize];
+
+    for y in (0..height).step_by(tile_height as usize) {
+        for x in (0..width).step_by(tile_width as usize) {
+            let region = (tile_width.min(width - x), tile_height.min(height - y));
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            });
+
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(tile_bytes_per_row),
+                        rows_per_image: Some(region.1),
+                    },
+                },
+                wgpu::Extent3d {
+                    width: region.0,
+                    height: region.1,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            queue.submit(std::iter::once(encoder.finish()));
+
+            let slice = buffer.slice(..);
+            map_blocking(device, slice)?;
+
+            {
+                let mapped = slice.get_mapped_range();
+                let region_row_len = (bytes_per_pixel * region.0) as usize;
+                let x_offset = (bytes_per_pixel * x) as usize;
+
+                if region.0 == width {
+                    let start = y as usize * row_len;
+                    convert::remove_stride(&mapped, row_len, tile_bytes_per_row as usize, &mut pixels[start..start + row_len * region.1 as usize]);
+                } else {
+                    for (row, source) in mapped.chunks(tile_bytes_per_row as usize).take(region.1 as usize).enumerate() {
+                        let start = (y as usize + row) * row_len + x_offset;
+                        pixels[start..start + region_row_len].copy_from_slice(&source[..region_row_len]);
+                    }
+                }
+            }
+
+            buffer.unmap();
+        }
+    }
+
+    Ok(pixels)
+}
+
+// one texel of a texture layer as stored, e.g. the frame pixel under the cursor
+pub(crate) fn read_texel(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture, texel: Pair<u32>, layer: u32) -> Result<Vec<u8>, ReadbackError> {
+    let format = texture.format();
+    let bytes_per_pixel = format.block_copy_size(None).ok_or(ReadbackError::UnsupportedFormat(format))?;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Texel Readback Buffer"),
+        size: wgpu::COPY_BUFFER_ALIGNMENT.max(bytes_per_pixel as u64),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Texel Readback Encoder"),
+    });
+
+    // a single row needs no padded row length
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x: texel.0, y: texel.1, z: layer },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout::default(),
+        },
+        wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+    );
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    map_blocking(device, slice)?;
+    let bytes = slice.get_mapped_range()[..bytes_per_pixel as usize].to_vec();
+    buffer.unmap();
+
+    Ok(bytes)
+}
+
+// the contents of a buffer made with `COPY_SRC`, through a mappable copy
+#[cfg(feature = "compute")]
+pub(crate) fn read_buffer(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> Result<Vec<u8>, ReadbackError> {
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Buffer Readback Buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Buffer Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = staging.slice(..);
+    map_blocking(device, slice)?;
+    let bytes = slice.get_mapped_range().to_vec();
+    staging.unmap();
+
+    Ok(bytes)
+}
+
+fn map_blocking(device: &wgpu::Device, slice: wgpu::BufferSlice<'_>) -> Result<(), ReadbackError> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    receiver
+        .recv()
+        .map_err(|_| ReadbackError::Disconnected)?
+        .map_err(ReadbackError::Map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every tile of a readback fits the buffer and together they cover the texture once
+    fn check_tiles(texture_size: Pair<u32>, bytes_per_pixel: u32, max_buffer_size: u64) -> Pair<u32> {
+        let tile = tile_size(texture_size, bytes_per_pixel, max_buffer_size);
+        assert!(padded_bytes_per_row(tile.0, bytes_per_pixel) as u64 * tile.1 as u64 <= max_buffer_size);
+
+        let mut covered = vec![0u8; texture_size.0 as usize * texture_size.1 as usize];
+        for y in (0..texture_size.1).step_by(tile.1 as usize) {
+            for x in (0..texture_size.0).step_by(tile.0 as usize) {
+                for row in y..(y + tile.1).min(texture_size.1) {
+                    let start = (row * texture_size.0 + x) as usize;
+                    covered[start..start + tile.0.min(texture_size.0 - x) as usize].iter_mut().for_each(|texel| *texel += 1);
+                }
+            }
+        }
+
+        assert!(covered.iter().all(|&texel| texel == 1));
+        tile
+    }
+
+    #[test]
+    fn hashes_pixels_and_size() {
+        let pixels = [0x10, 0x7f, 0x80, 0xff];
+        assert_eq!(hash_pixels(&pixels, (1, 1), 0), hash_pixels(&[0x10, 0x7f, 0x80, 0xff], (1, 1), 0));
+        assert_ne!(hash_pixels(&pixels, (1, 1), 0), hash_pixels(&pixels, (2, 1), 0));
+        assert_ne!(hash_pixels(&pixels, (2, 1), 0), hash_pixels(&pixels, (1, 2), 0));
+
+        // within a bucket of 4 values, but not across one
+        assert_eq!(hash_pixels(&[0x10, 0x7c, 0x80, 0xff], (1, 1), 2), hash_pixels(&[0x13, 0x7f, 0x82, 0xfc], (1, 1), 2));
+        assert_ne!(hash_pixels(&[0x7f], (1, 1), 2), hash_pixels(&[0x80], (1, 1), 2));
+        assert_ne!(hash_pixels(&[0x7f], (1, 1), 7), hash_pixels(&[0x80], (1, 1), 7));
+
+        // everything goes at 8 and above, only the size is left
+        assert_eq!(hash_pixels(&pixels, (1, 1), 8), hash_pixels(&[0; 4], (1, 1), 8));
+        assert_eq!(hash_pixels(&pixels, (1, 1), 9), hash_pixels(&pixels, (1, 1), 8));
+        assert_eq!(hash_pixels(&pixels, (1, 1), u32::MAX), hash_pixels(&pixels, (1, 1), 8));
+        assert_ne!(hash_pixels(&pixels, (1, 1), 8), hash_pixels(&pixels, (4, 1), 8));
+    }
+
+    #[test]
+    fn pads_rows_to_the_copy_alignment() {
+        assert_eq!(padded_bytes_per_row(1, 4), 256);
+        assert_eq!(padded_bytes_per_row(64, 4), 256);
+        assert_eq!(padded_bytes_per_row(65, 4), 512);
+        assert_eq!(padded_bytes_per_row(100, 8), 1024);
+    }
+
+    #[test]
+    fn small_textures_are_one_tile() {
+        assert_eq!(check_tiles((1, 1), 4, 1 << 28), (1, 1));
+        assert_eq!(check_tiles((1, 1), 8, 256), (1, 1));
+        assert_eq!(check_tiles((640, 480), 4, 1 << 28), (640, 480));
+    }
+
+    #[test]
+    fn tall_textures_are_cut_into_bands() {
+        // 16k rows of 2560 padded bytes, 100 of them per buffer
+        assert_eq!(check_tiles((640, 16384), 4, 256_000), (640, 100));
+        assert_eq!(check_tiles((64, 3), 4, 512), (64, 2));
+    }
+
+    #[test]
+    fn rows_larger_than_a_buffer_are_cut_into_columns() {
+        assert_eq!(check_tiles((16384, 8), 4, 1000), (192, 1));
+        assert_eq!(check_tiles((16384, 8192), 8, 1 << 16), (8192, 1));
+        assert_eq!(check_tiles((300, 2), 4, 1024), (256, 1));
+    }
+}
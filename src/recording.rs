@@ -0,0 +1,350 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use winit::dpi::PhysicalPosition;
+use winit::event::{MouseScrollDelta, WindowEvent};
+use winit::keyboard::KeyCode;
+
+use crate::input::InputEvent;
+
+// the first line of a saved recording
+const HEADER: &str = "# egami interactions";
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    // `line` counts from 1
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(error) => write!(f, "failed to read the recording: {error}"),
+            RecordingError::Parse { line, message } => write!(f, "invalid recording, line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecordingError::Io(error) => Some(error),
+            RecordingError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+// keeps the window events the viewer acts on with the time they came in, saved as text
+// with an event per line, e.g. `1250 key KeyS`, so a bug report can carry the
+// interactions that led to it and a test can play them again
+#[derive(Debug, Clone, Default)]
+pub struct InteractionRecorder {
+    started: Option<Instant>,
+    events: Vec<(Duration, InputEvent)>,
+}
+
+impl InteractionRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the times count from here, or from the first event when it came first
+    pub fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    pub fn record(&mut self, event: InputEvent) {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        self.events.push((started.elapsed(), event));
+    }
+
+    // returns whether the event was one the viewer acts on
+    pub fn record_window_event(&mut self, event: &WindowEvent) -> bool {
+        let event = InputEvent::from_window_event(event);
+
+        if let Some(event) = event {
+            self.record(event);
+        }
+
+        event.is_some()
+    }
+
+    pub fn events(&self) -> &[(Duration, InputEvent)] {
+        &self.events
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{HEADER}")?;
+
+        for (time, event) in &self.events {
+            writeln!(writer, "{} {event}", time.as_millis())?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+}
+
+// hands out recorded events once they are due, at the pace they were recorded
+#[derive(Debug, Clone)]
+pub struct InteractionPlayer {
+    started: Option<Instant>,
+    events: VecDeque<(Duration, InputEvent)>,
+}
+
+impl InteractionPlayer {
+    pub fn new(mut events: Vec<(Duration, InputEvent)>) -> Self {
+        events.sort_by_key(|(time, _)| *time);
+        Self { started: None, events: events.into() }
+    }
+
+    // the format `InteractionRecorder::save` writes, starting with its header, lines
+    // starting with `#` after it are comments
+    pub fn parse(text: &str) -> Result<Self, RecordingError> {
+        let mut events = Vec::new();
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line.trim())).filter(|(_, line)| !line.is_empty());
+
+        match lines.next() {
+            Some((_, HEADER)) => (),
+            Some((line, header)) => return Err(RecordingError::Parse { line, message: format!("expected `{HEADER}`, got `{header}`") }),
+            None => return Err(RecordingError::Parse { line: 1, message: format!("expected `{HEADER}`, got nothing") }),
+        }
+
+        for (line_number, line) in lines {
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let parse_error = |message: String| RecordingError::Parse { line: line_number, message };
+            let (time, event) = line.split_once(' ').ok_or_else(|| parse_error(format!("expected a time and an event, got `{line}`")))?;
+            let time = time.parse().map_err(|_| parse_error(format!("invalid time `{time}`")))?;
+            let event = event.parse().map_err(parse_error)?;
+
+            events.push((Duration::from_millis(time), event));
+        }
+
+        Ok(Self::new(events))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // nothing is due before the player is started
+    pub fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+
+    // when the next event is due, `None` before the start and after the last event
+    pub fn next_due(&self) -> Option<Instant> {
+        Some(self.started? + self.events.front()?.0)
+    }
+
+    // the events due by now, in order
+    pub fn due(&mut self) -> Vec<InputEvent> {
+        match self.started {
+            Some(started) => self.due_at(started.elapsed()),
+            None => Vec::new(),
+        }
+    }
+
+    // the events recorded up to `elapsed` after the start, for tests that step the clock
+    // themselves
+    pub fn due_at(&mut self, elapsed: Duration) -> Vec<InputEvent> {
+        let count = self.events.iter().take_while(|(time, _)| *time <= elapsed).count();
+        self.events.drain(..count).map(|(_, event)| event).collect()
+    }
+}
+
+impl From<InteractionRecorder> for InteractionPlayer {
+    fn from(recorder: InteractionRecorder) -> Self {
+        Self::new(recorder.events)
+    }
+}
+
+// `resize 1280 720`, `scale 1.5`, `key KeyS`, `cursor 200.5 140`, `cursor-left`,
+// `left down`, `left up`, `wheel lines 0 1`, `wheel pixels 0 -12.5` or `close`
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputEvent::Resized((width, height)) => write!(f, "resize {width} {height}"),
+            InputEvent::ScaleFactorChanged(scale_factor) => write!(f, "scale {scale_factor}"),
+            InputEvent::KeyPressed(key) => write!(f, "key {key:?}"),
+            InputEvent::CursorMoved((x, y)) => write!(f, "cursor {x} {y}"),
+            InputEvent::CursorLeft => write!(f, "cursor-left"),
+            InputEvent::LeftButton { pressed: true } => write!(f, "left down"),
+            InputEvent::LeftButton { pressed: false } => write!(f, "left up"),
+            InputEvent::Wheel(MouseScrollDelta::LineDelta(x, y)) => write!(f, "wheel lines {x} {y}"),
+            InputEvent::Wheel(MouseScrollDelta::PixelDelta(position)) => write!(f, "wheel pixels {} {}", position.x, position.y),
+            InputEvent::CloseRequested => write!(f, "close"),
+        }
+    }
+}
+
+impl FromStr for InputEvent {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let words: Vec<_> = text.split_whitespace().collect();
+
+        fn number<T: FromStr>(word: &str) -> Result<T, String> {
+            word.parse().map_err(|_| format!("invalid number `{word}`"))
+        }
+
+        match words.as_slice() {
+            ["resize", width, height] => Ok(InputEvent::Resized((number(width)?, number(height)?))),
+            ["scale", scale_factor] => Ok(InputEvent::ScaleFactorChanged(number(scale_factor)?)),
+            ["key", name] => key_code(name).map(InputEvent::KeyPressed).ok_or_else(|| format!("unknown key `{name}`")),
+            ["cursor", x, y] => Ok(InputEvent::CursorMoved((number(x)?, number(y)?))),
+            ["cursor-left"] => Ok(InputEvent::CursorLeft),
+            ["left", "down"] => Ok(InputEvent::LeftButton { pressed: true }),
+            ["left", "up"] => Ok(InputEvent::LeftButton { pressed: false }),
+            ["wheel", "lines", x, y] => Ok(InputEvent::Wheel(MouseScrollDelta::LineDelta(number(x)?, number(y)?))),
+            ["wheel", "pixels", x, y] => Ok(InputEvent::Wheel(MouseScrollDelta::PixelDelta(PhysicalPosition::new(number(x)?, number(y)?)))),
+            ["close"] => Ok(InputEvent::CloseRequested),
+            _ => Err(format!("unknown event `{text}`")),
+        }
+    }
+}
+
+// winit can't parse key names, the names are those of `KeyCode`'s debug output
+macro_rules! key_codes {
+    ($($key:ident)*) => {
+        fn key_code(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_codes! {
+    Backquote Backslash BracketLeft BracketRight Comma Digit0 Digit1 Digit2 Digit3 Digit4 Digit5 Digit6 Digit7 Digit8 Digit9
+    Equal IntlBackslash IntlRo IntlYen KeyA KeyB KeyC KeyD KeyE KeyF KeyG KeyH KeyI KeyJ KeyK KeyL KeyM KeyN KeyO KeyP KeyQ
+    KeyR KeyS KeyT KeyU KeyV KeyW KeyX KeyY KeyZ Minus Period Quote Semicolon Slash AltLeft AltRight Backspace CapsLock
+    ContextMenu ControlLeft ControlRight Enter SuperLeft SuperRight ShiftLeft ShiftRight Space Tab Convert KanaMode Lang1
+    Lang2 Lang3 Lang4 Lang5 NonConvert Delete End Help Home Insert PageDown PageUp ArrowDown ArrowLeft ArrowRight ArrowUp
+    NumLock Numpad0 Numpad1 Numpad2 Numpad3 Numpad4 Numpad5 Numpad6 Numpad7 Numpad8 Numpad9 NumpadAdd NumpadBackspace
+    NumpadClear NumpadClearEntry NumpadComma NumpadDecimal NumpadDivide NumpadEnter NumpadEqual NumpadHash NumpadMemoryAdd
+    NumpadMemoryClear NumpadMemoryRecall NumpadMemoryStore NumpadMemorySubtract NumpadMultiply NumpadParenLeft
+    NumpadParenRight NumpadStar NumpadSubtract Escape Fn FnLock PrintScreen ScrollLock Pause BrowserBack BrowserFavorites
+    BrowserForward BrowserHome BrowserRefresh BrowserSearch BrowserStop Eject LaunchApp1 LaunchApp2 LaunchMail
+    MediaPlayPause MediaSelect MediaStop MediaTrackNext MediaTrackPrevious Power Sleep AudioVolumeDown AudioVolumeMute
+    AudioVolumeUp WakeUp Meta Hyper Turbo Abort Resume Suspend Again Copy Cut Find Open Paste Props Select Undo Hiragana
+    Katakana F1 F2 F3 F4 F5 F6 F7 F8 F9 F10 F11 F12 F13 F14 F15 F16 F17 F18 F19 F20 F21 F22 F23 F24 F25 F26 F27 F28 F29
+    F30 F31 F32 F33 F34 F35
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str) -> (usize, String) {
+        match InteractionPlayer::parse(text) {
+            Err(RecordingError::Parse { line, message }) => (line, message),
+            result => panic!("expected a parse error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn recordings_survive_a_round_trip() {
+        let events = [
+            InputEvent::Resized((1280, 720)),
+            InputEvent::ScaleFactorChanged(1.5),
+            InputEvent::KeyPressed(KeyCode::ShiftLeft),
+            InputEvent::KeyPressed(KeyCode::ControlRight),
+            InputEvent::KeyPressed(KeyCode::KeyS),
+            InputEvent::CursorMoved((200.5, 140.0)),
+            InputEvent::LeftButton { pressed: true },
+            InputEvent::LeftButton { pressed: false },
+            InputEvent::Wheel(MouseScrollDelta::LineDelta(0.0, -1.0)),
+            InputEvent::Wheel(MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 12.5))),
+            InputEvent::CursorLeft,
+            InputEvent::CloseRequested,
+        ];
+
+        let mut recorder = InteractionRecorder::new();
+        for event in events {
+            recorder.record(event);
+        }
+
+        let mut text = Vec::new();
+        recorder.write_to(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with(HEADER));
+
+        let mut player = InteractionPlayer::parse(&text).unwrap();
+        assert_eq!(player.remaining(), events.len());
+        assert_eq!(player.due_at(Duration::MAX), events);
+    }
+
+    #[test]
+    fn players_hand_out_events_in_time_order() {
+        let text = format!("{HEADER}\n# a comment\n300 key KeyB\n\n100 key KeyA\n300 close\n");
+        let mut player = InteractionPlayer::parse(&text).unwrap();
+
+        assert_eq!(player.next_due(), None);
+        assert!(player.due().is_empty());
+
+        assert!(player.due_at(Duration::from_millis(99)).is_empty());
+        assert_eq!(player.due_at(Duration::from_millis(100)), [InputEvent::KeyPressed(KeyCode::KeyA)]);
+        assert_eq!(player.due_at(Duration::from_millis(300)), [InputEvent::KeyPressed(KeyCode::KeyB), InputEvent::CloseRequested]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn players_start_the_clock_once() {
+        let mut player = InteractionPlayer::new(vec![(Duration::from_secs(60), InputEvent::CloseRequested)]);
+        player.start();
+
+        let due = player.next_due().unwrap();
+        player.start();
+        assert_eq!(player.next_due(), Some(due));
+        assert!(player.due().is_empty());
+        assert_eq!(player.remaining(), 1);
+    }
+
+    #[test]
+    fn bad_recordings_are_rejected() {
+        assert_eq!(parse_error("# other interactions\n100 close").0, 1);
+        assert_eq!(parse_error("\n100 close").0, 2);
+        assert_eq!(parse_error("").0, 1);
+
+        let (line, message) = parse_error(&format!("{HEADER}\n100 close\n200 key NotAKey"));
+        assert_eq!((line, message.as_str()), (3, "unknown key `NotAKey`"));
+
+        assert_eq!(parse_error(&format!("{HEADER}\nclose")).0, 2);
+        assert_eq!(parse_error(&format!("{HEADER}\n-5 close")).0, 2);
+        assert_eq!(parse_error(&format!("{HEADER}\n10 cursor 1")).0, 2);
+        assert_eq!(parse_error(&format!("{HEADER}\n10 resize 1 x")).0, 2);
+        assert_eq!(parse_error(&format!("{HEADER}\n10 left sideways")).0, 2);
+    }
+}
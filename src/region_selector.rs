@@ -0,0 +1,243 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use image::{DynamicImage, RgbaImage};
+use winit::{
+    application::ApplicationHandler, dpi::PhysicalSize, event::*, event_loop::{ActiveEventLoop, EventLoop}, keyboard::{KeyCode, PhysicalKey}, window::{Fullscreen, Window, WindowId}
+};
+
+use crate::annotation::{AnnotationEvent, BoundingBox, BoxEditor};
+use crate::builder::RenderContextBuilder;
+use crate::providers::ImageProvider;
+use crate::render::WgpuFrameRenderContext;
+use crate::types::{FrameRenderContext, Pair};
+use crate::viewer_builder::ViewerError;
+
+// how close to a handle a press grabs it, in surface pixels
+const HANDLE_REACH: f32 = 8.0;
+
+// a full screen window over a screenshot, dimmed except for the rectangle dragged on it,
+// enter takes the rectangle and shows it in a regular window, escape cancels or closes:
+//
+//     let region = RegionSelector::new(screenshot).save_to("region.png").run()?;
+//
+// the rectangle can be moved and resized by its handles before it is taken
+pub struct RegionSelector {
+    screen: RgbaImage,
+    save_to: Option<PathBuf>,
+}
+
+impl RegionSelector {
+    // the whole screen as it was captured, it is shown pixel for pixel on a screen of its size
+    pub fn new(screen: RgbaImage) -> Self {
+        Self { screen, save_to: None }
+    }
+
+    // the format follows the extension
+    pub fn save_to<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.save_to = Some(path.into());
+        self
+    }
+
+    // blocks until the window is closed, `None` when no region was taken
+    pub fn run(self) -> Result<Option<RgbaImage>, ViewerError> {
+        let mut app = Selection {
+            provider: ImageProvider::from_image(DynamicImage::ImageRgba8(self.screen.clone())),
+            screen: self.screen,
+            save_to: self.save_to,
+            editor: BoxEditor::new(),
+            region: None,
+            error: None,
+            window: None,
+            render_context: None,
+            cursor: None,
+        };
+
+        EventLoop::new()?.run_app(&mut app)?;
+
+        match app.error {
+            Some(error) => Err(error),
+            None => Ok(app.region),
+        }
+    }
+}
+
+struct Selection {
+    screen: RgbaImage,
+    // the screen while selecting, the region once it is taken
+    provider: ImageProvider,
+    save_to: Option<PathBuf>,
+    editor: BoxEditor,
+    region: Option<RgbaImage>,
+    error: Option<ViewerError>,
+
+    window: Option<Arc<Window>>,
+    render_context: Option<WgpuFrameRenderContext>,
+    cursor: Option<Pair<f32>>,
+}
+
+impl Selection {
+    fn frame_point(&self) -> Option<Pair<f32>> {
+        self.render_context.as_ref()?.surface_to_frame(self.cursor?)
+    }
+
+    fn selected(&self) -> Option<BoundingBox> {
+        let id = self.editor.selected()?;
+        self.editor.annotations().iter().find(|annotation| annotation.id == id).map(|annotation| annotation.rect)
+    }
+
+    fn press(&mut self, pressed: bool) {
+        let (Some(point), Some(context)) = (self.frame_point(), self.render_context.as_ref()) else {
+            return;
+        };
+
+        match pressed {
+            true => {
+                let tolerance = HANDLE_REACH / context.frame_scale().unwrap_or(1.0).max(f32::EPSILON);
+                self.editor.set_bounds(context.frame_size());
+                self.editor.pointer_down(point, tolerance);
+            },
+            // a new rectangle replaces the previous one
+            false => if let Some(AnnotationEvent::Created { id, .. }) = self.editor.pointer_up(point) {
+                let annotations = self.editor.annotations().iter().filter(|annotation| annotation.id == id).copied().collect();
+                self.editor = BoxEditor::new().with_annotations(annotations);
+                self.editor.select(Some(id));
+            },
+        }
+
+        self.sync();
+    }
+
+    fn sync(&mut self) {
+        let selected = self.selected();
+
+        if let Some(context) = self.render_context.as_mut() {
+            match self.region.is_some() {
+                true => {
+                    context.set_annotations(&[], None);
+                    context.set_spotlight(None);
+                },
+                false => {
+                    context.set_annotations(self.editor.annotations(), self.editor.selected());
+                    context.set_spotlight(Some(selected.unwrap_or(BoundingBox::from_corners((0.0, 0.0), (0.0, 0.0)))));
+                },
+            }
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    // crops the screenshot to whole pixels around the rectangle
+    fn take(&mut self) {
+        let Some(rect) = self.selected() else {
+            return;
+        };
+
+        let (x, y) = (rect.min.0.floor() as u32, rect.min.1.floor() as u32);
+        let (right, bottom) = (rect.max.0.ceil() as u32, rect.max.1.ceil() as u32);
+        let size = (right.min(self.screen.width()).saturating_sub(x), bottom.min(self.screen.height()).saturating_sub(y));
+
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+
+        let region = image::imageops::crop_imm(&self.screen, x, y, size.0, size.1).to_image();
+
+        if let Some(path) = self.save_to.as_ref() {
+            if let Err(error) = region.save(path) {
+                log::error!("failed to save {}: {error}", path.display());
+                self.error = Some(ViewerError::Save(error));
+            }
+        }
+
+        self.provider = ImageProvider::from_image(DynamicImage::ImageRgba8(region.clone()));
+        self.region = Some(region);
+
+        if let Some(window) = self.window.as_ref() {
+            window.set_fullscreen(None);
+            window.set_title("Region");
+            let _ = window.request_inner_size(PhysicalSize::new(size.0, size.1));
+        }
+
+        self.sync();
+    }
+
+    fn render(&mut self) {
+        let Some(context) = self.render_context.as_mut() else {
+            return;
+        };
+
+        if let Err(error) = context.draw_frame(&self.provider) {
+            log::warn!("{error}");
+        }
+    }
+}
+
+impl ApplicationHandler for Selection {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let attributes = Window::default_attributes()
+            .with_title("Select a region")
+            .with_fullscreen(Some(Fullscreen::Borderless(None)));
+
+        let window = Arc::new(event_loop.create_window(attributes).unwrap());
+        let window_size = window.inner_size();
+        self.window = Some(Arc::clone(&window));
+
+        match RenderContextBuilder::new(window).size(window_size.width.max(1), window_size.height.max(1)).build() {
+            Ok(context) => self.render_context = Some(context),
+            Err(error) => {
+                log::error!("failed to initialize rendering: {error}");
+                event_loop.exit();
+            },
+        }
+
+        self.sync();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.window = None;
+        self.render_context = None;
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested | WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state: ElementState::Pressed,
+                    physical_key: PhysicalKey::Code(KeyCode::Escape),
+                    ..
+                },
+                ..
+            } => event_loop.exit(),
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    state: ElementState::Pressed,
+                    physical_key: PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter),
+                    ..
+                },
+                ..
+            } if self.region.is_none() => self.take(),
+            WindowEvent::Resized(size) => if let Some(context) = self.render_context.as_mut() {
+                context.configure((size.width, size.height));
+                self.sync();
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some((position.x as f32, position.y as f32));
+
+                if self.editor.is_dragging() {
+                    if let Some(point) = self.frame_point() {
+                        self.editor.pointer_moved(point);
+                        self.sync();
+                    }
+                }
+            },
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } if self.region.is_none() => {
+                self.press(state == ElementState::Pressed);
+            },
+            WindowEvent::RedrawRequested => self.render(),
+            _ => (),
+        }
+    }
+}
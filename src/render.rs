@@ -1,377 +1,3836 @@
+#[cfg(feature = "compute")]
+use std::cell::OnceCell;
+use std::future::Future;
+use std::task::Poll;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
 use wgpu::util::DeviceExt;
+use crate::annotation::{Annotation, AnnotationId, BoundingBox};
+use crate::mask::{MaskStyle, SegmentationMask};
+use crate::heatmap::{Colormap, Heatmap, HeatmapStyle};
 use crate::vertex::{self, INDICES, Vertex};
-use crate::types::{Pair, FrameRenderContext, HasData, HasPosition, HasSize, HasRatio};
+use crate::transform::{RegionOfInterest, TransformUniform, ViewTransform};
+use crate::cubemap::{CubeFace, CubeLayout, SkyboxView};
+use crate::shading::ShadingMode;
+use crate::viewport::ScalingMode;
+use crate::adapter::{self, AdapterError, AdapterSelection};
+use crate::gpu::{self, GpuContext};
+use crate::filters::{Filter, FilterChain};
+use crate::error::{DrawError, RenderInitError};
+use crate::filmstrip::{Filmstrip, FilmstripCell, FilmstripPass, FilmstripStyle};
+#[cfg(feature = "compute")]
+use crate::histogram::{Histogram, HistogramPass, Region};
+use crate::memory::{self, MemoryReport};
+use crate::overlay::{self, Overlay};
+use crate::scale_bar::{self, ScaleBar};
+use crate::scrubber::{self, Marker, Timeline};
+use crate::stats::{GpuTimer, RenderStats};
+use crate::subtitles::CaptionStyle;
+use crate::glyphs::GlyphPass;
+use crate::text::{self, Font, TextShapes};
+use crate::tiling::{self, TileCache, TileFrame, TileKey};
+use crate::upload::UploadBelt;
+use crate::readback::{self, PixelValue, ReadbackError};
+use crate::feedback::FeedbackHandle;
+use crate::{diagnostics, executor};
+use crate::convert;
+use crate::types::{self, Pair, FrameProvider, FrameRenderContext, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Orientation, PixelFormat, Rect, RowOrder};
+
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    pub name: String,
+    pub backend: wgpu::Backend,
+    pub device_type: wgpu::DeviceType,
+    pub driver: String,
+    pub driver_info: String,
+    pub limits: wgpu::Limits,
+    pub features: wgpu::Features,
+    pub surface_format: wgpu::TextureFormat,
+}
+
+// how presentation is paced against the display, a mode the surface can't do falls back
+// to the closest one it can
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PresentPreference {
+    // waits for the vertical blank and never tears
+    #[default]
+    Vsync,
+    // the newest frame replaces the queued one, low latency without tearing
+    Mailbox,
+    // presents right away and may tear
+    Immediate,
+}
+
+impl PresentPreference {
+    // most wanted first, fifo is supported everywhere
+    fn candidates(self) -> &'static [wgpu::PresentMode] {
+        match self {
+            PresentPreference::Vsync => &[wgpu::PresentMode::Fifo],
+            PresentPreference::Mailbox => &[wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo],
+            PresentPreference::Immediate => &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo],
+        }
+    }
+}
+
+// how frame pixels get to the gpu
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UploadPath {
+    // `Queue::write_texture`, which stages every upload in a new buffer
+    #[default]
+    Queue,
+    // rows are written straight into reused mapped buffers, for high rate providers like
+    // video and cameras, together with borrowed frames nothing is allocated per frame
+    StagingBelt,
+}
+
+// how frame pixels are filtered when they don't land one to one on the surface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FilterPreset {
+    // nearest, pixel art stays crisp at any zoom
+    Pixelated,
+    // linear, photos scale without jaggies
+    Smooth,
+    // smooth when zoomed out, pixelated once zoomed in far enough to show true pixels, see
+    // `AutoFilterThresholds`
+    #[default]
+    Auto,
+}
+
+// the zooms `FilterPreset::Auto` switches at, in surface pixels per frame pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoFilterThresholds {
+    // from here on pixels are big enough that blurring them hides detail rather than jaggies
+    pub pixelated_from: f32,
+    // below this it is smooth again, in between the filter stays as it was so zooming back
+    // and forth across a single threshold doesn't flicker
+    pub smooth_below: f32,
+}
+
+// the scale goes through the view transform, 100% comes out a hair under 1
+const AUTO_FILTER_TOLERANCE: f32 = 1e-3;
 
-impl HasRatio for Pair<u32> {
-    fn ratio(&self) -> f32 {
-        self.0 as f32 / self.1 as f32
+impl Default for AutoFilterThresholds {
+    // true pixels from 100% on
+    fn default() -> Self {
+        Self::at(1.0)
+    }
+}
+
+impl AutoFilterThresholds {
+    // one zoom both ways
+    pub fn at(scale: f32) -> Self {
+        Self { pixelated_from: scale, smooth_below: scale }
+    }
+}
+
+// lines between the frame's pixels once they are large enough to tell apart, e.g. to count
+// the pixels of pixel art
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelGrid {
+    // surface pixels per frame pixel from which the lines show
+    pub min_scale: f32,
+    // in surface pixels
+    pub width: f32,
+    // srgb, alpha blends the lines over the frame
+    pub color: [u8; 4],
+}
+
+impl Default for PixelGrid {
+    // a thin gray that shows on light and dark pixels alike, from 800%
+    fn default() -> Self {
+        Self { min_scale: 8.0, width: 1.0, color: [128, 128, 128, 160] }
+    }
+}
+
+// what happens to float frames brighter than white, e.g. pfm renders or a decoder's exr and
+// avif output, 8 bit frames look the same in every mode
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HdrMode {
+    // left as they are, sdr surfaces clip them at white
+    #[default]
+    Off,
+    // highlights are rolled off into the sdr range so they keep their detail
+    ToneMap,
+    // an extended range float surface when the display has one, values above 1 are shown
+    // brighter than sdr white, without one it tone maps
+    PassThrough,
+}
+
+// how the alpha of frames is read, transparent pixels show the clear color behind them
+// unless it's `Opaque`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AlphaBehavior {
+    // alpha is left out, frames cover the clear color whatever it says
+    Opaque,
+    // color and alpha are independent, as png, gif and webp store them
+    #[default]
+    Straight,
+    // color was multiplied by alpha already, e.g. frames from a compositor or a renderer,
+    // it's divided out again before shading, so the frame is shaded like a straight one
+    Premultiplied,
+}
+
+impl AlphaBehavior {
+    // `Image::alpha` in shader.wgsl
+    fn index(self) -> u32 {
+        match self {
+            AlphaBehavior::Opaque => 0,
+            AlphaBehavior::Straight => 1,
+            AlphaBehavior::Premultiplied => 2,
+        }
     }
 
-    fn inverse_ratio(&self) -> f32 {
-        self.1 as f32 / self.0 as f32
+    // the shader writes premultiplied color
+    fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            AlphaBehavior::Opaque => wgpu::BlendState::REPLACE,
+            AlphaBehavior::Straight | AlphaBehavior::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
     }
 }
 
+// the label next to the cursor, read again when the frame under it changes
+#[derive(Debug)]
+struct Readout {
+    point: Pair<f32>,
+    // `None` off the frame
+    label: Option<String>,
+    stale: bool,
+}
+
 #[derive(Debug)]
 pub struct WgpuFrameRenderContext {
-    queue: wgpu::Queue,
-    device: wgpu::Device,
+    gpu: GpuContext,
     clear_color: wgpu::Color,
-    surface: wgpu::Surface<'static>,
+    target: RenderTarget,
     config: wgpu::SurfaceConfiguration,
+    present_mode: PresentPreference,
+    wide_gamut: bool,
+    hdr: HdrMode,
+    alpha: AlphaBehavior,
+    upload_path: UploadPath,
+    upload_belt: Option<UploadBelt>,
+    gpu_timer: Option<GpuTimer>,
+    stats: RenderStats,
+    filter_preset: FilterPreset,
+    auto_filter: AutoFilterThresholds,
 
     index_count: u32,
     index_buffer: wgpu::Buffer,
 
+    strict_validation: bool,
+    source_label: Option<String>,
+    label_prefix: Option<String>,
+    // set by `trigger_capture`, cleared by the next draw
+    capture_next: bool,
+    transform: ViewTransform,
+    scaling_mode: ScalingMode,
+    tiles: Pair<u32>,
+    crop: Option<Rect<u32>>,
+    // set while the last draw was `draw_split`
+    split_view: bool,
+    // as a fraction of the surface width
+    split_position: f32,
+    shading_mode: ShadingMode,
+    checkerboard: bool,
+    pixel_grid: Option<PixelGrid>,
+    skybox: Option<SkyboxView>,
+    annotations: Vec<Annotation>,
+    spotlight: Option<BoundingBox>,
+    selected_annotation: Option<AnnotationId>,
+    mask: Option<SegmentationMask>,
+    mask_style: MaskStyle,
+    heatmap: Option<Heatmap>,
+    heatmap_style: HeatmapStyle,
+    timeline: Option<Timeline>,
+    markers: Vec<Marker>,
+    caption: Option<String>,
+    caption_style: CaptionStyle,
+    badge: Option<String>,
+    hud: Option<String>,
+    scale_bar: Option<ScaleBar>,
+    readout: Option<Readout>,
+    filters: Vec<Filter>,
+    feedback: FeedbackHandle,
+    scale_factor: f64,
+    // set by anything that changes what a draw would show, cleared by drawing
+    needs_redraw: bool,
+    // what the texture holds when every frame drawn last had a content id
+    shown_content: Option<Vec<(u64, PixelFormat, Placement)>>,
+    // whether the last draw uploaded frames, two in a row count as a stream
+    uploaded_last_draw: bool,
+
+    overlay: Option<Overlay>,
+    filmstrip: Option<Filmstrip>,
+    filmstrip_pass: Option<FilmstripPass>,
+    // see `set_font`, without one text is drawn in the built in font with the overlay
+    font: Option<Font>,
+    glyph_pass: Option<GlyphPass>,
+    #[cfg(feature = "compute")]
+    histogram_pass: OnceCell<HistogramPass>,
     resources: Option<WgpuFrameRenderContextResources>,
+    // set while a frame too large for a texture is drawn in tiles
+    tile_cache: Option<TileCache>,
+}
+
+// offscreen targets back presentation paths without a window system
+#[derive(Debug)]
+enum RenderTarget {
+    Surface(wgpu::Surface<'static>),
+    Texture(wgpu::Texture),
+    // the window's surface was given up, e.g. while the app is suspended
+    Released,
+}
+
+// every resource carries the label prefix of its context, in strict mode also the name of
+// the source it was created for, e.g. "left: xixi.png: Image Texture"
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResourceLabels {
+    prefix: Option<String>,
+    source: Option<String>,
+}
+
+impl ResourceLabels {
+    pub(crate) fn get(&self, name: &str) -> String {
+        [self.prefix.as_deref(), self.source.as_deref(), Some(name)].into_iter().flatten().collect::<Vec<_>>().join(": ")
+    }
 }
 
 impl WgpuFrameRenderContext {
-    fn init_resources<Frame>(&mut self, frame: &Frame)
-    where
-        Frame: HasSize<u32>
-    {
-        match self.resources {
-            None => {
-                self.resources = Some(WgpuFrameRenderContextResources::new(&self.config, &self.device, frame.size(), self.size()));
-            },
-            _ => (),
-        }    
+    // what this context holds on the gpu right now, e.g. to show next to the view or to
+    // pick a smaller tile budget
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+
+        if let Some(resources) = self.resources.as_ref() {
+            resources.add_to_report(&mut report, self.tile_cache.is_some());
+        }
+
+        report.overlays += self.overlay.as_ref().map_or(0, Overlay::allocated_bytes);
+        report.overlays += self.filmstrip_pass.as_ref().map_or(0, FilmstripPass::allocated_bytes);
+        report.overlays += self.glyph_pass.as_ref().map_or(0, GlyphPass::allocated_bytes);
+        report.staging += self.upload_belt.as_ref().map_or(0, UploadBelt::allocated_bytes);
+        report.other += self.gpu_timer.as_ref().map_or(0, GpuTimer::allocated_bytes);
+
+        if let RenderTarget::Texture(texture) = &self.target {
+            report.other += memory::texture_bytes(texture);
+        }
+
+        report
     }
 
-    fn draw<Func>(&self, update_render_pass: Func) -> Result<(), wgpu::SurfaceError>
-    where
-        Func: FnOnce(&mut wgpu::CommandEncoder, &wgpu::TextureView)
-    {
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+    pub fn gpu_info(&self) -> GpuInfo {
+        let info = self.gpu.adapter().get_info();
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+        GpuInfo {
+            name: info.name,
+            backend: info.backend,
+            device_type: info.device_type,
+            driver: info.driver,
+            driver_info: info.driver_info,
+            limits: self.gpu.device().limits(),
+            features: self.gpu.device().features(),
+            surface_format: self.config.format,
+        }
+    }
 
-        update_render_pass(&mut encoder, &view);
+    // adapters that can drive the current target, in the order `AdapterSelection::Index` uses
+    pub fn available_adapters(&self) -> Vec<wgpu::AdapterInfo> {
+        adapter::compatible_adapters(self.gpu.instance(), self.surface())
+            .iter()
+            .map(|adapter| adapter.get_info())
+            .collect()
+    }
+
+    // moves rendering to another adapter, device-owned resources are rebuilt and the
+    // next frame from the provider is uploaded to the new device, contexts sharing the
+    // old one keep it
+    pub fn switch_adapter(&mut self, selection: AdapterSelection) -> Result<(), AdapterError> {
+        let future = GpuContext::with_instance(self.gpu.shared_instance(), self.surface(), &selection, self.strict_validation);
+        let gpu = executor::block_on(future)?;
+
+        if let Some(surface) = self.surface() {
+            self.config = surface_config(&surface.get_capabilities(gpu.adapter()), self.size(), self.present_mode, self.wide_gamut, self.hdr);
+        }
+
+        log::info!("switching from {} to {}", self.gpu.adapter().get_info().name, gpu.adapter().get_info().name);
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        self.resources = None;
+        self.overlay = None;
+        self.filmstrip_pass = None;
+        self.glyph_pass = None;
+        #[cfg(feature = "compute")]
+        {
+            self.histogram_pass = OnceCell::new();
+        }
+        self.upload_belt = None;
+        self.gpu_timer = None;
+        self.index_buffer = create_index_buffer(gpu.device(), &self.labels());
+        self.gpu = gpu;
 
+        self.configure(self.size());
         Ok(())
     }
-}
 
-#[derive(Debug)]
-struct WgpuFrameRenderContextResources {
-    frame_size: Pair<u32>,
-    texture: wgpu::Texture,
-    bind_group: wgpu::BindGroup,
-    vertex_buffer: wgpu::Buffer,
-    render_pipeline: wgpu::RenderPipeline,
-}
+    pub fn present_mode(&self) -> PresentPreference {
+        self.present_mode
+    }
 
-impl HasSize<u32> for WgpuFrameRenderContext {
-    fn size(&self) -> Pair<u32> {
-        (self.config.width, self.config.height)
+    // the mode the surface actually presents with, offscreen targets always report fifo
+    pub fn active_present_mode(&self) -> wgpu::PresentMode {
+        self.config.present_mode
     }
-}
 
-pub struct WgpuFrameRenderContextInit {
-    pub surface_size: Pair<u32>,
-    pub clear_color: Option<wgpu::Color>,
-    pub surface_handle: wgpu::SurfaceTarget<'static>,
-}
+    // reconfigures the surface right away and returns the mode it ended up with
+    pub fn set_present_mode(&mut self, preference: PresentPreference) -> wgpu::PresentMode {
+        self.present_mode = preference;
 
-impl HasSize<u32> for WgpuFrameRenderContextInit {
-    fn size(&self) -> Pair<u32> {
-        self.surface_size
+        if let RenderTarget::Surface(surface) = &self.target {
+            self.config.present_mode = select_present_mode(&surface.get_capabilities(self.gpu.adapter()), preference);
+            surface.configure(self.gpu.device(), &self.config);
+            diagnostics::record_surface_config(&self.config);
+        }
+
+        self.config.present_mode
     }
-}
 
-impl From<WgpuFrameRenderContextInit> for WgpuFrameRenderContext {
-    fn from(WgpuFrameRenderContextInit {
-        clear_color ,
-        surface_size,
-        surface_handle,
-    }: WgpuFrameRenderContextInit) -> Self {
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
+    pub fn upload_path(&self) -> UploadPath {
+        self.upload_path
+    }
 
-        let surface = instance.create_surface(surface_handle).unwrap();
+    // applies from the next frame on
+    pub fn set_upload_path(&mut self, path: UploadPath) {
+        self.upload_path = path;
 
-        let ((device, queue), adapter) = smol::block_on(async {
-            let adapter = instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-                power_preference: wgpu::PowerPreference::default(),
-            }).await.unwrap();
+        if path == UploadPath::Queue {
+            self.upload_belt = None;
+        }
+    }
 
-            (adapter.request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_limits: wgpu::Limits::default(),
-                    required_features: wgpu::Features::empty(),
-                },
-                None,
-            ).await.unwrap(), adapter)
-        });
+    pub fn hdr_mode(&self) -> HdrMode {
+        self.hdr
+    }
 
-        let surface_caps = surface.get_capabilities(&adapter);
+    // set at init, see `RenderContextBuilder::alpha_behavior`
+    pub fn alpha_behavior(&self) -> AlphaBehavior {
+        self.alpha
+    }
 
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .filter(|f| f.is_srgb())
-            .next()
-            .unwrap_or(surface_caps.formats[0]);
+    // whether values above 1 reach the display, only with `HdrMode::PassThrough` on a
+    // float surface
+    pub fn hdr_output(&self) -> bool {
+        self.hdr == HdrMode::PassThrough && is_extended_range(self.config.format)
+    }
 
-        let config = wgpu::SurfaceConfiguration {
-            width: surface_size.0,
-            height: surface_size.1,
+    // whether the surface could show values above 1, whatever mode is set
+    pub fn supports_hdr(&self) -> bool {
+        match self.surface() {
+            Some(surface) => hdr_surface_format(&surface.get_capabilities(self.gpu.adapter())).is_some(),
+            None => is_extended_range(self.config.format),
+        }
+    }
 
-            view_formats: vec![],
-            format: surface_format,
-            desired_maximum_frame_latency: 2,
-            alpha_mode: surface_caps.alpha_modes[0],
-            present_mode: surface_caps.present_modes[0],
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        };
+    // reconfigures the surface when the mode needs another format, offscreen targets keep
+    // theirs and only switch between clipping and tone mapping
+    pub fn set_hdr_mode(&mut self, mode: HdrMode) {
+        self.hdr = mode;
 
-        surface.configure(&device, &config);
+        if let RenderTarget::Surface(surface) = &self.target {
+            let config = surface_config(&surface.get_capabilities(self.gpu.adapter()), self.size(), self.present_mode, self.wide_gamut, mode);
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            usage: wgpu::BufferUsages::INDEX,
-            contents: bytemuck::cast_slice(INDICES),
-        });
+            if config.format != self.config.format || config.alpha_mode != self.config.alpha_mode {
+                self.config = config;
+                self.resources = None;
+                self.overlay = None;
+                self.filmstrip_pass = None;
+                self.glyph_pass = None;
+                self.configure(self.size());
+            }
+        }
 
-        Self {
-            queue,
-            config,
-            device,
-            surface,
-            clear_color: clear_color.unwrap_or(wgpu::Color::default()),
+        self.write_image_uniform();
+    }
 
-            index_buffer,
-            index_count: INDICES.len() as u32,
+    pub fn filter_preset(&self) -> FilterPreset {
+        self.filter_preset
+    }
 
-            resources: None,
+    // applies to the current frame right away
+    pub fn set_filter_preset(&mut self, preset: FilterPreset) {
+        self.filter_preset = preset;
+        self.write_transform();
+    }
+
+    // the filter the preset picks at the current zoom
+    pub fn filter_mode(&self) -> wgpu::FilterMode {
+        match self.filter_preset {
+            FilterPreset::Pixelated => wgpu::FilterMode::Nearest,
+            FilterPreset::Smooth => wgpu::FilterMode::Linear,
+            FilterPreset::Auto => match self.frame_scale().map(|scale| scale + AUTO_FILTER_TOLERANCE) {
+                Some(scale) if scale >= self.auto_filter.pixelated_from => wgpu::FilterMode::Nearest,
+                Some(scale) if scale >= self.auto_filter.smooth_below => {
+                    self.resources.as_ref().map_or(wgpu::FilterMode::Linear, |resources| resources.filter)
+                },
+                _ => wgpu::FilterMode::Linear,
+            },
         }
     }
-}
 
-fn get_vertices(device: &wgpu::Device, frame_size: Pair<u32>, surface_size: Pair<u32>) -> wgpu::Buffer {
-    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Vertex Buffer"),
-        usage: wgpu::BufferUsages::VERTEX,
-        contents: bytemuck::cast_slice(&Vertex::get_vertices((frame_size.inverse_ratio(), surface_size.inverse_ratio()))),
-    })
-}
+    pub fn auto_filter_thresholds(&self) -> AutoFilterThresholds {
+        self.auto_filter
+    }
 
-impl WgpuFrameRenderContextResources {
-    fn new(config: &wgpu::SurfaceConfiguration, device: &wgpu::Device, frame_size: Pair<u32>, surface_size: Pair<u32>) -> Self {
-        let texture_size = wgpu::Extent3d {
-            width: frame_size.0,
-            height: frame_size.1,
-            depth_or_array_layers: 1,
+    // applies to the current frame right away, a `smooth_below` over `pixelated_from` is
+    // lowered to it
+    pub fn set_auto_filter_thresholds(&mut self, thresholds: AutoFilterThresholds) {
+        self.auto_filter = AutoFilterThresholds {
+            smooth_below: thresholds.smooth_below.min(thresholds.pixelated_from),
+            ..thresholds
         };
+        self.write_transform();
+    }
 
-        let vertex_buffer = get_vertices(device, frame_size, surface_size);
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Image Texture"),
-            sample_count: 1,
-            view_formats: &[],
-            mip_level_count: 1,
-            size: texture_size,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        });
-        
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let image_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Image Sampler"),
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+    fn surface(&self) -> Option<&wgpu::Surface<'static>> {
+        match &self.target {
+            RenderTarget::Surface(surface) => Some(surface),
+            RenderTarget::Texture(_) | RenderTarget::Released => None,
+        }
+    }
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Texture Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        });
+    // drops the surface while keeping the device and everything uploaded, for platforms
+    // that destroy the window's surface on suspend, e.g. android and some wayland
+    // compositors, draws are skipped until `recreate_surface`
+    // offscreen targets have no surface and are left alone
+    pub fn release_surface(&mut self) {
+        if let RenderTarget::Surface(_) = self.target {
+            log::debug!("releasing the surface");
+            self.target = RenderTarget::Released;
+        }
+    }
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Image Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&image_sampler),
-                },
-            ],
-        });
+    pub fn is_surface_released(&self) -> bool {
+        matches!(self.target, RenderTarget::Released)
+    }
 
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges:&[],
-        });
+    // presents to a new surface for `handle`, e.g. the window recreated on resume, the
+    // frame, view and overlays stay as they were and are drawn again on the next draw
+    // gpu resources are only rebuilt when the new surface needs another format
+    pub fn recreate_surface(&mut self, handle: impl Into<wgpu::SurfaceTarget<'static>>) -> Result<(), RenderInitError> {
+        if let RenderTarget::Texture(_) = self.target {
+            return Ok(());
+        }
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
+        let surface = self.gpu.shared_instance().create_surface(handle)?;
+        if !self.gpu.supports(&surface) {
+            return Err(RenderInitError::UnsupportedSurface(self.gpu.adapter().get_info().name));
+        }
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[vertex::Vertex::desc()],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                compilation_options: Default::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        let config = surface_config(&surface.get_capabilities(self.gpu.adapter()), self.size(), self.present_mode, self.wide_gamut, self.hdr);
+        if config.format != self.config.format || config.alpha_mode != self.config.alpha_mode {
+            log::info!("the new surface is {:?}, rebuilding resources made for {:?}", config.format, self.config.format);
+            self.resources = None;
+            self.overlay = None;
+            self.filmstrip_pass = None;
+            self.glyph_pass = None;
+            self.shown_content = None;
+        }
 
-        Self {
-            texture,
-            bind_group,
-            frame_size,
-            vertex_buffer,
-            render_pipeline,
+        self.config = config;
+        self.target = RenderTarget::Surface(surface);
+        self.configure(self.size());
+        self.write_image_uniform();
+        Ok(())
+    }
+
+    // whether a draw would show something the last one didn't, e.g. after a resize, a
+    // change of the view or a new frame, event loops can wait for input while it's false
+    pub fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    // for changes the context can't see, e.g. to a provider that yields frames without
+    // content ids
+    pub fn request_redraw(&mut self) {
+        self.needs_redraw = true;
+    }
+
+    // clones of this handle let providers see presents, drops and gpu queue depth
+    pub fn feedback(&self) -> &FeedbackHandle {
+        &self.feedback
+    }
+
+    pub fn set_source_label<S: Into<String>>(&mut self, label: S) {
+        self.source_label = Some(label.into());
+
+        // resources are rebuilt on the next frame so they pick up the new label
+        if self.strict_validation {
+            self.resources = None;
         }
     }
 
-    fn queue_write_texture<Frame>(&self, queue: &wgpu::Queue, frame: &Frame)
-    where
-        Frame: HasSize<u32> + HasPosition<u32> + HasData
-    {
-        let frame_size = frame.size();
+    pub fn label_prefix(&self) -> Option<&str> {
+        self.label_prefix.as_deref()
+    }
 
-        queue.write_texture(
-            self.texture.as_image_copy(),
-            &frame.data(),
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * frame_size.0),
-                rows_per_image: Some(frame_size.1),
-            },
-            self.texture.size(),
-        )
+    // names every resource of this context in gpu captures, e.g. to tell apart the views
+    // of an app with several contexts in RenderDoc or Xcode, everything is created again
+    // for the next frame to pick it up
+    pub fn set_label_prefix(&mut self, prefix: Option<&str>) {
+        self.label_prefix = prefix.map(str::to_owned);
+
+        self.resources = None;
+        self.overlay = None;
+        self.filmstrip_pass = None;
+        self.glyph_pass = None;
+        self.upload_belt = None;
+        self.index_buffer = create_index_buffer(self.gpu.device(), &self.labels());
+        self.configure(self.size());
     }
-}
 
-impl FrameRenderContext for WgpuFrameRenderContext {
-    type RenderError = wgpu::SurfaceError;
-    type Init = WgpuFrameRenderContextInit;
+    // what the last draw cost, whichever of the draw calls made it, see `RenderStats`,
+    // `draw_frame` returns it too, this is where the `_if_needed` draws leave it
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
 
-    fn configure(&mut self, size: Pair<u32>) {
-        self.config.width = size.0;
-        self.config.height = size.1;
-        self.surface.configure(&self.device, &self.config);
+    // captures the next frame drawn with the capture tool the app runs under, e.g.
+    // RenderDoc or Xcode, nothing happens without one
+    pub fn trigger_capture(&mut self) {
+        self.capture_next = true;
+        self.needs_redraw = true;
+    }
 
-        if let Some(resources) = self.resources.as_mut() {
-            resources.vertex_buffer = get_vertices(&self.device, resources.frame_size, size);
+    pub(crate) fn labels(&self) -> ResourceLabels {
+        ResourceLabels {
+            prefix: self.label_prefix.clone(),
+            source: match self.strict_validation {
+                true => Some(self.source_label.clone().unwrap_or_else(|| String::from("egami"))),
+                false => None,
+            },
         }
     }
 
-    fn draw_frame<Frame>(&mut self, mut frame_provider: impl Iterator<Item = Frame>) -> Result<(), Self::RenderError>
-    where
-        Frame: HasSize<u32> + HasPosition<u32> + HasData
-    {
-        let frame = frame_provider.next();
+    // physical pixels per logical pixel of the window the surface is in, 1 for offscreen
+    // targets unless set
+    // sizes and points of this context are in physical pixels, so `ScalingMode::Original`
+    // shows a frame pixel per physical pixel at any scale factor
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
 
-        if let Some(frame) = frame.as_ref() {
-            self.init_resources(frame);
+    // e.g. from `WindowEvent::ScaleFactorChanged`, the new physical size still comes
+    // through `configure`
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        if !scale_factor.is_finite() || scale_factor <= 0.0 {
+            log::warn!("ignoring invalid scale factor {scale_factor}");
+            return;
         }
 
-        let resources = self.resources.as_ref();
+        self.scale_factor = scale_factor;
+    }
 
-        self.draw(|encoder, view| {
-            if let (Some(frame), Some(resources)) = (frame.as_ref(), resources) {
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(self.clear_color),
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    timestamp_writes: None,
-                    occlusion_query_set: None,
-                    depth_stencil_attachment: None,
-                });
-        
-                resources.queue_write_texture(&self.queue, frame);
-
-                render_pass.set_pipeline(&resources.render_pipeline);
-                render_pass.set_bind_group(0, &resources.bind_group, &[]);
-                render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
-
-                render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    // the surface in logical pixels, e.g. to lay out a ui in points around the view
+    pub fn logical_size(&self) -> Pair<f32> {
+        let (width, height) = self.size();
+        self.to_logical((width as f32, height as f32))
+    }
+
+    pub fn to_logical(&self, point: Pair<f32>) -> Pair<f32> {
+        let scale = self.scale_factor as f32;
+        (point.0 / scale, point.1 / scale)
+    }
+
+    pub fn to_physical(&self, point: Pair<f32>) -> Pair<f32> {
+        let scale = self.scale_factor as f32;
+        (point.0 * scale, point.1 * scale)
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.transform.zoom
+    }
+
+    // scales the quad around the surface center, 1.0 is the size the scaling mode gives
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if !zoom.is_finite() || zoom <= 0.0 {
+            log::warn!("ignoring invalid zoom {zoom}");
+            return;
+        }
+
+        self.transform.zoom = zoom;
+        self.write_transform();
+    }
+
+    pub fn pan(&self) -> Pair<f32> {
+        self.transform.pan
+    }
+
+    // moves the quad by a distance in surface pixels, e.g. a cursor drag delta
+    pub fn pan_by(&mut self, delta: Pair<f32>) {
+        self.transform.pan = (self.transform.pan.0 + delta.0, self.transform.pan.1 + delta.1);
+        self.write_transform();
+    }
+
+    pub fn scaling_mode(&self) -> ScalingMode {
+        self.scaling_mode
+    }
+
+    // applies to the current frame right away
+    pub fn set_scaling_mode(&mut self, mode: ScalingMode) {
+        self.scaling_mode = mode;
+
+        let framing = self.framing();
+        if let Some(resources) = self.resources.as_mut() {
+            let placements = resources.placements.clone();
+            resources.place(self.gpu.device(), placements, (self.config.width, self.config.height), framing);
+        }
+
+        self.write_transform();
+    }
+
+    pub fn tiles(&self) -> Pair<u32> {
+        self.tiles
+    }
+
+    // repeats every frame `tiles.0` times across and `tiles.1` times down, filtering wraps
+    // around its edges through the sampler's repeat addressing, to check that a texture
+    // tiles without seams, (1, 1) turns it off
+    pub fn set_tiles(&mut self, tiles: Pair<u32>) {
+        self.tiles = (tiles.0.max(1), tiles.1.max(1));
+
+        let framing = self.framing();
+        if let Some(resources) = self.resources.as_mut() {
+            let placements = resources.placements.clone();
+            resources.place(self.gpu.device(), placements, (self.config.width, self.config.height), framing);
+        }
+
+        self.write_transform();
+    }
+
+    pub fn crop(&self) -> Option<Rect<u32>> {
+        self.crop
+    }
+
+    // shows only this part of the frame area, in frame pixels from its top-left corner and
+    // tiling included, scaled, zoomed and panned as if it was the whole frame, e.g. to
+    // preview a crop or zoom to a selection
+    // a crop past the frame area is clamped to it, `None` shows all of it
+    pub fn set_crop(&mut self, crop: Option<Rect<u32>>) {
+        if crop.is_some_and(|crop| crop.size.0 == 0 || crop.size.1 == 0) {
+            log::warn!("ignoring empty crop {crop:?}");
+            return;
+        }
+
+        self.crop = crop;
+
+        let framing = self.framing();
+        if let Some(resources) = self.resources.as_mut() {
+            let placements = resources.placements.clone();
+            resources.place(self.gpu.device(), placements, (self.config.width, self.config.height), framing);
+        }
+
+        self.write_transform();
+    }
+
+    pub fn split_position(&self) -> f32 {
+        self.split_position
+    }
+
+    // where `draw_split` changes from the first frame to the second, as a fraction of the
+    // surface width, it stays there through resizes
+    pub fn set_split_position(&mut self, position: f32) {
+        if !position.is_finite() {
+            log::warn!("ignoring invalid split position {position}");
+            return;
+        }
+
+        self.split_position = position.clamp(0.0, 1.0);
+
+        if self.split_view {
+            self.write_image_uniform();
+        }
+    }
+
+    // the split position under a surface point, e.g. the cursor dragging the split line
+    pub fn split_position_at(&self, point: Pair<f32>) -> f32 {
+        (point.0 / self.config.width.max(1) as f32).clamp(0.0, 1.0)
+    }
+
+    // the surface column of the split line
+    pub fn split_x(&self) -> f32 {
+        self.split_position * self.config.width as f32
+    }
+
+    fn set_split_view(&mut self, split_view: bool) {
+        if split_view != self.split_view {
+            self.split_view = split_view;
+            self.write_image_uniform();
+        }
+    }
+
+    pub fn shading_mode(&self) -> ShadingMode {
+        self.shading_mode
+    }
+
+    // applies to the current frame right away, no upload needed
+    pub fn set_shading_mode(&mut self, mode: ShadingMode) {
+        self.shading_mode = mode;
+        self.write_image_uniform();
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
+    // fills the surface around the frame from the next draw on
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.clear_color = color;
+        self.needs_redraw = true;
+    }
+
+    pub fn checkerboard(&self) -> bool {
+        self.checkerboard
+    }
+
+    // draws transparent pixels over a gray checkerboard like image editors do, instead of
+    // letting the clear color through
+    pub fn set_checkerboard(&mut self, enabled: bool) {
+        self.checkerboard = enabled;
+        self.write_image_uniform();
+    }
+
+    pub fn pixel_grid(&self) -> Option<PixelGrid> {
+        self.pixel_grid
+    }
+
+    // outlines every frame pixel while the view is zoomed in at least `min_scale`, the
+    // lines keep their width at any zoom
+    pub fn set_pixel_grid(&mut self, grid: Option<PixelGrid>) {
+        self.pixel_grid = grid;
+        self.write_image_uniform();
+    }
+
+    pub fn skybox(&self) -> Option<SkyboxView> {
+        self.skybox
+    }
+
+    // shows the frame as the inside of a cube instead of flat, `None` goes back to the
+    // flat view, meant for a single frame in one of the cube layouts
+    pub fn set_skybox(&mut self, view: Option<SkyboxView>) {
+        self.skybox = view;
+        self.write_image_uniform();
+    }
+
+    // the cross layout the current frame looks like it has, reads a few texels back
+    pub fn cube_layout(&self) -> Option<CubeLayout> {
+        let resources = self.resources.as_ref()?;
+        let (device, queue) = (self.gpu.device(), self.gpu.queue());
+
+        CubeLayout::detect(resources.layout.size, |texel| readback::read_texel(device, queue, &resources.texture, texel, 0).ok())
+    }
+
+    // outlines drawn over the frame in frame pixels, the selected box also gets handles
+    pub fn set_annotations(&mut self, annotations: &[Annotation], selected: Option<AnnotationId>) {
+        self.annotations = annotations.to_vec();
+        self.selected_annotation = selected;
+        self.needs_redraw = true;
+    }
+
+    pub fn spotlight(&self) -> Option<&BoundingBox> {
+        self.spotlight.as_ref()
+    }
+
+    // dims everything outside a box in frame pixels, e.g. a region being selected, the whole
+    // surface is dimmed while the box is empty
+    pub fn set_spotlight(&mut self, spotlight: Option<BoundingBox>) {
+        if spotlight != self.spotlight {
+            self.spotlight = spotlight;
+            self.needs_redraw = true;
+        }
+    }
+
+    pub fn mask(&self) -> Option<&SegmentationMask> {
+        self.mask.as_ref()
+    }
+
+    // tints the frame by class, the mask covers the frame's pixels as decoded so it stays
+    // aligned when the frame is shown turned, a mask of another size is stretched over it
+    pub fn set_mask(&mut self, mask: Option<SegmentationMask>) {
+        self.mask = mask;
+
+        if let Some(resources) = self.resources.as_mut() {
+            resources.upload_mask(self.gpu.device(), self.gpu.queue(), self.mask.as_ref());
+        }
+
+        self.write_image_uniform();
+    }
+
+    pub fn mask_style(&self) -> &MaskStyle {
+        &self.mask_style
+    }
+
+    // applies right away, no upload of the mask needed
+    pub fn set_mask_style(&mut self, style: MaskStyle) {
+        self.mask_style = style;
+
+        if let Some(resources) = self.resources.as_ref() {
+            resources.queue_write_mask_colors(self.gpu.queue(), &self.mask_style);
+        }
+
+        self.write_image_uniform();
+    }
+
+    pub fn heatmap(&self) -> Option<&Heatmap> {
+        self.heatmap.as_ref()
+    }
+
+    // colors the frame by value under the mask, lined up with the frame like masks are
+    pub fn set_heatmap(&mut self, heatmap: Option<Heatmap>) {
+        self.heatmap = heatmap;
+
+        if let Some(resources) = self.resources.as_mut() {
+            resources.upload_heatmap(self.gpu.device(), self.gpu.queue(), self.heatmap.as_ref());
+        }
+
+        self.write_image_uniform();
+    }
+
+    pub fn heatmap_style(&self) -> HeatmapStyle {
+        self.heatmap_style
+    }
+
+    // applies right away, no upload of the heatmap needed
+    pub fn set_heatmap_style(&mut self, style: HeatmapStyle) {
+        if let Some(resources) = self.resources.as_ref().filter(|_| style.colormap != self.heatmap_style.colormap) {
+            resources.queue_write_colormap(self.gpu.queue(), style.colormap);
+        }
+
+        self.heatmap_style = style;
+        self.write_image_uniform();
+    }
+
+    pub fn timeline(&self) -> Option<&Timeline> {
+        self.timeline.as_ref()
+    }
+
+    // a scrub bar along the bottom of the surface, over the frame and the boxes
+    pub fn set_timeline(&mut self, timeline: Option<Timeline>) {
+        if timeline != self.timeline {
+            self.timeline = timeline;
+            self.needs_redraw = true;
+        }
+    }
+
+    // sorted by position
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    // ticks on the scrub bar, they show once there is a timeline with a duration
+    pub fn set_markers(&mut self, markers: &[Marker]) {
+        self.markers = markers.to_vec();
+        self.markers.sort_by_key(|marker| marker.position);
+        self.needs_redraw = true;
+    }
+
+    pub fn caption(&self) -> Option<&str> {
+        self.caption.as_deref()
+    }
+
+    // text over the frame, e.g. `Subtitles::text_at` the position of a video
+    pub fn set_caption(&mut self, caption: Option<&str>) {
+        if caption != self.caption.as_deref() {
+            self.caption = caption.map(str::to_owned);
+            self.needs_redraw = true;
+        }
+    }
+
+    pub fn caption_style(&self) -> &CaptionStyle {
+        &self.caption_style
+    }
+
+    pub fn set_caption_style(&mut self, style: CaptionStyle) {
+        self.caption_style = style;
+        self.needs_redraw = true;
+    }
+
+    pub fn badge(&self) -> Option<&str> {
+        self.badge.as_deref()
+    }
+
+    // a warning in the top left corner, e.g. that a damaged file is only partly shown
+    pub fn set_badge(&mut self, badge: Option<&str>) {
+        if badge != self.badge.as_deref() {
+            self.badge = badge.map(str::to_owned);
+            self.needs_redraw = true;
+        }
+    }
+
+    pub fn hud(&self) -> Option<&str> {
+        self.hud.as_deref()
+    }
+
+    // lines of status in the top right corner, e.g. the file name, zoom and frame rate, drawn
+    // after the frame so they keep their size at any zoom
+    pub fn set_hud(&mut self, hud: Option<&str>) {
+        if hud != self.hud.as_deref() {
+            self.hud = hud.map(str::to_owned);
+            self.needs_redraw = true;
+        }
+    }
+
+    #[cfg(feature = "fonts")]
+    pub fn font(&self) -> Option<&Font> {
+        self.font.as_ref()
+    }
+
+    // captions, badges, the hud, labels and the scale bar are drawn in `font` rather than the
+    // built in font, which only has ascii
+    #[cfg(feature = "fonts")]
+    pub fn set_font(&mut self, font: Option<Font>) {
+        self.font = font;
+        self.glyph_pass = None;
+        self.needs_redraw = true;
+    }
+
+    pub fn scale_bar(&self) -> Option<&ScaleBar> {
+        self.scale_bar.as_ref()
+    }
+
+    // drawn over the view and so also in `capture_frame`, it follows the zoom on its own
+    pub fn set_scale_bar(&mut self, scale_bar: Option<ScaleBar>) {
+        if scale_bar.as_ref() != self.scale_bar.as_ref() {
+            self.scale_bar = scale_bar;
+            self.needs_redraw = true;
+        }
+    }
+
+    // counts the values of every channel of the frames as they were uploaded, like
+    // `pixel_at` before filters and shading, srgb encoded or linear and clipped for float
+    // frames, e.g. for an exposure overlay or the range of an auto contrast
+    // `None` before the first frame, frames drawn in tiles count the tiles in view at the
+    // current level, indexed and yuv frames can't be counted
+    #[cfg(feature = "compute")]
+    pub fn read_histogram(&self) -> Result<Option<Histogram>, ReadbackError> {
+        let Some(resources) = self.resources.as_ref() else {
+            return Ok(None);
+        };
+
+        let format = resources.texture.format();
+        if matches!(resources.layout.format, PixelFormat::Indexed8 | PixelFormat::Nv12 | PixelFormat::I420) {
+            return Err(ReadbackError::UnsupportedFormat(format));
+        }
+
+        let regions: Vec<_> = resources
+            .placements
+            .iter()
+            .map(|placement| {
+                let (origin, size) = placement.stored_texels();
+                Region { layer: placement.layer, origin, size }
+            })
+            .collect();
+
+        let (device, queue) = (self.gpu.device(), self.gpu.queue());
+        let labels = self.labels();
+        let pass = self.histogram_pass.get_or_init(|| HistogramPass::new(device, &labels));
+
+        pass.run(device, queue, &labels, &resources.texture, &regions).map(Some)
+    }
+
+    // the value of the frame pixel at a point in frame pixels, e.g. from `surface_to_frame`,
+    // `None` off the frame, frames drawn in tiles give the value of the texel shown
+    // at the current zoom
+    pub fn pixel_at(&self, point: Pair<f32>) -> Result<Option<PixelValue>, ReadbackError> {
+        let Some(resources) = self.resources.as_ref() else {
+            return Ok(None);
+        };
+
+        // repeats of a tiled frame show the same pixels
+        let (origin, size) = resources.bounds();
+        let point = (
+            origin.0 as f32 + (point.0 - origin.0 as f32).rem_euclid(size.0.max(1) as f32),
+            origin.1 as f32 + (point.1 - origin.1 as f32).rem_euclid(size.1.max(1) as f32),
+        );
+
+        let Some((layer, texel)) = resources
+            .placements
+            .iter()
+            .rev()
+            .find_map(|placement| Some((placement.layer, placement.texel_at(point)?)))
+        else {
+            return Ok(None);
+        };
+
+        let (device, queue) = (self.gpu.device(), self.gpu.queue());
+        let bytes = readback::read_texel(device, queue, &resources.texture, texel, layer)?;
+
+        let value = match resources.texture.format() {
+            wgpu::TextureFormat::Bgra8UnormSrgb => PixelValue::Rgba8([bytes[2], bytes[1], bytes[0], bytes[3]]),
+            wgpu::TextureFormat::Rgba16Float => {
+                let channel = |index: usize| convert::f16_to_f32(u16::from_le_bytes([bytes[2 * index], bytes[2 * index + 1]]));
+                PixelValue::Rgba16Float([channel(0), channel(1), channel(2), channel(3)])
+            },
+            wgpu::TextureFormat::R8Unorm if resources.layout.format.is_planar() => {
+                let chroma = readback::read_texel(device, queue, &resources.chroma_texture, (texel.0 / 2, texel.1 / 2), layer)?;
+                let (y, u, v) = (bytes[0], chroma[0], chroma[1]);
+                PixelValue::Yuv { y, u, v, color: convert::yuv_to_rgba(y, u, v) }
+            },
+            wgpu::TextureFormat::R8Unorm => {
+                let color = readback::read_texel(device, queue, &resources.palette_texture, (bytes[0] as u32, layer), 0)?;
+                PixelValue::Indexed { index: bytes[0], color: [color[0], color[1], color[2], color[3]] }
+            },
+            _ => PixelValue::Rgba8([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        };
+
+        Ok(Some(value))
+    }
+
+    pub fn coordinate_readout(&self) -> Option<Pair<f32>> {
+        self.readout.as_ref().map(|readout| readout.point)
+    }
+
+    // a label next to a surface point, e.g. the cursor, with the frame pixel under it and
+    // its value, `None` hides it
+    pub fn set_coordinate_readout(&mut self, point: Option<Pair<f32>>) {
+        if point != self.coordinate_readout() {
+            self.readout = point.map(|point| Readout { point, label: None, stale: true });
+            self.needs_redraw = true;
+        }
+    }
+
+    fn readout_label(&self, point: Pair<f32>) -> Option<String> {
+        let position = self.surface_to_frame(point)?;
+        let value = self.pixel_at(position).unwrap_or_else(|error| {
+            log::warn!("failed to read the pixel under the cursor: {error}");
+            None
+        });
+
+        let coordinates = format!("{}, {}", position.0.floor(), position.1.floor());
+        value.map(|value| format!("{coordinates}  {value}"))
+    }
+
+    pub fn filmstrip(&self) -> Option<FilmstripStyle> {
+        self.filmstrip.as_ref().map(|filmstrip| filmstrip.style)
+    }
+
+    // a row of thumbnails along an edge of the surface, filled by `update_filmstrip`,
+    // `None` hides it, another thumbnail size shrinks the frames again
+    pub fn set_filmstrip(&mut self, style: Option<FilmstripStyle>) {
+        match (self.filmstrip.as_mut(), style) {
+            (Some(filmstrip), Some(style)) if filmstrip.style.thumbnail_size == style.thumbnail_size => filmstrip.style = style,
+            (_, style) => self.filmstrip = style.map(Filmstrip::new),
+        }
+
+        self.needs_redraw = true;
+    }
+
+    // a thumbnail per provider, in order, from the frame each one has now, providers
+    // without one keep their last thumbnail or a placeholder, does nothing while the strip
+    // is hidden
+    pub fn update_filmstrip<Provider, Frame>(&mut self, providers: &mut [Provider])
+    where
+        Provider: FrameProvider<Frame = Frame>,
+        Frame: HasSize<u32> + HasFormat + HasOrientation + HasData,
+    {
+        if let Some(filmstrip) = self.filmstrip.as_mut() {
+            self.needs_redraw |= filmstrip.poll(providers);
+        }
+    }
+
+    pub fn filmstrip_selection(&self) -> Option<usize> {
+        self.filmstrip.as_ref().and_then(|filmstrip| filmstrip.selected)
+    }
+
+    // outlines a thumbnail and scrolls the strip to it when they don't all fit
+    pub fn set_filmstrip_selection(&mut self, selected: Option<usize>) {
+        if let Some(filmstrip) = self.filmstrip.as_mut().filter(|filmstrip| filmstrip.selected != selected) {
+            filmstrip.selected = selected;
+            self.needs_redraw = true;
+        }
+    }
+
+    // the thumbnails in view and where they are on the surface, empty while hidden
+    pub fn filmstrip_cells(&self) -> Vec<FilmstripCell> {
+        self.filmstrip.as_ref().map_or_else(Vec::new, |filmstrip| filmstrip.cells(self.size()))
+    }
+
+    // the index of the thumbnail under a surface pixel, e.g. the cursor on a click
+    pub fn filmstrip_at(&self, point: Pair<f32>) -> Option<usize> {
+        self.filmstrip_cells().into_iter().find(|cell| cell.contains(point)).map(|cell| cell.index)
+    }
+
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    // runs after the filters pushed before it, on every frame drawn from then on
+    // indexed frames are drawn unfiltered
+    pub fn push_filter(&mut self, filter: Filter) {
+        self.filters.push(filter);
+        self.apply_filters();
+    }
+
+    pub fn clear_filters(&mut self) {
+        self.filters.clear();
+        self.apply_filters();
+    }
+
+    fn apply_filters(&mut self) {
+        self.needs_redraw = true;
+
+        if let Some(resources) = self.resources.as_mut() {
+            resources.set_filters(self.gpu.device(), &self.filters);
+        }
+    }
+
+    // the time under a surface pixel on the scrub bar, e.g. where it was clicked
+    pub fn timeline_at(&self, point: Pair<f32>) -> Option<Duration> {
+        scrubber::position_at(self.timeline.as_ref()?, self.size(), point)
+    }
+
+    // size of the area the current frames cover, `None` before the first frame
+    pub fn frame_size(&self) -> Option<Pair<u32>> {
+        self.resources.as_ref().map(|resources| resources.bounds().1)
+    }
+
+    // origin and size of the frame area in frame pixels, tiling and the crop included
+    fn frame_area(&self) -> Option<(Pair<u32>, Pair<u32>)> {
+        let (origin, size) = self.resources.as_ref()?.bounds();
+        Some(self.framing().area(origin, size))
+    }
+
+    // the part of the frame area in view, from the corner of the uncropped area
+    fn visible_region(&self, size: Pair<u32>) -> Option<RegionOfInterest> {
+        let (offset, size) = self.framing().area((0, 0), size);
+        let region = self.transform.visible_region(size, self.size(), self.scaling_mode)?;
+        Some(RegionOfInterest { origin: (region.origin.0 + offset.0 as f32, region.origin.1 + offset.1 as f32), ..region })
+    }
+
+    // the frame pixel position under a surface pixel, e.g. the cursor, `None` before the
+    // first frame
+    pub fn surface_to_frame(&self, point: Pair<f32>) -> Option<Pair<f32>> {
+        let (origin, size) = self.frame_area()?;
+        let position = self.transform.surface_to_frame(point, size, self.size(), self.scaling_mode);
+        Some((position.0 + origin.0 as f32, position.1 + origin.1 as f32))
+    }
+
+    pub fn frame_to_surface(&self, point: Pair<f32>) -> Option<Pair<f32>> {
+        let (origin, size) = self.frame_area()?;
+        Some(self.transform.frame_to_surface((point.0 - origin.0 as f32, point.1 - origin.1 as f32), size, self.size(), self.scaling_mode))
+    }
+
+    // surface pixels per frame pixel
+    pub fn frame_scale(&self) -> Option<f32> {
+        let (left, right) = (self.frame_to_surface((0.0, 0.0))?, self.frame_to_surface((1.0, 0.0))?);
+        Some(right.0 - left.0)
+    }
+
+    fn update_overlay(&mut self) {
+        let to_surface = |point| self.frame_to_surface(point).unwrap_or(point);
+        let font = self.font.as_ref();
+        let rects = match self.spotlight.as_ref() {
+            Some(spotlight) => {
+                let spotlight = BoundingBox::from_corners(to_surface(spotlight.min), to_surface(spotlight.max));
+                overlay::spotlight_rects(spotlight.min, spotlight.max, self.size())
+            },
+            None => Vec::new(),
+        };
+
+        let mut shapes = TextShapes { rects, glyphs: Vec::new() };
+        shapes.rects.extend(overlay::annotation_rects(&self.annotations, self.selected_annotation, to_surface));
+
+        if let Some(timeline) = self.timeline.as_ref() {
+            shapes.rects.extend(scrubber::rects(timeline, &self.markers, self.size()));
+        }
+
+        if let Some(caption) = self.caption.as_deref() {
+            let inset = if self.timeline.is_some() { scrubber::BAR_HEIGHT } else { 0.0 };
+            shapes.append(text::caption_shapes(caption, &self.caption_style, self.size(), inset, font));
+        }
+
+        if let Some(badge) = self.badge.as_deref() {
+            shapes.append(text::badge_shapes(badge, self.size(), font));
+        }
+
+        if let Some(hud) = self.hud.as_deref() {
+            shapes.append(text::hud_shapes(hud, self.size(), font));
+        }
+
+        if let (Some(scale_bar), Some(frame_scale)) = (self.scale_bar.as_ref(), self.frame_scale()) {
+            let inset = if self.timeline.is_some() { scrubber::BAR_HEIGHT } else { 0.0 };
+            shapes.append(scale_bar::shapes(scale_bar, frame_scale, self.size(), inset, font));
+        }
+
+        if let Some(point) = self.readout.as_ref().filter(|readout| readout.stale).map(|readout| readout.point) {
+            let label = self.readout_label(point);
+            self.readout = Some(Readout { point, label, stale: false });
+        }
+
+        if let Some(Readout { point, label: Some(label), .. }) = self.readout.as_ref() {
+            shapes.append(text::label_shapes(label, *point, self.size(), self.font.as_ref()));
+        }
+
+        if let Some(filmstrip) = self.filmstrip.as_ref() {
+            let surface_size = self.size();
+            let labels = self.labels();
+            shapes.rects.extend(filmstrip.rects(surface_size));
+
+            let pass = self.filmstrip_pass.get_or_insert_with(|| FilmstripPass::new(self.gpu.device(), &labels, self.config.format));
+            pass.prepare(self.gpu.device(), self.gpu.queue(), filmstrip, surface_size, encodes_in_shader(self.config.format));
+        }
+
+        let labels = self.labels();
+        let surface_size = (self.config.width, self.config.height);
+
+        if let Some(font) = self.font.as_ref().filter(|_| !shapes.glyphs.is_empty() || self.glyph_pass.is_some()) {
+            let pass = self.glyph_pass.get_or_insert_with(|| GlyphPass::new(self.gpu.device(), &labels, self.config.format));
+            pass.prepare(self.gpu.device(), self.gpu.queue(), font, &shapes.glyphs, surface_size);
+        }
+
+        if shapes.rects.is_empty() && self.overlay.is_none() {
+            return;
+        }
+
+        let overlay = self.overlay.get_or_insert_with(|| Overlay::new(self.gpu.device(), &labels, self.config.format));
+        overlay.upload(self.gpu.device(), self.gpu.queue(), &shapes.rects, surface_size);
+    }
+
+    #[cfg(feature = "compute")]
+    pub(crate) fn device(&self) -> &wgpu::Device {
+        self.gpu.device()
+    }
+
+    #[cfg(feature = "compute")]
+    pub(crate) fn queue(&self) -> &wgpu::Queue {
+        self.gpu.queue()
+    }
+
+    // what this context draws with, to back further contexts with the same device
+    pub fn gpu(&self) -> &GpuContext {
+        &self.gpu
+    }
+
+    fn write_image_uniform(&mut self) {
+        self.needs_redraw = true;
+
+        if let Some(resources) = self.resources.as_ref() {
+            let mut uniform = ImageUniform::of(&resources.layout, self.shading_mode, self.checkerboard);
+            uniform.encode_output = encodes_in_shader(self.config.format) as u32;
+            uniform.alpha = self.alpha.index();
+            uniform.tone_map = (self.hdr != HdrMode::Off && !self.hdr_output() && resources.layout.format == PixelFormat::Rgba16Float) as u32;
+            if let Some(view) = self.skybox {
+                uniform.show_skybox(&view);
+            }
+            if let Some(grid) = self.pixel_grid.as_ref() {
+                uniform.show_pixel_grid(grid);
+            }
+            if self.split_view && resources.placements.len() == 2 {
+                uniform.split = 1;
+                uniform.split_x = self.split_x();
+            }
+            if let Some(placement) = resources.placements.first() {
+                let frame_size = placement.orientation.displayed_size(placement.size);
+
+                if let Some(mask) = self.mask.as_ref() {
+                    uniform.show_mask(mask, &self.mask_style, frame_size);
+                }
+                if let Some(heatmap) = self.heatmap.as_ref() {
+                    uniform.show_heatmap(heatmap, &self.heatmap_style, frame_size);
+                }
+            }
+
+            self.gpu.queue().write_buffer(&resources.image_buffer, 0, bytemuck::bytes_of(&uniform));
+        }
+    }
+
+    // a frame drawn in tiles doesn't repeat, its tiles already go through the sampler's edges
+    fn framing(&self) -> Framing {
+        match self.tile_cache {
+            Some(_) => Framing { mode: self.scaling_mode, tiles: (1, 1), crop: self.crop },
+            None => Framing { mode: self.scaling_mode, tiles: self.tiles, crop: self.crop },
+        }
+    }
+
+    pub fn reset_view(&mut self) {
+        self.transform = ViewTransform::default();
+        self.write_transform();
+    }
+
+    // also publishes the visible region and picks the filter, which depend on the same state
+    fn write_transform(&mut self) {
+        self.needs_redraw = true;
+        let filter = self.filter_mode();
+
+        if let Some(resources) = self.resources.as_ref() {
+            let uniform = self.transform.uniform(self.size());
+            self.gpu.queue().write_buffer(&resources.transform_buffer, 0, bytemuck::bytes_of(&uniform));
+            self.feedback.record_visible_region(self.visible_region(resources.bounds().1));
+        }
+
+        if let Some(resources) = self.resources.as_mut() {
+            resources.set_filter(self.gpu.device(), filter);
+        }
+    }
+
+    fn init_resources<Frame>(&mut self, frames: &[Frame])
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation
+    {
+        // the split tells its frames apart by layer
+        let (layout, slots) = FrameLayout::of(frames, !self.split_view);
+        let placements: Vec<_> = frames.iter().zip(slots).map(|(frame, slot)| Placement::of(frame).in_slot(slot)).collect();
+
+        self.tile_cache = None;
+        self.prepare_resources(layout, placements, None);
+    }
+
+    fn prepare_resources(&mut self, mut layout: FrameLayout, placements: Vec<Placement>, extent: Option<Pair<u32>>) {
+        let surface_size = self.size();
+        let framing = self.framing();
+
+        // gl can only view a texture as an array when it has more than one layer
+        if self.gpu.adapter().get_info().backend == wgpu::Backend::Gl {
+            layout.layers = layout.layers.max(2);
+        }
+
+        match self.resources.as_mut() {
+            Some(resources) if resources.layout != layout => {
+                log::debug!("frame changed from {:?} to {:?}", resources.layout, layout);
+                resources.extent = extent;
+                resources.resize(self.gpu.device(), layout, placements, surface_size, framing);
+                self.write_image_uniform();
+                self.write_transform();
+            },
+            Some(resources) if resources.placements != placements || resources.extent != extent => {
+                resources.extent = extent;
+                resources.place(self.gpu.device(), placements, surface_size, framing);
+                // the mask, heatmap and split depend on the placements too
+                self.write_image_uniform();
+                self.write_transform();
+            },
+            Some(_) => (),
+            None => {
+                let mut resources = WgpuFrameRenderContextResources::new(
+                    self.config.format,
+                    self.alpha,
+                    self.gpu.device(),
+                    &self.labels(),
+                    layout,
+                    placements.clone(),
+                    self.size(),
+                    framing,
+                );
+
+                if extent.is_some() {
+                    resources.extent = extent;
+                    resources.place(self.gpu.device(), placements, surface_size, framing);
+                }
+
+                if self.mask.is_some() {
+                    resources.upload_mask(self.gpu.device(), self.gpu.queue(), self.mask.as_ref());
+                }
+                resources.queue_write_mask_colors(self.gpu.queue(), &self.mask_style);
+                if self.heatmap.is_some() {
+                    resources.upload_heatmap(self.gpu.device(), self.gpu.queue(), self.heatmap.as_ref());
+                }
+                resources.queue_write_colormap(self.gpu.queue(), self.heatmap_style.colormap);
+                if !self.filters.is_empty() {
+                    resources.set_filters(self.gpu.device(), &self.filters);
+                }
+
+                self.resources = Some(resources);
+                self.write_image_uniform();
+                self.write_transform();
+            },
+        }
+    }
+
+    // catches provider mistakes before they reach wgpu, an error in strict mode, otherwise
+    // the frame is skipped and `false`
+    fn validate_frame<Frame>(&self, frame: &Frame) -> Result<bool, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let (width, height) = frame.size();
+        let row_len = frame.format().bytes_per_pixel() * width as usize;
+        let stride = frame.row_stride().unwrap_or(row_len);
+        let expected_len = stride * (height as usize).saturating_sub(1) + row_len;
+
+        let problem = if width == 0 || height == 0 {
+            Some(format!("frame has an empty size {width}x{height}"))
+        } else if let Some(canvas) = frame.canvas_size().filter(|&canvas| !fits_canvas(frame, canvas)) {
+            Some(format!("{width}x{height} update at {:?} is outside its {}x{} canvas", frame.position(), canvas.0, canvas.1))
+        } else if frame.format() == PixelFormat::Indexed8 && frame.palette().is_none() {
+            Some(String::from("indexed frame has no palette"))
+        } else if frame.format().is_planar() && frame.canvas_size().is_some() && (frame.position().0 % 2 != 0 || frame.position().1 % 2 != 0) {
+            Some(format!("{:?} update at {:?} doesn't start on a chroma sample", frame.format(), frame.position()))
+        } else if let Some(index) = (1..frame.format().plane_count()).find(|&index| types::plane(frame, index).is_none()) {
+            Some(format!("frame data is {} bytes, too short for plane {index} of {width}x{height} {:?}", frame.data().len(), frame.format()))
+        } else if stride < row_len {
+            Some(format!("frame row stride {stride} is shorter than a {width} pixel {:?} row", frame.format()))
+        } else if frame.data().len() < expected_len || (frame.row_stride().is_none() && !frame.format().is_planar() && frame.data().len() != expected_len) {
+            Some(format!(
+                "frame data is {} bytes, expected {expected_len} for {width}x{height} {:?} (bytes_per_row {stride})",
+                frame.data().len(),
+                frame.format(),
+            ))
+        } else {
+            None
+        };
+
+        match problem {
+            Some(problem) if self.strict_validation => Err(DrawError::InvalidFrame(problem)),
+            Some(problem) => {
+                log::warn!("skipping frame: {problem}");
+                self.feedback.record_drop();
+                Ok(false)
+            },
+            None => Ok(true),
+        }
+    }
+
+    // a lost or outdated surface, e.g. after a display change or a missed resize, is
+    // configured again with the stored size and asked once more
+    fn acquire(&self, surface: &wgpu::Surface<'static>) -> Result<wgpu::SurfaceTexture, DrawError> {
+        match surface.get_current_texture() {
+            Err(error @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                if self.config.width == 0 || self.config.height == 0 {
+                    return Err(DrawError::SurfaceLost(error));
+                }
+
+                log::debug!("surface {error:?}, reconfiguring");
+                surface.configure(self.gpu.device(), &self.config);
+                Ok(surface.get_current_texture()?)
+            },
+            result => Ok(result?),
+        }
+    }
+
+    // returns how long it waited for a surface texture
+    fn draw<Func>(&self, update_render_pass: Func) -> Result<Duration, DrawError>
+    where
+        Func: FnOnce(&mut wgpu::CommandEncoder, &wgpu::TextureView)
+    {
+        let acquiring = Instant::now();
+        let output = match &self.target {
+            RenderTarget::Surface(surface) => Some(self.acquire(surface)?),
+            RenderTarget::Texture(_) | RenderTarget::Released => None,
+        };
+        let acquire_wait = acquiring.elapsed();
+
+        // draws return before getting here while the surface is released
+        let view = match (&output, &self.target) {
+            (Some(output), _) => &output.texture,
+            (None, RenderTarget::Texture(texture)) => texture,
+            (None, RenderTarget::Surface(_) | RenderTarget::Released) => unreachable!(),
+        }.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .gpu
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&self.labels().get("Render Encoder")),
+            });
+
+        update_render_pass(&mut encoder, &view);
+
+        self.gpu.queue().submit(std::iter::once(encoder.finish()));
+        self.feedback.record_submit();
+
+        let feedback = self.feedback.clone();
+        self.gpu.queue().on_submitted_work_done(move || feedback.record_done());
+
+        if let Some(output) = output {
+            output.present();
+        }
+
+        self.feedback.record_present();
+
+        Ok(acquire_wait)
+    }
+
+    pub(crate) fn offscreen(
+        size: Pair<u32>,
+        format: wgpu::TextureFormat,
+        clear_color: Option<wgpu::Color>,
+        alpha: AlphaBehavior,
+        adapter: &AdapterSelection,
+        strict_validation: bool,
+        gpu: Option<GpuContext>,
+    ) -> Result<Self, AdapterError> {
+        let gpu = match gpu {
+            Some(gpu) => gpu,
+            None => GpuContext::request(adapter, strict_validation)?,
+        };
+        let strict_validation = gpu.strict_validation();
+
+        let config = wgpu::SurfaceConfiguration {
+            width: size.0,
+            height: size.1,
+
+            format,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            present_mode: wgpu::PresentMode::Fifo,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        };
+
+        let texture = create_target_texture(gpu.device(), &ResourceLabels::default(), &config);
+
+        let mut context = Self::with_target(RenderTarget::Texture(texture), gpu, config, clear_color, strict_validation);
+        context.alpha = alpha;
+        Ok(context)
+    }
+
+    fn with_target(
+        target: RenderTarget,
+        gpu: GpuContext,
+        config: wgpu::SurfaceConfiguration,
+        clear_color: Option<wgpu::Color>,
+        strict_validation: bool,
+    ) -> Self {
+        let index_buffer = create_index_buffer(gpu.device(), &ResourceLabels::default());
+
+        Self {
+            gpu,
+            config,
+            present_mode: PresentPreference::default(),
+            wide_gamut: false,
+            hdr: HdrMode::default(),
+            alpha: AlphaBehavior::default(),
+            upload_path: UploadPath::default(),
+            upload_belt: None,
+            gpu_timer: None,
+            stats: RenderStats::default(),
+            filter_preset: FilterPreset::default(),
+            auto_filter: AutoFilterThresholds::default(),
+            target,
+            clear_color: clear_color.unwrap_or_default(),
+
+            index_buffer,
+            index_count: INDICES.len() as u32,
+
+            strict_validation,
+            source_label: None,
+            label_prefix: None,
+            capture_next: false,
+            transform: ViewTransform::default(),
+            scaling_mode: ScalingMode::default(),
+            tiles: (1, 1),
+            crop: None,
+            split_view: false,
+            split_position: 0.5,
+            shading_mode: ShadingMode::default(),
+            checkerboard: false,
+            pixel_grid: None,
+            skybox: None,
+            annotations: Vec::new(),
+            spotlight: None,
+            selected_annotation: None,
+            mask: None,
+            mask_style: MaskStyle::default(),
+            heatmap: None,
+            heatmap_style: HeatmapStyle::default(),
+            timeline: None,
+            markers: Vec::new(),
+            caption: None,
+            caption_style: CaptionStyle::default(),
+            badge: None,
+            hud: None,
+            scale_bar: None,
+            filters: Vec::new(),
+            feedback: FeedbackHandle::default(),
+            scale_factor: 1.0,
+            needs_redraw: true,
+            shown_content: None,
+            uploaded_last_draw: false,
+
+            readout: None,
+            overlay: None,
+            filmstrip: None,
+            filmstrip_pass: None,
+            font: None,
+            glyph_pass: None,
+            #[cfg(feature = "compute")]
+            histogram_pass: OnceCell::new(),
+            resources: None,
+            tile_cache: None,
+        }
+    }
+
+    // what the target shows as tightly packed srgb rgba8 rows, drawn again into a texture
+    // of the same size and format since presented surface textures can't be read back
+    pub fn capture_pixels(&self) -> Result<Vec<u8>, ReadbackError> {
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            ..self.config.clone()
+        };
+
+        let texture = create_target_texture(self.gpu.device(), &self.labels(), &config);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self.gpu.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(&self.labels().get("Capture Encoder")),
+        });
+        self.encode_render_pass(&mut encoder, &view, None);
+        self.gpu.queue().submit(std::iter::once(encoder.finish()));
+
+        readback::to_srgb_rgba8(readback::read_texture(self.gpu.device(), self.gpu.queue(), &texture)?, config.format)
+    }
+
+    // a hash of `capture_pixels`, e.g. for a test to check the view didn't change without
+    // keeping a reference image around, see `readback::hash_pixels` for `dropped_bits`
+    // the same view hashes the same on the same adapter, other adapters and drivers may
+    // round differently
+    pub fn frame_hash(&self, dropped_bits: u32) -> Result<u64, ReadbackError> {
+        Ok(readback::hash_pixels(&self.capture_pixels()?, self.size(), dropped_bits))
+    }
+
+    // the current view as an image, e.g. to save as a screenshot or compare in a test
+    #[cfg(feature = "providers")]
+    pub fn capture_frame(&self) -> Result<image::RgbaImage, ReadbackError> {
+        let (width, height) = self.size();
+        Ok(image::RgbaImage::from_raw(width, height, self.capture_pixels()?).expect("captures are tightly packed rgba8"))
+    }
+
+    // copies an offscreen target back to the cpu as tightly packed rows, `None` for surfaces
+    pub(crate) fn read_target(&self) -> Option<Result<Vec<u8>, ReadbackError>> {
+        match &self.target {
+            RenderTarget::Texture(texture) => Some(readback::read_texture(self.gpu.device(), self.gpu.queue(), texture)),
+            RenderTarget::Surface(_) | RenderTarget::Released => None,
+        }
+    }
+}
+
+fn create_index_buffer(device: &wgpu::Device, labels: &ResourceLabels) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&labels.get("Index Buffer")),
+        usage: wgpu::BufferUsages::INDEX,
+        contents: bytemuck::cast_slice(INDICES),
+    })
+}
+
+fn select_present_mode(surface_caps: &wgpu::SurfaceCapabilities, preference: PresentPreference) -> wgpu::PresentMode {
+    let candidates = preference.candidates();
+    let mode = candidates
+        .iter()
+        .copied()
+        .find(|mode| surface_caps.present_modes.contains(mode))
+        .unwrap_or(surface_caps.present_modes[0]);
+
+    if mode != candidates[0] {
+        log::warn!("present mode {:?} is not supported, falling back to {mode:?}", candidates[0]);
+    }
+
+    mode
+}
+
+// a wide gamut surface takes extended linear srgb, colors past the srgb gamut reach the
+// display where the platform composites float surfaces that way
+fn surface_config(
+    surface_caps: &wgpu::SurfaceCapabilities,
+    size: Pair<u32>,
+    present_mode: PresentPreference,
+    wide_gamut: bool,
+    hdr: HdrMode,
+) -> wgpu::SurfaceConfiguration {
+    let wants_float = wide_gamut || hdr == HdrMode::PassThrough;
+    let float = hdr_surface_format(surface_caps).filter(|_| wants_float);
+    let surface_format = float
+        .or_else(|| surface_caps.formats.iter().copied().find(|f| f.is_srgb()))
+        .unwrap_or(surface_caps.formats[0]);
+
+    if wide_gamut && float.is_none() {
+        log::info!("no wide gamut surface format, colors are clipped to srgb");
+    }
+    if hdr == HdrMode::PassThrough && float.is_none() {
+        log::info!("no hdr surface format, highlights are tone mapped");
+    }
+
+    wgpu::SurfaceConfiguration {
+        width: size.0,
+        height: size.1,
+
+        view_formats: vec![],
+        format: surface_format,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: select_alpha_mode(surface_caps, hdr == HdrMode::PassThrough && float.is_some()),
+        present_mode: select_present_mode(surface_caps, present_mode),
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+    }
+}
+
+// the float format compositors read as extended linear srgb, values above 1 are brighter
+// than sdr white
+fn hdr_surface_format(surface_caps: &wgpu::SurfaceCapabilities) -> Option<wgpu::TextureFormat> {
+    surface_caps.formats.iter().copied().find(|&format| is_extended_range(format))
+}
+
+fn is_extended_range(format: wgpu::TextureFormat) -> bool {
+    matches!(format, wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float)
+}
+
+// hdr output is opaque where it can be, compositors that blend extended range values with
+// the desktop tend to clip them back to sdr first
+fn select_alpha_mode(surface_caps: &wgpu::SurfaceCapabilities, hdr: bool) -> wgpu::CompositeAlphaMode {
+    let preferred = [wgpu::CompositeAlphaMode::Opaque, wgpu::CompositeAlphaMode::PreMultiplied];
+
+    match hdr {
+        true => preferred
+            .into_iter()
+            .find(|mode| surface_caps.alpha_modes.contains(mode))
+            .unwrap_or(surface_caps.alpha_modes[0]),
+        false => surface_caps.alpha_modes[0],
+    }
+}
+
+fn create_target_texture(device: &wgpu::Device, labels: &ResourceLabels, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get("Offscreen Target")),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+    })
+}
+
+#[derive(Debug)]
+struct WgpuFrameRenderContextResources {
+    layout: FrameLayout,
+    placements: Vec<Placement>,
+    // the size of a frame drawn in tiles, the placements then only cover the tiles in view
+    extent: Option<Pair<u32>>,
+    labels: ResourceLabels,
+    texture: wgpu::Texture,
+    palette_texture: wgpu::Texture,
+    chroma_texture: wgpu::Texture,
+    mask_texture: wgpu::Texture,
+    mask_colors_texture: wgpu::Texture,
+    heatmap_texture: wgpu::Texture,
+    colormap_texture: wgpu::Texture,
+    filters: Vec<Filter>,
+    // drawn in place of `texture` while there are filters
+    filter_chain: Option<FilterChain>,
+    bind_group: wgpu::BindGroup,
+    // the texture the previous frame was drawn from and its bind group, while frames
+    // stream in they swap with `texture` before every upload, so the upload doesn't wait
+    // for the draw still sampling the last one
+    back: Option<(wgpu::Texture, wgpu::BindGroup)>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    image_sampler: wgpu::Sampler,
+    filter: wgpu::FilterMode,
+    vertex_buffer: wgpu::Buffer,
+    // the texture layer of each quad in `vertex_buffer`
+    quad_layers: Vec<u32>,
+    transform_buffer: wgpu::Buffer,
+    image_buffer: wgpu::Buffer,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl HasSize<u32> for WgpuFrameRenderContext {
+    fn size(&self) -> Pair<u32> {
+        (self.config.width, self.config.height)
+    }
+}
+
+// built through `RenderContextBuilder`, new options can be added without breaking callers
+#[non_exhaustive]
+pub struct WgpuFrameRenderContextInit {
+    pub surface_size: Pair<u32>,
+    pub clear_color: Option<wgpu::Color>,
+    pub surface_handle: wgpu::SurfaceTarget<'static>,
+    pub present_mode: PresentPreference,
+    pub wide_gamut: bool,
+    pub hdr: HdrMode,
+    pub alpha: AlphaBehavior,
+    pub upload_path: UploadPath,
+    pub filter_preset: FilterPreset,
+    pub auto_filter: AutoFilterThresholds,
+    pub adapter: AdapterSelection,
+    pub strict_validation: bool,
+    pub label_prefix: Option<String>,
+    pub feedback: FeedbackHandle,
+    // shares another context's device, `adapter` and `strict_validation` are then its own
+    pub gpu: Option<GpuContext>,
+}
+
+impl HasSize<u32> for WgpuFrameRenderContextInit {
+    fn size(&self) -> Pair<u32> {
+        self.surface_size
+    }
+}
+
+impl TryFrom<WgpuFrameRenderContextInit> for WgpuFrameRenderContext {
+    type Error = RenderInitError;
+
+    fn try_from(init: WgpuFrameRenderContextInit) -> Result<Self, Self::Error> {
+        executor::block_on(Self::setup(init))
+    }
+}
+
+impl WgpuFrameRenderContext {
+    // the surface is created right away on the calling thread (some platforms require the
+    // window's thread), adapter and device are requested when the future is polled
+    fn setup(WgpuFrameRenderContextInit {
+        clear_color ,
+        surface_size,
+        surface_handle,
+        present_mode,
+        wide_gamut,
+        hdr,
+        alpha,
+        upload_path,
+        filter_preset,
+        auto_filter,
+        adapter,
+        strict_validation,
+        label_prefix,
+        feedback,
+        gpu,
+    }: WgpuFrameRenderContextInit) -> impl Future<Output = Result<Self, RenderInitError>> + Send {
+        let instance = match gpu.as_ref() {
+            Some(gpu) => gpu.shared_instance(),
+            None => Arc::new(gpu::create_instance(strict_validation)),
+        };
+        let surface = instance.create_surface(surface_handle);
+
+        async move {
+            let surface = surface?;
+            let gpu = match gpu {
+                Some(gpu) if gpu.supports(&surface) => gpu,
+                Some(gpu) => return Err(RenderInitError::UnsupportedSurface(gpu.adapter().get_info().name)),
+                None => GpuContext::with_instance(instance, Some(&surface), &adapter, strict_validation).await?,
+            };
+            let strict_validation = gpu.strict_validation();
+
+            let config = surface_config(&surface.get_capabilities(gpu.adapter()), surface_size, present_mode, wide_gamut, hdr);
+            surface.configure(gpu.device(), &config);
+
+            let mut context = Self::with_target(RenderTarget::Surface(surface), gpu, config, clear_color, strict_validation);
+            context.present_mode = present_mode;
+            context.wide_gamut = wide_gamut;
+            context.hdr = hdr;
+            context.alpha = alpha;
+            context.upload_path = upload_path;
+            context.filter_preset = filter_preset;
+            context.set_auto_filter_thresholds(auto_filter);
+            context.feedback = feedback;
+
+            if label_prefix.is_some() {
+                context.set_label_prefix(label_prefix.as_deref());
+            }
+
+            Ok(context)
+        }
+    }
+
+    // the async counterpart of `FrameRenderContext::init`
+    pub fn init_async(init: WgpuFrameRenderContextInit) -> impl Future<Output = Result<Self, RenderInitError>> + Send {
+        let setup = Self::setup(init);
+
+        async move {
+            let mut context = setup.await?;
+            context.configure(context.size());
+            Ok(context)
+        }
+    }
+
+    // runs `init_async` on a worker thread so an event loop can keep pumping events,
+    // poll the returned handle until it is ready
+    pub fn spawn_init(init: WgpuFrameRenderContextInit) -> PendingRenderContext {
+        let future = Self::init_async(init);
+
+        PendingRenderContext {
+            worker: Some(thread::Builder::new()
+                .name(String::from("egami-init"))
+                .spawn(move || executor::block_on(future))
+                .expect("failed to spawn the render context init thread")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PendingRenderContext {
+    worker: Option<thread::JoinHandle<Result<WgpuFrameRenderContext, RenderInitError>>>,
+}
+
+impl PendingRenderContext {
+    // never blocks, panics from the worker are resumed on the polling thread and
+    // polling again after `Ready` panics
+    pub fn poll_ready(&mut self) -> Poll<Result<WgpuFrameRenderContext, RenderInitError>> {
+        match self.worker.take() {
+            Some(worker) if worker.is_finished() => match worker.join() {
+                Ok(result) => Poll::Ready(result),
+                Err(panic) => std::panic::resume_unwind(panic),
+            },
+            Some(worker) => {
+                self.worker = Some(worker);
+                Poll::Pending
+            },
+            None => panic!("render context was already taken"),
+        }
+    }
+}
+
+// what the image texture and quad are built for, any change rebuilds them
+// bounds the staging memory a single upload needs
+const UPLOAD_BAND_BYTES: usize = 16 << 20;
+
+// one of the `convert::*_to_rgba` functions
+type ExpandRows = fn(&[u8], &mut [u8], u8);
+
+const PALETTE_LEN: u32 = 256;
+
+// layout of `Image` in shader.wgsl, how the fragment shader turns texels into colors
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct ImageUniform {
+    paletted: u32,
+    shading: u32,
+    // srgb textures are linearized by the sampler, which normals must not be
+    srgb: u32,
+    checkerboard: u32,
+    light: [f32; 3],
+    _padding_light: f32,
+    // rows of `ChannelMapping::weights`, read as the columns of a mat4x4
+    channels: [[f32; 4]; 4],
+    channel_offset: [f32; 4],
+    sky: u32,
+    _padding_sky: [u32; 3],
+    sky_cells: CubeCellsUniform,
+    sky_forward: [f32; 4],
+    sky_right: [f32; 4],
+    sky_up: [f32; 4],
+    coverage_channel: u32,
+    _padding_coverage: [u32; 3],
+    // threshold or edge, smoothing, contour spacing
+    coverage: [f32; 4],
+    encode_output: u32,
+    mask: u32,
+    mask_opacity: f32,
+    alpha: u32,
+    // mask pixels per frame pixel
+    mask_scale: [f32; 2],
+    heatmap: u32,
+    heatmap_opacity: f32,
+    // values at the ends of the colormap
+    heatmap_range: [f32; 2],
+    // heatmap pixels per frame pixel
+    heatmap_scale: [f32; 2],
+    tone_map: u32,
+    pixel_grid: u32,
+    pixel_grid_min_scale: f32,
+    pixel_grid_width: f32,
+    // srgb
+    pixel_grid_color: [f32; 4],
+    yuv: u32,
+    split: u32,
+    // in surface pixels
+    split_x: f32,
+    _padding_split: u32,
+}
+
+impl ImageUniform {
+    fn of(layout: &FrameLayout, shading_mode: ShadingMode, checkerboard: bool) -> Self {
+        let mut uniform = Self {
+            paletted: (layout.format == PixelFormat::Indexed8) as u32,
+            // yuv is converted to linear light in the shader, like the sampler does for srgb
+            srgb: (image_texture_format(layout.format).is_srgb() || layout.format.is_planar()) as u32,
+            yuv: layout.format.is_planar() as u32,
+            checkerboard: checkerboard as u32,
+            ..Default::default()
+        };
+
+        match shading_mode {
+            ShadingMode::Color => (),
+            ShadingMode::NormalMap { light } => {
+                uniform.shading = 1;
+                uniform.light = normalize(light);
+            },
+            ShadingMode::Channels(mapping) => {
+                uniform.shading = 2;
+                uniform.channels = mapping.weights;
+                uniform.channel_offset = mapping.offset;
+            },
+            ShadingMode::Threshold { channel, threshold } => {
+                uniform.shading = 3;
+                uniform.coverage_channel = channel.index() as u32;
+                uniform.coverage = [threshold, 0.0, 0.0, 0.0];
+            },
+            ShadingMode::DistanceField { channel, edge, smoothing, contour_spacing } => {
+                uniform.shading = 4;
+                uniform.coverage_channel = channel.index() as u32;
+                uniform.coverage = [edge, smoothing.max(0.0), contour_spacing.max(0.0), 0.0];
+            },
+            ShadingMode::HeatMap { channel } => {
+                uniform.shading = 5;
+                uniform.coverage_channel = channel.index() as u32;
+            },
+        }
+
+        uniform
+    }
+}
+
+impl ImageUniform {
+    // `frame_size` as stored, before any turning
+    fn show_mask(&mut self, mask: &SegmentationMask, style: &MaskStyle, frame_size: Pair<u32>) {
+        self.mask = 1;
+        self.mask_opacity = style.opacity.clamp(0.0, 1.0);
+        self.mask_scale = [
+            mask.size().0 as f32 / frame_size.0.max(1) as f32,
+            mask.size().1 as f32 / frame_size.1.max(1) as f32,
+        ];
+    }
+
+    fn show_pixel_grid(&mut self, grid: &PixelGrid) {
+        self.pixel_grid = 1;
+        self.pixel_grid_min_scale = grid.min_scale;
+        self.pixel_grid_width = grid.width.max(0.0);
+        self.pixel_grid_color = grid.color.map(|channel| channel as f32 / 255.0);
+    }
+
+    fn show_heatmap(&mut self, heatmap: &Heatmap, style: &HeatmapStyle, frame_size: Pair<u32>) {
+        // without finite values every pixel is nan and stays untinted anyway
+        let (min, max) = style.range.or(heatmap.range()).unwrap_or((0.0, 1.0));
+
+        self.heatmap = 1;
+        self.heatmap_opacity = style.opacity.clamp(0.0, 1.0);
+        self.heatmap_range = [min, max];
+        self.heatmap_scale = [
+            heatmap.size().0 as f32 / frame_size.0.max(1) as f32,
+            heatmap.size().1 as f32 / frame_size.1.max(1) as f32,
+        ];
+    }
+
+    fn show_skybox(&mut self, view: &SkyboxView) {
+        let (sin_yaw, cos_yaw) = view.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = view.pitch.sin_cos();
+        let scale = (view.fov.clamp(0.01, 3.1) / 2.0).tan();
+
+        self.sky = 1;
+        self.sky_cells = CubeCellsUniform::of(view.layout);
+        self.sky_forward = [sin_yaw * cos_pitch, sin_pitch, cos_yaw * cos_pitch, 0.0];
+        self.sky_right = [cos_yaw * scale, 0.0, -sin_yaw * scale, 0.0];
+        self.sky_up = [-sin_yaw * sin_pitch * scale, cos_pitch * scale, -cos_yaw * sin_pitch * scale, 0.0];
+    }
+}
+
+// layout of `CubeCells` in cube.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct CubeCellsUniform {
+    grid: [u32; 2],
+    _padding: [u32; 2],
+    cells: [[u32; 4]; 6],
+}
+
+impl CubeCellsUniform {
+    pub(crate) fn of(layout: CubeLayout) -> Self {
+        let (columns, rows) = layout.cells();
+
+        Self {
+            grid: [columns, rows],
+            cells: CubeFace::ALL.map(|face| {
+                let (column, row) = layout.cell(face);
+                [column, row, layout.is_flipped(face) as u32, 0]
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+// a zero vector lights straight on
+fn normalize(vector: [f32; 3]) -> [f32; 3] {
+    let len = vector.iter().map(|component| component * component).sum::<f32>().sqrt();
+
+    match len > f32::EPSILON {
+        true => vector.map(|component| component / len),
+        false => [0.0, 0.0, 1.0],
+    }
+}
+
+// texture array layers as large as the largest frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameLayout {
+    size: Pair<u32>,
+    format: PixelFormat,
+    layers: u32,
+}
+
+impl FrameLayout {
+    // frames are non-empty and share a texture format, each gets its layer and where in it
+    // its pixels start, with `share` smaller frames are packed into layers together,
+    // indexed frames never are, their palette goes by layer
+    fn of<Frame: HasSize<u32> + HasPosition<u32> + HasFormat>(frames: &[Frame], share: bool) -> (Self, Vec<(u32, Pair<u32>)>) {
+        let sizes: Vec<_> = frames.iter().map(|frame| frame.canvas_size().unwrap_or(frame.size())).collect();
+        let size = sizes.iter().fold((1, 1), |size, frame_size| (size.0.max(frame_size.0), size.1.max(frame_size.1)));
+        let format = frames[0].format();
+
+        let slots: Vec<_> = match share && format != PixelFormat::Indexed8 {
+            true => pack(&sizes, size),
+            false => (0..sizes.len() as u32).map(|layer| (layer, (0, 0))).collect(),
+        };
+        let layers = slots.iter().map(|(layer, _)| layer + 1).max().unwrap_or(1);
+
+        (Self { size, format, layers }, slots)
+    }
+}
+
+// between frames sharing a layer, so filtering at the edge of one doesn't pick up the
+// next, and even so chroma at half the size lines up
+const PACKING_GAP: u32 = 2;
+
+// shelves of frames in the order they come, so the first one is at the corner of the
+// first layer, every size fits `layer_size`
+fn pack(sizes: &[Pair<u32>], layer_size: Pair<u32>) -> Vec<(u32, Pair<u32>)> {
+    let mut layer = 0;
+    let mut cursor = (0, 0);
+    let mut shelf_height = 0;
+
+    sizes
+        .iter()
+        .map(|&size| {
+            if cursor.0 > 0 && cursor.0 + size.0 > layer_size.0 {
+                cursor = (0, cursor.1 + shelf_height);
+                shelf_height = 0;
+            }
+
+            if cursor.1 > 0 && cursor.1 + size.1 > layer_size.1 {
+                layer += 1;
+                cursor = (0, 0);
+                shelf_height = 0;
+            }
+
+            let slot = (layer, cursor);
+            cursor.0 += (size.0 + PACKING_GAP).next_multiple_of(2);
+            shelf_height = shelf_height.max((size.1 + PACKING_GAP).next_multiple_of(2));
+            slot
+        })
+        .collect()
+}
+
+// where a frame's quad sits, only the vertices depend on it
+// `size` is as shown, sideways frames are stored with width and height swapped
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    position: Pair<u32>,
+    size: Pair<u32>,
+    row_order: RowOrder,
+    orientation: Orientation,
+    layer: u32,
+    // where its pixels are in its layer, a tile's without its border, `None` for a frame at
+    // the corner of its layer
+    texels: Option<(Pair<u32>, Pair<u32>)>,
+}
+
+impl Placement {
+    // a dirty rectangle shows its whole canvas, its position is where it is copied to
+    fn of<Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation>(frame: &Frame) -> Self {
+        let (position, size) = match frame.canvas_size() {
+            Some(canvas) => ((0, 0), canvas),
+            None => (frame.position(), frame.size()),
+        };
+        let orientation = frame.orientation();
+
+        Self { position, size: orientation.displayed_size(size), row_order: frame.row_order(), orientation, layer: 0, texels: None }
+    }
+
+    // at `origin` in `layer`, e.g. packed into a layer with other frames
+    fn in_slot(mut self, (layer, origin): (u32, Pair<u32>)) -> Self {
+        self.layer = layer;
+        if origin != (0, 0) {
+            self.texels = Some((origin, self.orientation.displayed_size(self.size)));
+        }
+        self
+    }
+
+    // where its pixels are in its layer, origin and size
+    fn stored_texels(&self) -> (Pair<u32>, Pair<u32>) {
+        match self.texels {
+            Some(texels) => texels,
+            None => ((0, 0), self.orientation.displayed_size(self.size)),
+        }
+    }
+
+    // the texel of its layer that shows a point in frame pixels, `None` off its quad
+    fn texel_at(&self, point: Pair<f32>) -> Option<Pair<u32>> {
+        let local = (point.0 - self.position.0 as f32, point.1 - self.position.1 as f32);
+        if local.0 < 0.0 || local.1 < 0.0 || local.0 >= self.size.0 as f32 || local.1 >= self.size.1 as f32 {
+            return None;
+        }
+
+        let corner = (local.0 / self.size.0 as f32, local.1 / self.size.1 as f32);
+        let (origin, texels) = self.stored_texels();
+        let (s, t) = self.orientation.stored_point(corner);
+
+        let x = ((s * texels.0 as f32) as u32).min(texels.0 - 1);
+        let y = ((t * texels.1 as f32) as u32).min(texels.1 - 1);
+        let y = match self.row_order {
+            RowOrder::TopDown => y,
+            RowOrder::BottomUp => texels.1 - 1 - y,
+        };
+
+        Some((origin.0 + x, origin.1 + y))
+    }
+
+    // no quad for a layer that holds no tile in view
+    fn tile(key: Option<TileKey>, frame_size: Pair<u32>, layer: u32) -> Self {
+        let (position, size, texels) = match key {
+            Some(key) => {
+                let (position, size) = key.area(frame_size);
+                (position, size, Some(((tiling::BORDER, tiling::BORDER), key.texels(frame_size))))
+            },
+            None => ((0, 0), (0, 0), None),
+        };
+
+        Self { position, size, row_order: RowOrder::TopDown, orientation: Orientation::Normal, layer, texels }
+    }
+}
+
+fn fits_canvas<Frame: HasSize<u32> + HasPosition<u32>>(frame: &Frame, canvas: Pair<u32>) -> bool {
+    let (position, size) = (frame.position(), frame.size());
+    position.0 as u64 + size.0 as u64 <= canvas.0 as u64 && position.1 as u64 + size.1 as u64 <= canvas.1 as u64
+}
+
+// where in its layer a frame's pixels go when its slot starts at `slot`, bottom-up rows
+// are stored as they come so a dirty rectangle lands mirrored
+fn copy_origin<Frame: HasSize<u32> + HasPosition<u32> + HasFormat>(frame: &Frame, slot: Pair<u32>) -> Pair<u32> {
+    let (x, y) = match (frame.canvas_size(), frame.row_order()) {
+        (None, _) => (0, 0),
+        (Some(_), RowOrder::TopDown) => frame.position(),
+        (Some(canvas), RowOrder::BottomUp) => (frame.position().0, canvas.1 - frame.position().1 - frame.size().1),
+    };
+
+    (slot.0 + x, slot.1 + y)
+}
+
+// how the box around all placements is fit to the surface, how often each frame repeats
+// inside it and which part of it is shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Framing {
+    mode: ScalingMode,
+    tiles: Pair<u32>,
+    crop: Option<Rect<u32>>,
+}
+
+impl Framing {
+    fn tiled(&self, size: Pair<u32>) -> Pair<u32> {
+        (size.0.saturating_mul(self.tiles.0), size.1.saturating_mul(self.tiles.1))
+    }
+
+    // origin and size of what is fit to the surface, the crop kept inside the tiled box at
+    // `origin` and at least a pixel large
+    fn area(&self, origin: Pair<u32>, size: Pair<u32>) -> (Pair<u32>, Pair<u32>) {
+        let tiled = self.tiled(size);
+        let Some(crop) = self.crop else {
+            return (origin, tiled);
+        };
+
+        let clamp = |position: u32, len: u32, total: u32| {
+            let start = position.min(total.saturating_sub(1));
+            (start, len.min(total - start).max(1))
+        };
+        let (x, width) = clamp(crop.position.0, crop.size.0, tiled.0);
+        let (y, height) = clamp(crop.position.1, crop.size.1, tiled.1);
+
+        ((origin.0 + x, origin.1 + y), (width, height))
+    }
+}
+
+// origin and size of the box around all placements, frames reaching past `u32::MAX` are
+// cut off there
+fn bounds(placements: &[Placement]) -> (Pair<u32>, Pair<u32>) {
+    let origin = placements.iter().fold((u32::MAX, u32::MAX), |origin, placement| {
+        (origin.0.min(placement.position.0), origin.1.min(placement.position.1))
+    });
+    let end = placements.iter().fold((0, 0), |end, placement| {
+        (
+            end.0.max(placement.position.0.saturating_add(placement.size.0)),
+            end.1.max(placement.position.1.saturating_add(placement.size.1)),
+        )
+    });
+
+    (origin, (end.0.saturating_sub(origin.0), end.1.saturating_sub(origin.1)))
+}
+
+// the bounding box is scaled like a single frame, every frame gets a quad at its offset
+// inside it for each time it repeats, sampling only the part of its layer it was uploaded
+// to, quads are cut to the crop and left out when none of them is in it
+// returns the vertices with the layer of each quad
+fn get_vertices(
+    device: &wgpu::Device,
+    labels: &ResourceLabels,
+    layout: &FrameLayout,
+    placements: &[Placement],
+    extent: Option<Pair<u32>>,
+    surface_size: Pair<u32>,
+    framing: Framing,
+) -> (wgpu::Buffer, Vec<u32>) {
+    let (origin, size) = match extent {
+        Some(extent) => ((0, 0), extent),
+        None => bounds(placements),
+    };
+    let (area_origin, area_size) = framing.area(origin, size);
+    let corners = Vertex::get_vertices(area_size, surface_size, framing.mode);
+    let (across, down) = framing.tiles;
+    let (top_left, bottom_right) = (corners[0].position, corners[3].position);
+
+    // the shown area in pixels of the tiled box
+    let start = ((area_origin.0 - origin.0) as f32, (area_origin.1 - origin.1) as f32);
+    let end = (start.0 + area_size.0 as f32, start.1 + area_size.1 as f32);
+
+    let to_clip = |point: Pair<f32>| [
+        top_left[0] + (bottom_right[0] - top_left[0]) * (point.0 - start.0) / area_size.0 as f32,
+        top_left[1] + (bottom_right[1] - top_left[1]) * (point.1 - start.1) / area_size.1 as f32,
+    ];
+    let cut = |point: Pair<f32>| (point.0.clamp(start.0, end.0), point.1.clamp(start.1, end.1));
+
+    let mut vertices = Vec::new();
+    let mut layers = Vec::new();
+
+    for placement in placements {
+        let (texel_origin, texels) = placement.stored_texels();
+        let quad_size = (placement.size.0 as f32, placement.size.1 as f32);
+
+        // sideways and mirrored frames are uploaded as stored and turned when sampling,
+        // as are bottom-up rows
+        let texture_coords = |corner: Pair<f32>| {
+            let (s, t) = placement.orientation.stored_point(corner);
+            let t = match placement.row_order {
+                RowOrder::TopDown => t,
+                RowOrder::BottomUp => 1.0 - t,
+            };
+            [
+                (texel_origin.0 as f32 + s * texels.0 as f32) / layout.size.0 as f32,
+                (texel_origin.1 as f32 + t * texels.1 as f32) / layout.size.1 as f32,
+            ]
+        };
+
+        for repeat in 0..across * down {
+            // the quad in pixels of the tiled box, cut to the shown area
+            let quad_start = (
+                (placement.position.0 - origin.0) as f32 * across as f32 + (repeat % across) as f32 * quad_size.0,
+                (placement.position.1 - origin.1) as f32 * down as f32 + (repeat / across) as f32 * quad_size.1,
+            );
+            let (cut_start, cut_end) = (cut(quad_start), cut((quad_start.0 + quad_size.0, quad_start.1 + quad_size.1)));
+
+            if cut_start.0 >= cut_end.0 || cut_start.1 >= cut_end.1 {
+                continue;
+            }
+
+            // where a cut corner is on the whole quad
+            let corner = |point: Pair<f32>| ((point.0 - quad_start.0) / quad_size.0, (point.1 - quad_start.1) / quad_size.1);
+
+            vertices.extend(
+                [(cut_start.0, cut_start.1), (cut_end.0, cut_start.1), (cut_start.0, cut_end.1), (cut_end.0, cut_end.1)]
+                    .map(|point| Vertex { position: to_clip(point), texture_coords: texture_coords(corner(point)) }),
+            );
+            layers.push(placement.layer);
+        }
+    }
+
+    // buffers can't be empty
+    if vertices.is_empty() {
+        vertices.resize(4, Vertex { position: [0.0; 2], texture_coords: [0.0; 2] });
+    }
+
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&labels.get("Vertex Buffer")),
+        usage: wgpu::BufferUsages::VERTEX,
+        contents: bytemuck::cast_slice(&vertices),
+    });
+
+    (buffer, layers)
+}
+
+// formats without a matching texture format are expanded to rgba8 on upload, bgra8 is
+// sampled as rgba by the hardware
+// shaders write linear light, only srgb and float targets store it as intended, anything
+// else gets srgb encoded values from the shader
+fn encodes_in_shader(format: wgpu::TextureFormat) -> bool {
+    !format.is_srgb() && !is_extended_range(format)
+}
+
+fn image_texture_format(pixel_format: PixelFormat) -> wgpu::TextureFormat {
+    match pixel_format {
+        PixelFormat::Rgba8 | PixelFormat::Rgb8 | PixelFormat::Bgr8 | PixelFormat::Gray8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+        PixelFormat::Bgra8 => wgpu::TextureFormat::Bgra8UnormSrgb,
+        PixelFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        // the luma of planar frames, their chroma has a texture of its own
+        PixelFormat::Indexed8 | PixelFormat::Nv12 | PixelFormat::I420 => wgpu::TextureFormat::R8Unorm,
+    }
+}
+
+fn create_image_texture(device: &wgpu::Device, labels: &ResourceLabels, layout: &FrameLayout) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get("Image Texture")),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: layout.size.0,
+            height: layout.size.1,
+            depth_or_array_layers: layout.layers,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: image_texture_format(layout.format),
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+    })
+}
+
+// one row of 256 colors per layer, a placeholder texel when frames aren't indexed
+fn create_palette_texture(device: &wgpu::Device, labels: &ResourceLabels, layout: &FrameLayout) -> wgpu::Texture {
+    let size = match layout.format {
+        PixelFormat::Indexed8 => (PALETTE_LEN, layout.layers),
+        _ => (1, 1),
+    };
+
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get("Palette Texture")),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth_or_array_layers: 1,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+    })
+}
+
+// u and v of yuv frames side by side in two channels at the chroma planes' size, a
+// placeholder texel for other frames
+fn create_chroma_texture(device: &wgpu::Device, labels: &ResourceLabels, layout: &FrameLayout) -> wgpu::Texture {
+    let (size, layers) = match layout.format.is_planar() {
+        true => (layout.format.plane_size(1, layout.size), layout.layers),
+        false => ((1, 1), 1),
+    };
+
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get("Chroma Texture")),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth_or_array_layers: layers,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rg8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+    })
+}
+
+// a class per texel, a placeholder texel without a mask
+fn create_mask_texture(device: &wgpu::Device, labels: &ResourceLabels, size: Pair<u32>) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get("Mask Texture")),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            depth_or_array_layers: 1,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    })
+}
+
+// float values are interpolated in the shader, not every device can filter them
+fn create_heatmap_texture(device: &wgpu::Device, labels: &ResourceLabels, size: Pair<u32>) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get("Heatmap Texture")),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: size.0.max(1),
+            height: size.1.max(1),
+            depth_or_array_layers: 1,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    })
+}
+
+// one color per class
+fn create_mask_colors_texture(device: &wgpu::Device, labels: &ResourceLabels, name: &str) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&labels.get(name)),
+        sample_count: 1,
+        view_formats: &[],
+        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: PALETTE_LEN,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    })
+}
+
+// the same filter both ways, frames have no mipmaps to pick between
+fn create_image_sampler(device: &wgpu::Device, labels: &ResourceLabels, filter: wgpu::FilterMode) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(&labels.get("Image Sampler")),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        address_mode_w: wgpu::AddressMode::Repeat,
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_image_bind_group(
+    device: &wgpu::Device,
+    labels: &ResourceLabels,
+    layout: &wgpu::BindGroupLayout,
+    texture: &wgpu::Texture,
+    palette_texture: &wgpu::Texture,
+    mask_texture: &wgpu::Texture,
+    mask_colors_texture: &wgpu::Texture,
+    heatmap_texture: &wgpu::Texture,
+    colormap_texture: &wgpu::Texture,
+    chroma_texture: &wgpu::Texture,
+    sampler: &wgpu::Sampler,
+    transform_buffer: &wgpu::Buffer,
+    image_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    // a single layer would default to a plain 2d view
+    let array_view = |texture: &wgpu::Texture| texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+    let texture_view = array_view(texture);
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(&labels.get("Image Bind Group")),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: transform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: image_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&palette_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(&mask_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::TextureView(&mask_colors_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: wgpu::BindingResource::TextureView(&heatmap_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 8,
+                resource: wgpu::BindingResource::TextureView(&colormap_texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 9,
+                resource: wgpu::BindingResource::TextureView(&array_view(chroma_texture)),
+            },
+        ],
+    })
+}
+
+impl WgpuFrameRenderContextResources {
+    fn add_to_report(&self, report: &mut MemoryReport, tiled: bool) {
+        let frames = memory::texture_bytes(&self.texture) + self.back.as_ref().map_or(0, |(texture, _)| memory::texture_bytes(texture));
+
+        match tiled {
+            true => report.tiles += frames,
+            false => report.source_textures += frames,
+        }
+
+        report.source_textures += [&self.palette_texture, &self.chroma_texture, &self.mask_texture, &self.mask_colors_texture, &self.heatmap_texture, &self.colormap_texture]
+            .into_iter()
+            .map(memory::texture_bytes)
+            .sum::<u64>();
+        report.filters += self.filter_chain.as_ref().map_or(0, FilterChain::allocated_bytes);
+        report.other += self.vertex_buffer.size() + self.transform_buffer.size() + self.image_buffer.size();
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        format: wgpu::TextureFormat,
+        alpha: AlphaBehavior,
+        device: &wgpu::Device,
+        labels: &ResourceLabels,
+        layout: FrameLayout,
+        placements: Vec<Placement>,
+        surface_size: Pair<u32>,
+        framing: Framing,
+    ) -> Self {
+        let (vertex_buffer, quad_layers) = get_vertices(device, labels, &layout, &placements, None, surface_size, framing);
+
+        let transform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&labels.get("Transform Buffer")),
+            size: std::mem::size_of::<TransformUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let image_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&labels.get("Image Buffer")),
+            size: std::mem::size_of::<ImageUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let filter = wgpu::FilterMode::Linear;
+        let image_sampler = create_image_sampler(device, labels, filter);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&labels.get("Texture Bind Group Layout")),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&labels.get("Render Pipeline Layout")),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges:&[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&labels.get("Shader")),
+            source: wgpu::ShaderSource::Wgsl(concat!(include_str!("cube.wgsl"), include_str!("shader.wgsl")).into()),
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&labels.get("Render Pipeline")),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[vertex::Vertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(alpha.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let texture = create_image_texture(device, labels, &layout);
+        let palette_texture = create_palette_texture(device, labels, &layout);
+        let chroma_texture = create_chroma_texture(device, labels, &layout);
+        let mask_texture = create_mask_texture(device, labels, (1, 1));
+        let mask_colors_texture = create_mask_colors_texture(device, labels, "Mask Colors Texture");
+        let heatmap_texture = create_heatmap_texture(device, labels, (1, 1));
+        let colormap_texture = create_mask_colors_texture(device, labels, "Colormap Texture");
+        let bind_group = create_image_bind_group(
+            device,
+            labels,
+            &bind_group_layout,
+            &texture,
+            &palette_texture,
+            &mask_texture,
+            &mask_colors_texture,
+            &heatmap_texture,
+            &colormap_texture,
+            &chroma_texture,
+            &image_sampler,
+            &transform_buffer,
+            &image_buffer,
+        );
+
+        Self {
+            labels: labels.clone(),
+            texture,
+            palette_texture,
+            chroma_texture,
+            mask_texture,
+            mask_colors_texture,
+            heatmap_texture,
+            colormap_texture,
+            filters: Vec::new(),
+            filter_chain: None,
+            bind_group,
+            back: None,
+            bind_group_layout,
+            image_sampler,
+            filter,
+            layout,
+            placements,
+            extent: None,
+            vertex_buffer,
+            quad_layers,
+            transform_buffer,
+            image_buffer,
+            render_pipeline,
+        }
+    }
+
+    // the pipeline, sampler and transform survive, only what depends on the frame layout is rebuilt
+    fn resize(&mut self, device: &wgpu::Device, layout: FrameLayout, placements: Vec<Placement>, surface_size: Pair<u32>, framing: Framing) {
+        self.texture = create_image_texture(device, &self.labels, &layout);
+        self.palette_texture = create_palette_texture(device, &self.labels, &layout);
+        self.chroma_texture = create_chroma_texture(device, &self.labels, &layout);
+        self.back = None;
+        self.layout = layout;
+        self.rebuild_filter_chain(device);
+        self.rebuild_bind_group(device);
+        self.place(device, placements, surface_size, framing);
+    }
+
+    fn set_filter(&mut self, device: &wgpu::Device, filter: wgpu::FilterMode) {
+        if filter != self.filter {
+            self.image_sampler = create_image_sampler(device, &self.labels, filter);
+            self.filter = filter;
+            self.rebuild_bind_group(device);
+        }
+    }
+
+    fn set_filters(&mut self, device: &wgpu::Device, filters: &[Filter]) {
+        self.filters = filters.to_vec();
+        self.rebuild_filter_chain(device);
+        self.rebuild_bind_group(device);
+    }
+
+    // the palette lookup needs the indices and yuv frames need their chroma, those frames
+    // aren't filtered
+    fn rebuild_filter_chain(&mut self, device: &wgpu::Device) {
+        self.filter_chain = match self.layout.format {
+            PixelFormat::Indexed8 | PixelFormat::Nv12 | PixelFormat::I420 => None,
+            _ => FilterChain::new(device, &self.labels, &self.texture, &self.filters),
+        };
+
+        // the chain reads `texture` wherever it is, so filtered frames keep one
+        if self.filter_chain.is_some() {
+            self.back = None;
+        }
+    }
+
+    fn rebuild_bind_group(&mut self, device: &wgpu::Device) {
+        self.bind_group = self.create_bind_group(device, self.filter_chain.as_ref().map_or(&self.texture, FilterChain::output));
+
+        if let Some((texture, _)) = self.back.take() {
+            let bind_group = self.create_bind_group(device, &texture);
+            self.back = Some((texture, bind_group));
+        }
+    }
+
+    // makes the texture the previous frame was drawn from the one the next upload goes
+    // into, the back texture is created on the first swap
+    fn swap_textures(&mut self, device: &wgpu::Device) {
+        if self.filter_chain.is_some() {
+            return;
+        }
+
+        let (texture, bind_group) = self.back.take().unwrap_or_else(|| {
+            let texture = create_image_texture(device, &self.labels, &self.layout);
+            let bind_group = self.create_bind_group(device, &texture);
+            (texture, bind_group)
+        });
+
+        let texture = std::mem::replace(&mut self.texture, texture);
+        let bind_group = std::mem::replace(&mut self.bind_group, bind_group);
+        self.back = Some((texture, bind_group));
+    }
+
+    fn create_bind_group(&self, device: &wgpu::Device, texture: &wgpu::Texture) -> wgpu::BindGroup {
+        create_image_bind_group(
+            device,
+            &self.labels,
+            &self.bind_group_layout,
+            texture,
+            &self.palette_texture,
+            &self.mask_texture,
+            &self.mask_colors_texture,
+            &self.heatmap_texture,
+            &self.colormap_texture,
+            &self.chroma_texture,
+            &self.image_sampler,
+            &self.transform_buffer,
+            &self.image_buffer,
+        )
+    }
+
+    // the mask texture takes the mask's size, without a mask it shrinks back to a placeholder
+    fn upload_mask(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mask: Option<&SegmentationMask>) {
+        let size = mask.map_or((1, 1), SegmentationMask::size);
+
+        if (self.mask_texture.width(), self.mask_texture.height()) != (size.0.max(1), size.1.max(1)) {
+            self.mask_texture = create_mask_texture(device, &self.labels, size);
+            self.rebuild_bind_group(device);
+        }
+
+        if let Some(mask) = mask.filter(|mask| mask.size().0 > 0 && mask.size().1 > 0) {
+            queue.write_texture(
+                self.mask_texture.as_image_copy(),
+                mask.classes(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.0),
+                    rows_per_image: Some(size.1),
+                },
+                wgpu::Extent3d { width: size.0, height: size.1, depth_or_array_layers: 1 },
+            );
+        }
+    }
+
+    fn queue_write_mask_colors(&self, queue: &wgpu::Queue, style: &MaskStyle) {
+        let colors: Vec<[u8; 4]> = (0..PALETTE_LEN).map(|class| style.color(class as u8)).collect();
+
+        queue.write_texture(
+            self.mask_colors_texture.as_image_copy(),
+            bytemuck::cast_slice(&colors),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * PALETTE_LEN),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d { width: PALETTE_LEN, height: 1, depth_or_array_layers: 1 },
+        );
+    }
+
+    // like `upload_mask`
+    fn upload_heatmap(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, heatmap: Option<&Heatmap>) {
+        let size = heatmap.map_or((1, 1), Heatmap::size);
+
+        if (self.heatmap_texture.width(), self.heatmap_texture.height()) != size {
+            self.heatmap_texture = create_heatmap_texture(device, &self.labels, size);
+            self.rebuild_bind_group(device);
+        }
+
+        if let Some(heatmap) = heatmap {
+            queue.write_texture(
+                self.heatmap_texture.as_image_copy(),
+                bytemuck::cast_slice(heatmap.values()),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * size.0),
+                    rows_per_image: Some(size.1),
+                },
+                wgpu::Extent3d { width: size.0, height: size.1, depth_or_array_layers: 1 },
+            );
+        }
+    }
+
+    fn queue_write_colormap(&self, queue: &wgpu::Queue, colormap: Colormap) {
+        let colors: Vec<[u8; 4]> = (0..PALETTE_LEN)
+            .map(|index| {
+                let [r, g, b] = colormap.rgb(index as f32 / (PALETTE_LEN - 1) as f32);
+                [r, g, b, 255]
+            })
+            .collect();
+
+        queue.write_texture(
+            self.colormap_texture.as_image_copy(),
+            bytemuck::cast_slice(&colors),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * PALETTE_LEN),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d { width: PALETTE_LEN, height: 1, depth_or_array_layers: 1 },
+        );
+    }
+
+    fn place(&mut self, device: &wgpu::Device, placements: Vec<Placement>, surface_size: Pair<u32>, framing: Framing) {
+        (self.vertex_buffer, self.quad_layers) = get_vertices(device, &self.labels, &self.layout, &placements, self.extent, surface_size, framing);
+        self.placements = placements;
+    }
+
+    // origin and size of what is framed, the whole frame when it is drawn in tiles
+    fn bounds(&self) -> (Pair<u32>, Pair<u32>) {
+        match self.extent {
+            Some(extent) => ((0, 0), extent),
+            None => bounds(&self.placements),
+        }
+    }
+
+    // missing entries stay transparent black
+    fn queue_write_palette(&self, queue: &wgpu::Queue, palette: &[[u8; 4]], layer: u32) {
+        let mut row = [[0u8; 4]; PALETTE_LEN as usize];
+        let len = palette.len().min(row.len());
+        row[..len].copy_from_slice(&palette[..len]);
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                origin: wgpu::Origin3d { x: 0, y: layer, z: 0 },
+                ..self.palette_texture.as_image_copy()
+            },
+            bytemuck::cast_slice(&row),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * PALETTE_LEN),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d { width: PALETTE_LEN, height: 1, depth_or_array_layers: 1 },
+        );
+    }
+
+    // the u and v planes of a yuv frame into the two channels of the chroma texture, nv12
+    // already has them side by side, i420's are interleaved first
+    // chroma is a quarter of the frame, it always goes through the queue
+    fn queue_write_chroma<Frame>(&self, queue: &wgpu::Queue, frame: &Frame, (layer, slot): (u32, Pair<u32>))
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let Some(first) = types::plane(frame, 1) else {
+            return;
+        };
+
+        let (width, height) = first.size;
+        let interleaved: Vec<u8>;
+
+        let (data, bytes_per_row) = match types::plane(frame, 2) {
+            None => (first.data, first.row_stride),
+            Some(second) => {
+                interleaved = (0..height)
+                    .flat_map(|row| first.row(row)[..width as usize].iter().zip(&second.row(row)[..width as usize]))
+                    .flat_map(|(&u, &v)| [u, v])
+                    .collect();
+                (interleaved.as_slice(), 2 * width as usize)
+            },
+        };
+
+        let origin = copy_origin(frame, slot);
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                origin: wgpu::Origin3d { x: origin.0 / 2, y: origin.1 / 2, z: layer },
+                ..self.chroma_texture.as_image_copy()
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row as u32),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+    }
+
+    // uploads in bands of rows so huge (e.g. memory mapped) frames never need a second
+    // full size copy, formats without a texture equivalent are expanded one band at a time
+    fn queue_write_texture<Frame>(&self, queue: &wgpu::Queue, frame: &Frame, (layer, slot): (u32, Pair<u32>))
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let (width, height) = frame.size();
+        let row_len = frame.format().bytes_per_pixel() * width as usize;
+        let stride = frame.row_stride().unwrap_or(row_len);
+        let texture_row_len = self.texture.format().block_copy_size(None).unwrap_or(4) as usize * width as usize;
+        let band_rows = (UPLOAD_BAND_BYTES / texture_row_len.max(stride)).clamp(1, height as usize);
+        let origin = copy_origin(frame, slot);
+
+        let expand = expand_rows(frame.format());
+        let mut expanded = Vec::new();
+
+        for first_row in (0..height as usize).step_by(band_rows) {
+            let rows = band_rows.min(height as usize - first_row);
+            let band = &frame.data()[first_row * stride..(first_row + rows - 1) * stride + row_len];
+
+            let (data, bytes_per_row) = match expand {
+                Some(expand) => {
+                    expanded.resize(rows * texture_row_len, 0);
+
+                    for (source, target) in band.chunks(stride).zip(expanded.chunks_exact_mut(texture_row_len)) {
+                        expand(&source[..row_len], target, u8::MAX);
+                    }
+
+                    (expanded.as_slice(), texture_row_len)
+                },
+                None => (band, stride),
+            };
+
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    origin: wgpu::Origin3d { x: origin.0, y: origin.1 + first_row as u32, z: layer },
+                    ..self.texture.as_image_copy()
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row as u32),
+                    rows_per_image: Some(rows as u32),
+                },
+                wgpu::Extent3d { width, height: rows as u32, depth_or_array_layers: 1 },
+            );
+        }
+    }
+
+    // the staging belt counterpart of `queue_write_texture`, rows are expanded or copied
+    // into mapped memory once and the gpu copies them into the texture
+    fn encode_texture_upload<Frame>(&self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, belt: &mut UploadBelt, frame: &Frame, (layer, slot): (u32, Pair<u32>))
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let (width, height) = frame.size();
+        let row_len = frame.format().bytes_per_pixel() * width as usize;
+        let stride = frame.row_stride().unwrap_or(row_len);
+        let texture_row_len = self.texture.format().block_copy_size(None).unwrap_or(4) as usize * width as usize;
+        let padded_row_len = texture_row_len.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+        let band_rows = (UPLOAD_BAND_BYTES / padded_row_len).clamp(1, height as usize);
+        let origin = copy_origin(frame, slot);
+        let expand = expand_rows(frame.format());
+
+        for first_row in (0..height as usize).step_by(band_rows) {
+            let rows = band_rows.min(height as usize - first_row);
+
+            belt.copy_to_texture(
+                device,
+                encoder,
+                wgpu::ImageCopyTexture {
+                    origin: wgpu::Origin3d { x: origin.0, y: origin.1 + first_row as u32, z: layer },
+                    ..self.texture.as_image_copy()
+                },
+                wgpu::Extent3d { width, height: rows as u32, depth_or_array_layers: 1 },
+                padded_row_len as u32,
+                |staging| {
+                    for (row, target) in staging.chunks_exact_mut(padded_row_len).enumerate() {
+                        let source = &frame.data()[(first_row + row) * stride..][..row_len];
+
+                        match expand {
+                            Some(expand) => expand(source, &mut target[..texture_row_len], u8::MAX),
+                            None => target[..row_len].copy_from_slice(source),
+                        }
+                    }
+                },
+            );
+        }
+    }
+}
+
+fn expand_rows(format: PixelFormat) -> Option<ExpandRows> {
+    match format {
+        PixelFormat::Rgb8 => Some(convert::rgb_to_rgba),
+        PixelFormat::Bgr8 => Some(convert::bgr_to_rgba),
+        PixelFormat::Gray8 => Some(convert::gray_to_rgba),
+        PixelFormat::Rgba8 | PixelFormat::Bgra8 | PixelFormat::Rgba16Float | PixelFormat::Indexed8 | PixelFormat::Nv12 | PixelFormat::I420 => None,
+    }
+}
+
+impl FrameRenderContext for WgpuFrameRenderContext {
+    type RenderError = DrawError;
+    type Init = WgpuFrameRenderContextInit;
+    type Stats = RenderStats;
+
+    fn configure(&mut self, size: Pair<u32>) {
+        self.config.width = size.0;
+        self.config.height = size.1;
+        let labels = self.labels();
+        match &mut self.target {
+            RenderTarget::Surface(surface) => surface.configure(self.gpu.device(), &self.config),
+            RenderTarget::Texture(texture) => *texture = create_target_texture(self.gpu.device(), &labels, &self.config),
+            RenderTarget::Released => (),
+        }
+
+        diagnostics::record_surface_config(&self.config);
+
+        let framing = self.framing();
+        if let Some(resources) = self.resources.as_mut() {
+            (resources.vertex_buffer, resources.quad_layers) =
+                get_vertices(self.gpu.device(), &resources.labels, &resources.layout, &resources.placements, resources.extent, size, framing);
+        }
+
+        // the split line is kept in surface pixels
+        if self.split_view {
+            self.write_image_uniform();
+        }
+
+        self.write_transform();
+    }
+
+    // the stats are zero when nothing was drawn because the surface is released
+    fn draw_frame<Frame>(&mut self, mut frame_provider: impl FrameProvider<Frame = Frame>) -> Result<Self::Stats, Self::RenderError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        // the provider keeps its frame for after the surface is back
+        if self.is_surface_released() {
+            return Ok(RenderStats::default());
+        }
+
+        self.set_split_view(false);
+        let frames = self.batch(frame_provider.poll_frames().into_frame().unwrap_or_default())?;
+
+        if let [frame] = frames.as_slice() {
+            if self.exceeds_texture(frame) {
+                return self.draw_tiles(frame, true).map(|_| self.stats);
             }
-        })
+        }
+
+        let frames = self.fresh_frames(&frames);
+        self.render_frames(frames).map(|_| self.stats)
+    }
+}
+
+impl WgpuFrameRenderContext {
+    // draws every frame of a finite batch as its own quad at its `HasPosition` offset, e.g.
+    // the cells of a gallery grid, the batch is framed like a single image would be, the
+    // same as `draw_frame(types::batch(frames))`, an empty batch redraws the previous one
+    pub fn draw_frames<Frame>(&mut self, frames: impl IntoIterator<Item = Frame>) -> Result<RenderStats, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        self.draw_frame(types::batch(frames))
+    }
+
+    // the frames of a draw that can go into one texture, frames whose format doesn't
+    // share a texture with the first one are dropped, as are frames too large for a
+    // texture unless they are drawn alone, in tiles
+    fn batch<Frame>(&mut self, frames: Vec<Frame>) -> Result<Vec<Frame>, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let max_layers = self.gpu.device().limits().max_texture_array_layers as usize;
+        let alone = frames.len() == 1;
+        let mut batch: Vec<Frame> = Vec::new();
+
+        for frame in frames {
+            if !self.validate_frame(&frame)? {
+                continue;
+            }
+
+            let texture_format = image_texture_format(frame.format());
+
+            if batch.first().is_some_and(|first| image_texture_format(first.format()) != texture_format) {
+                log::warn!("skipping {:?} frame, the batch started with {:?}", frame.format(), batch[0].format());
+                self.feedback.record_drop();
+            } else if !alone && self.exceeds_texture(&frame) {
+                log::warn!("skipping {}x{} frame, only a frame drawn alone can exceed a texture", frame.size().0, frame.size().1);
+                self.feedback.record_drop();
+            } else if batch.len() == max_layers {
+                log::warn!("skipping frame, the device holds at most {max_layers} frames per batch");
+                self.feedback.record_drop();
+            } else {
+                batch.push(frame);
+            }
+        }
+
+        Ok(batch)
+    }
+
+    // takes the next frame like `draw_frame` but only draws when it is new or
+    // `needs_redraw` is set, returns whether it drew, to poll providers without a gpu
+    // pass per poll
+    pub fn draw_frame_if_needed<Frame>(&mut self, mut frame_provider: impl FrameProvider<Frame = Frame>) -> Result<bool, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        if self.is_surface_released() {
+            return Ok(false);
+        }
+
+        self.set_split_view(false);
+        let frames = self.batch(frame_provider.poll_frames().into_frame().unwrap_or_default())?;
+
+        if let [frame] = frames.as_slice() {
+            if self.exceeds_texture(frame) {
+                return self.draw_tiles(frame, false);
+            }
+        }
+
+        let frames = self.fresh_frames(&frames);
+
+        match self.needs_redraw {
+            true => self.render_frames(frames).map(|_| true),
+            false => Ok(false),
+        }
+    }
+
+    // draws the frames of `first` and `second` over each other, `first` left of the split
+    // line and `second` right of it, e.g. an original and its processed version, see
+    // `set_split_position`
+    // the pair is replaced only when both have a frame and the frames share a texture, until
+    // then the previous pair is drawn again
+    pub fn draw_split<Frame>(&mut self, first: impl FrameProvider<Frame = Frame>, second: impl FrameProvider<Frame = Frame>) -> Result<RenderStats, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        if self.is_surface_released() {
+            return Ok(RenderStats::default());
+        }
+
+        let pair = self.split_pair(first, second)?;
+        let pair = self.fresh_frames(&pair);
+        self.render_frames(pair).map(|_| self.stats)
+    }
+
+    // `draw_split` like `draw_frame_if_needed`
+    pub fn draw_split_if_needed<Frame>(&mut self, first: impl FrameProvider<Frame = Frame>, second: impl FrameProvider<Frame = Frame>) -> Result<bool, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        if self.is_surface_released() {
+            return Ok(false);
+        }
+
+        let pair = self.split_pair(first, second)?;
+        let pair = self.fresh_frames(&pair);
+
+        match self.needs_redraw {
+            true => self.render_frames(pair).map(|_| true),
+            false => Ok(false),
+        }
+    }
+
+    // both frames as layers of one texture, or none
+    fn split_pair<Frame>(&mut self, mut first: impl FrameProvider<Frame = Frame>, mut second: impl FrameProvider<Frame = Frame>) -> Result<Vec<Frame>, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        self.set_split_view(true);
+
+        let first = first.poll_frame().into_frame().map(|frame| self.validate_frame(&frame).map(|valid| valid.then_some(frame))).transpose()?.flatten();
+        let second = second.poll_frame().into_frame().map(|frame| self.validate_frame(&frame).map(|valid| valid.then_some(frame))).transpose()?.flatten();
+        let (Some(first), Some(second)) = (first, second) else {
+            return Ok(Vec::new());
+        };
+
+        if image_texture_format(first.format()) != image_texture_format(second.format()) {
+            log::warn!("can't split between a {:?} and a {:?} frame", first.format(), second.format());
+            self.feedback.record_drop();
+            return Ok(Vec::new());
+        }
+
+        if self.exceeds_texture(&first) || self.exceeds_texture(&second) {
+            log::warn!("can't split between frames larger than a texture");
+            self.feedback.record_drop();
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![first, second])
+    }
+
+    // drops frames whose pixels are on screen already, see `HasData::content_id`
+    fn fresh_frames<'a, Frame>(&mut self, frames: &'a [Frame]) -> &'a [Frame]
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        if frames.is_empty() {
+            return frames;
+        }
+
+        let content: Option<Vec<_>> = frames
+            .iter()
+            .map(|frame| frame.content_id().map(|id| (id, frame.format(), Placement::of(frame))))
+            .collect();
+
+        if content.is_some() && content == self.shown_content && self.resources.is_some() {
+            return &frames[..0];
+        }
+
+        self.shown_content = content;
+        self.needs_redraw = true;
+        frames
+    }
+
+    // the frames as they were last uploaded and the overlay
+    // `timer` times the pass, see `GpuTimer::poll`
+    fn encode_render_pass(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, timer: Option<&GpuTimer>) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&self.labels().get("Render Pass")),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            timestamp_writes: timer.map(GpuTimer::timestamp_writes),
+            occlusion_query_set: None,
+            depth_stencil_attachment: None,
+        });
+
+        if let Some(resources) = self.resources.as_ref() {
+            render_pass.set_pipeline(&resources.render_pipeline);
+            render_pass.set_bind_group(0, &resources.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+            // the instance index picks the texture layer
+            for (quad, &layer) in resources.quad_layers.iter().enumerate() {
+                render_pass.draw_indexed(0..self.index_count, 4 * quad as i32, layer..layer + 1);
+            }
+
+            if let Some(overlay) = self.overlay.as_ref() {
+                overlay.draw(&mut render_pass);
+            }
+        }
+
+        // over the overlay, which draws the band behind the thumbnails
+        if let Some(pass) = self.filmstrip_pass.as_ref().filter(|_| self.filmstrip.is_some()) {
+            pass.draw(&mut render_pass);
+        }
+
+        drop(render_pass);
+
+        if let Some(pass) = self.glyph_pass.as_ref() {
+            pass.encode(encoder, view);
+        }
+
+        if let Some(timer) = timer {
+            timer.resolve(encoder);
+        }
+    }
+
+    fn render_frames<Frame>(&mut self, frames: &[Frame]) -> Result<(), DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        if !frames.is_empty() {
+            self.init_resources(frames);
+        }
+
+        // yuv and indexed frames would need pairs of chroma and palette textures too, dirty
+        // rectangles need the rest of the canvas in the texture they go into
+        let streaming = self.uploaded_last_draw
+            && !frames.is_empty()
+            && frames.iter().all(|frame| frame.canvas_size().is_none() && !frame.format().is_planar() && frame.format() != PixelFormat::Indexed8);
+        self.uploaded_last_draw = !frames.is_empty();
+
+        if let Some(resources) = self.resources.as_mut().filter(|_| streaming) {
+            resources.swap_textures(self.gpu.device());
+        }
+
+        let slots = self.resources.as_ref().map_or(&[][..], |resources| &resources.placements[..]);
+        let uploads: Vec<_> = frames.iter().zip(slots).map(|(frame, placement)| ((placement.layer, placement.stored_texels().0), frame)).collect();
+        let result = self.present(&uploads);
+
+        // the frames were taken from the provider but never reached the screen
+        if result.is_err() {
+            for _ in frames {
+                self.feedback.record_drop();
+            }
+        }
+
+        result
+    }
+
+    // uploads each frame into its slot, a layer and where in it the frame starts, and draws
+    // everything uploaded so far
+    fn present<Frame>(&mut self, uploads: &[((u32, Pair<u32>), &Frame)]) -> Result<(), DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let started = Instant::now();
+
+        // the uploads and overlay writes belong to the frame, so the capture starts first
+        let capture = std::mem::take(&mut self.capture_next);
+        if capture {
+            log::info!("capturing a frame for the attached capture tool");
+            self.gpu.device().start_capture();
+        }
+
+        self.update_overlay();
+
+        if self.upload_path == UploadPath::StagingBelt && self.upload_belt.is_none() {
+            self.upload_belt = Some(UploadBelt::new(UPLOAD_BAND_BYTES as u64, self.labels().get("Upload Belt Chunk")));
+        }
+
+        if self.gpu_timer.is_none() {
+            self.gpu_timer = GpuTimer::new(self.gpu.device(), self.gpu.queue(), &self.labels());
+        }
+
+        let timed = self.gpu_timer.as_mut().is_some_and(|timer| timer.poll(self.gpu.device()));
+        let timer = self.gpu_timer.as_ref().filter(|_| timed);
+
+        let mut belt = self.upload_belt.take();
+        let resources = self.resources.as_ref();
+
+        let result = self.draw(|encoder, view| {
+            // copies can't be recorded inside the render pass
+            if let (Some(resources), Some(belt)) = (resources, belt.as_mut()) {
+                for &(slot, frame) in uploads {
+                    resources.encode_texture_upload(self.gpu.device(), encoder, belt, frame, slot);
+                }
+                belt.finish();
+            }
+
+            // without new frames the texture still holds the last upload
+            if let Some(resources) = resources {
+                for &(slot, frame) in uploads {
+                    if belt.is_none() {
+                        resources.queue_write_texture(self.gpu.queue(), frame, slot);
+                    }
+
+                    if let Some(palette) = frame.palette() {
+                        resources.queue_write_palette(self.gpu.queue(), palette, slot.0);
+                    }
+
+                    if frame.format().is_planar() {
+                        resources.queue_write_chroma(self.gpu.queue(), frame, slot);
+                    }
+                }
+            }
+
+            // the filters read the uploads, they run again only for new frames or filters
+            if let Some(chain) = resources.and_then(|resources| resources.filter_chain.as_ref()) {
+                if chain.stale || !uploads.is_empty() {
+                    chain.encode(encoder);
+                }
+            }
+
+            self.encode_render_pass(encoder, view, timer);
+        });
+
+        if let Some(mut belt) = belt {
+            belt.recall();
+            self.upload_belt = Some(belt);
+        }
+
+        if capture {
+            self.gpu.device().stop_capture();
+        }
+
+        if let Ok(acquire_wait) = result {
+            if let Some(timer) = self.gpu_timer.as_mut().filter(|_| timed) {
+                timer.read_back();
+            }
+
+            let present_latency = started.elapsed();
+            self.stats = RenderStats {
+                cpu_time: present_latency.saturating_sub(acquire_wait),
+                gpu_time: self.gpu_timer.as_ref().and_then(GpuTimer::last),
+                upload_bytes: uploads.iter().map(|(_, frame)| frame.data().len() as u64).sum(),
+                present_latency,
+            };
+
+            self.needs_redraw = false;
+
+            if let Some(chain) = self.resources.as_mut().and_then(|resources| resources.filter_chain.as_mut()) {
+                chain.stale = false;
+            }
+
+            // the label was read before the new frames were uploaded, it catches up on the
+            // next draw
+            if let Some(readout) = self.readout.as_mut().filter(|_| !uploads.is_empty()) {
+                readout.stale = true;
+                self.needs_redraw = true;
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    // wider or taller than the device's largest texture, dirty rectangles always fit theirs
+    fn exceeds_texture<Frame>(&self, frame: &Frame) -> bool
+    where
+        Frame: HasSize<u32> + HasPosition<u32>
+    {
+        let max = self.gpu.device().limits().max_texture_dimension_2d;
+        frame.canvas_size().is_none() && (frame.size().0 > max || frame.size().1 > max)
+    }
+
+    // draws a frame too large for a texture as a grid of tiles, only the tiles in view
+    // are cut and uploaded, at a level that has about a texel per surface pixel, tiles
+    // that stay in view keep their layer
+    // frames are drawn as stored, orientation aside, masks and heatmaps don't line up
+    // with them, returns whether it drew like `draw_frame_if_needed`
+    fn draw_tiles<Frame>(&mut self, frame: &Frame, always: bool) -> Result<bool, DrawError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let frame_size = frame.size();
+
+        if !self.tile_cache.as_ref().is_some_and(|cache| cache.holds(frame)) {
+            self.tile_cache.get_or_insert_with(TileCache::default).reset(frame);
+            self.needs_redraw = true;
+        }
+
+        // the next regular frame is uploaded whatever its content id, into the texture
+        // the tiles are in
+        self.shown_content = None;
+        self.uploaded_last_draw = false;
+
+        let region = self.visible_region(frame_size);
+        let mut visible = region.map_or_else(Vec::new, |region| tiling::visible(&region, frame_size, tiling::level_for(region.scale)));
+
+        let max_layers = self.gpu.device().limits().max_texture_array_layers as usize;
+        if visible.len() > max_layers {
+            log::warn!("{} tiles in view, the device holds {max_layers}", visible.len());
+            visible.truncate(max_layers);
+        }
+
+        let mut layout = FrameLayout {
+            size: (tiling::TILE_TEXELS, tiling::TILE_TEXELS),
+            format: frame.format(),
+            layers: (visible.len() as u32).max(1),
+        };
+
+        // layers are only ever added while the tiles stay, so panning doesn't recreate the texture
+        let reused = self.resources.as_ref().filter(|resources| resources.layout.size == layout.size && resources.layout.format == layout.format);
+        layout.layers = layout.layers.max(reused.map_or(0, |resources| resources.layout.layers));
+
+        let cache = self.tile_cache.get_or_insert_with(TileCache::default);
+
+        // a new texture holds none of the tiles
+        if reused.is_none_or(|resources| resources.layout.layers != layout.layers) {
+            cache.layers.clear();
+        }
+        cache.layers.retain(|key, _| visible.contains(key));
+
+        let missing: Vec<TileKey> = visible.iter().copied().filter(|key| !cache.layers.contains_key(key)).collect();
+        if missing.is_empty() && !self.needs_redraw && !always {
+            return Ok(false);
+        }
+
+        let mut free = (0..layout.layers).filter(|layer| !cache.layers.values().any(|used| used == layer));
+        let uploads: Vec<(u32, TileFrame)> = missing
+            .iter()
+            .zip(free.by_ref())
+            .map(|(&key, layer)| (layer, TileFrame::cut(frame, key)))
+            .collect();
+
+        for (&key, &(layer, _)) in missing.iter().zip(&uploads) {
+            cache.layers.insert(key, layer);
+        }
+
+        let mut placements: Vec<_> = (0..layout.layers).map(|layer| Placement::tile(None, frame_size, layer)).collect();
+        for (&key, &layer) in &cache.layers {
+            placements[layer as usize] = Placement::tile(Some(key), frame_size, layer);
+        }
+
+        self.prepare_resources(layout, placements, Some(frame_size));
+
+        // tiles start at the corner of their layer, their border included
+        let uploads: Vec<_> = uploads.iter().map(|(layer, tile)| ((*layer, (0, 0)), tile)).collect();
+        let result = self.present(&uploads);
+
+        if result.is_err() {
+            self.feedback.record_drop();
+            // what the failed draw would have uploaded is cut again next time
+            self.tile_cache = None;
+        }
+
+        result.map(|_| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "compute")]
+    use crate::shading::Channel;
+    use crate::types::FrameRef;
+
+    fn placement(position: Pair<u32>, size: Pair<u32>) -> Placement {
+        Placement { position, size, row_order: RowOrder::TopDown, orientation: Orientation::Normal, layer: 0, texels: None }
+    }
+
+    // needs an adapter, passes without one like on a ci machine without a gpu
+    #[cfg(feature = "compute")]
+    #[test]
+    fn histograms_follow_an_adapter_switch() {
+        let offscreen = || WgpuFrameRenderContext::offscreen((4, 4), wgpu::TextureFormat::Rgba8Unorm, None, AlphaBehavior::default(), &AdapterSelection::Default, true, None);
+        let Ok(mut context) = offscreen() else {
+            return;
+        };
+
+        let data = [0x80; 4 * 4 * 4];
+        let count = |histogram: Histogram| histogram.channel(Channel::Red)[0x80];
+
+        context.draw_frames([FrameRef::new((4, 4), PixelFormat::Rgba8, &data)]).unwrap();
+        assert_eq!(context.read_histogram().unwrap().map(count), Some(16));
+
+        context.switch_adapter(AdapterSelection::Default).unwrap();
+        context.draw_frames([FrameRef::new((4, 4), PixelFormat::Rgba8, &data)]).unwrap();
+        assert_eq!(context.read_histogram().unwrap().map(count), Some(16));
+    }
+
+    #[test]
+    fn packs_small_frames_together() {
+        let slots = pack(&[(32, 32), (8, 8), (8, 8), (16, 8), (32, 30)], (32, 32));
+        assert_eq!(slots, [(0, (0, 0)), (1, (0, 0)), (1, (10, 0)), (1, (0, 10)), (2, (0, 0))]);
+
+        // odd sizes keep even origins for the chroma of yuv frames
+        assert_eq!(pack(&[(3, 3), (3, 3), (3, 3)], (9, 9)), [(0, (0, 0)), (0, (6, 0)), (0, (0, 6))]);
+    }
+
+    #[test]
+    fn indexed_and_split_frames_get_layers_of_their_own() {
+        let data = [0; 16 * 16 * 4];
+        let frames = |format: PixelFormat, sizes: &[Pair<u32>]| sizes.iter().map(|&size| FrameRef::new(size, format, &data)).collect::<Vec<_>>();
+
+        let (layout, slots) = FrameLayout::of(&frames(PixelFormat::Rgba8, &[(16, 16), (4, 4)]), true);
+        assert_eq!((layout.size, layout.layers), ((16, 16), 2));
+        assert_eq!(slots, [(0, (0, 0)), (1, (0, 0))]);
+
+        let three = [(16, 16), (4, 4), (4, 4)];
+        assert_eq!(FrameLayout::of(&frames(PixelFormat::Rgba8, &three), true).0.layers, 2);
+        assert_eq!(FrameLayout::of(&frames(PixelFormat::Rgba8, &three), false).0.layers, 3);
+        assert_eq!(FrameLayout::of(&frames(PixelFormat::Indexed8, &three), true).0.layers, 3);
+    }
+
+    #[test]
+    fn packed_frames_sample_their_slot() {
+        let packed = placement((0, 0), (4, 2)).in_slot((1, (6, 8)));
+        assert_eq!((packed.layer, packed.stored_texels()), (1, ((6, 8), (4, 2))));
+        assert_eq!(packed.texel_at((3.5, 0.5)), Some((9, 8)));
+
+        let turned = Placement { orientation: Orientation::Rotate90, ..placement((0, 0), (2, 4)) }.in_slot((0, (10, 0)));
+        assert_eq!(turned.stored_texels(), ((10, 0), (4, 2)));
+    }
+
+    #[test]
+    fn bounds_saturate() {
+        assert_eq!(bounds(&[placement((2, 3), (4, 5)), placement((10, 1), (1, 1))]), ((2, 1), (9, 7)));
+        assert_eq!(bounds(&[placement((u32::MAX - 1, 0), (10, 10))]), ((u32::MAX - 1, 0), (1, 10)));
+        assert_eq!(bounds(&[]), ((u32::MAX, u32::MAX), (0, 0)));
     }
 }
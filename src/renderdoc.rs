@@ -0,0 +1,155 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use renderdoc_sys::{eRENDERDOC_API_Version_1_4_1, pRENDERDOC_GetAPI, RENDERDOC_API_1_4_1};
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const LIBRARY: &str = "librenderdoc.so";
+
+#[cfg(windows)]
+const LIBRARY: &str = "renderdoc.dll";
+
+#[derive(Debug)]
+pub enum RenderDocError {
+    // renderdoc isn't in the process, it has to launch or inject the app
+    NotLoaded(libloading::Error),
+    // the renderdoc in the process is older than 1.4.1
+    UnsupportedVersion,
+    // renderdoc has no in-application api on this platform
+    UnsupportedPlatform,
+}
+
+impl fmt::Display for RenderDocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderDocError::NotLoaded(error) => write!(f, "renderdoc isn't attached: {error}"),
+            RenderDocError::UnsupportedVersion => write!(f, "renderdoc doesn't offer api version 1.4.1"),
+            RenderDocError::UnsupportedPlatform => write!(f, "renderdoc isn't available on this platform"),
+        }
+    }
+}
+
+impl std::error::Error for RenderDocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderDocError::NotLoaded(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// the in-application api of a renderdoc that launched or was injected into the app, it is
+// never loaded by egami itself
+// captures cover everything drawn for a frame, including the filters and overlays, so
+// they are the way to look at a custom filter's shader on the gpu
+pub struct RenderDoc {
+    api: *const RENDERDOC_API_1_4_1,
+    // keeps the api table mapped, the library stays loaded by renderdoc anyway
+    _library: libloading::Library,
+}
+
+// renderdoc's api may be called from any thread
+unsafe impl Send for RenderDoc {}
+unsafe impl Sync for RenderDoc {}
+
+impl fmt::Debug for RenderDoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderDoc").field("captures", &self.capture_count()).finish()
+    }
+}
+
+impl RenderDoc {
+    pub fn connect() -> Result<Self, RenderDocError> {
+        let library = open_loaded()?;
+
+        let mut api: *mut c_void = ptr::null_mut();
+        let found = unsafe {
+            let get_api = library.get::<pRENDERDOC_GetAPI>(b"RENDERDOC_GetAPI\0").map_err(RenderDocError::NotLoaded)?;
+            (*get_api).is_some_and(|get_api| get_api(eRENDERDOC_API_Version_1_4_1, &mut api) == 1)
+        };
+
+        if !found || api.is_null() {
+            return Err(RenderDocError::UnsupportedVersion);
+        }
+
+        log::info!("connected to renderdoc");
+        Ok(Self { api: api as *const RENDERDOC_API_1_4_1, _library: library })
+    }
+
+    fn api(&self) -> &RENDERDOC_API_1_4_1 {
+        // the table is static data of the library kept loaded by `_library`
+        unsafe { &*self.api }
+    }
+
+    // captures the next frame presented to a window, frames drawn offscreen have no
+    // present, see `WgpuFrameRenderContext::trigger_capture` for those
+    pub fn trigger_capture(&self) {
+        if let Some(trigger) = self.api().TriggerCapture {
+            unsafe { trigger() };
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.api().IsFrameCapturing.is_some_and(|capturing| unsafe { capturing() } == 1)
+    }
+
+    // captures taken since the app started
+    pub fn capture_count(&self) -> u32 {
+        self.api().GetNumCaptures.map_or(0, |count| unsafe { count() })
+    }
+
+    // where the capture at `index` was written, `None` past the last one
+    pub fn capture_path(&self, index: u32) -> Option<PathBuf> {
+        let get = self.api().GetCapture?;
+        let mut length = 0;
+
+        if unsafe { get(index, ptr::null_mut(), &mut length, ptr::null_mut()) } != 1 {
+            return None;
+        }
+
+        let mut path = vec![0u8; length as usize];
+        if unsafe { get(index, path.as_mut_ptr() as *mut c_char, &mut length, ptr::null_mut()) } != 1 {
+            return None;
+        }
+
+        let path = CStr::from_bytes_until_nul(&path).ok()?;
+        Some(PathBuf::from(path.to_string_lossy().into_owned()))
+    }
+
+    // captures are written to `template` followed by the frame number and `.rdc`, e.g.
+    // "captures/egami" gives "captures/egami_frame123.rdc"
+    pub fn set_capture_path_template<P: AsRef<Path>>(&self, template: P) {
+        let Ok(template) = CString::new(template.as_ref().to_string_lossy().into_owned()) else {
+            return;
+        };
+
+        if let Some(set) = unsafe { self.api().__bindgen_anon_2.SetCaptureFilePathTemplate } {
+            unsafe { set(template.as_ptr()) };
+        }
+    }
+
+    // opens the replay ui connected to this process, false when it didn't start
+    pub fn launch_replay_ui(&self) -> bool {
+        self.api().LaunchReplayUI.is_some_and(|launch| unsafe { launch(1, ptr::null()) } != 0)
+    }
+}
+
+// the library only if something already loaded it, so connecting never injects renderdoc
+#[cfg(all(unix, not(target_os = "macos")))]
+fn open_loaded() -> Result<libloading::Library, RenderDocError> {
+    use libloading::os::unix::Library;
+
+    unsafe { Library::open(Some(LIBRARY), libc::RTLD_NOW | libc::RTLD_NOLOAD) }.map(Into::into).map_err(RenderDocError::NotLoaded)
+}
+
+#[cfg(windows)]
+fn open_loaded() -> Result<libloading::Library, RenderDocError> {
+    libloading::os::windows::Library::open_already_loaded(LIBRARY).map(Into::into).map_err(RenderDocError::NotLoaded)
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+fn open_loaded() -> Result<libloading::Library, RenderDocError> {
+    Err(RenderDocError::UnsupportedPlatform)
+}
@@ -0,0 +1,343 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use winit::keyboard::KeyCode;
+
+use crate::providers::{self, DecodeError, DecodeLimits, FileImageProvider, ImageFrame};
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasOrientation, HasSize, Pair};
+use crate::viewer::KeyResponse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Review {
+    pub verdict: Option<Verdict>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewEvent {
+    Accepted,
+    Rejected,
+    Cleared,
+    Tagged(String),
+    Untagged(String),
+}
+
+// where reviews are persisted, e.g. a csv next to the dataset or a labeling database
+pub trait ReviewSink {
+    // called after every change with the review as it stands afterwards
+    fn record(&mut self, path: &Path, event: &ReviewEvent, review: &Review);
+
+    // an earlier review of `path` to continue from, asked once when it is first shown
+    fn load(&mut self, _path: &Path) -> Option<Review> {
+        None
+    }
+}
+
+impl<F> ReviewSink for F
+where
+    F: FnMut(&Path, &ReviewEvent, &Review),
+{
+    fn record(&mut self, path: &Path, event: &ReviewEvent, review: &Review) {
+        self(path, event, review)
+    }
+}
+
+// steps through a list of images for triage, each is decoded when it is shown
+// meant to run in a `Viewer` with `handle_key` as its key handler: the arrows move between
+// images, a accepts and r rejects and then moves on, delete clears the verdict and 1 to 9
+// toggle the matching tag, the window title shows the current review, the caption its
+// verdict and tags and the background turns green or red with the verdict
+pub struct DatasetReview<Sink> {
+    paths: Vec<PathBuf>,
+    // `None` until the image is first shown
+    reviews: Vec<Option<Review>>,
+    tags: Vec<String>,
+    sink: Sink,
+    limits: DecodeLimits,
+
+    index: usize,
+    image: Option<Result<FileImageProvider, DecodeError>>,
+}
+
+impl<Sink: ReviewSink> DatasetReview<Sink> {
+    // only the first nine tags get a key
+    pub fn new(paths: Vec<PathBuf>, tags: Vec<String>, sink: Sink) -> Self {
+        let mut review = Self {
+            reviews: vec![None; paths.len()],
+            paths,
+            tags,
+            sink,
+            limits: DecodeLimits::default(),
+
+            index: 0,
+            image: None,
+        };
+
+        review.show(0);
+        review
+    }
+
+    // every image below `dir` in path order
+    pub fn from_directory<P: AsRef<Path>>(dir: P, tags: Vec<String>, sink: Sink) -> io::Result<Self> {
+        Ok(Self::new(providers::image_paths(dir.as_ref())?, tags, sink))
+    }
+
+    pub fn with_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self.image = None;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    pub fn current_path(&self) -> Option<&Path> {
+        self.paths.get(self.index).map(PathBuf::as_path)
+    }
+
+    pub fn review(&self) -> Option<&Review> {
+        self.reviews.get(self.index).and_then(Option::as_ref)
+    }
+
+    // returns false when `index` is past the end
+    pub fn show(&mut self, index: usize) -> bool {
+        let Some(path) = self.paths.get(index) else {
+            return false;
+        };
+
+        if self.reviews[index].is_none() {
+            self.reviews[index] = Some(self.sink.load(path).unwrap_or_default());
+        }
+
+        if index != self.index || self.image.is_none() {
+            self.index = index;
+            self.image = None;
+        }
+
+        true
+    }
+
+    pub fn forward(&mut self) -> bool {
+        self.show(self.index + 1)
+    }
+
+    pub fn back(&mut self) -> bool {
+        self.index.checked_sub(1).is_some_and(|index| self.show(index))
+    }
+
+    // moves on to the next image
+    pub fn accept(&mut self) {
+        self.set_verdict(Some(Verdict::Accepted));
+        self.forward();
+    }
+
+    // moves on to the next image
+    pub fn reject(&mut self) {
+        self.set_verdict(Some(Verdict::Rejected));
+        self.forward();
+    }
+
+    pub fn clear_verdict(&mut self) {
+        self.set_verdict(None);
+    }
+
+    // returns false when there is no tag at `tag`
+    pub fn toggle_tag(&mut self, tag: usize) -> bool {
+        let Some(name) = self.tags.get(tag).cloned() else {
+            return false;
+        };
+
+        self.update(|review| match review.tags.iter().position(|existing| *existing == name) {
+            Some(position) => {
+                review.tags.remove(position);
+                ReviewEvent::Untagged(name)
+            },
+            None => {
+                review.tags.push(name.clone());
+                ReviewEvent::Tagged(name)
+            },
+        })
+    }
+
+    fn set_verdict(&mut self, verdict: Option<Verdict>) {
+        self.update(|review| {
+            review.verdict = verdict;
+            match verdict {
+                Some(Verdict::Accepted) => ReviewEvent::Accepted,
+                Some(Verdict::Rejected) => ReviewEvent::Rejected,
+                None => ReviewEvent::Cleared,
+            }
+        });
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut Review) -> ReviewEvent) -> bool {
+        let (Some(path), Some(Some(review))) = (self.paths.get(self.index), self.reviews.get_mut(self.index)) else {
+            return false;
+        };
+
+        let event = change(review);
+        self.sink.record(path, &event, review);
+        true
+    }
+
+    // e.g. "cat_0042.jpg [42/1000] accepted: blurry, occluded"
+    pub fn title(&self) -> String {
+        let Some(path) = self.current_path() else {
+            return String::from("no images");
+        };
+
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        let mut title = format!("{name} [{}/{}]", self.index + 1, self.len());
+
+        if let Some(review) = self.review() {
+            match review.verdict {
+                Some(Verdict::Accepted) => title.push_str(" accepted"),
+                Some(Verdict::Rejected) => title.push_str(" rejected"),
+                None => {},
+            }
+
+            if !review.tags.is_empty() {
+                title.push_str(&format!(": {}", review.tags.join(", ")));
+            }
+        }
+
+        if let Some(Err(error)) = &self.image {
+            title.push_str(&format!(" (failed to decode: {error})"));
+        }
+
+        title
+    }
+
+    // the verdict and tags of the current image for the overlay, e.g. "accepted: blurry,
+    // occluded", empty while it has neither
+    pub fn caption(&self) -> String {
+        let Some(review) = self.review() else {
+            return String::new();
+        };
+
+        let verdict = match review.verdict {
+            Some(Verdict::Accepted) => "accepted",
+            Some(Verdict::Rejected) => "rejected",
+            None => "",
+        };
+
+        match (verdict, review.tags.is_empty()) {
+            (verdict, true) => verdict.to_owned(),
+            ("", false) => review.tags.join(", "),
+            (verdict, false) => format!("{verdict}: {}", review.tags.join(", ")),
+        }
+    }
+
+    pub fn key_response(&self) -> KeyResponse {
+        let clear_color = match self.review().and_then(|review| review.verdict) {
+            Some(Verdict::Accepted) => wgpu::Color { r: 0.0, g: 0.2, b: 0.0, a: 1.0 },
+            Some(Verdict::Rejected) => wgpu::Color { r: 0.2, g: 0.0, b: 0.0, a: 1.0 },
+            None => wgpu::Color::default(),
+        };
+
+        KeyResponse { title: Some(self.title()), clear_color: Some(clear_color), caption: Some(self.caption()) }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) -> Option<KeyResponse> {
+        match key {
+            KeyCode::ArrowRight | KeyCode::Space => {
+                self.forward();
+            },
+            KeyCode::ArrowLeft | KeyCode::Backspace => {
+                self.back();
+            },
+            KeyCode::KeyA => self.accept(),
+            KeyCode::KeyR => self.reject(),
+            KeyCode::Delete => self.clear_verdict(),
+            _ => {
+                let tag = tag_key(key)?;
+                self.toggle_tag(tag);
+            },
+        }
+
+        // decoded now so the title can report a broken file
+        self.load();
+        Some(self.key_response())
+    }
+
+    fn load(&mut self) -> Option<&FileImageProvider> {
+        if self.image.is_none() {
+            let path = self.paths.get(self.index)?;
+            let image = FileImageProvider::open_with_limits(path, &self.limits);
+
+            if let Err(error) = &image {
+                log::warn!("failed to decode {}: {error}", path.display());
+            }
+
+            self.image = Some(image);
+        }
+
+        self.image.as_ref().and_then(|image| image.as_ref().ok())
+    }
+}
+
+fn tag_key(key: KeyCode) -> Option<usize> {
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+
+    keys.iter().position(|candidate| *candidate == key)
+}
+
+// a broken image yields nothing, so the previous one stays on screen while the title
+// reports the error
+impl<Sink: ReviewSink> Iterator for DatasetReview<Sink> {
+    type Item = ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.load().and_then(|mut image| image.next())
+    }
+}
+
+impl<Sink: ReviewSink> FrameProvider for DatasetReview<Sink> {
+    type Frame = ImageFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Pending, FramePoll::Ready)
+    }
+
+    // the image shown now, once it was decoded
+    fn current_size(&self) -> Option<Pair<u32>> {
+        self.image.as_ref()?.as_ref().ok().map(|image| image.orientation().displayed_size(image.size()))
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+}
@@ -0,0 +1,169 @@
+use crate::overlay::OverlayRect;
+use crate::text::{self, Font, TextShapes};
+use crate::types::Pair;
+
+// between the bar and the edges of the surface, in surface pixels
+const MARGIN: f32 = 16.0;
+
+// between the bar and its label
+const LABEL_GAP: f32 = 6.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScaleBarCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+// a bar of a known length in calibrated units, e.g. micrometers under a microscope, with
+// the length written above it
+// the bar follows the zoom, its length in units stays put unless it's picked automatically
+// sizes are in surface pixels, so it stays readable at any zoom and in captures
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaleBar {
+    // frame pixels per unit
+    pub pixels_per_unit: f32,
+    // written after the length, the font only has ascii so e.g. "um" rather than "µm"
+    pub unit: String,
+    // `None` picks the longest 1, 2 or 5 times a power of ten that fits `target_length`
+    pub length: Option<f32>,
+    pub target_length: f32,
+    pub corner: ScaleBarCorner,
+    pub thickness: f32,
+    // height of a line of capitals, 0 leaves the label out
+    pub font_size: f32,
+    // linear rgba
+    pub color: [f32; 4],
+    pub outline_color: [f32; 4],
+}
+
+impl ScaleBar {
+    pub fn new<S: Into<String>>(pixels_per_unit: f32, unit: S) -> Self {
+        Self {
+            pixels_per_unit,
+            unit: unit.into(),
+            length: None,
+            target_length: 120.0,
+            corner: ScaleBarCorner::default(),
+            thickness: 4.0,
+            font_size: 14.0,
+            color: [1.0; 4],
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    pub fn with_length(mut self, length: f32) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub fn with_target_length(mut self, target_length: f32) -> Self {
+        self.target_length = target_length;
+        self
+    }
+
+    pub fn with_corner(mut self, corner: ScaleBarCorner) -> Self {
+        self.corner = corner;
+        self
+    }
+
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    // units the bar stands for at `frame_scale` surface pixels per frame pixel, `None`
+    // without a usable calibration
+    pub fn length_at(&self, frame_scale: f32) -> Option<f32> {
+        let surface_per_unit = self.pixels_per_unit * frame_scale;
+
+        if !surface_per_unit.is_finite() || surface_per_unit <= 0.0 {
+            return None;
+        }
+
+        match self.length {
+            Some(length) => Some(length).filter(|length| length.is_finite() && *length > 0.0),
+            None => Some(round_length(self.target_length / surface_per_unit)),
+        }
+    }
+
+    // e.g. "50 um"
+    pub fn label(&self, length: f32) -> String {
+        match self.unit.is_empty() {
+            true => format!("{length}"),
+            false => format!("{length} {}", self.unit),
+        }
+    }
+}
+
+// the largest 1, 2 or 5 times a power of ten not above `length`
+pub fn round_length(length: f32) -> f32 {
+    let exponent = length.log10().floor() as i32;
+    let power = |mantissa: f32| match exponent < 0 {
+        // divided rather than multiplied by a fraction, so e.g. 0.05 prints as such
+        true => mantissa / 10f32.powi(-exponent),
+        false => mantissa * 10f32.powi(exponent),
+    };
+
+    [5.0, 2.0, 1.0].into_iter().map(power).find(|round| *round <= length).unwrap_or(power(1.0))
+}
+
+// the bar and its label in `bar.corner`, `inset` keeps them clear of whatever else is drawn
+// along the bottom edge, e.g. the scrub bar
+pub(crate) fn shapes(bar: &ScaleBar, frame_scale: f32, surface_size: Pair<u32>, inset: f32, font: Option<&Font>) -> TextShapes {
+    let Some(length) = bar.length_at(frame_scale) else {
+        return TextShapes::default();
+    };
+
+    let width = length * bar.pixels_per_unit * frame_scale;
+    let label = bar.label(length);
+    let label_size = match bar.font_size > 0.0 {
+        true => text::line_size(&label, bar.font_size, font),
+        false => (0.0, 0.0),
+    };
+
+    let block = (width.max(label_size.0), bar.thickness + LABEL_GAP + label_size.1);
+    let (surface_width, surface_height) = (surface_size.0 as f32, surface_size.1 as f32);
+    let left = match bar.corner {
+        ScaleBarCorner::TopLeft | ScaleBarCorner::BottomLeft => MARGIN,
+        ScaleBarCorner::TopRight | ScaleBarCorner::BottomRight => surface_width - MARGIN - block.0,
+    };
+    let top = match bar.corner {
+        ScaleBarCorner::TopLeft | ScaleBarCorner::TopRight => MARGIN,
+        ScaleBarCorner::BottomLeft | ScaleBarCorner::BottomRight => surface_height - inset - MARGIN - block.1,
+    };
+
+    // the label sits above the bar, both centered in the block
+    let bar_min = (left + (block.0 - width) / 2.0, top + block.1 - bar.thickness);
+    let outline = (bar.thickness / 2.0).clamp(1.0, 2.0);
+
+    let rects = vec![
+        OverlayRect {
+            min: (bar_min.0 - outline, bar_min.1 - outline),
+            max: (bar_min.0 + width + outline, bar_min.1 + bar.thickness + outline),
+            color: bar.outline_color,
+        },
+        OverlayRect { min: bar_min, max: (bar_min.0 + width, bar_min.1 + bar.thickness), color: bar.color },
+    ];
+
+    let mut shapes = TextShapes { rects, glyphs: Vec::new() };
+
+    if bar.font_size > 0.0 {
+        let origin = (left + (block.0 - label_size.0) / 2.0, top);
+        shapes.append(text::line_shapes(&label, origin, bar.font_size, outline, bar.color, bar.outline_color, font));
+    }
+
+    shapes
+}
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use crate::overlay::OverlayRect;
+use crate::transport::Transport;
+use crate::types::Pair;
+
+// what the scrub bar along the bottom of the surface shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeline {
+    pub position: Duration,
+    // `None` shows the position alone, there is nothing to seek along
+    pub duration: Option<Duration>,
+    pub paused: bool,
+}
+
+impl Timeline {
+    pub fn of(transport: &dyn Transport) -> Self {
+        Self {
+            position: transport.position().unwrap_or_default(),
+            duration: transport.duration(),
+            paused: transport.is_paused(),
+        }
+    }
+}
+
+// a named point on the timeline, e.g. where a step of a recorded test run starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    pub name: String,
+    pub position: Duration,
+}
+
+impl Marker {
+    pub fn new<S: Into<String>>(name: S, position: Duration) -> Self {
+        Self { name: name.into(), position }
+    }
+}
+
+// jumping back this close after a marker goes to the one before it, like players skip
+// back to the previous chapter rather than restarting the current one
+const MARKER_GRACE: Duration = Duration::from_millis(500);
+
+// the first marker after `position`, `markers` sorted by position
+pub fn marker_after(markers: &[Marker], position: Duration) -> Option<&Marker> {
+    markers.iter().find(|marker| marker.position > position)
+}
+
+pub fn marker_before(markers: &[Marker], position: Duration) -> Option<&Marker> {
+    markers.iter().rev().find(|marker| marker.position + MARKER_GRACE < position)
+}
+
+pub(crate) const BAR_HEIGHT: f32 = 32.0;
+const MARGIN: f32 = 8.0;
+const TRACK_HEIGHT: f32 = 4.0;
+const KNOB_SIZE: Pair<f32> = (4.0, 14.0);
+const TICK_SIZE: Pair<f32> = (2.0, 10.0);
+
+// glyphs are 3x5 cells of this many surface pixels
+const CELL: f32 = 2.0;
+const ADVANCE: f32 = 4.0 * CELL;
+
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TRACK: [f32; 4] = [1.0, 1.0, 1.0, 0.3];
+const PLAYED: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+const TEXT: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const TICK: [f32; 4] = [1.0, 0.6, 0.05, 1.0];
+
+// rows top to bottom, the high bit of the three is the left cell
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+const COLON: [u8; 5] = [0b000, 0b010, 0b000, 0b010, 0b000];
+
+// m:ss, or h:mm:ss from an hour on
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{hours}:{:02}:{:02}", seconds / 60 % 60, seconds % 60),
+    }
+}
+
+fn text_width(text: &str) -> f32 {
+    text.len() as f32 * ADVANCE - CELL
+}
+
+fn text_rects(text: &str, origin: Pair<f32>, rects: &mut Vec<OverlayRect>) {
+    for (index, character) in text.chars().enumerate() {
+        let glyph = match character {
+            ':' => COLON,
+            digit => DIGITS[digit.to_digit(10).unwrap_or(0) as usize],
+        };
+
+        let left = origin.0 + index as f32 * ADVANCE;
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in (0..3).filter(|column| bits & (0b100 >> column) != 0) {
+                let min = (left + column as f32 * CELL, origin.1 + row as f32 * CELL);
+                rects.push(OverlayRect { min, max: (min.0 + CELL, min.1 + CELL), color: TEXT });
+            }
+        }
+    }
+}
+
+struct Layout {
+    top: f32,
+    // where the position text starts
+    text_left: f32,
+    // horizontal extent, `None` when there is no duration or no room
+    track: Option<Pair<f32>>,
+}
+
+fn layout(timeline: &Timeline, surface_size: Pair<u32>) -> Layout {
+    let (width, height) = (surface_size.0 as f32, surface_size.1 as f32);
+    // the pause stripes have room whether they show or not
+    let text_left = 2.0 * MARGIN + 3.0 * CELL;
+
+    // both times get the room of the longer one so the bar doesn't move as they grow
+    let track = timeline.duration.map(|duration| {
+        let room = text_width(&format_time(duration.max(timeline.position))) + 1.5 * MARGIN;
+        (text_left + room, width - MARGIN - room)
+    });
+
+    Layout { top: height - BAR_HEIGHT, text_left, track: track.filter(|(start, end)| end > start) }
+}
+
+fn fraction(timeline: &Timeline, position: Duration) -> f32 {
+    match timeline.duration {
+        Some(duration) if !duration.is_zero() => (position.as_secs_f32() / duration.as_secs_f32()).clamp(0.0, 1.0),
+        _ => 0.0,
+    }
+}
+
+// the bar with a tick per marker, the times on either side of it and two stripes in front
+// while paused
+pub(crate) fn rects(timeline: &Timeline, markers: &[Marker], surface_size: Pair<u32>) -> Vec<OverlayRect> {
+    let layout = layout(timeline, surface_size);
+    let width = surface_size.0 as f32;
+    let middle = layout.top + BAR_HEIGHT / 2.0;
+    let text_top = middle - 2.5 * CELL;
+
+    let mut rects = vec![OverlayRect { min: (0.0, layout.top), max: (width, layout.top + BAR_HEIGHT), color: BACKGROUND }];
+
+    if timeline.paused {
+        for left in [MARGIN, MARGIN + 2.0 * CELL] {
+            rects.push(OverlayRect { min: (left, text_top), max: (left + CELL, text_top + 5.0 * CELL), color: TEXT });
+        }
+    }
+
+    text_rects(&format_time(timeline.position), (layout.text_left, text_top), &mut rects);
+
+    if let Some(duration) = timeline.duration {
+        let text = format_time(duration);
+        text_rects(&text, (width - MARGIN - text_width(&text), text_top), &mut rects);
+    }
+
+    if let Some((start, end)) = layout.track {
+        let along = |position| start + fraction(timeline, position) * (end - start);
+        let played = along(timeline.position);
+        let (track_top, track_bottom) = (middle - TRACK_HEIGHT / 2.0, middle + TRACK_HEIGHT / 2.0);
+
+        rects.extend([
+            OverlayRect { min: (start, track_top), max: (end, track_bottom), color: TRACK },
+            OverlayRect { min: (start, track_top), max: (played, track_bottom), color: PLAYED },
+        ]);
+
+        rects.extend(markers.iter().map(|marker| {
+            let x = along(marker.position);
+            OverlayRect {
+                min: (x - TICK_SIZE.0 / 2.0, middle - TICK_SIZE.1 / 2.0),
+                max: (x + TICK_SIZE.0 / 2.0, middle + TICK_SIZE.1 / 2.0),
+                color: TICK,
+            }
+        }));
+
+        rects.extend([
+            OverlayRect {
+                min: (played - KNOB_SIZE.0 / 2.0, middle - KNOB_SIZE.1 / 2.0),
+                max: (played + KNOB_SIZE.0 / 2.0, middle + KNOB_SIZE.1 / 2.0),
+                color: TEXT,
+            },
+        ]);
+    }
+
+    rects
+}
+
+// the time under a surface point on the bar, anywhere along its height
+pub(crate) fn position_at(timeline: &Timeline, surface_size: Pair<u32>, point: Pair<f32>) -> Option<Duration> {
+    let layout = layout(timeline, surface_size);
+    let (start, end) = layout.track?;
+
+    if point.1 < layout.top || point.1 > surface_size.1 as f32 {
+        return None;
+    }
+
+    let fraction = ((point.0 - start) / (end - start)).clamp(0.0, 1.0);
+    timeline.duration.map(|duration| duration.mul_f32(fraction))
+}
@@ -6,23 +6,425 @@ struct VertexInput {
 struct VertexOutput {
     @builtin(position) clip_position : vec4<f32>,
     @location(0) tex_coords : vec2<f32>,
+    @location(1) @interpolate(flat) layer : u32,
 }
 
+struct Transform {
+    offset : vec2<f32>,
+    zoom : f32,
+}
+
+@group(0) @binding(2)
+var<uniform> transform: Transform;
+
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(model: VertexInput, @builtin(instance_index) layer: u32) -> VertexOutput {
     var out : VertexOutput;
     out.tex_coords = model.tex_coords;
-    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.layer = layer;
+    out.clip_position = vec4<f32>(model.position * transform.zoom + transform.offset, 0.0, 1.0);
     return out;
 }
 
 @group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
+var t_diffuse: texture_2d_array<f32>;
 
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+struct Image {
+    paletted : u32,
+    shading : u32,
+    srgb : u32,
+    checkerboard : u32,
+    light : vec3<f32>,
+    channels : mat4x4<f32>,
+    channel_offset : vec4<f32>,
+    // a skybox is shown when `sky` isn't 0, the basis is pre-scaled by the field of view
+    sky : u32,
+    sky_cells : CubeCells,
+    sky_forward : vec4<f32>,
+    sky_right : vec4<f32>,
+    sky_up : vec4<f32>,
+    coverage_channel : u32,
+    // threshold or edge, smoothing, contour spacing
+    coverage : vec4<f32>,
+    // set when the target stores what it's given without srgb encoding it
+    encode_output : u32,
+    mask : u32,
+    mask_opacity : f32,
+    // 0 ignores alpha, 1 is straight and 2 premultiplied, see `AlphaBehavior`
+    alpha : u32,
+    // mask pixels per frame pixel
+    mask_scale : vec2<f32>,
+    heatmap : u32,
+    heatmap_opacity : f32,
+    // values at the ends of the colormap
+    heatmap_range : vec2<f32>,
+    // heatmap pixels per frame pixel
+    heatmap_scale : vec2<f32>,
+    // float frames are rolled off into the sdr range rather than clipped
+    tone_map : u32,
+    pixel_grid : u32,
+    // surface pixels per frame pixel from which the grid shows
+    pixel_grid_min_scale : f32,
+    pixel_grid_width : f32,
+    pixel_grid_color : vec4<f32>,
+    // luma in `t_diffuse` and chroma in `t_chroma`
+    yuv : u32,
+    // layer 0 is shown left of `split_x` and layer 1 right of it
+    split : u32,
+    split_x : f32,
+}
+
+@group(0) @binding(3)
+var<uniform> image: Image;
+
+// row `layer` holds the palette of that layer's frame
+@group(0) @binding(4)
+var t_palette: texture_2d<f32>;
+
+// a class per frame pixel, 0 leaves the pixel alone
+@group(0) @binding(5)
+var t_mask: texture_2d<f32>;
+
+// the color of each class, transparent for 0
+@group(0) @binding(6)
+var t_mask_colors: texture_2d<f32>;
+
+@group(0) @binding(7)
+var t_heatmap: texture_2d<f32>;
+
+// 256 colors from the low to the high end of the range
+@group(0) @binding(8)
+var t_colormap: texture_2d<f32>;
+
+// u and v of yuv frames at half the luma's size
+@group(0) @binding(9)
+var t_chroma: texture_2d_array<f32>;
+
+fn srgb_encode(linear : vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+fn srgb_decode(encoded : vec3<f32>) -> vec3<f32> {
+    let low = encoded / 12.92;
+    let high = pow((encoded + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, encoded <= vec3<f32>(0.04045));
+}
+
+// the values as they are in the file, undoing the sampler's srgb decoding
+fn stored(texel : vec4<f32>) -> vec4<f32> {
+    if image.srgb != 0u {
+        return vec4<f32>(srgb_encode(texel.rgb), texel.a);
+    }
+
+    return texel;
+}
+
+// shows a color computed from stored values the way the same stored color would be shown
+fn displayed(color : vec4<f32>) -> vec4<f32> {
+    if image.srgb != 0u {
+        return vec4<f32>(srgb_decode(color.rgb), color.a);
+    }
+
+    return color;
+}
+
+fn map_channels(texel : vec4<f32>) -> vec4<f32> {
+    let mapped = stored(texel) * image.channels + image.channel_offset;
+    return displayed(clamp(mapped, vec4<f32>(0.0), vec4<f32>(1.0)));
+}
+
+fn coverage_value(texel : vec4<f32>) -> f32 {
+    return stored(texel)[image.coverage_channel];
+}
+
+fn threshold(texel : vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(vec3<f32>(step(image.coverage.x, coverage_value(texel))), 1.0);
+}
+
+// fwidth keeps the outline and isolines about a pixel wide at any zoom
+fn distance_field(texel : vec4<f32>) -> vec4<f32> {
+    let distance = coverage_value(texel);
+    let edge = image.coverage.x;
+    let ramp = max(fwidth(distance), 0.0001);
+    let blur = ramp * 0.5 + image.coverage.y;
+
+    var color = vec3<f32>(smoothstep(edge - blur, edge + blur, distance));
+
+    let spacing = image.coverage.z;
+    if spacing > 0.0 {
+        let offset = (distance - edge) / spacing;
+        let line = 1.0 - smoothstep(0.0, ramp / spacing, abs(fract(offset + 0.5) - 0.5));
+        let tint = select(vec3<f32>(1.0, 0.55, 0.1), vec3<f32>(0.2, 0.6, 1.0), distance >= edge);
+        color = mix(color, tint, line * 0.8);
+    }
+
+    return displayed(vec4<f32>(color, 1.0));
+}
+
+// polynomial fit of the turbo color map
+fn heat_map(texel : vec4<f32>) -> vec4<f32> {
+    let x = clamp(coverage_value(texel), 0.0, 1.0);
+    let v4 = vec4<f32>(1.0, x, x * x, x * x * x);
+    let v2 = v4.zw * v4.z;
+
+    let color = vec3<f32>(
+        dot(v4, vec4<f32>(0.13572138, 4.61539260, -42.66032258, 132.13108234)) + dot(v2, vec2<f32>(-152.94239396, 59.28637943)),
+        dot(v4, vec4<f32>(0.09140261, 2.19418839, 4.84296658, -14.18503333)) + dot(v2, vec2<f32>(4.27729857, 2.82956604)),
+        dot(v4, vec4<f32>(0.10667330, 12.64194608, -60.58204836, 110.36276771)) + dot(v2, vec2<f32>(-89.90310912, 27.34824973)),
+    );
+
+    return displayed(vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
+}
+
+// lambert with a little ambient so faces turned away stay readable
+fn shade_normal(texel : vec4<f32>) -> vec4<f32> {
+    var encoded = texel.rgb;
+    if image.srgb != 0u {
+        encoded = srgb_encode(encoded);
+    }
+
+    let normal = normalize(encoded * 2.0 - 1.0);
+    let diffuse = max(dot(normal, image.light), 0.0);
+    return vec4<f32>(vec3<f32>(0.1 + 0.9 * diffuse), 1.0);
+}
+
+// premultiplied frames were multiplied as stored, so that is where the division happens,
+// filtering them first is what keeps transparent edges from darkening
+fn unpremultiplied(texel : vec4<f32>) -> vec4<f32> {
+    if image.alpha != 2u || texel.a <= 0.0 {
+        return texel;
+    }
+
+    return displayed(vec4<f32>(min(stored(texel).rgb / texel.a, vec3<f32>(1.0)), texel.a));
+}
+
+// bt.601 studio range like `convert::yuv_to_rgba`, both planes are filtered on their own
+// so chroma is interpolated between its samples rather than repeated
+fn sample_yuv(tex_coords : vec2<f32>, layer : u32) -> vec4<f32> {
+    let y = textureSampleLevel(t_diffuse, s_diffuse, tex_coords, layer, 0.0).r * 255.0 - 16.0;
+    let uv = textureSampleLevel(t_chroma, s_diffuse, tex_coords, layer, 0.0).rg * 255.0 - 128.0;
+
+    let rgb = vec3<f32>(
+        298.0 * y + 409.0 * uv.y,
+        298.0 * y - 100.0 * uv.x - 208.0 * uv.y,
+        298.0 * y + 516.0 * uv.x,
+    ) / (256.0 * 255.0);
+
+    return displayed(vec4<f32>(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
+}
+
+fn sample_at(tex_coords : vec2<f32>, layer : u32) -> vec4<f32> {
+    // indices can't be filtered, the nearest one is looked up in the palette, wrapping
+    // like the sampler does when tiling
+    if image.paletted != 0u {
+        let size = textureDimensions(t_diffuse);
+        let texel = min(vec2<u32>(fract(tex_coords) * vec2<f32>(size)), size - 1u);
+        let index = u32(textureLoad(t_diffuse, texel, layer, 0).r * 255.0 + 0.5);
+        return textureLoad(t_palette, vec2<u32>(index, layer), 0);
+    }
+
+    if image.yuv != 0u {
+        return sample_yuv(tex_coords, layer);
+    }
+
+    return unpremultiplied(textureSampleLevel(t_diffuse, s_diffuse, tex_coords, layer, 0.0));
+}
+
+// the quad becomes a window into the cube, as wide as the frame is
+fn sample_sky(in : VertexOutput) -> vec4<f32> {
+    let size = textureDimensions(t_diffuse);
+    let aspect = f32(size.x) / f32(size.y);
+    let point = in.tex_coords * 2.0 - 1.0;
+    let dir = image.sky_forward.xyz + image.sky_right.xyz * point.x * aspect - image.sky_up.xyz * point.y;
+
+    return sample_at(cube_uv(image.sky_cells, dir, size), in.layer);
+}
+
+fn sample(in : VertexOutput) -> vec4<f32> {
+    if image.sky != 0u {
+        return sample_sky(in);
+    }
+
+    return sample_at(in.tex_coords, in.layer);
+}
+
+// 8 pixel squares of light and lighter gray in surface space, so they stay put while zooming
+fn checker(position : vec2<f32>) -> vec3<f32> {
+    let cell = vec2<u32>(position / 8.0);
+    return select(vec3<f32>(1.0), vec3<f32>(0.6), (cell.x + cell.y) % 2u == 0u);
+}
+
+fn shade(in : VertexOutput) -> vec4<f32> {
+    let texel = sample(in);
+
+    if image.shading == 1u {
+        return shade_normal(texel);
+    }
+
+    if image.shading == 2u {
+        return map_channels(texel);
+    }
+
+    if image.shading == 3u {
+        return threshold(texel);
+    }
+
+    if image.shading == 4u {
+        return distance_field(texel);
+    }
+
+    if image.shading == 5u {
+        return heat_map(texel);
+    }
+
+    return texel;
+}
+
+// classes are looked up like palette indices and tinted over the shaded color, so the
+// mask stays crisp at any zoom
+fn overlay_mask(in : VertexOutput, color : vec4<f32>) -> vec4<f32> {
+    if image.mask == 0u || image.sky != 0u {
+        return color;
+    }
+
+    let position = fract(in.tex_coords) * vec2<f32>(textureDimensions(t_diffuse)) * image.mask_scale;
+    let size = textureDimensions(t_mask);
+    if any(position >= vec2<f32>(size)) {
+        return color;
+    }
+
+    let label = u32(textureLoad(t_mask, vec2<u32>(position), 0).r * 255.0 + 0.5);
+    let tint = textureLoad(t_mask_colors, vec2<u32>(label, 0u), 0);
+    let weight = tint.a * image.mask_opacity;
+
+    return vec4<f32>(mix(color.rgb, tint.rgb, weight), mix(color.a, 1.0, weight));
+}
+
+// bilinear by hand, float textures can't be filtered everywhere, next to a nan it falls
+// back to the nearest value so gaps keep their edges
+fn heatmap_value(position : vec2<f32>) -> f32 {
+    let last = vec2<i32>(textureDimensions(t_heatmap)) - 1;
+    let corner = position - 0.5;
+    let base = vec2<i32>(floor(corner));
+    let t = fract(corner);
+
+    let low = clamp(base, vec2<i32>(0), last);
+    let high = clamp(base + 1, vec2<i32>(0), last);
+
+    let top = mix(textureLoad(t_heatmap, low, 0).r, textureLoad(t_heatmap, vec2<i32>(high.x, low.y), 0).r, t.x);
+    let bottom = mix(textureLoad(t_heatmap, vec2<i32>(low.x, high.y), 0).r, textureLoad(t_heatmap, high, 0).r, t.x);
+    let value = mix(top, bottom, t.y);
+
+    if value != value {
+        return textureLoad(t_heatmap, min(vec2<i32>(position), last), 0).r;
+    }
+
+    return value;
+}
+
+fn overlay_heatmap(in : VertexOutput, color : vec4<f32>) -> vec4<f32> {
+    if image.heatmap == 0u || image.sky != 0u {
+        return color;
+    }
+
+    let position = fract(in.tex_coords) * vec2<f32>(textureDimensions(t_diffuse)) * image.heatmap_scale;
+    if any(position >= vec2<f32>(textureDimensions(t_heatmap))) {
+        return color;
+    }
+
+    // nan marks pixels without a value
+    let value = heatmap_value(position);
+    if value != value {
+        return color;
+    }
+
+    let span = image.heatmap_range.y - image.heatmap_range.x;
+    let t = clamp(select((value - image.heatmap_range.x) / span, 0.5, span == 0.0), 0.0, 1.0);
+    let tint = textureSampleLevel(t_colormap, s_diffuse, vec2<f32>((t * 255.0 + 0.5) / 256.0, 0.5), 0.0);
+
+    return vec4<f32>(mix(color.rgb, tint.rgb, image.heatmap_opacity), mix(color.a, 1.0, image.heatmap_opacity));
+}
+
+// where the roll off starts, below it colors are left as they are
+const TONE_MAP_KNEE : f32 = 0.75;
+
+// scales by the brightest channel so hues don't shift, the curve meets the identity at the
+// knee with the same slope and approaches 1 from below
+fn tone_mapped(color : vec4<f32>) -> vec4<f32> {
+    let peak = max(color.r, max(color.g, color.b));
+    if image.tone_map == 0u || peak <= TONE_MAP_KNEE {
+        return color;
+    }
+
+    let shoulder = 1.0 - TONE_MAP_KNEE;
+    let mapped = TONE_MAP_KNEE + shoulder * (1.0 - exp(-(peak - TONE_MAP_KNEE) / shoulder));
+    return vec4<f32>(color.rgb * (mapped / peak), color.a);
+}
+
+// antialiased lines on the edges between texels, `pixel_grid_width` surface pixels wide
+// whatever the zoom
+fn overlay_pixel_grid(in : VertexOutput, color : vec4<f32>) -> vec4<f32> {
+    if image.pixel_grid == 0u || image.sky != 0u {
+        return color;
+    }
+
+    let position = in.tex_coords * vec2<f32>(textureDimensions(t_diffuse));
+    // texels per surface pixel
+    let footprint = max(fwidth(position), vec2<f32>(0.0001));
+    if 1.0 / max(footprint.x, footprint.y) < image.pixel_grid_min_scale {
+        return color;
+    }
+
+    // measured from half a surface pixel inside each texel, so whole zooms put a one pixel
+    // line on a single row of pixels rather than half on either side of the edge
+    let distance = abs(fract(position - footprint * 0.5 + 0.5) - 0.5) / footprint;
+    let line = clamp(image.pixel_grid_width * 0.5 + 0.5 - min(distance.x, distance.y), 0.0, 1.0);
+    let weight = line * image.pixel_grid_color.a;
+    let tint = srgb_decode(image.pixel_grid_color.rgb);
+
+    return vec4<f32>(mix(color.rgb, tint, weight), mix(color.a, 1.0, weight));
+}
+
+// premultiplied for the blend state, see `AlphaBehavior::blend_state`
+fn output(color : vec4<f32>) -> vec4<f32> {
+    var encoded = color;
+
+    if image.encode_output != 0u {
+        encoded = vec4<f32>(srgb_encode(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))), color.a);
+    }
+
+    return vec4<f32>(encoded.rgb * encoded.a, encoded.a);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
-}
\ No newline at end of file
+    if image.split != 0u && (in.layer == 0u) != (in.clip_position.x < image.split_x) {
+        discard;
+    }
+
+    var shaded = shade(in);
+
+    if image.alpha == 0u {
+        shaded.a = 1.0;
+    }
+
+    var color = overlay_mask(in, overlay_heatmap(in, tone_mapped(shaded)));
+
+    if image.checkerboard != 0u {
+        color = vec4<f32>(mix(checker(in.clip_position.xy), color.rgb, color.a), 1.0);
+    }
+
+    color = overlay_pixel_grid(in, color);
+
+    // a line two pixels wide on the split
+    if image.split != 0u && abs(in.clip_position.x - image.split_x) < 1.0 {
+        color = vec4<f32>(1.0);
+    }
+
+    return output(color);
+}
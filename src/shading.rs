@@ -0,0 +1,206 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::types::Pair;
+
+// how sampled texels are turned into the color on screen
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ShadingMode {
+    #[default]
+    Color,
+    // rgb read as a tangent space normal with green pointing up, lit by a directional
+    // light from `light` with plain lambert shading
+    NormalMap { light: [f32; 3] },
+    // recombines the raw channel values, e.g. to look at one map of a packed texture
+    Channels(ChannelMapping),
+    // one channel as coverage: white at or above `threshold`, black below
+    Threshold { channel: Channel, threshold: f32 },
+    // one channel as a signed distance field with the outline at `edge`, filled with
+    // `smoothing` extra blur on top of a one pixel antialiasing ramp, and isolines every
+    // `contour_spacing` (none at 0.0), blue inside and orange outside
+    DistanceField { channel: Channel, edge: f32, smoothing: f32, contour_spacing: f32 },
+    // one channel through a blue to red color ramp
+    HeatMap { channel: Channel },
+}
+
+impl ShadingMode {
+    pub const fn normal_map() -> Self {
+        ShadingMode::NormalMap { light: [0.0, 0.0, 1.0] }
+    }
+
+    // the usual setup for font atlases
+    pub const fn distance_field(channel: Channel) -> Self {
+        ShadingMode::DistanceField { channel, edge: 0.5, smoothing: 0.0, contour_spacing: 0.1 }
+    }
+}
+
+// a light hanging above `point` on the surface, straight on at the center and grazing
+// towards the edges, e.g. to move the light with the cursor
+pub fn light_towards(point: Pair<f32>, surface_size: Pair<u32>) -> [f32; 3] {
+    let x = 2.0 * point.0 / surface_size.0.max(1) as f32 - 1.0;
+    let y = 1.0 - 2.0 * point.1 / surface_size.1.max(1) as f32;
+
+    [x, y, 0.5]
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_lowercase() {
+            'r' => Some(Channel::Red),
+            'g' => Some(Channel::Green),
+            'b' => Some(Channel::Blue),
+            'a' => Some(Channel::Alpha),
+            _ => None,
+        }
+    }
+}
+
+// every output channel is a weighted sum of the input channels plus a constant, all on
+// the stored 0..1 values with no color space conversion
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChannelMapping {
+    // one row of red, green, blue and alpha weights per output channel
+    pub weights: [[f32; 4]; 4],
+    pub offset: [f32; 4],
+}
+
+impl Default for ChannelMapping {
+    fn default() -> Self {
+        Self::swizzle([Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha])
+    }
+}
+
+impl ChannelMapping {
+    pub fn swizzle(sources: [Channel; 4]) -> Self {
+        let mut weights = [[0.0; 4]; 4];
+        for (row, source) in weights.iter_mut().zip(sources) {
+            row[source.index()] = 1.0;
+        }
+
+        Self { weights, offset: [0.0; 4] }
+    }
+
+    // one channel as opaque gray
+    pub fn gray(channel: Channel) -> Self {
+        let mut mapping = Self::swizzle([channel; 4]);
+        mapping.weights[3] = [0.0; 4];
+        mapping.offset[3] = 1.0;
+        mapping
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseMappingError {
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid channel mapping: {}", self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseMappingError {}
+
+// either a swizzle of up to four letters out of `rgba01` ("r" shows red as gray, "bgra"
+// swaps red and blue, "rgb1" drops alpha), or four comma separated sums of terms like
+// `r`, `0.5`, `0.5*g` for red, green, blue and alpha ("0.5*r+0.5*g, b, 0, 1")
+impl FromStr for ChannelMapping {
+    type Err = ParseMappingError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+
+        match text.contains(',') {
+            true => parse_sums(text),
+            false => parse_swizzle(text),
+        }
+    }
+}
+
+fn parse_swizzle(text: &str) -> Result<ChannelMapping, ParseMappingError> {
+    let count = text.chars().count();
+    if count == 0 || count > 4 {
+        return Err(ParseMappingError { reason: "a swizzle has one to four letters" });
+    }
+
+    let mut mapping = ChannelMapping { weights: [[0.0; 4]; 4], offset: [0.0; 4] };
+
+    // a single letter is shown as gray, missing channels are black and opaque
+    let outputs = match count {
+        1 => 3,
+        _ => count,
+    };
+    for (output, letter) in text.chars().cycle().take(outputs).enumerate() {
+        set_term(&mut mapping, output, letter)?;
+    }
+
+    if count < 4 {
+        mapping.offset[3] = 1.0;
+    }
+
+    Ok(mapping)
+}
+
+fn set_term(mapping: &mut ChannelMapping, output: usize, letter: char) -> Result<(), ParseMappingError> {
+    match (letter, Channel::from_letter(letter)) {
+        (_, Some(channel)) => mapping.weights[output][channel.index()] = 1.0,
+        ('0', None) => (),
+        ('1', None) => mapping.offset[output] = 1.0,
+        _ => return Err(ParseMappingError { reason: "swizzle letters are r, g, b, a, 0 or 1" }),
+    }
+
+    Ok(())
+}
+
+fn parse_sums(text: &str) -> Result<ChannelMapping, ParseMappingError> {
+    let mut mapping = ChannelMapping { weights: [[0.0; 4]; 4], offset: [0.0; 4] };
+    let mut outputs = text.split(',');
+
+    for output in 0..4 {
+        let sum = outputs.next().ok_or(ParseMappingError { reason: "expected four comma separated channels" })?;
+
+        for term in sum.split('+').map(str::trim) {
+            let (weight, letter) = match term.split_once('*') {
+                Some((weight, letter)) => (parse_weight(weight.trim())?, letter.trim()),
+                None => match term.parse::<f32>() {
+                    Ok(constant) => {
+                        mapping.offset[output] += constant;
+                        continue;
+                    },
+                    Err(_) => (1.0, term),
+                },
+            };
+
+            let mut letters = letter.chars();
+            let channel = match (letters.next().and_then(Channel::from_letter), letters.next()) {
+                (Some(channel), None) => channel,
+                _ => return Err(ParseMappingError { reason: "terms are a number, a channel or number*channel" }),
+            };
+
+            mapping.weights[output][channel.index()] += weight;
+        }
+    }
+
+    match outputs.next() {
+        Some(_) => Err(ParseMappingError { reason: "expected four comma separated channels" }),
+        None => Ok(mapping),
+    }
+}
+
+fn parse_weight(text: &str) -> Result<f32, ParseMappingError> {
+    text.parse().map_err(|_| ParseMappingError { reason: "invalid weight" })
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::{DrawError, RenderInitError};
+use crate::gpu::GpuContext;
+use crate::headless::{HeadlessFrameRenderContext, HeadlessFrameRenderContextInit};
+use crate::readback::ReadbackError;
+use crate::render::WgpuFrameRenderContext;
+use crate::types::Pair;
+
+// set to anything to have `SnapshotSuite::run` rewrite the golden images
+pub const UPDATE_VAR: &str = "EGAMI_UPDATE_SNAPSHOTS";
+
+type DrawCase = Box<dyn Fn(&mut WgpuFrameRenderContext) -> Result<(), DrawError>>;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Init(RenderInitError),
+    Draw { case: String, error: DrawError },
+    Readback { case: String, error: ReadbackError },
+    Image { case: String, error: image::ImageError },
+    Io(std::io::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Init(error) => write!(f, "failed to open a headless context: {error}"),
+            SnapshotError::Draw { case, error } => write!(f, "failed to draw {case}: {error}"),
+            SnapshotError::Readback { case, error } => write!(f, "failed to read back {case}: {error}"),
+            SnapshotError::Image { case, error } => write!(f, "failed to load or save the golden image of {case}: {error}"),
+            SnapshotError::Io(error) => write!(f, "failed to create the golden image directory: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Init(error) => Some(error),
+            SnapshotError::Draw { error, .. } => Some(error),
+            SnapshotError::Readback { error, .. } => Some(error),
+            SnapshotError::Image { error, .. } => Some(error),
+            SnapshotError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<RenderInitError> for SnapshotError {
+    fn from(error: RenderInitError) -> Self {
+        SnapshotError::Init(error)
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotMode {
+    // compares every case against its golden image and leaves the files alone
+    #[default]
+    Check,
+    // writes every case as its new golden image, still reporting what changed
+    Update,
+}
+
+impl SnapshotMode {
+    // `Update` when `UPDATE_VAR` is set
+    pub fn from_env() -> Self {
+        match std::env::var_os(UPDATE_VAR) {
+            Some(_) => SnapshotMode::Update,
+            None => SnapshotMode::Check,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    // no channel differs by more than the tolerance
+    Unchanged,
+    Changed { pixels: u64, max_delta: u8 },
+    Resized { golden: Pair<u32>, rendered: Pair<u32> },
+    // there was no golden image yet
+    New,
+}
+
+impl fmt::Display for SnapshotOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotOutcome::Unchanged => write!(f, "unchanged"),
+            SnapshotOutcome::Changed { pixels, max_delta } => write!(f, "{pixels} pixels changed, by up to {max_delta}"),
+            SnapshotOutcome::Resized { golden, rendered } => {
+                write!(f, "resized from {}x{} to {}x{}", golden.0, golden.1, rendered.0, rendered.1)
+            },
+            SnapshotOutcome::New => write!(f, "new"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotReport {
+    pub mode: SnapshotMode,
+    // in registration order
+    pub cases: Vec<(String, SnapshotOutcome)>,
+}
+
+impl SnapshotReport {
+    pub fn changed(&self) -> impl Iterator<Item = &(String, SnapshotOutcome)> {
+        self.cases.iter().filter(|(_, outcome)| *outcome != SnapshotOutcome::Unchanged)
+    }
+
+    // a check passes when every case matched, an update always does
+    pub fn passed(&self) -> bool {
+        self.mode == SnapshotMode::Update || self.changed().next().is_none()
+    }
+}
+
+// one line per case that didn't match and a count at the end
+impl fmt::Display for SnapshotReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, outcome) in self.changed() {
+            writeln!(f, "{name}: {outcome}")?;
+        }
+
+        let changed = self.changed().count();
+        let verb = match self.mode {
+            SnapshotMode::Check => "differ",
+            SnapshotMode::Update => "updated",
+        };
+
+        write!(f, "{} cases, {changed} {verb}, {} unchanged", self.cases.len(), self.cases.len() - changed)
+    }
+}
+
+struct SnapshotCase {
+    name: String,
+    size: Pair<u32>,
+    draw: DrawCase,
+}
+
+// named draws rendered headless and kept as png files in one directory, `check` compares
+// them with the files and `update` rewrites the files, so a change that moves pixels on
+// purpose is one update run and a look at the summary
+pub struct SnapshotSuite {
+    directory: PathBuf,
+    size: Pair<u32>,
+    tolerance: u8,
+    gpu: Option<GpuContext>,
+    cases: Vec<SnapshotCase>,
+}
+
+impl SnapshotSuite {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+            size: (320, 240),
+            tolerance: 2,
+            gpu: None,
+            cases: Vec::new(),
+        }
+    }
+
+    // the target size of cases registered after this
+    pub fn with_size(mut self, size: Pair<u32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    // how far a channel may be off before the pixel counts as changed, the default of 2
+    // lets rounding differences between drivers through
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    // renders on this gpu instead of opening the default adapter
+    pub fn with_gpu(mut self, gpu: GpuContext) -> Self {
+        self.gpu = Some(gpu);
+        self
+    }
+
+    // `draw` gets a fresh context of the suite's size and draws the case into it,
+    // `name` is the file name of the golden image without `.png`, what the draw returns
+    // besides an error is dropped
+    pub fn case<F, T>(mut self, name: &str, draw: F) -> Self
+    where
+        F: Fn(&mut WgpuFrameRenderContext) -> Result<T, DrawError> + 'static,
+    {
+        let draw = move |context: &mut WgpuFrameRenderContext| draw(context).map(|_| ());
+        self.cases.push(SnapshotCase { name: name.to_string(), size: self.size, draw: Box::new(draw) });
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn golden_path(&self, name: &str) -> PathBuf {
+        self.directory.join(format!("{name}.png"))
+    }
+
+    pub fn check(&self) -> Result<SnapshotReport, SnapshotError> {
+        self.run_as(SnapshotMode::Check)
+    }
+
+    pub fn update(&self) -> Result<SnapshotReport, SnapshotError> {
+        self.run_as(SnapshotMode::Update)
+    }
+
+    // `check`, or `update` when `UPDATE_VAR` is set
+    pub fn run(&self) -> Result<SnapshotReport, SnapshotError> {
+        self.run_as(SnapshotMode::from_env())
+    }
+
+    pub fn run_as(&self, mode: SnapshotMode) -> Result<SnapshotReport, SnapshotError> {
+        if mode == SnapshotMode::Update {
+            std::fs::create_dir_all(&self.directory)?;
+        }
+
+        let mut gpu = self.gpu.clone();
+        let mut cases = Vec::with_capacity(self.cases.len());
+
+        for case in &self.cases {
+            let mut headless = HeadlessFrameRenderContext::open(HeadlessFrameRenderContextInit {
+                size: case.size,
+                label_prefix: Some(format!("snapshot {}", case.name)),
+                gpu: gpu.clone(),
+                ..Default::default()
+            })?;
+
+            // every case after the first reuses the device
+            gpu.get_or_insert_with(|| headless.render_context().gpu().clone());
+
+            let context = headless.render_context();
+            (case.draw)(context).map_err(|error| SnapshotError::Draw { case: case.name.clone(), error })?;
+            let rendered = context.capture_frame().map_err(|error| SnapshotError::Readback { case: case.name.clone(), error })?;
+
+            let path = self.golden_path(&case.name);
+            let outcome = match image::open(&path) {
+                Ok(golden) => self.compare(&golden.to_rgba8(), &rendered),
+                Err(image::ImageError::IoError(error)) if error.kind() == std::io::ErrorKind::NotFound => SnapshotOutcome::New,
+                Err(error) => return Err(SnapshotError::Image { case: case.name.clone(), error }),
+            };
+
+            if mode == SnapshotMode::Update && outcome != SnapshotOutcome::Unchanged {
+                rendered.save(&path).map_err(|error| SnapshotError::Image { case: case.name.clone(), error })?;
+            }
+
+            log::debug!("snapshot {}: {outcome}", case.name);
+            cases.push((case.name.clone(), outcome));
+        }
+
+        Ok(SnapshotReport { mode, cases })
+    }
+
+    fn compare(&self, golden: &image::RgbaImage, rendered: &image::RgbaImage) -> SnapshotOutcome {
+        if golden.dimensions() != rendered.dimensions() {
+            return SnapshotOutcome::Resized { golden: golden.dimensions(), rendered: rendered.dimensions() };
+        }
+
+        let mut pixels = 0;
+        let mut max_delta = 0;
+
+        for (golden, rendered) in golden.pixels().zip(rendered.pixels()) {
+            let delta = golden.0.iter().zip(rendered.0).map(|(a, b)| a.abs_diff(b)).max().unwrap_or(0);
+            if delta > self.tolerance {
+                pixels += 1;
+            }
+            max_delta = max_delta.max(delta);
+        }
+
+        match pixels {
+            0 => SnapshotOutcome::Unchanged,
+            pixels => SnapshotOutcome::Changed { pixels, max_delta },
+        }
+    }
+}
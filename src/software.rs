@@ -0,0 +1,301 @@
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use winit::window::Window;
+
+use crate::convert;
+use crate::types::{self, FrameProvider, FrameRenderContext, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Orientation, Pair, PixelFormat, RowOrder};
+
+#[derive(Debug)]
+pub enum SoftwareRenderError {
+    // no softbuffer backend for the window's platform or display
+    Init(softbuffer::SoftBufferError),
+    Present(softbuffer::SoftBufferError),
+}
+
+impl fmt::Display for SoftwareRenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoftwareRenderError::Init(error) => write!(f, "failed to create a software surface: {error}"),
+            SoftwareRenderError::Present(error) => write!(f, "failed to present a software frame: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SoftwareRenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SoftwareRenderError::Init(error) | SoftwareRenderError::Present(error) => Some(error),
+        }
+    }
+}
+
+pub struct SoftbufferFrameRenderContextInit {
+    pub window: Arc<Window>,
+    pub surface_size: Pair<u32>,
+    // srgb, black when `None`
+    pub clear_color: Option<[u8; 3]>,
+}
+
+impl HasSize<u32> for SoftbufferFrameRenderContextInit {
+    fn size(&self) -> Pair<u32> {
+        self.surface_size
+    }
+}
+
+// draws frames on the cpu into a window through softbuffer, for machines without usable
+// gpu drivers
+// frames are fit into the window and sampled nearest, without the filters, shading and
+// overlays of `WgpuFrameRenderContext`
+pub struct SoftbufferFrameRenderContext {
+    surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
+    size: Pair<u32>,
+    clear_color: [u8; 3],
+    zoom: f32,
+    pan: Pair<f32>,
+    // the last frame as stored, upright only when drawn, kept to redraw after a resize or
+    // a view change and to copy dirty rectangles into
+    image: Vec<[u8; 4]>,
+    image_size: Pair<u32>,
+    orientation: Orientation,
+    content_id: Option<u64>,
+    needs_redraw: bool,
+}
+
+impl TryFrom<SoftbufferFrameRenderContextInit> for SoftbufferFrameRenderContext {
+    type Error = SoftwareRenderError;
+
+    fn try_from(init: SoftbufferFrameRenderContextInit) -> Result<Self, Self::Error> {
+        let context = softbuffer::Context::new(init.window.clone()).map_err(SoftwareRenderError::Init)?;
+        let surface = softbuffer::Surface::new(&context, init.window).map_err(SoftwareRenderError::Init)?;
+
+        Ok(Self {
+            surface,
+            size: init.surface_size,
+            clear_color: init.clear_color.unwrap_or_default(),
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+            image: Vec::new(),
+            image_size: (0, 0),
+            orientation: Orientation::Normal,
+            content_id: None,
+            needs_redraw: true,
+        })
+    }
+}
+
+impl HasSize<u32> for SoftbufferFrameRenderContext {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl SoftbufferFrameRenderContext {
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    // scales the frame around the window center, 1.0 fits it
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if !zoom.is_finite() || zoom <= 0.0 {
+            log::warn!("ignoring invalid zoom {zoom}");
+            return;
+        }
+
+        self.zoom = zoom;
+        self.needs_redraw = true;
+    }
+
+    pub fn pan(&self) -> Pair<f32> {
+        self.pan
+    }
+
+    // moves the frame by a distance in window pixels
+    pub fn pan_by(&mut self, delta: Pair<f32>) {
+        self.pan = (self.pan.0 + delta.0, self.pan.1 + delta.1);
+        self.needs_redraw = true;
+    }
+
+    pub fn clear_color(&self) -> [u8; 3] {
+        self.clear_color
+    }
+
+    pub fn set_clear_color(&mut self, color: [u8; 3]) {
+        self.clear_color = color;
+        self.needs_redraw = true;
+    }
+
+    pub fn reset_view(&mut self) {
+        self.zoom = 1.0;
+        self.pan = (0.0, 0.0);
+        self.needs_redraw = true;
+    }
+
+    // copies a frame into `image`, a frame with a canvas size lands at its position on the
+    // previous one, or on a cleared canvas of that size when the size changed
+    fn store<Frame>(&mut self, frame: &Frame)
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        let size = frame.size();
+        let (canvas, position) = match frame.canvas_size() {
+            Some(canvas) => (canvas, frame.position()),
+            None => (size, (0, 0)),
+        };
+
+        if self.image_size != canvas || frame.canvas_size().is_none() {
+            self.image = vec![[0; 4]; canvas.0 as usize * canvas.1 as usize];
+            self.image_size = canvas;
+        }
+
+        let format = frame.format();
+        let bytes_per_pixel = format.bytes_per_pixel();
+        let stride = frame.row_stride().unwrap_or(size.0 as usize * bytes_per_pixel);
+        let palette = frame.palette().unwrap_or_default();
+        let data = frame.data();
+
+        for row in 0..size.1.min(canvas.1.saturating_sub(position.1)) {
+            let target = (row + position.1) as usize * canvas.0 as usize + position.0 as usize;
+            let width = size.0.min(canvas.0.saturating_sub(position.0)) as usize;
+
+            if format.is_planar() {
+                for x in 0..width {
+                    self.image[target + x] = types::yuv_at(frame, x as u32, row).unwrap_or_default();
+                }
+                continue;
+            }
+
+            let source_row = match frame.row_order() {
+                RowOrder::TopDown => row,
+                RowOrder::BottomUp => size.1 - 1 - row,
+            };
+            let Some(source) = data.get(source_row as usize * stride..) else {
+                break;
+            };
+
+            for (x, pixel) in source.chunks_exact(bytes_per_pixel).take(width).enumerate() {
+                self.image[target + x] = to_rgba8(pixel, format, palette);
+            }
+        }
+
+        self.orientation = frame.orientation();
+        self.content_id = frame.content_id();
+    }
+
+    fn present(&mut self) -> Result<(), SoftwareRenderError> {
+        let (Some(width), Some(height)) = (NonZeroU32::new(self.size.0), NonZeroU32::new(self.size.1)) else {
+            return Ok(());
+        };
+
+        self.surface.resize(width, height).map_err(SoftwareRenderError::Present)?;
+        let mut buffer = self.surface.buffer_mut().map_err(SoftwareRenderError::Present)?;
+
+        let [r, g, b] = self.clear_color;
+        let clear = [r, g, b, u8::MAX];
+        let stored = self.image_size;
+        let displayed = self.orientation.displayed_size(stored);
+
+        // fit like `ScalingMode::Fit`, then zoom around the center
+        let scale = (self.size.0 as f32 / displayed.0.max(1) as f32).min(self.size.1 as f32 / displayed.1.max(1) as f32) * self.zoom;
+        let extent = (displayed.0 as f32 * scale, displayed.1 as f32 * scale);
+        let origin = (
+            (self.size.0 as f32 - extent.0) / 2.0 + self.pan.0,
+            (self.size.1 as f32 - extent.1) / 2.0 + self.pan.1,
+        );
+
+        for (y, row) in buffer.chunks_exact_mut(self.size.0 as usize).enumerate() {
+            let v = (y as f32 + 0.5 - origin.1) / extent.1;
+
+            for (x, target) in row.iter_mut().enumerate() {
+                let u = (x as f32 + 0.5 - origin.0) / extent.0;
+
+                let pixel = match (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) && !self.image.is_empty() {
+                    true => {
+                        let (u, v) = self.orientation.stored_point((u, v));
+                        let column = ((u * stored.0 as f32) as u32).min(stored.0 - 1);
+                        let row = ((v * stored.1 as f32) as u32).min(stored.1 - 1);
+                        blend(self.image[(row * stored.0 + column) as usize], clear)
+                    },
+                    false => clear,
+                };
+
+                *target = u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]]);
+            }
+        }
+
+        buffer.present().map_err(SoftwareRenderError::Present)?;
+        self.needs_redraw = false;
+        Ok(())
+    }
+}
+
+impl FrameRenderContext for SoftbufferFrameRenderContext {
+    type RenderError = SoftwareRenderError;
+    type Init = SoftbufferFrameRenderContextInit;
+    type Stats = ();
+
+    fn configure(&mut self, size: Pair<u32>) {
+        self.size = size;
+        self.needs_redraw = true;
+    }
+
+    fn draw_frame<Frame>(&mut self, mut frame_provider: impl FrameProvider<Frame = Frame>) -> Result<Self::Stats, Self::RenderError>
+    where
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData
+    {
+        if let Some(frame) = frame_provider.poll_frame().into_frame() {
+            let (width, height) = frame.size();
+            let row_len = width as usize * frame.format().bytes_per_pixel();
+            // the last row doesn't have to be padded to the stride
+            let expected = frame.row_stride().unwrap_or(row_len) * (height as usize).saturating_sub(1) + row_len;
+
+            let planes_fit = (1..frame.format().plane_count()).all(|index| types::plane(&frame, index).is_some());
+
+            if width == 0 || height == 0 || frame.data().len() < expected || !planes_fit {
+                log::warn!("dropping a {width}x{height} frame with {} bytes of data", frame.data().len());
+            } else if frame.content_id().is_none() || frame.content_id() != self.content_id {
+                self.store(&frame);
+                self.needs_redraw = true;
+            }
+        }
+
+        match self.needs_redraw {
+            true => self.present(),
+            false => Ok(()),
+        }
+    }
+}
+
+// srgb encoded rgba8 of one stored pixel, float frames are linear and clipped
+fn to_rgba8(pixel: &[u8], format: PixelFormat, palette: &[[u8; 4]]) -> [u8; 4] {
+    match format {
+        PixelFormat::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+        PixelFormat::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        PixelFormat::Rgb8 => [pixel[0], pixel[1], pixel[2], u8::MAX],
+        PixelFormat::Bgr8 => [pixel[2], pixel[1], pixel[0], u8::MAX],
+        // the luma, planar frames are read with `types::yuv_at`
+        PixelFormat::Gray8 | PixelFormat::Nv12 | PixelFormat::I420 => [pixel[0], pixel[0], pixel[0], u8::MAX],
+        PixelFormat::Indexed8 => palette.get(pixel[0] as usize).copied().unwrap_or_default(),
+        PixelFormat::Rgba16Float => {
+            let channel = |index: usize| convert::f16_to_f32(u16::from_le_bytes([pixel[2 * index], pixel[2 * index + 1]])).clamp(0.0, 1.0);
+            let encode = |linear: f32| {
+                let srgb = match linear <= 0.003_130_8 {
+                    true => linear * 12.92,
+                    false => 1.055 * linear.powf(1.0 / 2.4) - 0.055,
+                };
+                (srgb * 255.0).round() as u8
+            };
+
+            [encode(channel(0)), encode(channel(1)), encode(channel(2)), (channel(3) * 255.0).round() as u8]
+        },
+    }
+}
+
+// straight alpha over an opaque background, in srgb like the gpu path without hdr
+fn blend(pixel: [u8; 4], background: [u8; 4]) -> [u8; 4] {
+    let alpha = pixel[3] as u32;
+    let mix = |index: usize| ((pixel[index] as u32 * alpha + background[index] as u32 * (255 - alpha) + 127) / 255) as u8;
+
+    [mix(0), mix(1), mix(2), u8::MAX]
+}
@@ -0,0 +1,150 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use crate::types::{FramePoll, FrameProvider};
+
+// a frame provider that can be driven by an async runtime, `Poll::Ready(None)` ends the stream
+// the same way an exhausted `Iterator` does
+pub trait AsyncFrameSource {
+    type Frame;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Frame>>;
+}
+
+impl<S: AsyncFrameSource + Unpin + ?Sized> AsyncFrameSource for &mut S {
+    type Frame = S::Frame;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Frame>> {
+        Pin::new(&mut **self).poll_frame(cx)
+    }
+}
+
+pub trait AsyncFrameSourceExt: AsyncFrameSource {
+    fn next_frame(&mut self) -> NextFrame<'_, Self>
+    where
+        Self: Unpin
+    {
+        NextFrame { source: self }
+    }
+
+    // bridges to the synchronous render loop without ever blocking it: a pending source
+    // yields `None` for that draw and the previous frame stays on screen
+    fn polling(self) -> PollingFrames<Self>
+    where
+        Self: Sized + Unpin
+    {
+        PollingFrames { source: self, finished: false }
+    }
+
+    // bridges to the synchronous render loop by parking the render thread until a frame is ready
+    #[cfg(feature = "std")]
+    fn blocking(self) -> BlockingFrames<Self>
+    where
+        Self: Sized + Unpin
+    {
+        BlockingFrames { source: self }
+    }
+}
+
+impl<S: AsyncFrameSource + ?Sized> AsyncFrameSourceExt for S {}
+
+pub struct NextFrame<'a, S: ?Sized> {
+    source: &'a mut S,
+}
+
+impl<S: AsyncFrameSource + Unpin + ?Sized> Future for NextFrame<'_, S> {
+    type Output = Option<S::Frame>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.source).poll_frame(cx)
+    }
+}
+
+// any iterator is a source that is always ready
+pub struct IterSource<I> {
+    iter: I,
+}
+
+pub fn from_iter<I: IntoIterator>(iter: I) -> IterSource<I::IntoIter> {
+    IterSource { iter: iter.into_iter() }
+}
+
+impl<I: Iterator + Unpin> AsyncFrameSource for IterSource<I> {
+    type Frame = I::Item;
+
+    fn poll_frame(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Frame>> {
+        Poll::Ready(self.iter.next())
+    }
+}
+
+pub struct PollingFrames<S> {
+    source: S,
+    finished: bool,
+}
+
+impl<S> PollingFrames<S> {
+    // true once the source returned `Ready(None)`, pending draws look the same from the outside
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl<S: AsyncFrameSource + Unpin> Iterator for PollingFrames<S> {
+    type Item = S::Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        // the render loop polls again on its next redraw, so nobody needs waking
+        let mut cx = Context::from_waker(Waker::noop());
+
+        match Pin::new(&mut self.source).poll_frame(&mut cx) {
+            Poll::Ready(Some(frame)) => Some(frame),
+            Poll::Ready(None) => {
+                self.finished = true;
+                None
+            },
+            Poll::Pending => None,
+        }
+    }
+}
+
+// a pending source is no new frame yet, the end of the stream finishes the provider
+impl<S: AsyncFrameSource + Unpin> FrameProvider for PollingFrames<S> {
+    type Frame = S::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self.next() {
+            Some(frame) => FramePoll::Ready(frame),
+            None if self.finished => FramePoll::Finished,
+            None => FramePoll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub struct BlockingFrames<S> {
+    source: S,
+}
+
+#[cfg(feature = "std")]
+impl<S: AsyncFrameSource + Unpin> Iterator for BlockingFrames<S> {
+    type Item = S::Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        crate::executor::block_on(self.source.next_frame())
+    }
+}
+
+// only the end of the stream comes back without a frame
+#[cfg(feature = "std")]
+impl<S: AsyncFrameSource + Unpin> FrameProvider for BlockingFrames<S> {
+    type Frame = S::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        self.next().map_or(FramePoll::Finished, FramePoll::Ready)
+    }
+}
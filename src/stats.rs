@@ -0,0 +1,146 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::render::ResourceLabels;
+
+// two timestamps of 8 bytes, one at either end of the pass
+const TIMESTAMP_BYTES: u64 = 2 * wgpu::QUERY_SIZE as u64;
+
+// what the last draw cost, e.g. to log next to the frame number or compare between builds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    // recording, uploading and submitting the frame, without the wait for a surface texture
+    pub cpu_time: Duration,
+    // the pass drawing the view, measured with timestamp queries, so it's `None` on devices
+    // without `wgpu::Features::TIMESTAMP_QUERY`
+    // the timestamps come back a frame or more later, this is the latest that did, filters
+    // and uploads run before the pass and aren't counted
+    pub gpu_time: Option<Duration>,
+    // frame pixels written into textures, palettes aside, 0 when the view was drawn again
+    // from the texture
+    pub upload_bytes: u64,
+    // from the start of the draw until the frame was handed to the compositor, including
+    // the wait for a surface texture, which is where vsync shows
+    pub present_latency: Duration,
+}
+
+// times the render pass with a pair of timestamps resolved into a buffer that is mapped
+// once the gpu is done with it, passes drawn while a readback is in flight aren't timed
+#[derive(Debug)]
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    // nanoseconds per tick
+    period: f32,
+    in_flight: bool,
+    // set by the map callback, whether the mapping worked
+    mapped: Arc<Mutex<Option<bool>>>,
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    // `None` when the device can't write timestamps
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue, labels: &ResourceLabels) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(&labels.get("Timestamp Query Set")),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&labels.get("Timestamp Resolve Buffer")),
+            size: TIMESTAMP_BYTES,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&labels.get("Timestamp Read Buffer")),
+            size: TIMESTAMP_BYTES,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            read_buffer,
+            period: queue.get_timestamp_period(),
+            in_flight: false,
+            mapped: Arc::new(Mutex::new(None)),
+            last: None,
+        })
+    }
+
+    // picks up the timestamps of an earlier pass if they came back, true when the next pass
+    // can be timed
+    pub(crate) fn poll(&mut self, device: &wgpu::Device) -> bool {
+        if !self.in_flight {
+            return true;
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+
+        let mapped = self.mapped.lock().unwrap().take();
+        match mapped {
+            None => return false,
+            // the buffer was never mapped, so the next pass can use it again
+            Some(false) => {
+                self.in_flight = false;
+                return true;
+            },
+            Some(true) => (),
+        }
+
+        {
+            let range = self.read_buffer.slice(..).get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&range);
+            let ticks = timestamps[1].saturating_sub(timestamps[0]);
+            self.last = Some(Duration::from_nanos((ticks as f64 * self.period as f64) as u64));
+        }
+
+        self.read_buffer.unmap();
+        self.in_flight = false;
+        true
+    }
+
+    pub(crate) fn last(&self) -> Option<Duration> {
+        self.last
+    }
+
+    pub(crate) fn timestamp_writes(&self) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        }
+    }
+
+    // after the timed pass, in the same encoder
+    pub(crate) fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.read_buffer, 0, TIMESTAMP_BYTES);
+    }
+
+    // once the encoder holding `resolve` was submitted
+    pub(crate) fn read_back(&mut self) {
+        let mapped = self.mapped.clone();
+        self.read_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            if let Err(error) = &result {
+                log::warn!("failed to read timestamps: {error}");
+            }
+
+            *mapped.lock().unwrap() = Some(result.is_ok());
+        });
+        self.in_flight = true;
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.resolve_buffer.size() + self.read_buffer.size()
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+use std::time::Duration;
+
+// timed text from an srt file, e.g. the captions of a video
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subtitles {
+    // sorted by start
+    cues: Vec<Cue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    pub start: Duration,
+    pub end: Duration,
+    // formatting tags stripped, lines separated by '\n'
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleError {
+    // 1 based
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for SubtitleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid srt on line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for SubtitleError {}
+
+impl Subtitles {
+    // cues are numbered blocks of a timing line and the text, separated by blank lines,
+    // the numbers are optional and ignored
+    pub fn from_srt(srt: &str) -> Result<Self, SubtitleError> {
+        let srt = srt.strip_prefix('\u{feff}').unwrap_or(srt);
+        let mut lines = srt.lines().map(|line| line.trim_end_matches('\r')).enumerate().peekable();
+        let mut cues = Vec::new();
+
+        loop {
+            while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+
+            let Some((mut index, mut line)) = lines.next() else {
+                break;
+            };
+
+            if !line.contains("-->") {
+                (index, line) = lines.next().ok_or(SubtitleError { line: index + 2, reason: "missing timing line" })?;
+            }
+
+            let (start, end) = parse_timing(line).ok_or(SubtitleError { line: index + 1, reason: "bad timing line" })?;
+            let mut text = String::new();
+
+            while let Some((_, line)) = lines.next_if(|(_, line)| !line.trim().is_empty()) {
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&strip_tags(line));
+            }
+
+            cues.push(Cue { start, end, text });
+        }
+
+        cues.sort_by_key(|cue| cue.start);
+        Ok(Self { cues })
+    }
+
+    pub fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    // every cue showing at `position`, overlapping ones too
+    pub fn cues_at(&self, position: Duration) -> impl Iterator<Item = &Cue> {
+        self.cues
+            .iter()
+            .take_while(move |cue| cue.start <= position)
+            .filter(move |cue| position < cue.end)
+    }
+
+    // the text of `cues_at` one cue after another, `None` between cues
+    pub fn text_at(&self, position: Duration) -> Option<String> {
+        let texts: Vec<&str> = self.cues_at(position).map(|cue| cue.text.as_str()).collect();
+        Some(texts.join("\n")).filter(|text| !text.is_empty())
+    }
+}
+
+// `00:01:02,500 --> 00:01:04,000`, players also accept a dot before the milliseconds and
+// position hints after the end
+fn parse_timing(line: &str) -> Option<(Duration, Duration)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let (clock, millis) = timestamp.split_once([',', '.'])?;
+    let mut parts = clock.split(':').map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+
+    if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+
+    let seconds = hours.checked_mul(3600)?.checked_add(minutes * 60 + seconds)?;
+    Duration::from_secs(seconds).checked_add(Duration::from_millis(millis.parse().ok()?))
+}
+
+// `<i>`, `<font color=..>` and the like, and ass style overrides such as `{\an8}`
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+
+    for character in line.chars() {
+        match (closing, character) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, character) => text.push(character),
+            (Some(close), character) if character == close => closing = None,
+            (Some(_), _) => (),
+        }
+    }
+
+    text
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CaptionPosition {
+    #[default]
+    Bottom,
+    Top,
+}
+
+// how captions are drawn over the frame, in surface pixels so they stay readable at any zoom
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionStyle {
+    // height of a line of text, of the capitals in the built in font
+    pub font_size: f32,
+    // 0 draws the text without an outline
+    pub outline: f32,
+    pub position: CaptionPosition,
+    // linear rgba
+    pub color: [f32; 4],
+    pub outline_color: [f32; 4],
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 21.0,
+            outline: 2.0,
+            position: CaptionPosition::default(),
+            color: [1.0; 4],
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl CaptionStyle {
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    pub fn with_outline(mut self, outline: f32) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    pub fn with_position(mut self, position: CaptionPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_outline_color(mut self, outline_color: [f32; 4]) -> Self {
+        self.outline_color = outline_color;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start: u64, end: u64, text: &str) -> Cue {
+        Cue { start: Duration::from_millis(start), end: Duration::from_millis(end), text: String::from(text) }
+    }
+
+    #[test]
+    fn parses_loose_srt() {
+        let srt = "\u{feff}1\r\n00:00:05,000 --> 00:00:07,500\r\n<i>Second</i>\r\n{\\an8}line\r\n\r\n\r\n\
+                   00:00:01.250 --> 00:00:06,000 X1:0 X2:10\nFirst\n\n3\n01:00:00,000 --> 01:00:01,000\n";
+
+        let subtitles = Subtitles::from_srt(srt).unwrap();
+        assert_eq!(subtitles.cues(), [cue(1250, 6000, "First"), cue(5000, 7500, "Second\nline"), cue(3_600_000, 3_601_000, "")]);
+
+        assert_eq!(subtitles.text_at(Duration::from_secs(2)).as_deref(), Some("First"));
+        assert_eq!(subtitles.text_at(Duration::from_millis(5500)).as_deref(), Some("First\nSecond\nline"));
+        assert_eq!(subtitles.text_at(Duration::from_secs(6)).as_deref(), Some("Second\nline"));
+        assert_eq!(subtitles.text_at(Duration::from_secs(8)), None);
+        assert_eq!(subtitles.text_at(Duration::from_secs(3600)), None);
+        assert_eq!(Subtitles::from_srt("\n \n").unwrap(), Subtitles::default());
+    }
+
+    #[test]
+    fn reports_the_failing_line() {
+        let error = |srt: &str| Subtitles::from_srt(srt).unwrap_err();
+
+        assert_eq!(error("1\n00:00:01,000 --> 00:00:02,000\nok\n\n2"), SubtitleError { line: 6, reason: "missing timing line" });
+        assert_eq!(error("\n\n1\n00:00:01 --> 00:00:02,000"), SubtitleError { line: 4, reason: "bad timing line" });
+        assert_eq!(error("00:61:00,000 --> 01:00:00,000"), SubtitleError { line: 1, reason: "bad timing line" });
+        assert_eq!(error("99999999999999999:00:00,000 --> 0:00:00,000"), SubtitleError { line: 1, reason: "bad timing line" });
+    }
+}
@@ -0,0 +1,531 @@
+use crate::overlay::OverlayRect;
+use crate::subtitles::{CaptionPosition, CaptionStyle};
+use crate::types::Pair;
+
+#[cfg(feature = "fonts")]
+pub(crate) use crate::font::Font;
+
+// without the `fonts` feature there is only the built in font
+#[cfg(not(feature = "fonts"))]
+#[derive(Clone, Debug)]
+pub(crate) enum Font {}
+
+#[cfg(not(feature = "fonts"))]
+impl Font {
+    pub(crate) fn glyph_id(&self, _character: char) -> u16 {
+        match *self {}
+    }
+
+    pub(crate) fn metrics(&self, _size: f32) -> (f32, f32, f32) {
+        match *self {}
+    }
+
+    pub(crate) fn h_advance(&self, _glyph: u16, _size: f32) -> f32 {
+        match *self {}
+    }
+
+    pub(crate) fn kern(&self, _previous: u16, _glyph: u16, _size: f32) -> f32 {
+        match *self {}
+    }
+
+    pub(crate) fn rasterize(&self, _glyph: u16, _size: f32) -> Option<(Pair<f32>, Pair<u32>, Vec<u8>)> {
+        match *self {}
+    }
+}
+
+// glyphs are 5x7 cells, a cell is a seventh of the font size
+const GLYPH_ROWS: f32 = 7.0;
+const ADVANCE: f32 = 6.0;
+const LINE_HEIGHT: f32 = 10.0;
+
+// between the captions and the edge of the surface, in surface pixels
+const MARGIN: f32 = 16.0;
+
+// badges are smaller than captions, 14 pixel capitals on linear amber
+const BADGE_CELL: f32 = 2.0;
+const BADGE_COLOR: [f32; 4] = [0.9, 0.45, 0.02, 0.9];
+
+// labels next to a point, e.g. the cursor, white on a dark plate
+const LABEL_CELL: f32 = 2.0;
+const LABEL_OFFSET: f32 = 16.0;
+const LABEL_PLATE: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+
+// lines of status in the top right corner, e.g. the zoom, white on a dark plate like the
+// labels
+const HUD_CELL: f32 = 2.0;
+
+// printable ascii from the space on, rows top to bottom, the high bit of the five is the
+// left cell, anything else is drawn as '?'
+const FONT: [[u8; 7]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x04, 0x04, 0x04, 0x04, 0x00, 0x00, 0x04],
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00],
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a],
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04],
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d],
+    [0x0c, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00],
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08],
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08],
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00],
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e],
+    [0x0e, 0x11, 0x11, 0x11, 0x1f, 0x11, 0x11],
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+    [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e],
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00],
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e],
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f],
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e],
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e],
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f],
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e],
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08],
+    [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e],
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11],
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e],
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c],
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12],
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11],
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11],
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e],
+    [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10],
+    [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01],
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10],
+    [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e],
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06],
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d],
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04],
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a],
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11],
+    [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e],
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f],
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02],
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08],
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00],
+];
+
+fn glyph(character: char) -> &'static [u8; 7] {
+    match character {
+        ' '..='~' => &FONT[character as usize - ' ' as usize],
+        _ => &FONT['?' as usize - ' ' as usize],
+    }
+}
+
+// a glyph of a loaded font with its pen position on the baseline, in surface pixels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct PlacedGlyph {
+    pub glyph: u16,
+    pub size: f32,
+    pub position: Pair<f32>,
+    pub color: [f32; 4],
+}
+
+// plates and the cells of the built in font go with the other overlay rects, the glyphs of
+// a loaded font are drawn over all of them by the glyph pass
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TextShapes {
+    pub rects: Vec<OverlayRect>,
+    pub glyphs: Vec<PlacedGlyph>,
+}
+
+impl TextShapes {
+    pub(crate) fn append(&mut self, mut shapes: TextShapes) {
+        self.rects.append(&mut shapes.rects);
+        self.glyphs.append(&mut shapes.glyphs);
+    }
+}
+
+// the lit cells of `line` with its top left at `origin`, `grow` pixels added on every side
+fn cell_rects(line: &str, origin: Pair<f32>, cell: f32, grow: f32, color: [f32; 4], rects: &mut Vec<OverlayRect>) {
+    for (index, character) in line.chars().enumerate() {
+        let left = origin.0 + index as f32 * ADVANCE * cell;
+
+        for (row, bits) in glyph(character).iter().enumerate() {
+            for column in (0..5).filter(|column| bits & (0b10000 >> column) != 0) {
+                let min = (left + column as f32 * cell, origin.1 + row as f32 * cell);
+                rects.push(OverlayRect {
+                    min: (min.0 - grow, min.1 - grow),
+                    max: (min.0 + cell + grow, min.1 + cell + grow),
+                    color,
+                });
+            }
+        }
+    }
+}
+
+// text `size` pixels from the top of the capitals to the bottom of the descenders, in
+// `font` or the built in one without it
+#[derive(Clone, Copy, Debug)]
+struct Face<'a> {
+    font: Option<&'a Font>,
+    size: f32,
+}
+
+impl Face<'_> {
+    // surface pixels per cell of the built in font
+    fn cell(&self) -> f32 {
+        (self.size / GLYPH_ROWS).max(1.0)
+    }
+
+    // of a single line
+    fn height(&self) -> f32 {
+        match self.font {
+            Some(font) => {
+                let (ascent, descent, _) = font.metrics(self.size);
+                ascent - descent
+            },
+            None => GLYPH_ROWS * self.cell(),
+        }
+    }
+
+    // from the top of a line to the top of the next
+    fn line_height(&self) -> f32 {
+        match self.font {
+            Some(font) => {
+                let (ascent, descent, line_gap) = font.metrics(self.size);
+                ascent - descent + line_gap
+            },
+            None => LINE_HEIGHT * self.cell(),
+        }
+    }
+
+    // of `lines` lines without the gap below the last one
+    fn block_height(&self, lines: usize) -> f32 {
+        lines.saturating_sub(1) as f32 * self.line_height() + self.height()
+    }
+
+    // the pen position of every glyph of `line` from its start, and of the end of the line
+    fn pen_positions(&self, line: &str, font: &Font) -> (Vec<(u16, f32)>, f32) {
+        let mut pen = 0.0;
+        let mut previous = None;
+
+        let glyphs = line
+            .chars()
+            .map(|character| {
+                let glyph = font.glyph_id(character);
+                pen += previous.map_or(0.0, |previous| font.kern(previous, glyph, self.size));
+                let position = pen;
+
+                pen += font.h_advance(glyph, self.size);
+                previous = Some(glyph);
+                (glyph, position)
+            })
+            .collect();
+
+        (glyphs, pen)
+    }
+
+    // how far the pen moves over `line`, up to where a next character would start
+    fn advance(&self, line: &str) -> f32 {
+        match self.font {
+            Some(font) => self.pen_positions(line, font).1,
+            None => line.chars().count() as f32 * ADVANCE * self.cell(),
+        }
+    }
+
+    fn width(&self, line: &str) -> f32 {
+        match self.font {
+            Some(font) => self.pen_positions(line, font).1,
+            None => (line.chars().count() as f32 * ADVANCE - 1.0).max(0.0) * self.cell(),
+        }
+    }
+
+    // `line` with its top left at `origin`, `grow` pixels added on every side
+    fn push_line(&self, line: &str, origin: Pair<f32>, grow: f32, color: [f32; 4], shapes: &mut TextShapes) {
+        let Some(font) = self.font else {
+            cell_rects(line, origin, self.cell(), grow, color, &mut shapes.rects);
+            return;
+        };
+
+        // grown glyphs are the glyph drawn around where it is
+        let offsets: Vec<Pair<f32>> = match grow > 0.0 {
+            true => (0..8)
+                .map(|step| step as f32 * std::f32::consts::FRAC_PI_4)
+                .map(|angle| (grow * angle.cos(), grow * angle.sin()))
+                .collect(),
+            false => vec![(0.0, 0.0)],
+        };
+
+        let baseline = origin.1 + font.metrics(self.size).0;
+        let (glyphs, _) = self.pen_positions(line, font);
+
+        for (glyph, pen) in glyphs {
+            for offset in &offsets {
+                shapes.glyphs.push(PlacedGlyph { glyph, size: self.size, position: (origin.0 + pen + offset.0, baseline + offset.1), color });
+            }
+        }
+    }
+
+    // breaks lines wider than `width` between words, words wider than that are broken
+    // anywhere
+    fn wrap(&self, text: &str, width: f32) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for paragraph in text.lines() {
+            let mut line = String::new();
+
+            for word in paragraph.split_whitespace() {
+                let joined = match line.is_empty() {
+                    true => word.to_owned(),
+                    false => format!("{line} {word}"),
+                };
+
+                if self.advance(&joined) <= width {
+                    line = joined;
+                    continue;
+                }
+
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+
+                // at least one character a line
+                let mut rest = word;
+                while rest.chars().nth(1).is_some() && self.advance(rest) > width {
+                    let first = rest.chars().next().map_or(rest.len(), char::len_utf8);
+                    let end = rest
+                        .char_indices()
+                        .map(|(index, _)| index)
+                        .skip(1)
+                        .take_while(|end| self.advance(&rest[..*end]) <= width)
+                        .last()
+                        .unwrap_or(first);
+
+                    lines.push(rest[..end].to_owned());
+                    rest = &rest[end..];
+                }
+
+                line = rest.to_owned();
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+// centered lines along the bottom or top of the surface, `inset` keeps them clear of
+// whatever else is drawn along that edge, e.g. the scrub bar
+pub(crate) fn caption_shapes(text: &str, style: &CaptionStyle, surface_size: Pair<u32>, inset: f32, font: Option<&Font>) -> TextShapes {
+    let face = Face { font, size: style.font_size };
+    let (width, height) = (surface_size.0 as f32, surface_size.1 as f32);
+
+    let lines = face.wrap(text, width - 2.0 * MARGIN);
+    let block_height = face.block_height(lines.len());
+    let top = match style.position {
+        CaptionPosition::Bottom => height - inset - MARGIN - block_height,
+        CaptionPosition::Top => MARGIN,
+    };
+
+    let origins: Vec<Pair<f32>> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| ((width - face.width(line)) / 2.0, top + index as f32 * face.line_height()))
+        .collect();
+
+    let mut shapes = TextShapes::default();
+
+    // every outline goes under every fill so neighbouring glyphs don't cover each other
+    if style.outline > 0.0 {
+        for (line, origin) in lines.iter().zip(&origins) {
+            face.push_line(line, *origin, style.outline, style.outline_color, &mut shapes);
+        }
+    }
+
+    for (line, origin) in lines.iter().zip(&origins) {
+        face.push_line(line, *origin, 0.0, style.color, &mut shapes);
+    }
+
+    shapes
+}
+
+// a warning on an amber plate in the top left corner, e.g. that the frame is incomplete,
+// it keeps its size at any zoom like the captions
+pub(crate) fn badge_shapes(text: &str, surface_size: Pair<u32>, font: Option<&Font>) -> TextShapes {
+    let face = Face { font, size: GLYPH_ROWS * BADGE_CELL };
+    let padding = 3.0 * BADGE_CELL;
+
+    let lines = face.wrap(text, surface_size.0 as f32 - 2.0 * (MARGIN + padding));
+    let width = lines.iter().map(|line| face.width(line)).fold(0.0, f32::max);
+    let height = face.block_height(lines.len());
+
+    let mut shapes = TextShapes::default();
+    shapes.rects.push(OverlayRect {
+        min: (MARGIN, MARGIN),
+        max: (MARGIN + width + 2.0 * padding, MARGIN + height + 2.0 * padding),
+        color: BADGE_COLOR,
+    });
+
+    for (index, line) in lines.iter().enumerate() {
+        let origin = (MARGIN + padding, MARGIN + padding + index as f32 * face.line_height());
+        face.push_line(line, origin, 0.0, [0.0, 0.0, 0.0, 1.0], &mut shapes);
+    }
+
+    shapes
+}
+
+// a single line below and right of `point`, flipped to the other side of it where it
+// would run off the surface
+pub(crate) fn label_shapes(text: &str, point: Pair<f32>, surface_size: Pair<u32>, font: Option<&Font>) -> TextShapes {
+    let face = Face { font, size: GLYPH_ROWS * LABEL_CELL };
+    let padding = 2.0 * LABEL_CELL;
+    let size = (face.width(text) + 2.0 * padding, face.height() + 2.0 * padding);
+
+    let place = |point: f32, size: f32, surface: u32| match point + LABEL_OFFSET + size > surface as f32 {
+        true => (point - LABEL_OFFSET - size).max(0.0),
+        false => point + LABEL_OFFSET,
+    };
+    let min = (place(point.0, size.0, surface_size.0), place(point.1, size.1, surface_size.1));
+
+    let mut shapes = TextShapes::default();
+    shapes.rects.push(OverlayRect { min, max: (min.0 + size.0, min.1 + size.1), color: LABEL_PLATE });
+    face.push_line(text, (min.0 + padding, min.1 + padding), 0.0, [1.0; 4], &mut shapes);
+    shapes
+}
+
+// lines in the top right corner, left aligned on a plate as wide as the longest, too long
+// lines run off the left edge rather than being wrapped
+pub(crate) fn hud_shapes(text: &str, surface_size: Pair<u32>, font: Option<&Font>) -> TextShapes {
+    let face = Face { font, size: GLYPH_ROWS * HUD_CELL };
+    let padding = 3.0 * HUD_CELL;
+    let lines: Vec<&str> = text.lines().collect();
+    let mut shapes = TextShapes::default();
+
+    if lines.is_empty() {
+        return shapes;
+    }
+
+    let width = lines.iter().map(|line| face.width(line)).fold(0.0, f32::max);
+    let height = face.block_height(lines.len());
+    let min = (surface_size.0 as f32 - MARGIN - width - 2.0 * padding, MARGIN);
+
+    shapes.rects.push(OverlayRect { min, max: (min.0 + width + 2.0 * padding, min.1 + height + 2.0 * padding), color: LABEL_PLATE });
+
+    for (index, line) in lines.iter().enumerate() {
+        let origin = (min.0 + padding, min.1 + padding + index as f32 * face.line_height());
+        face.push_line(line, origin, 0.0, [1.0; 4], &mut shapes);
+    }
+
+    shapes
+}
+
+// width and height of a single line of `font_size` text
+pub(crate) fn line_size(line: &str, font_size: f32, font: Option<&Font>) -> Pair<f32> {
+    let face = Face { font, size: font_size };
+    (face.width(line), face.height())
+}
+
+// a single line with its top left at `origin`, outlined like the captions
+pub(crate) fn line_shapes(line: &str, origin: Pair<f32>, font_size: f32, outline: f32, color: [f32; 4], outline_color: [f32; 4], font: Option<&Font>) -> TextShapes {
+    let face = Face { font, size: font_size };
+    let mut shapes = TextShapes::default();
+
+    if outline > 0.0 {
+        face.push_line(line, origin, outline, outline_color, &mut shapes);
+    }
+
+    face.push_line(line, origin, 0.0, color, &mut shapes);
+    shapes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_between_words_and_breaks_long_ones() {
+        // a cell of a pixel, six pixels a character
+        let face = Face { font: None, size: GLYPH_ROWS };
+
+        assert_eq!(face.wrap("one two three", 42.0), ["one two", "three"]);
+        assert_eq!(face.wrap("abcdefghij", 24.0), ["abcd", "efgh", "ij"]);
+        assert_eq!(face.wrap("a\nb", 100.0), ["a", "b"]);
+        assert_eq!(face.wrap("wide", 1.0), ["w", "i", "d", "e"]);
+    }
+
+    #[test]
+    fn built_in_font_draws_cells() {
+        let shapes = caption_shapes("A", &CaptionStyle { outline: 0.0, ..CaptionStyle::default() }, (200, 100), 0.0, None);
+
+        // the capital a has 18 lit cells
+        assert_eq!(shapes.rects.len(), 18);
+        assert!(shapes.glyphs.is_empty());
+        assert_eq!(line_size("ab", 14.0, None), (22.0, 14.0));
+    }
+
+    #[cfg(feature = "fonts")]
+    #[test]
+    fn loaded_font_places_glyphs() {
+        // skipped where the system has no dejavu
+        let font = ["/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", "/usr/share/fonts/truetype/DejaVuSans.ttf"]
+            .into_iter()
+            .find_map(|path| Font::open(path).ok());
+        let Some(font) = font else {
+            return;
+        };
+
+        let style = CaptionStyle { outline: 0.0, ..CaptionStyle::default() };
+        let shapes = caption_shapes("Grüße ✓", &style, (400, 100), 0.0, Some(&font));
+
+        assert!(shapes.rects.is_empty());
+        assert_eq!(shapes.glyphs.len(), 7);
+        assert!(shapes.glyphs.iter().all(|glyph| glyph.glyph != 0));
+        assert!(shapes.glyphs.windows(2).all(|pair| pair[0].position.0 < pair[1].position.0));
+
+        // outlines are eight copies around every glyph, under the fills
+        let outlined = caption_shapes("ü", &CaptionStyle::default(), (400, 100), 0.0, Some(&font));
+        assert_eq!(outlined.glyphs.len(), 9);
+        assert!(font.rasterize(shapes.glyphs[2].glyph, style.font_size).is_some());
+    }
+}
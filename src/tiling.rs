@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use crate::transform::RegionOfInterest;
+use crate::types::{self, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair, PixelFormat, RowOrder};
+
+// texels per tile side, the border included
+pub(crate) const TILE_TEXELS: u32 = 512;
+
+// texels repeated from the neighbouring tiles on every side, linear filtering then blends
+// across tile edges like it does inside a single texture
+pub(crate) const BORDER: u32 = 1;
+
+const CONTENT: u32 = TILE_TEXELS - 2 * BORDER;
+
+// at most this many samples per axis are averaged into a texel of a reduced level, so
+// cutting a tile costs the same at any level
+const MAX_SAMPLES: u32 = 4;
+
+// levels past this would be a single texel for any frame a device can hold in memory
+const MAX_LEVEL: u32 = 24;
+
+// a tile of a frame too large for a single texture, at `level` every texel covers
+// 2^level frame pixels on a side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct TileKey {
+    pub level: u32,
+    pub column: u32,
+    pub row: u32,
+}
+
+impl TileKey {
+    fn step(self) -> u64 {
+        1 << self.level
+    }
+
+    // origin and size of the part of the frame the tile shows, in frame pixels
+    pub fn area(self, frame_size: Pair<u32>) -> (Pair<u32>, Pair<u32>) {
+        let span = CONTENT as u64 * self.step();
+        let origin = (self.column as u64 * span, self.row as u64 * span);
+        let size = (span.min(frame_size.0 as u64 - origin.0), span.min(frame_size.1 as u64 - origin.1));
+
+        ((origin.0 as u32, origin.1 as u32), (size.0 as u32, size.1 as u32))
+    }
+
+    // texels of the area, the border excluded
+    pub fn texels(self, frame_size: Pair<u32>) -> Pair<u32> {
+        let (_, size) = self.area(frame_size);
+        ((size.0 as u64).div_ceil(self.step()) as u32, (size.1 as u64).div_ceil(self.step()) as u32)
+    }
+}
+
+// the coarsest level whose texels are still no larger than a surface pixel
+pub(crate) fn level_for(scale: f32) -> u32 {
+    let mut level = 0;
+
+    while level < MAX_LEVEL && scale * (1u64 << (level + 1)) as f32 <= 1.0 {
+        level += 1;
+    }
+
+    level
+}
+
+// the tiles at `level` that cover `region`, rows top to bottom
+pub(crate) fn visible(region: &RegionOfInterest, frame_size: Pair<u32>, level: u32) -> Vec<TileKey> {
+    let span = (CONTENT as u64 * (1 << level)) as f32;
+    let count = |len: u32| (len as u64).div_ceil(span as u64) as u32;
+
+    let first = ((region.origin.0 / span).floor() as u32, (region.origin.1 / span).floor() as u32);
+    let end = (
+        (((region.origin.0 + region.size.0) / span).ceil() as u32).min(count(frame_size.0)),
+        (((region.origin.1 + region.size.1) / span).ceil() as u32).min(count(frame_size.1)),
+    );
+
+    (first.1..end.1)
+        .flat_map(|row| (first.0..end.0).map(move |column| TileKey { level, column, row }))
+        .collect()
+}
+
+// which texture layer holds which tile of the frame on screen
+#[derive(Debug, Default)]
+pub(crate) struct TileCache {
+    pub frame_size: Pair<u32>,
+    pub format: PixelFormat,
+    // `None` when the frame can't tell whether its pixels changed, its tiles are then cut
+    // again on every draw
+    pub content: Option<u64>,
+    pub layers: HashMap<TileKey, u32>,
+}
+
+impl TileCache {
+    pub fn holds<Frame: HasSize<u32> + HasFormat + HasData>(&self, frame: &Frame) -> bool {
+        frame.content_id().is_some() && self.content == frame.content_id() && self.frame_size == frame.size() && self.format == frame.format()
+    }
+
+    pub fn reset<Frame: HasSize<u32> + HasFormat + HasData>(&mut self, frame: &Frame) {
+        *self = Self { frame_size: frame.size(), format: frame.format(), content: frame.content_id(), layers: HashMap::new() };
+    }
+}
+
+// the pixels of a tile with its border, cut from the frame in the frame's own format so
+// they go through the regular upload
+#[derive(Debug)]
+pub(crate) struct TileFrame {
+    size: Pair<u32>,
+    format: PixelFormat,
+    data: Vec<u8>,
+    palette: Option<Vec<[u8; 4]>>,
+}
+
+impl TileFrame {
+    // reduced levels average up to `MAX_SAMPLES` squared pixels per texel, float, indexed
+    // and yuv frames take the one in the middle
+    pub fn cut<Frame: HasSize<u32> + HasFormat + HasData>(frame: &Frame, key: TileKey) -> Self {
+        let frame_size = frame.size();
+        let format = frame.format();
+        let pixel_len = format.bytes_per_pixel();
+        let stride = frame.row_stride().unwrap_or(pixel_len * frame_size.0 as usize);
+        let texels = key.texels(frame_size);
+        let size = (texels.0 + 2 * BORDER, texels.1 + 2 * BORDER);
+        let (origin, _) = key.area(frame_size);
+
+        let step = key.step();
+        let samples = match format {
+            PixelFormat::Rgba16Float | PixelFormat::Indexed8 | PixelFormat::Nv12 | PixelFormat::I420 => 1,
+            _ => step.min(MAX_SAMPLES as u64),
+        };
+
+        // frame pixels sampled for a texel along one axis, clamped to the frame so the
+        // border past its edges repeats the outermost pixels
+        let sample_positions = |texel: u32, origin: u32, len: u32| -> Vec<usize> {
+            let start = origin as i64 + (texel as i64 - BORDER as i64) * step as i64;
+            (0..samples)
+                .map(|sample| (start + ((2 * sample + 1) * step / (2 * samples)) as i64).clamp(0, len as i64 - 1) as usize)
+                .collect()
+        };
+        let columns: Vec<_> = (0..size.0).map(|x| sample_positions(x, origin.0, frame_size.0)).collect();
+        let source_row = |y: usize| match frame.row_order() {
+            RowOrder::TopDown => y,
+            RowOrder::BottomUp => frame_size.1 as usize - 1 - y,
+        };
+
+        // yuv tiles keep their planes, a chroma texel goes with the square of luma texels it
+        // covers
+        if format.is_planar() {
+            let rows: Vec<_> = (0..size.1).map(|y| source_row(sample_positions(y, origin.1, frame_size.1)[0])).collect();
+            let mut data = Vec::new();
+
+            for (index, plane) in (0..format.plane_count()).map_while(|index| Some((index, types::plane(frame, index)?))) {
+                let (plane_size, pixel_len) = (format.plane_size(index, size), format.plane_bytes_per_pixel(index));
+                let scale = if index == 0 { 1 } else { 2 };
+
+                for y in 0..plane_size.1 as usize {
+                    let row = plane.row((rows[y * scale] / scale) as u32);
+
+                    for x in 0..plane_size.0 as usize {
+                        data.extend_from_slice(&row[columns[x * scale][0] / scale * pixel_len..][..pixel_len]);
+                    }
+                }
+            }
+
+            return Self { size, format, data, palette: None };
+        }
+
+        let mut data = Vec::with_capacity(size.0 as usize * size.1 as usize * pixel_len);
+        let mut sum = vec![0u32; pixel_len];
+
+        for y in 0..size.1 {
+            let rows: Vec<_> = sample_positions(y, origin.1, frame_size.1).into_iter().map(|y| &frame.data()[source_row(y) * stride..]).collect();
+
+            for xs in &columns {
+                if samples == 1 {
+                    data.extend_from_slice(&rows[0][xs[0] * pixel_len..][..pixel_len]);
+                    continue;
+                }
+
+                sum.fill(0);
+                for row in &rows {
+                    for x in xs {
+                        for (total, &byte) in sum.iter_mut().zip(&row[x * pixel_len..][..pixel_len]) {
+                            *total += byte as u32;
+                        }
+                    }
+                }
+
+                let count = (rows.len() * xs.len()) as u32;
+                data.extend(sum.iter().map(|total| ((total + count / 2) / count) as u8));
+            }
+        }
+
+        Self { size, format, data, palette: frame.palette().map(<[_]>::to_vec) }
+    }
+}
+
+impl HasSize<u32> for TileFrame {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for TileFrame {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for TileFrame {
+    fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    fn palette(&self) -> Option<&[[u8; 4]]> {
+        self.palette.as_deref()
+    }
+}
+
+impl HasOrientation for TileFrame {}
+
+impl HasData for TileFrame {
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
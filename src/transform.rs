@@ -0,0 +1,107 @@
+use crate::types::Pair;
+use crate::viewport::ScalingMode;
+
+// zoom and pan applied on top of the scaled quad, pan is in surface pixels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ViewTransform {
+    pub zoom: f32,
+    pub pan: Pair<f32>,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self { zoom: 1.0, pan: (0.0, 0.0) }
+    }
+}
+
+impl ViewTransform {
+    // surface pixels grow downwards while clip space grows upwards
+    pub fn uniform(&self, surface_size: Pair<u32>) -> TransformUniform {
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+
+        TransformUniform {
+            offset: [2.0 * self.pan.0 / width, -2.0 * self.pan.1 / height],
+            zoom: self.zoom,
+            _padding: 0.0,
+        }
+    }
+}
+
+// layout of `Transform` in shader.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TransformUniform {
+    pub offset: [f32; 2],
+    pub zoom: f32,
+    _padding: f32,
+}
+
+// the part of a frame that is on screen, in frame pixels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RegionOfInterest {
+    pub origin: Pair<f32>,
+    pub size: Pair<f32>,
+    // surface pixels per frame pixel, above 1.0 when zoomed past the native resolution
+    pub scale: f32,
+}
+
+impl ViewTransform {
+    // `None` when the frame has been panned entirely off screen
+    pub fn visible_region(&self, frame_size: Pair<u32>, surface_size: Pair<u32>, mode: ScalingMode) -> Option<RegionOfInterest> {
+        let rect = mode.clip_rect(frame_size, surface_size);
+        let uniform = self.uniform(surface_size);
+
+        // edges of the quad in clip space after zoom and pan
+        let left = rect.left * self.zoom + uniform.offset[0];
+        let right = rect.right * self.zoom + uniform.offset[0];
+        let top = rect.top * self.zoom + uniform.offset[1];
+        let bottom = rect.bottom * self.zoom + uniform.offset[1];
+
+        let to_frame = |clip: f32, start: f32, end: f32, frame_len: u32| {
+            ((clip - start) / (end - start) * frame_len as f32).clamp(0.0, frame_len as f32)
+        };
+
+        let x0 = to_frame(-1.0, left, right, frame_size.0);
+        let x1 = to_frame(1.0, left, right, frame_size.0);
+        let y0 = to_frame(1.0, top, bottom, frame_size.1);
+        let y1 = to_frame(-1.0, top, bottom, frame_size.1);
+
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+
+        Some(RegionOfInterest {
+            origin: (x0, y0),
+            size: (x1 - x0, y1 - y0),
+            scale: (right - left) / 2.0 * surface_size.0 as f32 / frame_size.0 as f32,
+        })
+    }
+
+    // the frame pixel position under a surface pixel, outside 0..frame_size when the point
+    // is off the frame
+    pub fn surface_to_frame(&self, point: Pair<f32>, frame_size: Pair<u32>, surface_size: Pair<u32>, mode: ScalingMode) -> Pair<f32> {
+        let rect = mode.clip_rect(frame_size, surface_size);
+        let uniform = self.uniform(surface_size);
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+
+        // back through pan and zoom onto the unscaled quad
+        let x = (2.0 * point.0 / width - 1.0 - uniform.offset[0]) / self.zoom;
+        let y = (1.0 - 2.0 * point.1 / height - uniform.offset[1]) / self.zoom;
+
+        (
+            (x - rect.left) / (rect.right - rect.left) * frame_size.0 as f32,
+            (y - rect.top) / (rect.bottom - rect.top) * frame_size.1 as f32,
+        )
+    }
+
+    pub fn frame_to_surface(&self, point: Pair<f32>, frame_size: Pair<u32>, surface_size: Pair<u32>, mode: ScalingMode) -> Pair<f32> {
+        let rect = mode.clip_rect(frame_size, surface_size);
+        let uniform = self.uniform(surface_size);
+        let (width, height) = (surface_size.0.max(1) as f32, surface_size.1.max(1) as f32);
+
+        let x = (rect.left + point.0 / frame_size.0.max(1) as f32 * (rect.right - rect.left)) * self.zoom + uniform.offset[0];
+        let y = (rect.top + point.1 / frame_size.1.max(1) as f32 * (rect.bottom - rect.top)) * self.zoom + uniform.offset[1];
+
+        ((x + 1.0) / 2.0 * width, (1.0 - y) / 2.0 * height)
+    }
+}
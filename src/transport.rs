@@ -0,0 +1,43 @@
+use core::time::Duration;
+
+// playback control of a timed provider, e.g. an animation or a video, what the viewer's
+// scrub bar reads and drives
+pub trait Transport {
+    // where playback is, at least the timestamp of the frame on screen, `None` before the
+    // first one
+    fn position(&self) -> Option<Duration>;
+
+    // `None` when the length isn't known up front, e.g. a live stream
+    fn duration(&self) -> Option<Duration>;
+
+    // the frame showing at `position` is the next one yielded, returns false when the
+    // provider can't seek there
+    fn seek(&mut self, position: Duration) -> bool;
+
+    fn is_paused(&self) -> bool;
+
+    // a paused provider keeps its position and yields no frames except after a seek
+    fn set_paused(&mut self, paused: bool);
+}
+
+impl<T: Transport + ?Sized> Transport for &mut T {
+    fn position(&self) -> Option<Duration> {
+        (**self).position()
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        (**self).duration()
+    }
+
+    fn seek(&mut self, position: Duration) -> bool {
+        (**self).seek(position)
+    }
+
+    fn is_paused(&self) -> bool {
+        (**self).is_paused()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        (**self).set_paused(paused)
+    }
+}
@@ -1,3 +1,5 @@
+use crate::convert;
+
 pub type Pair<Type> = (Type, Type);
 
 pub trait HasSize<Type> {
@@ -6,6 +8,19 @@ pub trait HasSize<Type> {
 
 pub trait HasPosition<Type> {
     fn position(&self) -> Pair<Type>;
+
+    // the size of the whole image when the frame only carries a dirty rectangle of it at
+    // `position`, e.g. a remote desktop update
+    // such a frame is copied into the texture that holds the previous frame instead of
+    // replacing it, so the first frame of a canvas size has to cover all of it
+    fn canvas_size(&self) -> Option<Pair<Type>> {
+        None
+    }
+}
+
+// frame pixels per source pixel, below 1.0 when a source was downscaled on load
+pub trait HasScale {
+    fn scale(&self) -> f32;
 }
 
 pub trait HasRatio {
@@ -13,24 +28,785 @@ pub trait HasRatio {
     fn inverse_ratio(&self) -> f32;
 }
 
+// an area given by its top-left corner and its size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rect<Type> {
+    pub position: Pair<Type>,
+    pub size: Pair<Type>,
+}
+
+impl<Type> Rect<Type> {
+    pub fn new(position: Pair<Type>, size: Pair<Type>) -> Self {
+        Self { position, size }
+    }
+}
+
+impl<Type: Copy> HasSize<Type> for Rect<Type> {
+    fn size(&self) -> Pair<Type> {
+        self.size
+    }
+}
+
+impl<Type: Copy> HasPosition<Type> for Rect<Type> {
+    fn position(&self) -> Pair<Type> {
+        self.position
+    }
+}
+
+impl HasRatio for Pair<u32> {
+    fn ratio(&self) -> f32 {
+        self.0 as f32 / self.1 as f32
+    }
+
+    fn inverse_ratio(&self) -> f32 {
+        self.1 as f32 / self.0 as f32
+    }
+}
+
 pub trait HasData {
     fn data(&self) -> &[u8];
+
+    // stays the same for as long as the pixels do, e.g. for the image a still provider hands
+    // out on every poll, a frame with the id of the one on screen isn't uploaded or drawn
+    // again, `None` for frames that are new every time
+    fn content_id(&self) -> Option<u64> {
+        None
+    }
+
+    // where the planes after the first start in `data` and their row strides, for planar
+    // formats like `PixelFormat::Nv12`, empty when each plane directly follows the one
+    // before it with rows as long as the plane is wide
+    // the first plane starts at 0 and uses `HasFormat::row_stride`
+    fn plane_layouts(&self) -> &[PlaneLayout] {
+        &[]
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PlaneLayout {
+    pub offset: usize,
+    pub row_stride: usize,
+}
+
+// layout of a frame's pixels
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    #[default]
+    Rgba8,
+    Bgra8,
+    Rgb8,
+    Bgr8,
+    Gray8,
+    Rgba16Float,
+    // one byte per pixel indexing the frame's palette
+    Indexed8,
+    // bt.601 studio range yuv 4:2:0 as video decoders and cameras hand it out, a full size
+    // luma plane followed by a half size plane of interleaved u and v
+    Nv12,
+    // like `Nv12` with separate half size u and v planes, e.g. y4m and most software decoders
+    I420,
+}
+
+impl PixelFormat {
+    // of the first plane for planar formats
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+            PixelFormat::Rgb8 | PixelFormat::Bgr8 => 3,
+            PixelFormat::Gray8 | PixelFormat::Indexed8 | PixelFormat::Nv12 | PixelFormat::I420 => 1,
+            PixelFormat::Rgba16Float => 8,
+        }
+    }
+
+    pub fn is_planar(self) -> bool {
+        self.plane_count() > 1
+    }
+
+    pub fn plane_count(self) -> usize {
+        match self {
+            PixelFormat::Nv12 => 2,
+            PixelFormat::I420 => 3,
+            _ => 1,
+        }
+    }
+
+    // the size of plane `index` of a frame of `size`, chroma planes round up
+    pub fn plane_size(self, index: usize, size: Pair<u32>) -> Pair<u32> {
+        match index {
+            0 => size,
+            _ => (size.0.div_ceil(2), size.1.div_ceil(2)),
+        }
+    }
+
+    pub fn plane_bytes_per_pixel(self, index: usize) -> usize {
+        match (self, index) {
+            (PixelFormat::Nv12, 1) => 2,
+            (_, 0) => self.bytes_per_pixel(),
+            _ => 1,
+        }
+    }
+}
+
+// one plane of a frame, its rows are `row_stride` apart and stored in the frame's row order
+#[derive(Copy, Clone, Debug)]
+pub struct Plane<'a> {
+    pub data: &'a [u8],
+    pub size: Pair<u32>,
+    pub row_stride: usize,
+}
+
+impl Plane<'_> {
+    // the bytes of a row in storage order, up to the next row or the end of the plane
+    pub fn row(&self, row: u32) -> &[u8] {
+        let start = row as usize * self.row_stride;
+        &self.data[start..(start + self.row_stride).min(self.data.len())]
+    }
+}
+
+// plane `index` of a frame, `None` past the last plane or when `data` ends before the
+// plane does
+pub fn plane<Frame: HasSize<u32> + HasFormat + HasData + ?Sized>(frame: &Frame, index: usize) -> Option<Plane<'_>> {
+    let format = frame.format();
+    if index >= format.plane_count() {
+        return None;
+    }
+
+    let plane_row_len = |index: usize| format.plane_size(index, frame.size()).0 as usize * format.plane_bytes_per_pixel(index);
+    let (offset, row_stride) = match (index, frame.plane_layouts()) {
+        (0, _) => (0, frame.row_stride().unwrap_or(plane_row_len(0))),
+        (index, layouts) if !layouts.is_empty() => layouts.get(index - 1).map(|layout| (layout.offset, layout.row_stride))?,
+        // tightly packed planes, the first one may still have padded rows
+        (index, _) => {
+            let first = frame.row_stride().unwrap_or(plane_row_len(0)) * frame.size().1 as usize;
+            let between: usize = (1..index).map(|index| plane_row_len(index) * format.plane_size(index, frame.size()).1 as usize).sum();
+            (first + between, plane_row_len(index))
+        },
+    };
+
+    let size = format.plane_size(index, frame.size());
+    let row_len = plane_row_len(index);
+    // the last row doesn't have to be padded to the stride
+    let len = row_stride * (size.1 as usize).saturating_sub(1) + row_len;
+
+    if row_stride < row_len {
+        return None;
+    }
+
+    let data = frame.data().get(offset..)?.get(..len)?;
+    Some(Plane { data, size, row_stride })
+}
+
+// the srgb rgba8 color of the pixel of a planar yuv frame at `column` and `row`, rows
+// counted from the top whatever the frame's row order
+pub fn yuv_at<Frame: HasSize<u32> + HasFormat + HasData + ?Sized>(frame: &Frame, column: u32, row: u32) -> Option<[u8; 4]> {
+    let sample = |index: usize, byte: usize| {
+        let plane = plane(frame, index)?;
+        let (x, y) = match index {
+            0 => (column, row),
+            _ => (column / 2, row / 2),
+        };
+        let y = match frame.row_order() {
+            RowOrder::TopDown => y,
+            RowOrder::BottomUp => plane.size.1.checked_sub(y + 1)?,
+        };
+
+        plane.row(y).get(x as usize * frame.format().plane_bytes_per_pixel(index) + byte).copied()
+    };
+
+    let (u, v) = match frame.format() {
+        PixelFormat::Nv12 => (sample(1, 0)?, sample(1, 1)?),
+        PixelFormat::I420 => (sample(1, 0)?, sample(2, 0)?),
+        _ => return None,
+    };
+
+    Some(convert::yuv_to_rgba(sample(0, 0)?, u, v))
+}
+
+// the srgb rgba8 color of the pixel at `column` and `row` of a frame of any format, rows
+// counted from the top whatever the frame's row order, float frames are clipped
+// palette entries past the end are transparent
+#[cfg(feature = "std")]
+pub fn rgba8_at<Frame: HasSize<u32> + HasFormat + HasData + ?Sized>(frame: &Frame, column: u32, row: u32) -> Option<[u8; 4]> {
+    let format = frame.format();
+    if format.is_planar() {
+        return yuv_at(frame, column, row);
+    }
+
+    let (width, height) = frame.size();
+    if column >= width || row >= height {
+        return None;
+    }
+
+    let pixel_len = format.bytes_per_pixel();
+    let stride = frame.row_stride().unwrap_or(pixel_len * width as usize);
+    let row = match frame.row_order() {
+        RowOrder::TopDown => row,
+        RowOrder::BottomUp => height - 1 - row,
+    };
+    let pixel = frame.data().get(row as usize * stride + column as usize * pixel_len..)?.get(..pixel_len)?;
+
+    Some(match format {
+        PixelFormat::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+        PixelFormat::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        PixelFormat::Rgb8 => [pixel[0], pixel[1], pixel[2], u8::MAX],
+        PixelFormat::Bgr8 => [pixel[2], pixel[1], pixel[0], u8::MAX],
+        PixelFormat::Gray8 | PixelFormat::Nv12 | PixelFormat::I420 => [pixel[0], pixel[0], pixel[0], u8::MAX],
+        PixelFormat::Indexed8 => frame.palette().and_then(|palette| palette.get(pixel[0] as usize)).copied().unwrap_or([0; 4]),
+        PixelFormat::Rgba16Float => {
+            let channel = |index: usize| convert::f16_to_f32(u16::from_ne_bytes([pixel[2 * index], pixel[2 * index + 1]]));
+            let encoded = |linear: f32| {
+                let linear = linear.clamp(0.0, 1.0);
+                let value = match linear <= 0.0031308 {
+                    true => linear * 12.92,
+                    false => 1.055 * linear.powf(1.0 / 2.4) - 0.055,
+                };
+                (value * 255.0).round() as u8
+            };
+
+            [encoded(channel(0)), encoded(channel(1)), encoded(channel(2)), (channel(3).clamp(0.0, 1.0) * 255.0).round() as u8]
+        },
+    })
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RowOrder {
+    #[default]
+    TopDown,
+    BottomUp,
+}
+
+// rows are tightly packed and top to bottom unless a frame says otherwise, which lets
+// mapped files (bmp, padded raw dumps) be uploaded without rearranging them first
+pub trait HasFormat {
+    fn format(&self) -> PixelFormat;
+
+    fn row_order(&self) -> RowOrder {
+        RowOrder::TopDown
+    }
+
+    // distance between row starts in bytes, `None` when rows are tightly packed
+    fn row_stride(&self) -> Option<usize> {
+        None
+    }
+
+    // rgba colors of an `Indexed8` frame, at most 256, missing entries are transparent
+    fn palette(&self) -> Option<&[[u8; 4]]> {
+        None
+    }
+}
+
+// how a frame's stored pixels are turned to show it upright, the exif orientation values
+// e.g. phone photos are stored sideways and tagged `Rotate90`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    #[default]
+    Normal = 1,
+    FlipHorizontal = 2,
+    Rotate180 = 3,
+    FlipVertical = 4,
+    // mirrored along the top left to bottom right diagonal
+    Transpose = 5,
+    // clockwise
+    Rotate90 = 6,
+    // mirrored along the top right to bottom left diagonal
+    Transverse = 7,
+    Rotate270 = 8,
+}
+
+impl Orientation {
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Orientation::Normal),
+            2 => Some(Orientation::FlipHorizontal),
+            3 => Some(Orientation::Rotate180),
+            4 => Some(Orientation::FlipVertical),
+            5 => Some(Orientation::Transpose),
+            6 => Some(Orientation::Rotate90),
+            7 => Some(Orientation::Transverse),
+            8 => Some(Orientation::Rotate270),
+            _ => None,
+        }
+    }
+
+    // width and height trade places when shown
+    pub fn swaps_axes(self) -> bool {
+        matches!(self, Orientation::Transpose | Orientation::Rotate90 | Orientation::Transverse | Orientation::Rotate270)
+    }
+
+    pub fn displayed_size<Type>(self, stored: Pair<Type>) -> Pair<Type> {
+        match self.swaps_axes() {
+            true => (stored.1, stored.0),
+            false => stored,
+        }
+    }
+
+    // where a point of the upright image is in the stored one, both normalized to 0..1
+    pub fn stored_point(self, displayed: Pair<f32>) -> Pair<f32> {
+        let (x, y) = displayed;
+
+        match self {
+            Orientation::Normal => (x, y),
+            Orientation::FlipHorizontal => (1.0 - x, y),
+            Orientation::Rotate180 => (1.0 - x, 1.0 - y),
+            Orientation::FlipVertical => (x, 1.0 - y),
+            Orientation::Transpose => (y, x),
+            Orientation::Rotate90 => (y, 1.0 - x),
+            Orientation::Transverse => (1.0 - y, 1.0 - x),
+            Orientation::Rotate270 => (1.0 - y, x),
+        }
+    }
+}
+
+// frames are stored upright unless they say otherwise
+pub trait HasOrientation {
+    fn orientation(&self) -> Orientation {
+        Orientation::Normal
+    }
+}
+
+// borrowed frames draw like owned ones, so a provider can hand out references to frames it
+// keeps (e.g. a pool of decode buffers) instead of moving a new one out per draw
+impl<Type, Frame: HasSize<Type> + ?Sized> HasSize<Type> for &Frame {
+    fn size(&self) -> Pair<Type> {
+        (**self).size()
+    }
+}
+
+impl<Type, Frame: HasPosition<Type> + ?Sized> HasPosition<Type> for &Frame {
+    fn position(&self) -> Pair<Type> {
+        (**self).position()
+    }
+
+    fn canvas_size(&self) -> Option<Pair<Type>> {
+        (**self).canvas_size()
+    }
+}
+
+impl<Frame: HasScale + ?Sized> HasScale for &Frame {
+    fn scale(&self) -> f32 {
+        (**self).scale()
+    }
+}
+
+impl<Frame: HasData + ?Sized> HasData for &Frame {
+    fn data(&self) -> &[u8] {
+        (**self).data()
+    }
+
+    fn content_id(&self) -> Option<u64> {
+        (**self).content_id()
+    }
+
+    fn plane_layouts(&self) -> &[PlaneLayout] {
+        (**self).plane_layouts()
+    }
+}
+
+impl<Frame: HasFormat + ?Sized> HasFormat for &Frame {
+    fn format(&self) -> PixelFormat {
+        (**self).format()
+    }
+
+    fn row_order(&self) -> RowOrder {
+        (**self).row_order()
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        (**self).row_stride()
+    }
+
+    fn palette(&self) -> Option<&[[u8; 4]]> {
+        (**self).palette()
+    }
+}
+
+impl<Frame: HasOrientation + ?Sized> HasOrientation for &Frame {
+    fn orientation(&self) -> Orientation {
+        (**self).orientation()
+    }
+}
+
+// pixels someone else owns, e.g. a mapped camera buffer or a decoder's output, drawn
+// without copying them into a frame first
+#[derive(Copy, Clone, Debug)]
+pub struct FrameRef<'a> {
+    pub size: Pair<u32>,
+    pub format: PixelFormat,
+    pub data: &'a [u8],
+    pub row_stride: Option<usize>,
+    pub row_order: RowOrder,
+    pub orientation: Orientation,
+    // see `HasData::plane_layouts`
+    pub planes: &'a [PlaneLayout],
+}
+
+impl<'a> FrameRef<'a> {
+    // tightly packed rows, top to bottom
+    pub fn new(size: Pair<u32>, format: PixelFormat, data: &'a [u8]) -> Self {
+        Self { size, format, data, row_stride: None, row_order: RowOrder::TopDown, orientation: Orientation::Normal, planes: &[] }
+    }
+
+    // e.g. nv12 from a decoder whose chroma plane doesn't directly follow the luma plane
+    pub fn with_planes(mut self, planes: &'a [PlaneLayout]) -> Self {
+        self.planes = planes;
+        self
+    }
+
+    pub fn with_row_stride(mut self, row_stride: usize) -> Self {
+        self.row_stride = Some(row_stride);
+        self
+    }
+
+    pub fn with_row_order(mut self, row_order: RowOrder) -> Self {
+        self.row_order = row_order;
+        self
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+}
+
+impl HasSize<u32> for FrameRef<'_> {
+    fn size(&self) -> Pair<u32> {
+        self.size
+    }
+}
+
+impl HasPosition<u32> for FrameRef<'_> {
+    fn position(&self) -> Pair<u32> {
+        (0, 0)
+    }
+}
+
+impl HasFormat for FrameRef<'_> {
+    fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    fn row_order(&self) -> RowOrder {
+        self.row_order
+    }
+
+    fn row_stride(&self) -> Option<usize> {
+        self.row_stride
+    }
+}
+
+impl HasOrientation for FrameRef<'_> {
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+}
+
+impl HasData for FrameRef<'_> {
+    fn data(&self) -> &[u8] {
+        self.data
+    }
+
+    fn plane_layouts(&self) -> &[PlaneLayout] {
+        self.planes
+    }
+}
+
+// what a provider has for the next draw
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FramePoll<Frame> {
+    Ready(Frame),
+    // nothing new yet, the frame on screen stays, e.g. a video between two frames
+    Pending,
+    // no frame will come until the provider is rewound
+    Finished,
+}
+
+impl<Frame> FramePoll<Frame> {
+    pub fn into_frame(self) -> Option<Frame> {
+        match self {
+            FramePoll::Ready(frame) => Some(frame),
+            FramePoll::Pending | FramePoll::Finished => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, FramePoll::Finished)
+    }
+
+    pub fn map<Other>(self, map: impl FnOnce(Frame) -> Other) -> FramePoll<Other> {
+        match self {
+            FramePoll::Ready(frame) => FramePoll::Ready(map(frame)),
+            FramePoll::Pending => FramePoll::Pending,
+            FramePoll::Finished => FramePoll::Finished,
+        }
+    }
+}
+
+// when polling a provider again can give a new frame, lets a viewer sleep in between
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FrameSchedule {
+    // any time, e.g. a camera or another thread, polled again shortly
+    Soon,
+    // not before then, e.g. the next frame of an animation
+    At(std::time::Instant),
+    // only after input reached the provider, e.g. a still image or a paused animation
+    Still,
+}
+
+#[cfg(feature = "std")]
+impl FrameSchedule {
+    // the sooner of two, e.g. for a viewer comparing two providers
+    pub fn earliest(self, other: Self) -> Self {
+        match (self, other) {
+            (FrameSchedule::Soon, _) | (_, FrameSchedule::Soon) => FrameSchedule::Soon,
+            (FrameSchedule::At(first), FrameSchedule::At(second)) => FrameSchedule::At(first.min(second)),
+            (FrameSchedule::At(at), FrameSchedule::Still) | (FrameSchedule::Still, FrameSchedule::At(at)) => FrameSchedule::At(at),
+            (FrameSchedule::Still, FrameSchedule::Still) => FrameSchedule::Still,
+        }
+    }
+}
+
+// where the frames a render context draws come from, polled once per draw
+pub trait FrameProvider {
+    type Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame>;
+
+    // every frame of the next draw, a render context draws each as its own quad at its
+    // `HasPosition` offset, e.g. the cells of a gallery, most providers have the one
+    #[cfg(feature = "std")]
+    fn poll_frames(&mut self) -> FramePoll<Vec<Self::Frame>> {
+        self.poll_frame().map(|frame| vec![frame])
+    }
+
+    // the size of the frames being shown as displayed, after their orientation, known without
+    // producing one, `None` when the provider can't tell before its first frame
+    fn current_size(&self) -> Option<Pair<u32>> {
+        None
+    }
+
+    // asked after a poll that gave nothing new
+    #[cfg(feature = "std")]
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Soon
+    }
+
+    // starts over at the first frame, which is the next one polled, false when the
+    // provider can't go back, e.g. a live stream
+    fn rewind(&mut self) -> bool {
+        false
+    }
+
+    // moves on to the next image of a provider holding several, e.g. the files of a
+    // directory, false when there is none or the provider only has the one
+    fn next_image(&mut self) -> bool {
+        false
+    }
+
+    fn previous_image(&mut self) -> bool {
+        false
+    }
 }
 
-pub trait FrameRenderContext: From<Self::Init> + HasSize<u32> {
+impl<P: FrameProvider + ?Sized> FrameProvider for &mut P {
+    type Frame = P::Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        (**self).poll_frame()
+    }
+
+    #[cfg(feature = "std")]
+    fn poll_frames(&mut self) -> FramePoll<Vec<Self::Frame>> {
+        (**self).poll_frames()
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        (**self).current_size()
+    }
+
+    #[cfg(feature = "std")]
+    fn schedule(&self) -> FrameSchedule {
+        (**self).schedule()
+    }
+
+    fn rewind(&mut self) -> bool {
+        (**self).rewind()
+    }
+
+    fn next_image(&mut self) -> bool {
+        (**self).next_image()
+    }
+
+    fn previous_image(&mut self) -> bool {
+        (**self).previous_image()
+    }
+}
+
+// a single poll's worth, e.g. a frame drawn once: `draw_frame(FramePoll::Ready(frame))`
+impl<Frame: HasSize<u32> + HasOrientation> FrameProvider for FramePoll<Frame> {
+    type Frame = Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self {
+            FramePoll::Finished => FramePoll::Finished,
+            _ => core::mem::replace(self, FramePoll::Pending),
+        }
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        match self {
+            FramePoll::Ready(frame) => Some(frame.orientation().displayed_size(frame.size())),
+            FramePoll::Pending | FramePoll::Finished => None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+}
+
+// any iterator as a provider, iterators can't tell the end of a stream from a pause, so
+// `None` is read as no new frame yet
+#[derive(Clone, Debug)]
+pub struct IterFrames<I> {
+    iter: I,
+}
+
+pub fn frames<I: IntoIterator>(iter: I) -> IterFrames<I::IntoIter> {
+    IterFrames { iter: iter.into_iter() }
+}
+
+impl<I: Iterator> FrameProvider for IterFrames<I> {
+    type Frame = I::Item;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self.iter.next() {
+            Some(frame) => FramePoll::Ready(frame),
+            None => FramePoll::Pending,
+        }
+    }
+}
+
+// frames drawn together once, e.g. the cells of a gallery grid: `draw_frame(batch(cells))`,
+// a context drawing a single frame gets the first one
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct FrameBatch<Frame> {
+    frames: Option<Vec<Frame>>,
+}
+
+#[cfg(feature = "std")]
+pub fn batch<I: IntoIterator>(frames: I) -> FrameBatch<I::Item> {
+    FrameBatch { frames: Some(frames.into_iter().collect()) }
+}
+
+#[cfg(feature = "std")]
+impl<Frame> FrameProvider for FrameBatch<Frame> {
+    type Frame = Frame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self.frames.take().and_then(|frames| frames.into_iter().next()) {
+            Some(frame) => FramePoll::Ready(frame),
+            None => FramePoll::Pending,
+        }
+    }
+
+    fn poll_frames(&mut self) -> FramePoll<Vec<Self::Frame>> {
+        match self.frames.take() {
+            Some(frames) => FramePoll::Ready(frames),
+            None => FramePoll::Pending,
+        }
+    }
+    fn schedule(&self) -> FrameSchedule {
+        FrameSchedule::Still
+    }
+}
+
+pub trait FrameRenderContext: TryFrom<Self::Init> + HasSize<u32> {
     type Init;
     type RenderError;
+    // what a draw reports back, e.g. what it cost
+    type Stats;
 
-    fn init(init: Self::Init) -> Self {
-        let mut instance = Self::from(init);
+    fn init(init: Self::Init) -> Result<Self, <Self as TryFrom<Self::Init>>::Error> {
+        let mut instance = Self::try_from(init)?;
         let size = instance.size();
         instance.configure(size);
-        instance
+        Ok(instance)
     }
 
     fn configure(&mut self, size: Pair<u32>);
 
-    fn draw_frame<Frame>(&mut self, frame_provider: impl Iterator<Item = Frame>) -> Result<(), Self::RenderError>
+    fn draw_frame<Frame>(&mut self, frame_provider: impl FrameProvider<Frame = Frame>) -> Result<Self::Stats, Self::RenderError>
     where
-        Frame: HasSize<u32> + HasPosition<u32> + HasData;
+        Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_size_is_displayed_size() {
+        let data = [0; 4 * 6];
+        let frame = FrameRef::new((3, 2), PixelFormat::Rgba8, &data);
+
+        assert_eq!(FramePoll::Ready(frame).current_size(), Some((3, 2)));
+        assert_eq!(FramePoll::Ready(frame.with_orientation(Orientation::Rotate90)).current_size(), Some((2, 3)));
+
+        // a 3x2 png whose exif turns it upright
+        #[cfg(feature = "providers")]
+        {
+            use image::codecs::png::PngEncoder;
+            use image::{ExtendedColorType, ImageEncoder};
+
+            let directory = std::env::temp_dir().join(format!("egami-displayed-size-{}", std::process::id()));
+            std::fs::create_dir_all(&directory).unwrap();
+
+            let exif = [b"II\x2a\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0\x06\0\0\0".as_slice(), &[0; 4]].concat();
+            let mut png = Vec::new();
+            let mut encoder = PngEncoder::new(&mut png);
+            encoder.set_exif_metadata(exif).unwrap();
+            encoder.write_image(&data, 3, 2, ExtendedColorType::Rgba8).unwrap();
+            std::fs::write(directory.join("portrait.png"), png).unwrap();
+
+            let mut provider = crate::providers::DirectoryProvider::new(&directory, crate::providers::SortOrder::Name).unwrap();
+            let started = std::time::Instant::now();
+            while matches!(provider.poll_frame(), FramePoll::Pending) && started.elapsed() < std::time::Duration::from_secs(10) {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+
+            assert_eq!(provider.current_size(), Some((2, 3)));
+            std::fs::remove_dir_all(&directory).unwrap();
+        }
+    }
+
+    #[test]
+    fn batches_are_polled_once() {
+        let mut cells = batch([1, 2, 3]);
+        assert_eq!(cells.clone().poll_frame(), FramePoll::Ready(1));
+        assert_eq!(cells.poll_frames(), FramePoll::Ready(vec![1, 2, 3]));
+        assert_eq!(cells.poll_frames(), FramePoll::Pending);
+
+        fn count(mut provider: impl FrameProvider) -> FramePoll<usize> {
+            provider.poll_frames().map(|frames| frames.len())
+        }
+
+        let mut single = FramePoll::Ready(FrameRef::new((1, 1), PixelFormat::Gray8, &[0]));
+        assert_eq!(count(&mut single), FramePoll::Ready(1));
+        assert!(matches!(single.poll_frames(), FramePoll::Pending));
+    }
+
+    #[test]
+    fn schedules_take_the_earliest() {
+        let now = std::time::Instant::now();
+        let later = now + std::time::Duration::from_millis(5);
+
+        assert_eq!(FrameSchedule::Still.earliest(FrameSchedule::At(now)), FrameSchedule::At(now));
+        assert_eq!(FrameSchedule::At(later).earliest(FrameSchedule::At(now)), FrameSchedule::At(now));
+        assert_eq!(FrameSchedule::At(now).earliest(FrameSchedule::Soon), FrameSchedule::Soon);
+        assert_eq!(FrameSchedule::Still.earliest(FrameSchedule::Still), FrameSchedule::Still);
+        assert_eq!(batch([1]).schedule(), FrameSchedule::Still);
+    }
 }
@@ -0,0 +1,147 @@
+use std::sync::{mpsc, Arc};
+
+// `wgpu::util::StagingBelt` for textures: frames are written straight into mapped staging
+// buffers and copied on the gpu, buffers are mapped again once the gpu is done with them
+// and reused, so a steady stream of frames allocates nothing after the first few
+#[derive(Debug)]
+pub(crate) struct UploadBelt {
+    chunk_size: u64,
+    chunk_label: String,
+    // mapped, written this frame
+    active: Vec<Chunk>,
+    // unmapped, waiting for the submission that reads them
+    closed: Vec<Chunk>,
+    // mapped again and empty
+    free: Vec<Chunk>,
+    // chunks are kept until the belt is dropped, so this only grows
+    allocated: u64,
+    sender: mpsc::Sender<Chunk>,
+    receiver: mpsc::Receiver<Chunk>,
+}
+
+#[derive(Debug)]
+struct Chunk {
+    buffer: Arc<wgpu::Buffer>,
+    offset: u64,
+}
+
+impl Chunk {
+    fn room(&self) -> u64 {
+        self.buffer.size() - self.offset
+    }
+}
+
+impl UploadBelt {
+    pub(crate) fn new(chunk_size: u64, chunk_label: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            chunk_size,
+            chunk_label,
+            active: Vec::new(),
+            closed: Vec::new(),
+            free: Vec::new(),
+            allocated: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    // buffer offsets of texture copies have to be aligned like their rows
+    fn allocate(&mut self, device: &wgpu::Device, size: u64) -> usize {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+
+        for chunk in self.active.iter_mut() {
+            chunk.offset = chunk.offset.next_multiple_of(align).min(chunk.buffer.size());
+        }
+
+        if let Some(index) = self.active.iter().position(|chunk| chunk.room() >= size) {
+            return index;
+        }
+
+        self.free.extend(self.receiver.try_iter());
+
+        let chunk = match self.free.iter().position(|chunk| chunk.buffer.size() >= size) {
+            Some(index) => self.free.swap_remove(index),
+            None => self.create_chunk(device, size),
+        };
+
+        self.active.push(chunk);
+        self.active.len() - 1
+    }
+
+    fn create_chunk(&mut self, device: &wgpu::Device, size: u64) -> Chunk {
+        let size = size.max(self.chunk_size);
+        self.allocated += size;
+
+        Chunk {
+            buffer: Arc::new(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&self.chunk_label),
+                size,
+                usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: true,
+            })),
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.allocated
+    }
+
+    // `fill` writes `rows` rows of `bytes_per_row` bytes, which has to be a multiple of
+    // `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`, the copy into `texture` is recorded on `encoder`
+    pub(crate) fn copy_to_texture(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: wgpu::ImageCopyTexture,
+        extent: wgpu::Extent3d,
+        bytes_per_row: u32,
+        fill: impl FnOnce(&mut [u8]),
+    ) {
+        let size = bytes_per_row as u64 * extent.height as u64;
+        let index = self.allocate(device, size);
+        let chunk = &mut self.active[index];
+        let offset = chunk.offset;
+
+        fill(&mut chunk.buffer.slice(offset..offset + size).get_mapped_range_mut());
+        chunk.offset += size;
+
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &chunk.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(extent.height),
+                },
+            },
+            texture,
+            extent,
+        );
+    }
+
+    // before the encoder is submitted
+    pub(crate) fn finish(&mut self) {
+        for chunk in self.active.drain(..) {
+            chunk.buffer.unmap();
+            self.closed.push(chunk);
+        }
+    }
+
+    // after the encoder is submitted, chunks come back as the gpu finishes reading them
+    pub(crate) fn recall(&mut self) {
+        for mut chunk in self.closed.drain(..) {
+            let sender = self.sender.clone();
+            let buffer = Arc::clone(&chunk.buffer);
+            chunk.offset = 0;
+
+            buffer.slice(..).map_async(wgpu::MapMode::Write, move |result| {
+                if result.is_ok() {
+                    let _ = sender.send(chunk);
+                }
+            });
+        }
+    }
+}
@@ -1,36 +1,40 @@
-use crate::viewport::ViewPortMargin;
+use crate::types::Pair;
+use crate::viewport::ScalingMode;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub(crate) struct Vertex {
-    position: [f32; 2],
-    texture_coords: [f32; 2],
+pub struct Vertex {
+    pub position: [f32; 2],
+    pub texture_coords: [f32; 2],
 }
 
 impl Vertex {
+    #[cfg(feature = "render")]
     pub(crate) const ATTRIBS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2];
 
+    #[cfg(feature = "render")]
     pub(crate) fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             attributes: &Self::ATTRIBS,
             step_mode: wgpu::VertexStepMode::Vertex,
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            array_stride: core::mem::size_of::<Vertex>() as wgpu::BufferAddress,
         }
     }
 
-    pub(crate) fn get_vertices(aspect_ratios: (f32, f32)) -> [Self; 4] {
-        let (h_margin, v_margin) = ViewPortMargin::from(aspect_ratios).into();
+    // a quad in clip space placed in the viewport according to `mode`
+    pub fn get_vertices(object_size: Pair<u32>, viewport_size: Pair<u32>, mode: ScalingMode) -> [Self; 4] {
+        let rect = mode.clip_rect(object_size, viewport_size);
 
         [
-            Self { position: [-1.0 + h_margin, 1.0 - v_margin], texture_coords: [0.0, 0.0] },
-            Self { position: [1.0 - h_margin, 1.0 - v_margin], texture_coords: [1.0, 0.0] },
-            Self { position: [-1.0 + h_margin, -1.0 + v_margin], texture_coords: [0.0, 1.0] },
-            Self { position: [1.0 - h_margin, -1.0 + v_margin], texture_coords: [1.0, 1.0] },
+            Self { position: [rect.left, rect.top], texture_coords: [0.0, 0.0] },
+            Self { position: [rect.right, rect.top], texture_coords: [1.0, 0.0] },
+            Self { position: [rect.left, rect.bottom], texture_coords: [0.0, 1.0] },
+            Self { position: [rect.right, rect.bottom], texture_coords: [1.0, 1.0] },
         ]
     }
 }
 
-pub(crate) const INDICES: &[u16] = &[
+pub const INDICES: &[u16] = &[
     0, 2, 1,
     2, 3, 1,
 ];
\ No newline at end of file
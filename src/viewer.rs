@@ -0,0 +1,1663 @@
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::FRAC_PI_2;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use winit::{
+    application::ApplicationHandler, dpi::{PhysicalPosition, PhysicalSize}, error::EventLoopError, event::*, event_loop::{ControlFlow, EventLoop, EventLoopProxy}, keyboard::KeyCode, monitor::MonitorHandle, window::{Fullscreen, Window}
+};
+
+use crate::adapter::AdapterSelection;
+use crate::annotation::{AnnotationEvent, BoxEditor};
+use crate::builder::RenderContextBuilder;
+use crate::cubemap::SkyboxView;
+use crate::error::DrawError;
+use crate::feedback::FeedbackHandle;
+use crate::heatmap::{Heatmap, HeatmapStyle};
+use crate::input::{Action, Direction, InputBindings, InputEvent};
+use crate::mask::{MaskStyle, SegmentationMask};
+use crate::recording::{InteractionPlayer, InteractionRecorder};
+#[cfg(feature = "renderdoc")]
+use crate::renderdoc::RenderDoc;
+#[cfg(feature = "fonts")]
+use crate::font::Font;
+use crate::render::{AlphaBehavior, AutoFilterThresholds, FilterPreset, HdrMode, PendingRenderContext, PixelGrid, PresentPreference, WgpuFrameRenderContext};
+use crate::types::{FramePoll, FrameProvider, FrameRenderContext, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Pair};
+use crate::scale_bar::ScaleBar;
+use crate::scrubber::{self, Marker, Timeline};
+use crate::shading::{self, Channel, ChannelMapping, ShadingMode};
+use crate::subtitles::{CaptionStyle, Subtitles};
+use crate::transport::Transport;
+use crate::viewport::ScalingMode;
+
+// how the window reflects a key the handler acted on, `None` fields stay as they are
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyResponse {
+    pub title: Option<String>,
+    pub clear_color: Option<wgpu::Color>,
+    // shown over the bottom of the frame like a subtitle, empty hides it
+    pub caption: Option<String>,
+}
+
+// how large the window opens, in physical pixels, never larger than the work area of its
+// monitor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowSize {
+    Fixed(Pair<u32>),
+    // of the monitor's width and height
+    MonitorFraction(f32),
+    // the size of the provider's frames, see `FrameProvider::current_size`, shrunk to the
+    // work area keeping their aspect, 1280x720 for providers that can't tell
+    Native,
+}
+
+// the monitor the window opens centered on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorSelection {
+    // the platform's primary monitor, or the first one where there is none, e.g. on wayland
+    Primary,
+    // in the platform's order
+    Index(usize),
+    // the first monitor whose name contains this
+    Name(String),
+}
+
+impl MonitorSelection {
+    // `None` without monitors or when none matches
+    fn find(&self, event_loop: &winit::event_loop::ActiveEventLoop) -> Option<MonitorHandle> {
+        match self {
+            MonitorSelection::Primary => event_loop.primary_monitor().or_else(|| event_loop.available_monitors().next()),
+            MonitorSelection::Index(index) => event_loop.available_monitors().nth(*index),
+            MonitorSelection::Name(name) => event_loop.available_monitors().find(|monitor| monitor.name().is_some_and(|monitor_name| monitor_name.contains(name.as_str()))),
+        }
+    }
+}
+
+// winit doesn't know where panels and docks are, so the work area is this much of the monitor
+const WORK_AREA_FRACTION: f32 = 0.9;
+
+fn work_area(monitor: &MonitorHandle) -> Pair<u32> {
+    let size = monitor.size();
+    ((size.width as f32 * WORK_AREA_FRACTION) as u32, (size.height as f32 * WORK_AREA_FRACTION) as u32)
+}
+
+// `size` shrunk as a whole to fit into `area`, so it keeps its aspect
+fn shrink_to(size: Pair<u32>, area: Pair<u32>) -> Pair<u32> {
+    let scale = (area.0 as f32 / size.0.max(1) as f32).min(area.1 as f32 / size.1.max(1) as f32).min(1.0);
+    ((size.0 as f32 * scale).round() as u32, (size.1 as f32 * scale).round() as u32)
+}
+
+#[derive(Default)]
+struct InjectedEvents {
+    queue: Mutex<VecDeque<InputEvent>>,
+    // wakes the event loop of a running viewer
+    proxy: Mutex<Option<EventLoopProxy<()>>>,
+}
+
+// queues events for a viewer as if its window had sent them, see `Viewer::inject_event`,
+// clones share the queue
+#[derive(Clone, Default)]
+pub struct EventInjector {
+    events: Arc<InjectedEvents>,
+}
+
+impl fmt::Debug for EventInjector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventInjector").field("queued", &self.events.queue.lock().unwrap().len()).finish()
+    }
+}
+
+impl EventInjector {
+    // returns false once the viewer's event loop is gone
+    pub fn inject(&self, event: InputEvent) -> bool {
+        self.events.queue.lock().unwrap().push_back(event);
+
+        match self.events.proxy.lock().unwrap().as_ref() {
+            Some(proxy) => proxy.send_event(()).is_ok(),
+            None => true,
+        }
+    }
+
+    fn connect(&self, proxy: EventLoopProxy<()>) {
+        *self.events.proxy.lock().unwrap() = Some(proxy);
+    }
+
+    fn take(&self) -> VecDeque<InputEvent> {
+        std::mem::take(&mut *self.events.queue.lock().unwrap())
+    }
+}
+
+type KeyHandler<Provider> = Box<dyn FnMut(&mut Provider, KeyCode) -> Option<KeyResponse>>;
+type AnnotationHandler = Box<dyn FnMut(&AnnotationEvent)>;
+type TransportAccess<Provider> = fn(&mut Provider) -> &mut dyn Transport;
+
+// how close to a handle a press grabs it, in logical pixels
+const HANDLE_REACH: f32 = 8.0;
+
+// how far a key press pans, in logical pixels
+const KEY_PAN_STEP: f32 = 48.0;
+
+// what a trackpad scrolls for a wheel notch, in logical pixels
+const PIXELS_PER_NOTCH: f32 = 40.0;
+
+// how often an idle viewer asks a provider that can have a new frame at any time, see
+// `FrameSchedule::Soon`
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// the frame rate on the hud counts the new frames shown this long ago at most
+const FRAME_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+// a single window showing the frames of `Provider`, closed with escape
+// the wheel zooms, dragging with the left button pans or edits boxes, the bottom bar seeks
+// when the viewer has a scrubber, the keys trigger the actions of `InputBindings` and any
+// other key goes to the key handler
+pub struct Viewer<Provider> {
+    title: String,
+    window_size: WindowSize,
+    monitor: Option<MonitorSelection>,
+    auto_resize: bool,
+    aspect_lock: bool,
+    scaling_mode: ScalingMode,
+    clear_color: Option<wgpu::Color>,
+    filter_preset: FilterPreset,
+    auto_filter: AutoFilterThresholds,
+    strict_validation: bool,
+    wide_gamut: bool,
+    hdr: HdrMode,
+    alpha: AlphaBehavior,
+    adapter: AdapterSelection,
+    feedback: FeedbackHandle,
+    frame_provider: Provider,
+    // drawn right of a split line, see `with_comparison`
+    comparison: Option<Provider>,
+    key_handler: Option<KeyHandler<Provider>>,
+    key_bindings: HashMap<KeyCode, KeyHandler<Provider>>,
+    input: InputBindings,
+    editor: BoxEditor,
+    annotation_handler: Option<AnnotationHandler>,
+    editing: bool,
+    mask: Option<(SegmentationMask, MaskStyle)>,
+    show_mask: bool,
+    heatmap: Option<(Heatmap, HeatmapStyle)>,
+    show_heatmap: bool,
+    transport: Option<TransportAccess<Provider>>,
+    scrubber: bool,
+    scrubbing: bool,
+    markers: Vec<Marker>,
+    subtitles: Option<Subtitles>,
+    caption_style: CaptionStyle,
+    badge: Option<String>,
+    // from `with_caption` or the last key response
+    caption: Option<String>,
+    #[cfg(feature = "fonts")]
+    font: Option<Font>,
+    scale_bar: Option<ScaleBar>,
+    pixel_grid: PixelGrid,
+    show_pixel_grid: bool,
+    readout: bool,
+    hud: bool,
+    // when the new frames of the last `FRAME_RATE_WINDOW` were shown
+    frame_times: VecDeque<Instant>,
+    #[cfg(feature = "renderdoc")]
+    renderdoc: Option<RenderDoc>,
+    // saved to the path on exit
+    recording: Option<(InteractionRecorder, PathBuf)>,
+    replay: Option<InteractionPlayer>,
+    injector: EventInjector,
+
+    window: Option<Arc<Window>>,
+    render_context: Option<WgpuFrameRenderContext>,
+    pending_context: Option<PendingRenderContext>,
+
+    // of the provider's frames when the window was last fitted to them
+    frame_size: Option<Pair<u32>>,
+    cursor: Option<Pair<f32>>,
+    dragging: bool,
+    // the left button holds the split line
+    dragging_split: bool,
+    // the last poll brought a new frame, so the provider is likely streaming
+    live: bool,
+    // the provider ran out of frames, it is only polled again for redraws until rewound
+    finished: bool,
+    // `None` while the provider waits for input, see `FrameSchedule::Still`
+    next_poll: Option<Instant>,
+}
+
+impl<Provider, Frame> Viewer<Provider>
+where
+    Provider: FrameProvider<Frame = Frame>,
+    Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData,
+{
+    pub fn new<S: Into<String>>(title: S, frame_provider: Provider) -> Self {
+        Self {
+            frame_provider,
+            comparison: None,
+            key_handler: None,
+            key_bindings: HashMap::new(),
+            input: InputBindings::default(),
+            editor: BoxEditor::new(),
+            annotation_handler: None,
+            editing: false,
+            mask: None,
+            show_mask: true,
+            heatmap: None,
+            show_heatmap: true,
+            transport: None,
+            scrubber: false,
+            scrubbing: false,
+            markers: Vec::new(),
+            subtitles: None,
+            caption_style: CaptionStyle::default(),
+            badge: None,
+            caption: None,
+            #[cfg(feature = "fonts")]
+            font: None,
+            scale_bar: None,
+            pixel_grid: PixelGrid::default(),
+            show_pixel_grid: true,
+            readout: false,
+            hud: false,
+            frame_times: VecDeque::new(),
+            #[cfg(feature = "renderdoc")]
+            renderdoc: RenderDoc::connect().ok(),
+            recording: None,
+            replay: None,
+            injector: EventInjector::default(),
+            title: title.into(),
+            window_size: WindowSize::Fixed((1280, 720)),
+            monitor: None,
+            auto_resize: false,
+            aspect_lock: false,
+            scaling_mode: ScalingMode::default(),
+            clear_color: None,
+            filter_preset: FilterPreset::default(),
+            auto_filter: AutoFilterThresholds::default(),
+            strict_validation: false,
+            wide_gamut: false,
+            hdr: HdrMode::Off,
+            alpha: AlphaBehavior::default(),
+            adapter: AdapterSelection::Default,
+            feedback: FeedbackHandle::default(),
+
+            window: None,
+            render_context: None,
+            pending_context: None,
+
+            frame_size: None,
+            cursor: None,
+            dragging: false,
+            dragging_split: false,
+            live: false,
+            finished: false,
+            next_poll: Some(Instant::now()),
+        }
+    }
+
+    // in physical pixels, `with_window_size` with `WindowSize::Fixed`
+    pub fn with_size(mut self, size: Pair<u32>) -> Self {
+        self.window_size = WindowSize::Fixed(size);
+        self
+    }
+
+    pub fn with_window_size(mut self, size: WindowSize) -> Self {
+        self.window_size = size;
+        self
+    }
+
+    // resizes the window to the size of the provider's frames whenever it changes, e.g. when
+    // stepping to another image, shrunk to the work area keeping their aspect, fullscreen
+    // and maximized windows stay as they are
+    pub fn with_auto_resize(mut self, auto_resize: bool) -> Self {
+        self.auto_resize = auto_resize;
+        self
+    }
+
+    // keeps the window at the aspect of the provider's frames while it is resized
+    pub fn with_aspect_lock(mut self, aspect_lock: bool) -> Self {
+        self.aspect_lock = aspect_lock;
+        self
+    }
+
+    // opens the window centered on `monitor`, otherwise the platform places it, when the
+    // monitor isn't there the window opens as if none was chosen
+    pub fn with_monitor(mut self, monitor: MonitorSelection) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    pub fn with_scaling_mode(mut self, mode: ScalingMode) -> Self {
+        self.scaling_mode = mode;
+        self
+    }
+
+    pub fn with_clear_color(mut self, color: wgpu::Color) -> Self {
+        self.clear_color = Some(color);
+        self
+    }
+
+    pub fn with_filter_preset(mut self, preset: FilterPreset) -> Self {
+        self.filter_preset = preset;
+        self
+    }
+
+    // see `RenderContextBuilder::auto_filter`
+    pub fn with_auto_filter(mut self, thresholds: AutoFilterThresholds) -> Self {
+        self.auto_filter = thresholds;
+        self
+    }
+
+    pub fn with_strict_validation(mut self, strict_validation: bool) -> Self {
+        self.strict_validation = strict_validation;
+        self
+    }
+
+    // see `RenderContextBuilder::wide_gamut`
+    pub fn with_wide_gamut(mut self, wide_gamut: bool) -> Self {
+        self.wide_gamut = wide_gamut;
+        self
+    }
+
+    pub fn with_hdr(mut self, hdr: HdrMode) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    // see `AlphaBehavior`, straight unless set
+    pub fn with_alpha_behavior(mut self, alpha: AlphaBehavior) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    // a warning shown in the top left corner for as long as the window is open
+    pub fn with_badge<S: Into<String>>(mut self, badge: S) -> Self {
+        self.badge = Some(badge.into());
+        self
+    }
+
+    // text over the bottom of the frame until a key response replaces it, e.g. the state a
+    // key handler starts in
+    pub fn with_caption<S: Into<String>>(mut self, caption: S) -> Self {
+        self.caption = Some(caption.into()).filter(|caption| !caption.is_empty());
+        self
+    }
+
+    // the captions, badge, hud and labels are drawn in `font` rather than the built in ascii
+    // font
+    #[cfg(feature = "fonts")]
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    // see `ScaleBar`
+    pub fn with_scale_bar(mut self, scale_bar: ScaleBar) -> Self {
+        self.scale_bar = Some(scale_bar);
+        self
+    }
+
+    // see `PixelGrid`, p shows and hides it
+    pub fn with_pixel_grid(mut self, grid: PixelGrid) -> Self {
+        self.pixel_grid = grid;
+        self
+    }
+
+    // starts with the label next to the cursor shown, i shows and hides it
+    pub fn with_coordinate_readout(mut self, readout: bool) -> Self {
+        self.readout = readout;
+        self
+    }
+
+    // starts with the window title, zoom and frame rate shown in the top right corner, f1
+    // shows and hides them
+    pub fn with_hud(mut self, hud: bool) -> Self {
+        self.hud = hud;
+        self
+    }
+
+    pub fn with_adapter(mut self, adapter: AdapterSelection) -> Self {
+        self.adapter = adapter;
+        self
+    }
+
+    pub fn with_feedback(mut self, feedback: FeedbackHandle) -> Self {
+        self.feedback = feedback;
+        self
+    }
+
+    // receives the keys the viewer has no binding for along with the provider, returning
+    // `None` when it ignored the key
+    pub fn with_key_handler<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(&mut Provider, KeyCode) -> Option<KeyResponse> + 'static,
+    {
+        self.key_handler = Some(Box::new(handler));
+        self
+    }
+
+    // replaces the keys of the viewer's own actions, e.g. to move them off keys the key
+    // handler wants
+    pub fn with_input_bindings(mut self, bindings: InputBindings) -> Self {
+        self.input = bindings;
+        self
+    }
+
+    // takes `key` over from the viewer's own binding or the key handler
+    pub fn with_key_binding<F>(mut self, key: KeyCode, handler: F) -> Self
+    where
+        F: FnMut(&mut Provider, KeyCode) -> Option<KeyResponse> + 'static,
+    {
+        self.key_bindings.insert(key, Box::new(handler));
+        self
+    }
+
+    // boxes to start from, e.g. loaded labels or an editor with a different outline color
+    pub fn with_annotations(mut self, editor: BoxEditor) -> Self {
+        self.editor = editor;
+        self
+    }
+
+    // receives every finished edit of a box in frame pixels
+    pub fn with_annotation_handler<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(&AnnotationEvent) + 'static,
+    {
+        self.annotation_handler = Some(Box::new(handler));
+        self
+    }
+
+    // tints the frame by class, see `WgpuFrameRenderContext::set_mask`
+    pub fn with_mask(mut self, mask: SegmentationMask, style: MaskStyle) -> Self {
+        self.mask = Some((mask, style));
+        self
+    }
+
+    // colors the frame by value, see `WgpuFrameRenderContext::set_heatmap`
+    pub fn with_heatmap(mut self, heatmap: Heatmap, style: HeatmapStyle) -> Self {
+        self.heatmap = Some((heatmap, style));
+        self
+    }
+
+    // shows the frames of `comparison` right of a line and those of the viewer's provider
+    // left of it, e.g. a processed version next to the original, dragging the line with the
+    // left button moves it, see `WgpuFrameRenderContext::draw_split`
+    // both providers step through their images together
+    pub fn with_comparison(mut self, comparison: Provider) -> Self {
+        self.comparison = Some(comparison);
+        self
+    }
+
+    // records the input to `path` for `with_replay`, saved when the viewer exits, the
+    // times count from when rendering is ready
+    pub fn with_recording<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.recording = Some((InteractionRecorder::new(), path.into()));
+        self
+    }
+
+    // plays recorded input once rendering is ready, the window's own input is ignored
+    // until the last event, a recording that ended with closing the window closes it
+    pub fn with_replay(mut self, player: InteractionPlayer) -> Self {
+        self.replay = Some(player);
+        self
+    }
+
+    // queues an event as if the window had sent it, e.g. to zoom, pan or step through images
+    // from a test or a scripted demo, see `InputEvent`
+    // events are handled in order, those queued before `run` once rendering is ready
+    pub fn inject_event(&mut self, event: InputEvent) {
+        self.injector.inject(event);
+    }
+
+    // a handle injecting events into the running viewer from another thread
+    pub fn injector(&self) -> EventInjector {
+        self.injector.clone()
+    }
+
+    pub fn run(mut self) -> Result<(), EventLoopError> {
+        let event_loop = EventLoop::new()?;
+        self.injector.connect(event_loop.create_proxy());
+        event_loop.set_control_flow(ControlFlow::Wait);
+        event_loop.run_app(&mut self)
+    }
+
+    // the scrub bar and the caption showing at the provider's position
+    fn sync_timeline(&mut self) {
+        let (Some(transport), Some(context)) = (self.transport, self.render_context.as_mut()) else {
+            return;
+        };
+
+        let transport = transport(&mut self.frame_provider);
+
+        if self.scrubber {
+            context.set_timeline(Some(Timeline::of(transport)));
+        }
+
+        if let Some(subtitles) = self.subtitles.as_ref() {
+            context.set_caption(subtitles.text_at(transport.position().unwrap_or_default()).as_deref());
+        }
+    }
+
+    // seeks to the time under `point`, false when it isn't on the scrub bar
+    fn seek_to(&mut self, point: Pair<f32>) -> bool {
+        let (Some(transport), Some(context)) = (self.transport, self.render_context.as_ref()) else {
+            return false;
+        };
+
+        let Some(position) = context.timeline_at(point) else {
+            return false;
+        };
+
+        transport(&mut self.frame_provider).seek(position);
+        self.next_poll = Some(Instant::now());
+        self.sync_timeline();
+        true
+    }
+
+    fn toggle_pause(&mut self, key: KeyCode) {
+        let Some(transport) = self.transport else {
+            self.handle_key(key);
+            return;
+        };
+
+        let transport = transport(&mut self.frame_provider);
+        let paused = !transport.is_paused();
+        transport.set_paused(paused);
+
+        log::info!("{}", if paused { "paused" } else { "playing" });
+        self.sync_timeline();
+    }
+
+    // providers that can't go back get the key instead
+    fn rewind(&mut self, key: KeyCode) {
+        if !self.frame_provider.rewind() {
+            self.handle_key(key);
+            return;
+        }
+
+        log::info!("rewound");
+        self.finished = false;
+        self.sync_timeline();
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn jump_to_marker(&mut self, forward: bool, key: KeyCode) {
+        let (Some(transport), Some(context)) = (self.transport, self.render_context.as_ref()) else {
+            self.handle_key(key);
+            return;
+        };
+
+        let transport = transport(&mut self.frame_provider);
+        let position = transport.position().unwrap_or_default();
+        let marker = match forward {
+            true => scrubber::marker_after(context.markers(), position),
+            false => scrubber::marker_before(context.markers(), position),
+        };
+
+        if let Some(marker) = marker {
+            log::info!("marker {} at {:?}", marker.name, marker.position);
+            transport.seek(marker.position);
+            self.sync_timeline();
+        }
+    }
+
+    fn press_left(&mut self, pressed: bool) {
+        match (pressed, self.scrubbing) {
+            (true, _) if self.cursor.is_some_and(|cursor| self.seek_to(cursor)) => self.scrubbing = true,
+            (false, true) => self.scrubbing = false,
+            (true, _) if self.cursor.is_some_and(|cursor| self.grabs_split(cursor)) => self.dragging_split = true,
+            (false, _) if self.dragging_split => self.dragging_split = false,
+            _ => match self.editing {
+                true => self.press_editor(pressed),
+                false => self.dragging = pressed,
+            },
+        }
+    }
+
+    fn grabs_split(&self, cursor: Pair<f32>) -> bool {
+        match (self.comparison.as_ref(), self.render_context.as_ref()) {
+            (Some(_), Some(context)) => (cursor.0 - context.split_x()).abs() <= HANDLE_REACH * self.scale_factor(),
+            _ => false,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.window = None;
+        self.render_context = None;
+        self.pending_context = None;
+    }
+
+    fn resize(&mut self, size: Pair<u32>) -> Result<(), bool> {
+        match self.render_context.as_mut() {
+            Some(context) => {
+                context.configure(size);
+                self.render()
+            },
+            None => Ok(()),
+        }
+    }
+
+    // an init failure is fatal for the viewer, it is logged and reported as `Err(true)`
+    fn poll_pending(&mut self) -> Result<(), bool> {
+        let Some(pending) = self.pending_context.as_mut() else {
+            return Ok(());
+        };
+
+        match pending.poll_ready() {
+            Poll::Ready(render_context) => {
+                self.pending_context = None;
+
+                let mut render_context = render_context.map_err(|error| {
+                    log::error!("failed to initialize rendering: {error}");
+                    true
+                })?;
+
+                render_context.set_source_label(self.title.as_str());
+                render_context.set_markers(&self.markers);
+                render_context.set_caption_style(self.caption_style);
+                render_context.set_badge(self.badge.as_deref());
+                render_context.set_caption(self.caption.as_deref());
+                #[cfg(feature = "fonts")]
+                render_context.set_font(self.font.clone());
+                render_context.set_scale_bar(self.scale_bar.clone());
+                render_context.set_pixel_grid(Some(self.pixel_grid).filter(|_| self.show_pixel_grid));
+                render_context.set_scaling_mode(self.scaling_mode);
+                render_context.set_annotations(self.editor.annotations(), self.editor.selected());
+                if let Some((mask, style)) = self.mask.as_ref() {
+                    render_context.set_mask_style(style.clone());
+                    render_context.set_mask(Some(mask.clone()).filter(|_| self.show_mask));
+                }
+                if let Some((heatmap, style)) = self.heatmap.as_ref() {
+                    render_context.set_heatmap_style(*style);
+                    render_context.set_heatmap(Some(heatmap.clone()).filter(|_| self.show_heatmap));
+                }
+
+                let gpu_info = render_context.gpu_info();
+                log::info!("rendering on {} ({:?}, {}) as {:?}", gpu_info.name, gpu_info.backend, gpu_info.driver, gpu_info.surface_format);
+
+                // the window may have been resized or moved to another display while the
+                // device was being requested
+                let window = self.window.as_ref().unwrap();
+                render_context.set_scale_factor(window.scale_factor());
+                let window_size = window.inner_size();
+                render_context.configure((window_size.width, window_size.height));
+
+                self.render_context = Some(render_context);
+
+                if let Some((recorder, _)) = self.recording.as_mut() {
+                    recorder.start();
+                }
+
+                if let Some(player) = self.replay.as_mut() {
+                    player.start();
+                }
+            },
+            Poll::Pending => self.window.as_ref().unwrap().request_redraw(),
+        }
+
+        Ok(())
+    }
+
+    fn render(&mut self) -> Result<(), bool> {
+        self.poll_pending()?;
+
+        self.update_hud();
+
+        match self.render_context.as_mut() {
+            Some(context) => {
+                let drawn = match self.comparison.as_mut() {
+                    Some(comparison) => context.draw_split(&mut self.frame_provider, comparison),
+                    None => context.draw_frame(&mut self.frame_provider),
+                };
+
+                match drawn {
+                    Ok(_) => Ok(()),
+                    Err(DrawError::OutOfMemory) => Err(true),
+                    Err(error @ DrawError::InvalidFrame(_)) => {
+                        log::error!("{error}");
+                        Err(true)
+                    },
+                    Err(error) => {
+                        log::warn!("{error}");
+                        Err(false)
+                    },
+                }
+            },
+            None => Ok(()),
+        }
+    }
+
+    // draws only when the provider has a new frame or the view changed, frames keep being
+    // pulled right away while they keep coming and otherwise when the provider's schedule
+    // says so, the loop sleeps until input while it waits for that
+    fn poll_provider(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) -> Result<(), bool> {
+        let Some(context) = self.render_context.as_mut() else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+
+        if self.live || (self.next_poll.is_some_and(|next| now >= next) && !self.finished) || context.needs_redraw() {
+            let view_changed = context.needs_redraw();
+            let poll = self.frame_provider.poll_frame();
+            let comparison = self.comparison.as_mut().map(FrameProvider::poll_frame);
+            self.finished = poll.is_finished() && comparison.as_ref().is_none_or(FramePoll::is_finished);
+
+            // folded into this draw, so a changed hud doesn't count as a changed view
+            self.update_hud();
+            let context = self.render_context.as_mut().unwrap();
+
+            let drawn = match comparison {
+                Some(comparison) => context.draw_split_if_needed(poll, comparison),
+                None => context.draw_frame_if_needed(poll),
+            };
+
+            self.live = match drawn {
+                Ok(drew) => drew && !view_changed,
+                Err(DrawError::OutOfMemory) => return Err(true),
+                Err(error @ DrawError::InvalidFrame(_)) => {
+                    log::error!("{error}");
+                    return Err(true);
+                },
+                Err(error) => {
+                    log::warn!("{error}");
+                    false
+                },
+            };
+
+            if self.live {
+                self.frame_times.push_back(now);
+            }
+
+            let schedule = self.frame_provider.schedule();
+            let schedule = self.comparison.as_ref().map_or(schedule, |comparison| schedule.earliest(comparison.schedule()));
+
+            self.next_poll = match schedule {
+                FrameSchedule::Soon => Some(now + IDLE_POLL_INTERVAL),
+                FrameSchedule::At(at) => Some(at),
+                FrameSchedule::Still => None,
+            };
+            self.sync_timeline();
+        }
+
+        event_loop.set_control_flow(match (self.live, self.finished, self.next_poll) {
+            (true, ..) => ControlFlow::Poll,
+            (false, false, Some(next)) => ControlFlow::WaitUntil(next),
+            (false, ..) => ControlFlow::Wait,
+        });
+
+        Ok(())
+    }
+
+    // g cycles through the adapters able to drive the window
+    fn next_adapter(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let count = context.available_adapters().len();
+            let next = match &self.adapter {
+                AdapterSelection::Index(index) => (index + 1) % count.max(1),
+                _ => 0,
+            };
+
+            match context.switch_adapter(AdapterSelection::Index(next)) {
+                Ok(()) => self.adapter = AdapterSelection::Index(next),
+                Err(error) => log::warn!("{error}"),
+            }
+        }
+    }
+
+    fn next_scaling_mode(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let next = match context.scaling_mode() {
+                ScalingMode::Fit => ScalingMode::Fill,
+                ScalingMode::Fill => ScalingMode::Stretch,
+                ScalingMode::Stretch => ScalingMode::Original,
+                ScalingMode::Original => ScalingMode::Fit,
+            };
+
+            log::info!("scaling mode {next:?}");
+            context.set_scaling_mode(next);
+        }
+    }
+
+    fn next_filter_preset(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let next = match context.filter_preset() {
+                FilterPreset::Auto => FilterPreset::Pixelated,
+                FilterPreset::Pixelated => FilterPreset::Smooth,
+                FilterPreset::Smooth => FilterPreset::Auto,
+            };
+
+            log::info!("filter preset {next:?}");
+            context.set_filter_preset(next);
+        }
+    }
+
+    fn next_present_mode(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let next = match context.present_mode() {
+                PresentPreference::Vsync => PresentPreference::Mailbox,
+                PresentPreference::Mailbox => PresentPreference::Immediate,
+                PresentPreference::Immediate => PresentPreference::Vsync,
+            };
+
+            let mode = context.set_present_mode(next);
+            log::info!("present mode {next:?}, presenting with {mode:?}");
+        }
+    }
+
+    // 3x3 is enough to see every edge meet its opposite one
+    fn toggle_tiling(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let tiles = match context.tiles() {
+                (1, 1) => (3, 3),
+                _ => (1, 1),
+            };
+
+            log::info!("tiling {}x{}", tiles.0, tiles.1);
+            context.set_tiles(tiles);
+        }
+    }
+
+    fn toggle_normal_map(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let mode = match context.shading_mode() {
+                ShadingMode::NormalMap { .. } => ShadingMode::Color,
+                _ => ShadingMode::normal_map(),
+            };
+
+            log::info!("shading mode {mode:?}");
+            context.set_shading_mode(mode);
+        }
+    }
+
+    fn toggle_skybox(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let view = match (context.skybox(), context.cube_layout()) {
+                (Some(_), _) => None,
+                (None, Some(layout)) => Some(SkyboxView::new(layout)),
+                (None, None) => {
+                    log::info!("frame is not a cube cross");
+                    return;
+                },
+            };
+
+            context.set_skybox(view);
+        }
+    }
+
+    // the red channel as coverage, a distance field and a heat map, then back to color
+    fn next_coverage_mode(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let mode = match context.shading_mode() {
+                ShadingMode::Threshold { .. } => ShadingMode::distance_field(Channel::Red),
+                ShadingMode::DistanceField { .. } => ShadingMode::HeatMap { channel: Channel::Red },
+                ShadingMode::HeatMap { .. } => ShadingMode::Color,
+                _ => ShadingMode::Threshold { channel: Channel::Red, threshold: 0.5 },
+            };
+
+            log::info!("shading mode {mode:?}");
+            context.set_shading_mode(mode);
+        }
+    }
+
+    // each channel on its own as gray, then back to the full color
+    fn next_channel(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            let next = [Channel::Red, Channel::Green, Channel::Blue, Channel::Alpha, Channel::Red]
+                .windows(2)
+                .find(|pair| context.shading_mode() == ShadingMode::Channels(ChannelMapping::gray(pair[0])))
+                .map(|pair| pair[1]);
+
+            let mode = match next {
+                Some(Channel::Red) => ShadingMode::Color,
+                Some(channel) => ShadingMode::Channels(ChannelMapping::gray(channel)),
+                None => ShadingMode::Channels(ChannelMapping::gray(Channel::Red)),
+            };
+
+            log::info!("shading mode {mode:?}");
+            context.set_shading_mode(mode);
+        }
+    }
+
+    // each wheel notch zooms by 10%
+    fn zoom_by(&mut self, delta: MouseScrollDelta) {
+        let notches = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / (PIXELS_PER_NOTCH * self.scale_factor()),
+        };
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_zoom(context.zoom() * 1.1f32.powf(notches));
+        }
+    }
+
+    // dragging looks around inside a skybox, the scene follows the cursor
+    fn drag_by(&mut self, delta: Pair<f32>) {
+        let Some(context) = self.render_context.as_mut() else {
+            return;
+        };
+
+        match context.skybox() {
+            Some(mut view) => {
+                let radians_per_pixel = view.fov / context.size().1.max(1) as f32;
+                view.yaw -= delta.0 * radians_per_pixel;
+                view.pitch = (view.pitch + delta.1 * radians_per_pixel).clamp(-FRAC_PI_2, FRAC_PI_2);
+                context.set_skybox(Some(view));
+            },
+            None => context.pan_by(delta),
+        }
+    }
+
+    fn move_cursor(&mut self, position: Pair<f32>) {
+        if self.dragging_split {
+            if let Some(context) = self.render_context.as_mut() {
+                context.set_split_position(context.split_position_at(position));
+            }
+
+            self.cursor = Some(position);
+            return;
+        }
+
+        // the bar keeps following the cursor after it left it
+        if self.scrubbing {
+            if let Some(context) = self.render_context.as_ref() {
+                let bottom = context.size().1 as f32 - 1.0;
+                self.seek_to((position.0, bottom));
+            }
+
+            self.cursor = Some(position);
+            return;
+        }
+
+        if self.editing && self.editor.is_dragging() {
+            if let Some(point) = self.render_context.as_ref().and_then(|context| context.surface_to_frame(position)) {
+                self.editor.pointer_moved(point);
+                self.sync_annotations();
+            }
+        }
+
+        if let (true, Some(previous)) = (self.dragging, self.cursor) {
+            self.drag_by((position.0 - previous.0, position.1 - previous.1));
+        }
+
+        // the light follows the cursor while looking at a normal map
+        if let Some(context) = self.render_context.as_mut() {
+            if let ShadingMode::NormalMap { .. } = context.shading_mode() {
+                let light = shading::light_towards(position, context.size());
+                context.set_shading_mode(ShadingMode::NormalMap { light });
+            }
+        }
+
+        if let Some(context) = self.render_context.as_mut().filter(|_| self.readout) {
+            context.set_coordinate_readout(Some(position));
+        }
+
+        self.cursor = Some(position);
+    }
+
+    fn toggle_readout(&mut self) {
+        self.readout = !self.readout;
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_coordinate_readout(self.cursor.filter(|_| self.readout));
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn perform(&mut self, action: Action, key: KeyCode, event_loop: &winit::event_loop::ActiveEventLoop) {
+        match action {
+            Action::Quit => event_loop.exit(),
+            Action::ZoomIn => self.zoom_by(MouseScrollDelta::LineDelta(0.0, 1.0)),
+            Action::ZoomOut => self.zoom_by(MouseScrollDelta::LineDelta(0.0, -1.0)),
+            Action::Pan(direction) => {
+                let step = KEY_PAN_STEP * self.scale_factor();
+                let delta = match direction {
+                    Direction::Left => (step, 0.0),
+                    Direction::Right => (-step, 0.0),
+                    Direction::Up => (0.0, step),
+                    Direction::Down => (0.0, -step),
+                };
+                self.drag_by(delta);
+            },
+            Action::ResetView => if let Some(context) = self.render_context.as_mut() {
+                context.reset_view();
+            },
+            Action::ToggleFullscreen => self.toggle_fullscreen(),
+            Action::NextImage => self.step_image(true, key),
+            Action::PreviousImage => self.step_image(false, key),
+            Action::NextAdapter => self.next_adapter(),
+            Action::NextScalingMode => self.next_scaling_mode(),
+            Action::NextFilterPreset => self.next_filter_preset(),
+            Action::ToggleTiling => self.toggle_tiling(),
+            Action::NextPresentMode => self.next_present_mode(),
+            Action::ToggleEditing => self.toggle_editing(),
+            Action::ToggleMask => self.toggle_mask(),
+            Action::ToggleHeatmap => self.toggle_heatmap(),
+            Action::TogglePixelGrid => self.toggle_pixel_grid(),
+            Action::ToggleReadout => self.toggle_readout(),
+            Action::ToggleNormalMap => self.toggle_normal_map(),
+            Action::NextChannel => self.next_channel(),
+            Action::ToggleSkybox => self.toggle_skybox(),
+            Action::NextCoverageMode => self.next_coverage_mode(),
+            Action::ToggleHud => self.toggle_hud(),
+            Action::CaptureFrame => self.capture_frame(),
+            Action::ToggleCheckerboard => if let Some(context) = self.render_context.as_mut() {
+                context.set_checkerboard(!context.checkerboard());
+            },
+            Action::TogglePause => self.toggle_pause(key),
+            Action::Rewind => self.rewind(key),
+            Action::NextMarker => self.jump_to_marker(true, key),
+            Action::PreviousMarker => self.jump_to_marker(false, key),
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn toggle_fullscreen(&mut self) {
+        if let Some(window) = self.window.as_ref() {
+            let fullscreen = match window.fullscreen() {
+                Some(_) => None,
+                None => Some(Fullscreen::Borderless(None)),
+            };
+
+            window.set_fullscreen(fullscreen);
+        }
+    }
+
+    // providers with a single image get the key instead
+    fn step_image(&mut self, forward: bool, key: KeyCode) {
+        let stepped = match forward {
+            true => self.frame_provider.next_image(),
+            false => self.frame_provider.previous_image(),
+        };
+
+        if let Some(comparison) = self.comparison.as_mut().filter(|_| stepped) {
+            match forward {
+                true => comparison.next_image(),
+                false => comparison.previous_image(),
+            };
+        }
+
+        if !stepped {
+            self.handle_key(key);
+            return;
+        }
+
+        self.finished = false;
+        self.sync_timeline();
+    }
+
+    fn handle_key(&mut self, key: KeyCode) {
+        if self.editing && matches!(key, KeyCode::Delete | KeyCode::Backspace) && !self.key_bindings.contains_key(&key) {
+            let event = self.editor.delete_selected();
+            self.finish_edit(event);
+            return;
+        }
+
+        let handler = match self.key_bindings.get_mut(&key) {
+            Some(handler) => Some(handler),
+            None => self.key_handler.as_mut(),
+        };
+
+        let Some(response) = handler.and_then(|handler| handler(&mut self.frame_provider, key)) else {
+            return;
+        };
+
+        if let (Some(title), Some(window)) = (response.title, self.window.as_ref()) {
+            window.set_title(&title);
+        }
+
+        if let (Some(color), Some(context)) = (response.clear_color, self.render_context.as_mut()) {
+            context.set_clear_color(color);
+        }
+
+        if let Some(caption) = response.caption {
+            self.caption = Some(caption).filter(|caption| !caption.is_empty());
+
+            if let Some(context) = self.render_context.as_mut() {
+                context.set_caption(self.caption.as_deref());
+            }
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn toggle_editing(&mut self) {
+        self.editing = !self.editing;
+        self.editor.cancel_drag();
+        self.dragging = false;
+
+        log::info!("{}", if self.editing { "editing boxes" } else { "panning" });
+        self.sync_annotations();
+    }
+
+    fn toggle_mask(&mut self) {
+        let Some((mask, _)) = self.mask.as_ref() else {
+            return;
+        };
+
+        self.show_mask = !self.show_mask;
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_mask(Some(mask.clone()).filter(|_| self.show_mask));
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn toggle_heatmap(&mut self) {
+        let Some((heatmap, _)) = self.heatmap.as_ref() else {
+            return;
+        };
+
+        self.show_heatmap = !self.show_heatmap;
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_heatmap(Some(heatmap.clone()).filter(|_| self.show_heatmap));
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn toggle_pixel_grid(&mut self) {
+        self.show_pixel_grid = !self.show_pixel_grid;
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_pixel_grid(Some(self.pixel_grid).filter(|_| self.show_pixel_grid));
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn toggle_hud(&mut self) {
+        self.hud = !self.hud;
+        self.update_hud();
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    // the window title, the zoom with 100% showing a frame pixel on every physical pixel, and
+    // the new frames shown over the last second, which stays 0 for a still image
+    fn update_hud(&mut self) {
+        let now = Instant::now();
+
+        while self.frame_times.front().is_some_and(|time| now.duration_since(*time) > FRAME_RATE_WINDOW) {
+            self.frame_times.pop_front();
+        }
+
+        let Some(context) = self.render_context.as_mut() else {
+            return;
+        };
+
+        if !self.hud {
+            context.set_hud(None);
+            return;
+        }
+
+        let title = self.window.as_ref().map(|window| window.title()).filter(|title| !title.is_empty());
+        let zoom = context.frame_scale().map_or_else(|| String::from("-"), |scale| format!("{:.0}%", scale * 100.0));
+        let hud = format!("{}\n{zoom}\n{} fps", title.as_deref().unwrap_or(&self.title), self.frame_times.len());
+
+        context.set_hud(Some(&hud));
+    }
+
+    // through renderdoc's own api when the app runs under it, so the capture starts at the
+    // present like its capture key does, through wgpu otherwise
+    fn capture_frame(&mut self) {
+        #[cfg(feature = "renderdoc")]
+        if let Some(renderdoc) = self.renderdoc.as_ref() {
+            renderdoc.trigger_capture();
+            return;
+        }
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.trigger_capture();
+        }
+    }
+
+    // physical pixels per logical pixel, 1 until there is a window
+    fn scale_factor(&self) -> f32 {
+        self.window.as_ref().map_or(1.0, |window| window.scale_factor() as f32)
+    }
+
+    // the platform resizes the window to keep its logical size, which arrives as a regular
+    // `Resized`, frames stay at a frame pixel per physical pixel in `ScalingMode::Original`
+    fn change_scale_factor(&mut self, scale_factor: f64) {
+        log::info!("scale factor changed to {scale_factor}");
+
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_scale_factor(scale_factor);
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn press_editor(&mut self, pressed: bool) {
+        let (Some(cursor), Some(context)) = (self.cursor, self.render_context.as_ref()) else {
+            return;
+        };
+
+        let Some(point) = context.surface_to_frame(cursor) else {
+            return;
+        };
+
+        match pressed {
+            true => {
+                let reach = HANDLE_REACH * context.scale_factor() as f32;
+                let tolerance = reach / context.frame_scale().unwrap_or(1.0).max(f32::EPSILON);
+                self.editor.set_bounds(context.frame_size());
+                self.editor.pointer_down(point, tolerance);
+                self.sync_annotations();
+            },
+            false => {
+                let event = self.editor.pointer_up(point);
+                self.finish_edit(event);
+            },
+        }
+    }
+
+    fn finish_edit(&mut self, event: Option<AnnotationEvent>) {
+        if let (Some(event), Some(handler)) = (event.as_ref(), self.annotation_handler.as_mut()) {
+            handler(event);
+        }
+
+        self.sync_annotations();
+    }
+
+    fn sync_annotations(&mut self) {
+        if let Some(context) = self.render_context.as_mut() {
+            context.set_annotations(self.editor.annotations(), self.editor.selected().filter(|_| self.editing));
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    // what `window_size` comes to on `monitor`, as is without one
+    fn initial_size(&self, monitor: Option<&MonitorHandle>) -> Pair<u32> {
+        let work_area = monitor.map(work_area);
+
+        let size = match self.window_size {
+            WindowSize::Fixed(size) => size,
+            WindowSize::MonitorFraction(fraction) => match monitor {
+                Some(monitor) => {
+                    let fraction = fraction.clamp(0.0, 1.0);
+                    ((monitor.size().width as f32 * fraction) as u32, (monitor.size().height as f32 * fraction) as u32)
+                },
+                None => (1280, 720),
+            },
+            WindowSize::Native => {
+                let size = self.frame_provider.current_size().unwrap_or((1280, 720));
+                work_area.map_or(size, |area| shrink_to(size, area))
+            },
+        };
+
+        let size = match work_area {
+            Some(area) => (size.0.min(area.0), size.1.min(area.1)),
+            None => size,
+        };
+
+        (size.0.max(1), size.1.max(1))
+    }
+
+    // follows the size of the provider's frames for `with_auto_resize` and
+    // `with_aspect_lock`, e.g. after stepping to the next image
+    fn fit_window(&mut self) -> Result<(), bool> {
+        if !self.auto_resize && !self.aspect_lock {
+            return Ok(());
+        }
+
+        let Some(frame_size) = self.frame_provider.current_size().filter(|size| size.0 > 0 && size.1 > 0) else {
+            return Ok(());
+        };
+
+        if self.frame_size == Some(frame_size) || self.render_context.is_none() {
+            return Ok(());
+        }
+
+        self.frame_size = Some(frame_size);
+
+        let Some(window) = self.window.as_ref().filter(|window| self.auto_resize && window.fullscreen().is_none() && !window.is_maximized()) else {
+            return Ok(());
+        };
+
+        let size = match window.current_monitor() {
+            Some(monitor) => shrink_to(frame_size, work_area(&monitor)),
+            None => frame_size,
+        };
+
+        match window.request_inner_size(PhysicalSize::new(size.0, size.1)) {
+            // otherwise the resize comes as a window event
+            Some(size) => self.resize((size.width, size.height)),
+            None => Ok(()),
+        }
+    }
+
+    // asks the window for the frame's aspect again after a resize that broke it, following
+    // the side that changed most, returns the size when it changed right away
+    fn keep_aspect(&self, size: Pair<u32>) -> Option<Pair<u32>> {
+        let frame_size = self.frame_size.filter(|_| self.aspect_lock)?;
+        let window = self.window.as_ref().filter(|window| window.fullscreen().is_none() && !window.is_maximized())?;
+        let previous = self.render_context.as_ref()?.size();
+
+        let aspect = frame_size.0 as f32 / frame_size.1 as f32;
+        let width_change = (size.0 as f32 / previous.0.max(1) as f32 - 1.0).abs();
+        let height_change = (size.1 as f32 / previous.1.max(1) as f32 - 1.0).abs();
+
+        let wanted = match width_change >= height_change {
+            true => (size.0, (size.0 as f32 / aspect).round() as u32),
+            false => ((size.1 as f32 * aspect).round() as u32, size.1),
+        };
+
+        // rounding is left alone, so the window doesn't keep asking
+        if wanted.0.abs_diff(size.0) <= 1 && wanted.1.abs_diff(size.1) <= 1 {
+            return None;
+        }
+
+        window.request_inner_size(PhysicalSize::new(wanted.0.max(1), wanted.1.max(1))).map(|size| (size.width, size.height))
+    }
+
+    fn handle_input(&mut self, event: InputEvent, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // keys and clicks can reach the provider, e.g. through a key handler, so it is asked
+        // again even when it was waiting for input
+        if matches!(event, InputEvent::KeyPressed(_) | InputEvent::LeftButton { .. }) {
+            self.next_poll = Some(Instant::now());
+        }
+
+        match event {
+            InputEvent::KeyPressed(key) => match self.input.action(key).filter(|_| !self.key_bindings.contains_key(&key)) {
+                Some(action) => self.perform(action, key, event_loop),
+                None => self.handle_key(key),
+            },
+            InputEvent::CloseRequested => event_loop.exit(),
+            InputEvent::Wheel(delta) => self.zoom_by(delta),
+            InputEvent::LeftButton { pressed } => self.press_left(pressed),
+            InputEvent::CursorMoved(position) => self.move_cursor(position),
+            InputEvent::CursorLeft => {
+                self.cursor = None;
+                self.dragging = false;
+                self.dragging_split = false;
+
+                if let Some(context) = self.render_context.as_mut() {
+                    context.set_coordinate_readout(None);
+                }
+            },
+            InputEvent::ScaleFactorChanged(scale_factor) => self.change_scale_factor(scale_factor),
+            InputEvent::Resized(size) => {
+                let size = self.keep_aspect(size).unwrap_or(size);
+
+                if let Err(true) = self.resize(size) {
+                    event_loop.exit();
+                }
+            },
+        }
+    }
+
+    // an event that didn't come from the window, a resize asks the window for the size and
+    // the resize follows as a window event
+    fn handle_synthetic(&mut self, event: InputEvent, event_loop: &winit::event_loop::ActiveEventLoop) {
+        match event {
+            InputEvent::Resized(size) => if let Some(window) = self.window.as_ref() {
+                if let Some(size) = window.request_inner_size(PhysicalSize::new(size.0, size.1)) {
+                    self.handle_input(InputEvent::Resized((size.width, size.height)), event_loop);
+                }
+            },
+            event => self.handle_input(event, event_loop),
+        }
+
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    // injected events wait for rendering to be ready, like a replay
+    fn handle_injected(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        if self.render_context.is_none() {
+            return;
+        }
+
+        for event in self.injector.take() {
+            self.handle_synthetic(event, event_loop);
+        }
+    }
+
+    fn replay_due(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let Some(player) = self.replay.as_mut() else {
+            return;
+        };
+
+        for event in player.due() {
+            self.handle_synthetic(event, event_loop);
+        }
+
+        // wakes up for the next event unless the provider keeps the loop busy anyway
+        if let Some(next) = self.replay.as_ref().and_then(InteractionPlayer::next_due) {
+            match event_loop.control_flow() {
+                ControlFlow::Poll => (),
+                ControlFlow::WaitUntil(wake) if wake <= next => (),
+                _ => event_loop.set_control_flow(ControlFlow::WaitUntil(next)),
+            }
+        }
+
+        if self.replay.as_ref().is_some_and(InteractionPlayer::is_finished) {
+            log::info!("replayed the recorded input");
+            self.replay = None;
+        }
+    }
+
+    // the recording, when there is one, is saved with the input up to the exit
+    fn save_recording(&mut self) {
+        if let Some((recorder, path)) = self.recording.take() {
+            match recorder.save(&path) {
+                Ok(()) => log::info!("saved {} input events to {}", recorder.events().len(), path.display()),
+                Err(error) => log::error!("failed to save the input recording to {}: {error}", path.display()),
+            }
+        }
+    }
+
+    fn has_window(&self, window_id: winit::window::WindowId) -> bool {
+        match &self.window {
+            Some(window) => window.id() == window_id,
+            None => false,
+        }
+    }
+}
+
+impl<Provider: Transport> Viewer<Provider> {
+    // a scrub bar along the bottom showing the provider's position, clicking or dragging
+    // on it seeks and space pauses
+    pub fn with_scrubber(mut self) -> Self {
+        self.transport = Some(|provider| provider);
+        self.scrubber = true;
+        self
+    }
+
+    // captions following the provider's position, space pauses
+    pub fn with_subtitles(mut self, subtitles: Subtitles) -> Self {
+        self.transport = Some(|provider| provider);
+        self.subtitles = Some(subtitles);
+        self
+    }
+
+    pub fn with_caption_style(mut self, style: CaptionStyle) -> Self {
+        self.caption_style = style;
+        self
+    }
+
+    // named points drawn on the scrub bar, [ and ] jump to the previous and next one
+    pub fn with_markers(mut self, markers: Vec<Marker>) -> Self {
+        self.markers = markers;
+        self
+    }
+}
+
+impl<Provider, Frame> ApplicationHandler for Viewer<Provider>
+where
+    Provider: FrameProvider<Frame = Frame>,
+    Frame: HasSize<u32> + HasPosition<u32> + HasFormat + HasOrientation + HasData,
+{
+    fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let chosen = self.monitor.as_ref().and_then(|monitor| monitor.find(event_loop));
+        if let (Some(monitor), None) = (self.monitor.as_ref(), chosen.as_ref()) {
+            log::warn!("no monitor {monitor:?}, leaving the window to the platform");
+        }
+
+        // sized for the monitor it is likely to open on when it isn't placed
+        let monitor = chosen.clone().or_else(|| MonitorSelection::Primary.find(event_loop));
+        let size = self.initial_size(monitor.as_ref());
+        let mut attributes = Window::default_attributes()
+            .with_title(self.title.as_str())
+            .with_inner_size(PhysicalSize::new(size.0, size.1));
+
+        if let Some(monitor) = chosen {
+            let (origin, area) = (monitor.position(), monitor.size());
+            let x = origin.x + (area.width.saturating_sub(size.0) / 2) as i32;
+            let y = origin.y + (area.height.saturating_sub(size.1) / 2) as i32;
+            attributes = attributes.with_position(PhysicalPosition::new(x, y));
+        }
+
+        let window = Arc::new(event_loop.create_window(attributes).unwrap());
+        window.request_redraw();
+
+        let window_size = window.inner_size();
+        self.window = Some(Arc::clone(&window));
+
+        // back from a suspend, the device and the frame on it survived
+        if let Some(context) = self.render_context.as_mut() {
+            match context.recreate_surface(Arc::clone(&window)) {
+                Ok(()) => {
+                    context.set_scale_factor(window.scale_factor());
+                    context.configure((window_size.width, window_size.height));
+                    return;
+                },
+                Err(error) => {
+                    log::warn!("starting over, {error}");
+                    self.render_context = None;
+                },
+            }
+        }
+
+        let mut builder = RenderContextBuilder::new(window)
+            .size(window_size.width, window_size.height)
+            .adapter(self.adapter.clone())
+            .strict_validation(self.strict_validation)
+            .wide_gamut(self.wide_gamut)
+            .hdr(self.hdr)
+            .alpha_behavior(self.alpha)
+            .filter_preset(self.filter_preset)
+            .auto_filter(self.auto_filter)
+            .feedback(self.feedback.clone());
+
+        if let Some(color) = self.clear_color {
+            builder = builder.clear_color(color);
+        }
+
+        // the device is requested in the background, events keep flowing until it is ready
+        self.pending_context = Some(builder.spawn().unwrap());
+    }
+
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        if let Err(true) = self.poll_provider(event_loop) {
+            event_loop.exit();
+        }
+
+        if let Err(true) = self.fit_window() {
+            event_loop.exit();
+        }
+
+        self.handle_injected(event_loop);
+        self.replay_due(event_loop);
+    }
+
+    // an injector woke the loop up
+    fn user_event(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, _event: ()) {
+        self.handle_injected(event_loop);
+    }
+
+    fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        self.save_recording();
+        self.clear();
+    }
+
+    // the surface goes with the window, the device and what it holds are kept for resuming,
+    // a context still being set up is started again then
+    fn suspended(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        if let Some(context) = self.render_context.as_mut() {
+            context.release_surface();
+        }
+
+        self.pending_context = None;
+        self.window = None;
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        window_id: winit::window::WindowId,
+        event: WindowEvent,
+    ) {
+        if self.has_window(window_id) {
+            if let WindowEvent::RedrawRequested = event {
+                if let Err(true) = self.render() {
+                    event_loop.exit();
+                }
+                return;
+            }
+
+            let Some(event) = InputEvent::from_window_event(&event) else {
+                return;
+            };
+
+            if let Some((recorder, _)) = self.recording.as_mut() {
+                recorder.record(event);
+            }
+
+            // what the window does to itself still counts while a replay drives the input
+            let replaying = self.replay.is_some();
+            if replaying && !matches!(event, InputEvent::Resized(_) | InputEvent::ScaleFactorChanged(_) | InputEvent::CloseRequested) {
+                return;
+            }
+
+            self.handle_input(event, event_loop);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injected_events_keep_their_order() {
+        let injector = EventInjector::default();
+        let clone = injector.clone();
+
+        assert!(injector.inject(InputEvent::KeyPressed(KeyCode::KeyS)));
+        std::thread::spawn(move || assert!(clone.inject(InputEvent::CloseRequested))).join().unwrap();
+
+        assert_eq!(injector.take(), [InputEvent::KeyPressed(KeyCode::KeyS), InputEvent::CloseRequested]);
+        assert!(injector.take().is_empty());
+    }
+}
@@ -0,0 +1,372 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use winit::error::EventLoopError;
+use winit::keyboard::KeyCode;
+
+#[cfg(feature = "clipboard")]
+use crate::providers::ClipboardProvider;
+use crate::providers::{AnimatedImageFrame, AnimatedImageProvider, DecodeError, DecodeLimits, Diagnosis, ImageFrame, ImageProvider};
+use crate::types::{FramePoll, FrameProvider, FrameSchedule, HasData, HasFormat, HasOrientation, HasPosition, HasSize, Orientation, Pair, PixelFormat, RowOrder};
+use crate::viewer::{KeyResponse, MonitorSelection, Viewer, WindowSize};
+use crate::viewport::ScalingMode;
+
+#[derive(Debug)]
+pub enum ViewerError {
+    NoSource,
+    Io(io::Error),
+    Decode(DecodeError),
+    EventLoop(EventLoopError),
+    Save(image::ImageError),
+    #[cfg(feature = "clipboard")]
+    Clipboard(arboard::Error),
+}
+
+impl fmt::Display for ViewerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewerError::NoSource => write!(f, "no image to show, set a path or bytes"),
+            ViewerError::Io(error) => write!(f, "failed to read image: {error}"),
+            ViewerError::Decode(error) => write!(f, "{error}"),
+            ViewerError::EventLoop(error) => write!(f, "{error}"),
+            ViewerError::Save(error) => write!(f, "failed to save image: {error}"),
+            #[cfg(feature = "clipboard")]
+            ViewerError::Clipboard(error) => write!(f, "failed to open the clipboard: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ViewerError {}
+
+impl From<io::Error> for ViewerError {
+    fn from(error: io::Error) -> Self {
+        ViewerError::Io(error)
+    }
+}
+
+impl From<DecodeError> for ViewerError {
+    fn from(error: DecodeError) -> Self {
+        ViewerError::Decode(error)
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl From<arboard::Error> for ViewerError {
+    fn from(error: arboard::Error) -> Self {
+        ViewerError::Clipboard(error)
+    }
+}
+
+impl From<EventLoopError> for ViewerError {
+    fn from(error: EventLoopError) -> Self {
+        ViewerError::EventLoop(error)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+    #[cfg(feature = "clipboard")]
+    Clipboard,
+}
+
+type KeyAction = Box<dyn FnMut() -> Option<KeyResponse>>;
+
+// a window showing one image in a single call, animated gifs and pngs play:
+//
+//     ViewerBuilder::new().path("photo.jpg").scaling_mode(ScalingMode::Fill).run()
+//
+// everything `Viewer` does is there, including its keys unless they are bound here
+pub struct ViewerBuilder {
+    title: Option<String>,
+    window_size: WindowSize,
+    monitor: Option<MonitorSelection>,
+    auto_resize: bool,
+    aspect_lock: bool,
+    scaling_mode: ScalingMode,
+    clear_color: Option<wgpu::Color>,
+    source: Option<Source>,
+    salvage: bool,
+    key_actions: Vec<(KeyCode, KeyAction)>,
+}
+
+impl Default for ViewerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViewerBuilder {
+    pub fn new() -> Self {
+        Self {
+            title: None,
+            window_size: WindowSize::Native,
+            monitor: None,
+            auto_resize: false,
+            aspect_lock: false,
+            scaling_mode: ScalingMode::default(),
+            clear_color: None,
+            source: None,
+            salvage: true,
+            key_actions: Vec::new(),
+        }
+    }
+
+    // the file name of a path source unless set
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    // in physical pixels, the image's own size fit into the work area unless set
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = WindowSize::Fixed((width, height));
+        self
+    }
+
+    pub fn window_size(mut self, size: WindowSize) -> Self {
+        self.window_size = size;
+        self
+    }
+
+    // see `Viewer::with_auto_resize`
+    pub fn auto_resize(mut self, auto_resize: bool) -> Self {
+        self.auto_resize = auto_resize;
+        self
+    }
+
+    // see `Viewer::with_aspect_lock`
+    pub fn aspect_lock(mut self, aspect_lock: bool) -> Self {
+        self.aspect_lock = aspect_lock;
+        self
+    }
+
+    // see `Viewer::with_monitor`
+    pub fn monitor(mut self, monitor: MonitorSelection) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    pub fn scaling_mode(mut self, mode: ScalingMode) -> Self {
+        self.scaling_mode = mode;
+        self
+    }
+
+    pub fn clear_color(mut self, clear_color: wgpu::Color) -> Self {
+        self.clear_color = Some(clear_color);
+        self
+    }
+
+    // read and decoded when the viewer runs
+    pub fn path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.source = Some(Source::Path(path.into()));
+        self
+    }
+
+    // an encoded image, e.g. one embedded with `include_bytes!`
+    pub fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.source = Some(Source::Bytes(bytes.to_vec()));
+        self
+    }
+
+    // whatever image is on the clipboard, replaced by every new one copied
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard(mut self) -> Self {
+        self.source = Some(Source::Clipboard);
+        self
+    }
+
+    // a truncated or corrupt still shows what could be decoded under a warning badge
+    // instead of failing, on unless set
+    pub fn salvage(mut self, salvage: bool) -> Self {
+        self.salvage = salvage;
+        self
+    }
+
+    // runs `action` on `key` instead of what the viewer would do, built in keys included
+    pub fn bind_key<F>(mut self, key: KeyCode, action: F) -> Self
+    where
+        F: FnMut() -> Option<KeyResponse> + 'static,
+    {
+        self.key_actions.push((key, Box::new(action)));
+        self
+    }
+
+    // `key` does nothing
+    pub fn unbind_key(self, key: KeyCode) -> Self {
+        self.bind_key(key, || None)
+    }
+
+    // blocks until the window is closed
+    pub fn run(self) -> Result<(), ViewerError> {
+        let limits = DecodeLimits { salvage: self.salvage, ..DecodeLimits::default() };
+        let (image, default_title) = match self.source.ok_or(ViewerError::NoSource)? {
+            Source::Path(path) => {
+                let title = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+                let bytes = std::fs::read(&path)?;
+                let image = AnyImage::decode(&bytes, &limits).map_err(|error| error.diagnosed(Diagnosis::of(Some(&path), &bytes)))?;
+                (image, title)
+            },
+            Source::Bytes(bytes) => {
+                let image = AnyImage::decode(&bytes, &limits).map_err(|error| error.diagnosed(Diagnosis::of(None, &bytes)))?;
+                (image, String::from("egami"))
+            },
+            #[cfg(feature = "clipboard")]
+            Source::Clipboard => (AnyImage::Clipboard(ClipboardProvider::watch()?), String::from("Clipboard")),
+        };
+
+        let badge = match &image {
+            AnyImage::Still(image) => image.salvage().map(ToString::to_string),
+            _ => None,
+        };
+
+        let mut viewer = Viewer::new(self.title.unwrap_or(default_title), image)
+            .with_window_size(self.window_size)
+            .with_auto_resize(self.auto_resize)
+            .with_aspect_lock(self.aspect_lock)
+            .with_scaling_mode(self.scaling_mode);
+
+        if let Some(monitor) = self.monitor {
+            viewer = viewer.with_monitor(monitor);
+        }
+
+        if let Some(badge) = badge {
+            viewer = viewer.with_badge(badge);
+        }
+
+        if let Some(color) = self.clear_color {
+            viewer = viewer.with_clear_color(color);
+        }
+
+        for (key, mut action) in self.key_actions {
+            viewer = viewer.with_key_binding(key, move |_, _| action());
+        }
+
+        Ok(viewer.run()?)
+    }
+}
+
+// stills and animations behind one provider type for the viewer
+enum AnyImage {
+    Still(ImageProvider),
+    Animated(AnimatedImageProvider),
+    #[cfg(feature = "clipboard")]
+    Clipboard(ClipboardProvider),
+}
+
+enum AnyFrame {
+    Still(ImageFrame),
+    Animated(AnimatedImageFrame),
+}
+
+impl AnyImage {
+    // a single frame gif or png goes the still way, which also honors icc profiles and
+    // exif orientation
+    fn decode(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        match AnimatedImageProvider::from_bytes(bytes) {
+            Ok(animation) if animation.frame_count() > 1 => Ok(AnyImage::Animated(animation)),
+            _ => ImageProvider::from_bytes_with_limits(bytes, limits).map(AnyImage::Still),
+        }
+    }
+}
+
+impl FrameProvider for AnyImage {
+    type Frame = AnyFrame;
+
+    fn poll_frame(&mut self) -> FramePoll<Self::Frame> {
+        match self {
+            AnyImage::Still(image) => image.poll_frame().map(AnyFrame::Still),
+            AnyImage::Animated(animation) => animation.poll_frame().map(AnyFrame::Animated),
+            #[cfg(feature = "clipboard")]
+            AnyImage::Clipboard(clipboard) => clipboard.poll_frame().map(AnyFrame::Still),
+        }
+    }
+
+    fn current_size(&self) -> Option<Pair<u32>> {
+        match self {
+            AnyImage::Still(image) => image.current_size(),
+            AnyImage::Animated(animation) => animation.current_size(),
+            #[cfg(feature = "clipboard")]
+            AnyImage::Clipboard(clipboard) => clipboard.current_size(),
+        }
+    }
+
+    fn schedule(&self) -> FrameSchedule {
+        match self {
+            AnyImage::Still(image) => image.schedule(),
+            AnyImage::Animated(animation) => animation.schedule(),
+            #[cfg(feature = "clipboard")]
+            AnyImage::Clipboard(clipboard) => clipboard.schedule(),
+        }
+    }
+
+    fn rewind(&mut self) -> bool {
+        match self {
+            AnyImage::Still(image) => FrameProvider::rewind(image),
+            AnyImage::Animated(animation) => FrameProvider::rewind(animation),
+            #[cfg(feature = "clipboard")]
+            AnyImage::Clipboard(clipboard) => clipboard.rewind(),
+        }
+    }
+}
+
+impl HasSize<u32> for AnyFrame {
+    fn size(&self) -> Pair<u32> {
+        match self {
+            AnyFrame::Still(frame) => frame.size(),
+            AnyFrame::Animated(frame) => frame.size(),
+        }
+    }
+}
+
+impl HasPosition<u32> for AnyFrame {
+    fn position(&self) -> Pair<u32> {
+        match self {
+            AnyFrame::Still(frame) => frame.position(),
+            AnyFrame::Animated(frame) => frame.position(),
+        }
+    }
+}
+
+impl HasFormat for AnyFrame {
+    fn format(&self) -> PixelFormat {
+        match self {
+            AnyFrame::Still(frame) => frame.format(),
+            AnyFrame::Animated(frame) => frame.format(),
+        }
+    }
+
+    fn row_order(&self) -> RowOrder {
+        match self {
+            AnyFrame::Still(frame) => frame.row_order(),
+            AnyFrame::Animated(frame) => frame.row_order(),
+        }
+    }
+}
+
+impl HasOrientation for AnyFrame {
+    fn orientation(&self) -> Orientation {
+        match self {
+            AnyFrame::Still(frame) => frame.orientation(),
+            AnyFrame::Animated(frame) => frame.orientation(),
+        }
+    }
+}
+
+impl HasData for AnyFrame {
+    fn data(&self) -> &[u8] {
+        match self {
+            AnyFrame::Still(frame) => frame.data(),
+            AnyFrame::Animated(frame) => frame.data(),
+        }
+    }
+
+    fn content_id(&self) -> Option<u64> {
+        match self {
+            AnyFrame::Still(frame) => frame.content_id(),
+            AnyFrame::Animated(frame) => frame.content_id(),
+        }
+    }
+}
@@ -1,4 +1,6 @@
-pub(crate) enum ViewPortMargin {
+use crate::types::{HasRatio, Pair};
+
+pub enum ViewPortMargin {
     Horizontal(f32),
     Vertical(f32),
 }
@@ -23,11 +25,70 @@ impl ViewPortMargin {
 }
 
 // (horizontal margin, vertical margin)
-impl Into<(f32, f32)> for ViewPortMargin {
-    fn into(self) -> (f32, f32) {
-        match self {
+impl From<ViewPortMargin> for (f32, f32) {
+    fn from(margin: ViewPortMargin) -> Self {
+        match margin {
             ViewPortMargin::Horizontal(margin) => (margin, 0.0),
             ViewPortMargin::Vertical(margin) => (0.0, margin),
         }
     }
-}
\ No newline at end of file
+}
+
+// how a frame is sized to the viewport before zoom and pan
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ScalingMode {
+    // the whole frame, letterboxed
+    #[default]
+    Fit,
+    // covers the viewport, the overflowing side is cropped
+    Fill,
+    // covers the viewport, ignoring the aspect ratio
+    Stretch,
+    // one frame pixel per physical viewport pixel, centered, whatever the scale factor of
+    // the display
+    Original,
+}
+
+// edges of a quad in clip space, top is above bottom
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClipRect {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl ScalingMode {
+    pub fn clip_rect(self, frame_size: Pair<u32>, viewport_size: Pair<u32>) -> ClipRect {
+        let (half_width, half_height) = match self {
+            ScalingMode::Fit => {
+                let (h_margin, v_margin) = ViewPortMargin::from((frame_size.inverse_ratio(), viewport_size.inverse_ratio())).into();
+                (1.0 - h_margin, 1.0 - v_margin)
+            },
+            ScalingMode::Fill => {
+                let (frame_ratio, viewport_ratio) = (frame_size.inverse_ratio(), viewport_size.inverse_ratio());
+
+                match frame_ratio > viewport_ratio {
+                    true => (1.0, frame_ratio / viewport_ratio),
+                    false => (viewport_ratio / frame_ratio, 1.0),
+                }
+            },
+            ScalingMode::Stretch => (1.0, 1.0),
+            ScalingMode::Original => {
+                // starts on a whole viewport pixel so texels land exactly on pixels, clip
+                // space y points up so the vertical edges are negated
+                let edges = |frame_len: u32, viewport_len: u32| {
+                    let start = (viewport_len as i64 - frame_len as i64).div_euclid(2);
+                    let to_clip = |pixel: i64| 2.0 * pixel as f32 / viewport_len as f32 - 1.0;
+                    (to_clip(start), to_clip(start + frame_len as i64))
+                };
+
+                let (left, right) = edges(frame_size.0, viewport_size.0.max(1));
+                let (top, bottom) = edges(frame_size.1, viewport_size.1.max(1));
+                return ClipRect { left, top: -top, right, bottom: -bottom };
+            },
+        };
+
+        ClipRect { left: -half_width, top: half_height, right: half_width, bottom: -half_height }
+    }
+}